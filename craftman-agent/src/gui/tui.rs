@@ -417,6 +417,7 @@ fn ui(frame: &mut Frame, app: &mut App) {
         RunType::CustomJar(args) => {
             format!("Custom: {}", args.join(" "))
         }
+        RunType::Bedrock => "Bedrock".to_string(),
     };
 
     let config_text: Vec<Line<'_>> = [
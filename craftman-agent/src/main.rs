@@ -5,7 +5,9 @@ use crate::{
         gui_actions::ConfigRequest,
         tui::{GuiEvents, handler as tui_handler},
     },
-    mods::{server_handler::ServerHandler, stdout_writer::TuiWriter, *},
+    mods::{
+        handler_request::HandlerRequest, server_handler::ServerHandler, stdout_writer::TuiWriter, *,
+    },
 };
 use connect::connect;
 use protocol::serveractions::ServerActions;
@@ -54,7 +56,9 @@ async fn main() {
     let config = configs::Configs::new();
     tracing::info!("Config generated");
 
-    let mut server_handler = ServerHandler::new(config.clone());
+    let (request_sender, mut request_receiver) = mpsc::unbounded_channel::<HandlerRequest>();
+
+    let mut server_handler = ServerHandler::new(config.clone(), request_sender);
 
     tracing::info!("Handler created");
 
@@ -72,6 +76,7 @@ async fn main() {
                 agent_to_tui.clone(),
                 sender.clone(),
                 &mut receiver,
+                &mut request_receiver,
             )
             .await
             {
@@ -102,7 +107,7 @@ async fn main() {
         }
     }
 
-    if let Err(e) = server_handler.stop_server().await {
+    if let Err(e) = server_handler.stop_process().await {
         tracing::error!("Error shutting down server: {}", e)
     }
 }
@@ -0,0 +1,703 @@
+use crate::mods::configs::Configs;
+use crate::mods::files::{checked, write_atomic};
+use crate::mods::sandbox::is_denied;
+use anyhow::{Result, anyhow, bail};
+use protocol::config_files::{ConfigEntry, ConfigFormat, ConfigRequest, ConfigResponse, ValueKind};
+use protocol::serveractions::ServerActions;
+use std::path::Path;
+use tokio::sync::mpsc::UnboundedSender;
+use uuid::Uuid;
+
+// Folders searched for config files and how deep to look. Only the server directory itself is
+// searched for yaml and toml because its json files are player lists
+const SEARCH: [(&str, usize); 3] = [("", 1), ("config", 4), ("plugins", 3)];
+const MAX_FILES: usize = 200;
+const MAX_SIZE: u64 = 1024 * 1024;
+
+pub async fn run(
+    request_id: Uuid,
+    config: Configs,
+    request: ConfigRequest,
+    sender: UnboundedSender<ServerActions>,
+) {
+    let response = handle(&config, request)
+        .await
+        .unwrap_or_else(|e| ConfigResponse::Error(e.to_string()));
+    if let Err(e) = sender.send(ServerActions::ConfigFileResponse(request_id, response)) {
+        tracing::error!("Failed to send config file response: {}", e);
+    }
+}
+
+async fn handle(config: &Configs, request: ConfigRequest) -> Result<ConfigResponse> {
+    match request {
+        ConfigRequest::Files => {
+            let config = config.clone();
+            let files = tokio::task::spawn_blocking(move || find_files(&config)).await?;
+            Ok(ConfigResponse::Files(files))
+        }
+        ConfigRequest::Keys(file, path) => {
+            let (format, text) = read(config, &file).await?;
+            Ok(ConfigResponse::Keys(entries(
+                &parse(format, &text)?,
+                &path,
+            )?))
+        }
+        ConfigRequest::Set(file, path, value) => {
+            let (format, text) = read(config, &file).await?;
+            let Some((key, section)) = path.split_last() else {
+                bail!("No key to set");
+            };
+            let kind = entries(&parse(format, &text)?, section)?
+                .into_iter()
+                .find(|entry| &entry.key == key)
+                .map(|entry| entry.kind)
+                .ok_or_else(|| anyhow!("{} does not exist", path.join(".")))?;
+            if !kind.is_editable() {
+                bail!("{} is not a single value", path.join("."));
+            }
+            let value = Scalar::parse(kind, &value)?;
+            let updated = match format {
+                ConfigFormat::Yaml => set_yaml(&text, &path, &value)?,
+                ConfigFormat::Toml => set_toml(&text, &path, &value)?,
+                ConfigFormat::Json => set_json(&text, &path, &value)?,
+            };
+            write_atomic(&checked(config, &file)?, updated.as_bytes()).await?;
+            Ok(ConfigResponse::Keys(entries(
+                &parse(format, &updated)?,
+                section,
+            )?))
+        }
+    }
+}
+
+async fn read(config: &Configs, file: &str) -> Result<(ConfigFormat, String)> {
+    let format = ConfigFormat::from_path(file)
+        .ok_or_else(|| anyhow!("{} is not a yaml, toml or json file", file))?;
+    let target = checked(config, file)?;
+    let metadata = tokio::fs::metadata(&target).await?;
+    if !metadata.is_file() {
+        bail!("{} is not a file", file);
+    }
+    if metadata.len() > MAX_SIZE {
+        bail!("{} is too large to edit here", file);
+    }
+    Ok((format, tokio::fs::read_to_string(&target).await?))
+}
+
+fn find_files(config: &Configs) -> Vec<String> {
+    let mut files = Vec::new();
+    for (folder, depth) in SEARCH {
+        walk(config, folder, depth, folder.is_empty(), &mut files);
+    }
+    files.sort();
+    files.truncate(MAX_FILES);
+    files
+}
+
+fn walk(config: &Configs, relative: &str, depth: usize, skip_json: bool, files: &mut Vec<String>) {
+    let Ok(read_dir) = std::fs::read_dir(Path::new(&config.dir).join(relative)) else {
+        return;
+    };
+    for entry in read_dir.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        let path = if relative.is_empty() {
+            name
+        } else {
+            format!("{}/{}", relative, name)
+        };
+        if is_denied(Path::new(&path), &config.file_deny_list) {
+            continue;
+        }
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        if file_type.is_dir() {
+            if depth > 1 {
+                walk(config, &path, depth - 1, skip_json, files);
+            }
+        } else if file_type.is_file() {
+            match ConfigFormat::from_path(&path) {
+                Some(ConfigFormat::Json) if skip_json => {}
+                Some(_) => files.push(path),
+                None => {}
+            }
+        }
+    }
+}
+
+// The parts of a config file the bot can show, whatever format it came from
+enum Node {
+    Section(Vec<(String, Node)>),
+    List(String),
+    Scalar(ValueKind, String),
+}
+
+fn parse(format: ConfigFormat, text: &str) -> Result<Node> {
+    Ok(match format {
+        ConfigFormat::Yaml => from_yaml(&serde_yaml::from_str(text)?),
+        ConfigFormat::Toml => from_toml(text.parse::<toml_edit::DocumentMut>()?.as_item()),
+        ConfigFormat::Json => from_json(&serde_json::from_str(text)?),
+    })
+}
+
+fn entries(root: &Node, path: &[String]) -> Result<Vec<ConfigEntry>> {
+    let mut node = root;
+    for key in path {
+        let Node::Section(children) = node else {
+            bail!("{} is not a section", key);
+        };
+        node = children
+            .iter()
+            .find(|(name, _)| name == key)
+            .map(|(_, child)| child)
+            .ok_or_else(|| anyhow!("{} does not exist", key))?;
+    }
+    let Node::Section(children) = node else {
+        bail!("{} is not a section", path.join("."));
+    };
+    Ok(children
+        .iter()
+        .map(|(key, child)| {
+            let (kind, value) = match child {
+                Node::Section(keys) => (ValueKind::Section, format!("{} keys", keys.len())),
+                Node::List(value) => (ValueKind::List, value.clone()),
+                Node::Scalar(kind, value) => (*kind, value.clone()),
+            };
+            ConfigEntry {
+                key: key.clone(),
+                kind,
+                value,
+            }
+        })
+        .collect())
+}
+
+fn from_yaml(value: &serde_yaml::Value) -> Node {
+    use serde_yaml::Value;
+    match value {
+        Value::Mapping(mapping) => Node::Section(
+            mapping
+                .iter()
+                .map(|(key, value)| (yaml_key(key), from_yaml(value)))
+                .collect(),
+        ),
+        Value::Sequence(_) => Node::List(serde_json::to_string(value).unwrap_or_default()),
+        Value::String(text) => Node::Scalar(ValueKind::Text, text.clone()),
+        Value::Bool(boolean) => Node::Scalar(ValueKind::Boolean, boolean.to_string()),
+        Value::Number(number) if number.is_f64() => {
+            Node::Scalar(ValueKind::Float, number.to_string())
+        }
+        Value::Number(number) => Node::Scalar(ValueKind::Integer, number.to_string()),
+        Value::Null => Node::Scalar(ValueKind::Null, String::new()),
+        Value::Tagged(tagged) => from_yaml(&tagged.value),
+    }
+}
+
+fn yaml_key(key: &serde_yaml::Value) -> String {
+    match key {
+        serde_yaml::Value::String(key) => key.clone(),
+        key => serde_yaml::to_string(key)
+            .unwrap_or_default()
+            .trim_end()
+            .to_string(),
+    }
+}
+
+fn from_toml(item: &toml_edit::Item) -> Node {
+    use toml_edit::Value;
+    if let Some(table) = item.as_table_like() {
+        return Node::Section(
+            table
+                .iter()
+                .map(|(key, item)| (key.to_string(), from_toml(item)))
+                .collect(),
+        );
+    }
+    match item.as_value() {
+        Some(Value::String(text)) => Node::Scalar(ValueKind::Text, text.value().clone()),
+        Some(Value::Integer(number)) => Node::Scalar(ValueKind::Integer, number.to_string()),
+        Some(Value::Float(number)) => Node::Scalar(ValueKind::Float, number.to_string()),
+        Some(Value::Boolean(boolean)) => Node::Scalar(ValueKind::Boolean, boolean.to_string()),
+        // Dates are rare in configs so they are shown but not edited
+        Some(Value::Datetime(date)) => Node::List(date.to_string()),
+        _ => Node::List(item.to_string().trim().to_string()),
+    }
+}
+
+fn from_json(value: &serde_json::Value) -> Node {
+    use serde_json::Value;
+    match value {
+        Value::Object(object) => Node::Section(
+            object
+                .iter()
+                .map(|(key, value)| (key.clone(), from_json(value)))
+                .collect(),
+        ),
+        Value::Array(_) => Node::List(value.to_string()),
+        Value::String(text) => Node::Scalar(ValueKind::Text, text.clone()),
+        Value::Bool(boolean) => Node::Scalar(ValueKind::Boolean, boolean.to_string()),
+        Value::Number(number) if number.is_f64() => {
+            Node::Scalar(ValueKind::Float, number.to_string())
+        }
+        Value::Number(number) => Node::Scalar(ValueKind::Integer, number.to_string()),
+        Value::Null => Node::Scalar(ValueKind::Null, String::new()),
+    }
+}
+
+enum Scalar {
+    Text(String),
+    Integer(i64),
+    Float(f64),
+    Boolean(bool),
+}
+
+impl Scalar {
+    // New values keep the type of the old one, null keys take whatever the input looks like
+    fn parse(kind: ValueKind, input: &str) -> Result<Scalar> {
+        let input = input.trim();
+        Ok(match kind {
+            ValueKind::Boolean => match input.to_ascii_lowercase().as_str() {
+                "true" | "on" | "yes" => Scalar::Boolean(true),
+                "false" | "off" | "no" => Scalar::Boolean(false),
+                _ => bail!("{} is not true or false", input),
+            },
+            ValueKind::Integer => Scalar::Integer(
+                input
+                    .parse()
+                    .map_err(|_| anyhow!("{} is not a whole number", input))?,
+            ),
+            ValueKind::Float => Scalar::Float(
+                input
+                    .parse()
+                    .map_err(|_| anyhow!("{} is not a number", input))?,
+            ),
+            ValueKind::Null => {
+                if let Ok(boolean) = input.parse() {
+                    Scalar::Boolean(boolean)
+                } else if let Ok(number) = input.parse() {
+                    Scalar::Integer(number)
+                } else if let Ok(number) = input.parse() {
+                    Scalar::Float(number)
+                } else {
+                    Scalar::Text(input.to_string())
+                }
+            }
+            _ => Scalar::Text(input.to_string()),
+        })
+    }
+}
+
+fn set_toml(text: &str, path: &[String], value: &Scalar) -> Result<String> {
+    let mut document = text.parse::<toml_edit::DocumentMut>()?;
+    let mut item = document.as_item_mut();
+    for key in path {
+        item = item
+            .get_mut(key.as_str())
+            .ok_or_else(|| anyhow!("{} does not exist", key))?;
+    }
+    let current = item
+        .as_value_mut()
+        .ok_or_else(|| anyhow!("{} is not a single value", path.join(".")))?;
+    let mut replacement = match value {
+        Scalar::Text(text) => toml_edit::Value::from(text.as_str()),
+        Scalar::Integer(number) => toml_edit::Value::from(*number),
+        Scalar::Float(number) => toml_edit::Value::from(*number),
+        Scalar::Boolean(boolean) => toml_edit::Value::from(*boolean),
+    };
+    // The decor holds the whitespace and trailing comment around the value
+    *replacement.decor_mut() = current.decor().clone();
+    *current = replacement;
+    Ok(document.to_string())
+}
+
+// Like yaml, only the value's own text is swapped so the file keeps its indentation and layout.
+// The file is parsed first, so the scan below can rely on it being valid json
+fn set_json(text: &str, path: &[String], value: &Scalar) -> Result<String> {
+    let document: serde_json::Value = serde_json::from_str(text)?;
+    let mut current = &document;
+    for key in path {
+        current = current
+            .get(key.as_str())
+            .ok_or_else(|| anyhow!("{} does not exist", key))?;
+    }
+    if current.is_object() || current.is_array() {
+        bail!("{} is not a single value", path.join("."));
+    }
+    let replacement = match value {
+        Scalar::Text(text) => serde_json::Value::from(text.as_str()),
+        Scalar::Integer(number) => serde_json::Value::from(*number),
+        Scalar::Float(number) => serde_json::Value::from(*number),
+        Scalar::Boolean(boolean) => serde_json::Value::from(*boolean),
+    };
+
+    let bytes = text.as_bytes();
+    let mut start = skip_json_space(bytes, 0);
+    for key in path {
+        start = json_member(text, start, key)?.ok_or_else(|| anyhow!("{} does not exist", key))?;
+    }
+    let end = json_value_end(bytes, start)?;
+    Ok(format!(
+        "{}{}{}",
+        &text[..start],
+        serde_json::to_string(&replacement)?,
+        &text[end..]
+    ))
+}
+
+// Where the value of key starts in the object at start. A repeated key's last value wins, as it
+// does for serde_json
+fn json_member(text: &str, start: usize, key: &str) -> Result<Option<usize>> {
+    let bytes = text.as_bytes();
+    if bytes.get(start) != Some(&b'{') {
+        return Ok(None);
+    }
+    let mut i = skip_json_space(bytes, start + 1);
+    if bytes.get(i) == Some(&b'}') {
+        return Ok(None);
+    }
+    let mut found = None;
+    loop {
+        let key_end = json_value_end(bytes, i)?;
+        let name: String = serde_json::from_str(&text[i..key_end])?;
+        i = skip_json_space(bytes, key_end);
+        if bytes.get(i) != Some(&b':') {
+            bail!("Expected a colon after {}", name);
+        }
+        let value_start = skip_json_space(bytes, i + 1);
+        if name == key {
+            found = Some(value_start);
+        }
+        i = skip_json_space(bytes, json_value_end(bytes, value_start)?);
+        match bytes.get(i) {
+            Some(b',') => i = skip_json_space(bytes, i + 1),
+            Some(b'}') => return Ok(found),
+            _ => bail!("Unexpected end of object"),
+        }
+    }
+}
+
+// Brackets inside strings are skipped along with the strings
+fn json_value_end(bytes: &[u8], start: usize) -> Result<usize> {
+    match bytes.get(start) {
+        Some(b'"') => {
+            let mut i = start + 1;
+            while i < bytes.len() {
+                match bytes[i] {
+                    b'\\' => i += 2,
+                    b'"' => return Ok(i + 1),
+                    _ => i += 1,
+                }
+            }
+            bail!("Unterminated string")
+        }
+        Some(b'{' | b'[') => {
+            let mut depth = 0;
+            let mut i = start;
+            while i < bytes.len() {
+                match bytes[i] {
+                    b'"' => {
+                        i = json_value_end(bytes, i)?;
+                        continue;
+                    }
+                    b'{' | b'[' => depth += 1,
+                    b'}' | b']' => {
+                        depth -= 1;
+                        if depth == 0 {
+                            return Ok(i + 1);
+                        }
+                    }
+                    _ => {}
+                }
+                i += 1;
+            }
+            bail!("Unterminated object or array")
+        }
+        Some(_) => Ok(bytes[start..]
+            .iter()
+            .position(|b| matches!(b, b',' | b'}' | b']') || b.is_ascii_whitespace())
+            .map_or(bytes.len(), |end| start + end)),
+        None => bail!("Missing value"),
+    }
+}
+
+fn skip_json_space(bytes: &[u8], start: usize) -> usize {
+    start
+        + bytes[start..]
+            .iter()
+            .take_while(|b| b.is_ascii_whitespace())
+            .count()
+}
+
+// serde_yaml drops comments, so yaml is edited line by line. The key's line is found by following
+// indentation and only the value is swapped, leaving everything else as it was
+fn set_yaml(text: &str, path: &[String], value: &Scalar) -> Result<String> {
+    let formatted = match value {
+        Scalar::Text(text) if text.contains('\n') => {
+            bail!("Multi-line values can't be edited here")
+        }
+        Scalar::Text(text) => serde_yaml::to_string(text)?.trim_end().to_string(),
+        Scalar::Integer(number) => number.to_string(),
+        Scalar::Float(number) => format!("{:?}", number),
+        Scalar::Boolean(boolean) => boolean.to_string(),
+    };
+
+    let mut lines: Vec<String> = text.lines().map(str::to_string).collect();
+    let mut parents: Vec<(usize, String)> = Vec::new();
+    let mut block_indent = None;
+    for line in lines.iter_mut() {
+        let trimmed = line.trim_start();
+        let indent = line.len() - trimmed.len();
+        if let Some(block) = block_indent {
+            if trimmed.is_empty() || indent > block {
+                continue;
+            }
+            block_indent = None;
+        }
+        if trimmed.is_empty()
+            || trimmed.starts_with('#')
+            || trimmed.starts_with('-')
+            || trimmed.starts_with("...")
+        {
+            continue;
+        }
+        let Some((key, value_start)) = yaml_line_key(trimmed) else {
+            continue;
+        };
+        while parents.last().is_some_and(|(parent, _)| *parent >= indent) {
+            parents.pop();
+        }
+        parents.push((indent, key));
+
+        let rest = &trimmed[value_start..];
+        let comment = comment_start(rest);
+        let old_value = rest[..comment].trim();
+        if old_value.starts_with('|') || old_value.starts_with('>') {
+            block_indent = Some(indent);
+        }
+        if parents.len() == path.len()
+            && parents
+                .iter()
+                .zip(path)
+                .all(|((_, key), wanted)| key == wanted)
+        {
+            if block_indent.is_some() {
+                bail!("Multi-line values can't be edited here");
+            }
+            *line = format!(
+                "{}{} {}{}",
+                &line[..indent],
+                &trimmed[..value_start],
+                formatted,
+                &rest[comment..]
+            );
+            let mut updated = lines.join("\n");
+            if text.ends_with('\n') {
+                updated.push('\n');
+            }
+            return Ok(updated);
+        }
+    }
+    bail!(
+        "Could not find {} in the file, it may be written in flow style",
+        path.join(".")
+    )
+}
+
+// Returns the key and where its value starts, just after the colon
+fn yaml_line_key(line: &str) -> Option<(String, usize)> {
+    if let Some(quote) = line.chars().next().filter(|c| *c == '"' || *c == '\'') {
+        let end = line[1..].find(quote)? + 1;
+        return line[end + 1..]
+            .starts_with(':')
+            .then(|| (line[1..end].to_string(), end + 2));
+    }
+    let colon = line
+        .char_indices()
+        .find(|(i, c)| *c == ':' && line[i + 1..].chars().next().is_none_or(char::is_whitespace))
+        .map(|(i, _)| i)?;
+    Some((line[..colon].trim_end().to_string(), colon + 1))
+}
+
+// Finds the whitespace before a trailing comment, ignoring # inside quotes
+fn comment_start(rest: &str) -> usize {
+    let mut quote = None;
+    let mut previous = ' ';
+    for (i, c) in rest.char_indices() {
+        match quote {
+            Some(open) if c == open => quote = None,
+            Some(_) => {}
+            None if c == '"' || c == '\'' => quote = Some(c),
+            None if c == '#' && previous.is_whitespace() => {
+                return rest[..i].trim_end().len();
+            }
+            None => {}
+        }
+        previous = c;
+    }
+    rest.trim_end().len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn path(path: &str) -> Vec<String> {
+        path.split('.').map(str::to_string).collect()
+    }
+
+    #[test]
+    fn json_keeps_formatting() {
+        for (text, key, value, expected) in [
+            (
+                "{\n  \"a\": 1,\n  \"b\": {\"c\": \"x\", \"d\": [1, 2]}\n}\n",
+                "b.c",
+                Scalar::Text("y".to_string()),
+                "{\n  \"a\": 1,\n  \"b\": {\"c\": \"y\", \"d\": [1, 2]}\n}\n",
+            ),
+            (
+                "{\n\t\"enabled\":false,\n\t\"limit\" : 10\n}",
+                "limit",
+                Scalar::Integer(20),
+                "{\n\t\"enabled\":false,\n\t\"limit\" : 20\n}",
+            ),
+            (
+                "{\"s\": \"a\\\"}{\", \"n\": 5}",
+                "n",
+                Scalar::Integer(6),
+                "{\"s\": \"a\\\"}{\", \"n\": 6}",
+            ),
+            (
+                "{\"list\": [{\"n\": 1}], \"n\": 2.5}",
+                "n",
+                Scalar::Float(0.5),
+                "{\"list\": [{\"n\": 1}], \"n\": 0.5}",
+            ),
+            (
+                "{\"a\": 1, \"a\": 2}",
+                "a",
+                Scalar::Integer(3),
+                "{\"a\": 1, \"a\": 3}",
+            ),
+            (
+                "{\"with \\\"quote\\\"\": null}",
+                "with \"quote\"",
+                Scalar::Boolean(true),
+                "{\"with \\\"quote\\\"\": true}",
+            ),
+        ] {
+            assert_eq!(set_json(text, &path(key), &value).unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn json_refuses_missing_and_nested() {
+        let text = "{\"a\": {\"b\": 1}}";
+        assert!(set_json(text, &path("a"), &Scalar::Integer(1)).is_err());
+        assert!(set_json(text, &path("a.c"), &Scalar::Integer(1)).is_err());
+        assert!(set_json(text, &path("b"), &Scalar::Integer(1)).is_err());
+    }
+
+    #[test]
+    fn yaml_swaps_only_the_value() {
+        for (text, key, value, expected) in [
+            (
+                "a: 1 # comment\n",
+                "a",
+                Scalar::Integer(2),
+                "a: 2 # comment\n",
+            ),
+            ("a: 1", "a", Scalar::Integer(2), "a: 2"),
+            (
+                "server:\n  port: 25565\nother:\n  port: 1\n",
+                "other.port",
+                Scalar::Integer(2),
+                "server:\n  port: 25565\nother:\n  port: 2\n",
+            ),
+            (
+                "# header\nserver:\n\n  # note\n  name: old   # keep\n",
+                "server.name",
+                Scalar::Text("new".to_string()),
+                "# header\nserver:\n\n  # note\n  name: new   # keep\n",
+            ),
+            (
+                "a: \"x # y\" # c\n",
+                "a",
+                Scalar::Text("z".to_string()),
+                "a: z # c\n",
+            ),
+            (
+                "a: |\n  b: 1\nb: 2\n",
+                "b",
+                Scalar::Integer(3),
+                "a: |\n  b: 1\nb: 3\n",
+            ),
+            (
+                "list:\n  - b: 1\nb: 2\n",
+                "b",
+                Scalar::Integer(3),
+                "list:\n  - b: 1\nb: 3\n",
+            ),
+            ("ratio: 1.0\n", "ratio", Scalar::Float(2.0), "ratio: 2.0\n"),
+        ] {
+            assert_eq!(set_yaml(text, &path(key), &value).unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn yaml_refuses_block_and_multi_line_values() {
+        assert!(set_yaml("a: |\n  text\n", &path("a"), &Scalar::Integer(1)).is_err());
+        assert!(set_yaml("a: >-\n  text\n", &path("a"), &Scalar::Integer(1)).is_err());
+        assert!(set_yaml("a: x\n", &path("a"), &Scalar::Text("1\n2".to_string())).is_err());
+        assert!(set_yaml("a: {b: 1}\n", &path("a.b"), &Scalar::Integer(2)).is_err());
+        // A quoted key with a dot is one key, not a path
+        assert!(set_yaml("'a.b': true\n", &path("a.b"), &Scalar::Boolean(false)).is_err());
+    }
+
+    #[test]
+    fn yaml_line_keys() {
+        for (line, expected) in [
+            ("key: value", Some(("key", 4))),
+            ("key:", Some(("key", 4))),
+            ("key : value", Some(("key", 5))),
+            ("url: http://example.com", Some(("url", 4))),
+            ("a:b: c", Some(("a:b", 4))),
+            ("\"quoted key\": 1", Some(("quoted key", 13))),
+            ("'k': 1", Some(("k", 4))),
+            ("no colon", None),
+            ("\"q\" 1", None),
+            ("\"unterminated: 1", None),
+        ] {
+            assert_eq!(
+                yaml_line_key(line),
+                expected.map(|(key, start)| (key.to_string(), start)),
+                "{:?}",
+                line
+            );
+        }
+    }
+
+    #[test]
+    fn comment_starts() {
+        for (rest, expected) in [
+            (" 1 # comment", 2),
+            (" 1", 2),
+            (" 1   ", 2),
+            (" \"a # b\" # c", 8),
+            (" 'it''s' # c", 8),
+            (" a#b", 4),
+            (" # only a comment", 0),
+        ] {
+            assert_eq!(comment_start(rest), expected, "{:?}", rest);
+        }
+    }
+
+    #[test]
+    fn toml_keeps_decor() {
+        let text = "[server]\nport = 25565 # default\nname = \"old\"\n";
+        assert_eq!(
+            set_toml(text, &path("server.port"), &Scalar::Integer(1)).unwrap(),
+            "[server]\nport = 1 # default\nname = \"old\"\n"
+        );
+        assert!(set_toml(text, &path("server"), &Scalar::Integer(1)).is_err());
+    }
+}
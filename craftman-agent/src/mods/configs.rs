@@ -1,6 +1,7 @@
 use std::path::PathBuf;
 
 use directories::ProjectDirs;
+use protocol::properties::Edition;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
@@ -14,6 +15,20 @@ pub struct Configs {
     pub dir: String,
     pub jar: String,
     pub run_type: RunType,
+    // Base URL that replaces https://cdn.modrinth.com when installing modpacks
+    #[serde(default)]
+    pub modrinth_mirror: Option<String>,
+    // Files /files refuses to show or touch. Patterns with a / match the whole path relative to
+    // the server directory, anything else matches a single file or folder name. * is a wildcard
+    #[serde(default = "default_file_deny_list")]
+    pub file_deny_list: Vec<String>,
+}
+
+fn default_file_deny_list() -> Vec<String> {
+    [".env", "*.pem", "*.key", "*.jks", "*.p12"]
+        .into_iter()
+        .map(str::to_string)
+        .collect()
 }
 
 #[derive(Serialize, Deserialize, Clone, PartialEq)]
@@ -21,6 +36,21 @@ pub enum RunType {
     Default,
     Script,
     CustomJar(Vec<String>),
+    // bedrock_server from a Bedrock Dedicated Server download, xms and xmx don't apply
+    Bedrock,
+}
+
+impl RunType {
+    pub fn edition(&self) -> Edition {
+        match self {
+            RunType::Bedrock => Edition::Bedrock,
+            _ => Edition::Java,
+        }
+    }
+}
+
+pub fn is_bedrock_server(file: &str) -> bool {
+    file == "bedrock_server" || file == "bedrock_server.exe"
 }
 
 impl Configs {
@@ -53,6 +83,8 @@ impl Configs {
             let (file, directory) = file_explorer::blocking_file_selection(&mut terminal).unwrap();
             let run_type = if file.ends_with(".sh") {
                 RunType::Script
+            } else if is_bedrock_server(&file) {
+                RunType::Bedrock
             } else {
                 RunType::Default
             };
@@ -64,6 +96,8 @@ impl Configs {
                 dir: directory,
                 jar: file,
                 run_type,
+                modrinth_mirror: None,
+                file_deny_list: default_file_deny_list(),
             }
         }
     }
@@ -91,6 +125,10 @@ impl Configs {
     pub fn set_jar(mut self, jar: String) -> Self {
         if jar.ends_with(".sh") {
             self.run_type = RunType::Script;
+        } else if is_bedrock_server(&jar) {
+            self.run_type = RunType::Bedrock;
+        } else if self.run_type == RunType::Bedrock {
+            self.run_type = RunType::Default;
         }
         self.jar = jar;
         self
@@ -1,5 +1,6 @@
 use crate::gui::gui_actions::ConfigRequest;
 use crate::gui::tui::GuiEvents;
+use crate::mods::handler_request::HandlerRequest;
 use crate::mods::{server_handler::ServerHandler, *};
 use futures_util::stream::StreamExt;
 use protocol::serveractions::ServerActions;
@@ -17,6 +18,7 @@ pub async fn connect(
     agent_to_tui: UnboundedSender<GuiEvents>,
     sender: UnboundedSender<ServerActions>,
     receiver: &mut UnboundedReceiver<ServerActions>,
+    handler_requests: &mut UnboundedReceiver<HandlerRequest>,
 ) -> anyhow::Result<()> {
     tracing::info!("Trying to connect to: {}", URL);
     let (ws_stream, _) = connect_async(URL).await?;
@@ -25,17 +27,21 @@ pub async fn connect(
 
     sender.send(ServerActions::ConnectAgent(handler.id()))?;
     tracing::info!("Connected to server!");
+    if let Err(e) = handler.start_sampling(sender.clone()) {
+        tracing::warn!("Not sampling the server until a monitor starts: {}", e);
+    }
 
-    listener::listen(
+    let result = listener::listen(
         ws_read,
         ws_write,
         sender,
         handler,
         agent_from_tui,
         agent_to_tui.clone(),
+        handler_requests,
         receiver,
     )
-    .await?;
-
-    Ok(())
+    .await;
+    handler.stop_sampling();
+    result
 }
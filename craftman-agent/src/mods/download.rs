@@ -0,0 +1,10 @@
+use anyhow::Result;
+
+pub async fn download(url: &str) -> Result<Vec<u8>> {
+    Ok(reqwest::get(url)
+        .await?
+        .error_for_status()?
+        .bytes()
+        .await?
+        .to_vec())
+}
@@ -0,0 +1,127 @@
+use crate::mods::configs::Configs;
+use crate::mods::download::download;
+use crate::mods::sandbox::{is_denied, real_path};
+use anyhow::{Result, anyhow, bail};
+use base64::Engine as _;
+use base64::engine::general_purpose::STANDARD;
+use protocol::files::{FileEntry, FileRequest, FileResponse};
+use protocol::serveractions::ServerActions;
+use std::path::{Path, PathBuf};
+use tokio::sync::mpsc::UnboundedSender;
+use uuid::Uuid;
+
+// Discord's default attachment limit. Sent as base64 it's a third bigger, which still fits in one
+// 16 MB websocket frame
+const MAX_READ: u64 = 8 * 1024 * 1024;
+
+pub async fn run(
+    request_id: Uuid,
+    config: Configs,
+    request: FileRequest,
+    sender: UnboundedSender<ServerActions>,
+) {
+    let response = handle(&config, request)
+        .await
+        .unwrap_or_else(|e| FileResponse::Error(e.to_string()));
+    if let Err(e) = sender.send(ServerActions::FilesResponse(request_id, response)) {
+        tracing::error!("Failed to send files response: {}", e);
+    }
+}
+
+async fn handle(config: &Configs, request: FileRequest) -> Result<FileResponse> {
+    match request {
+        FileRequest::List(path) => {
+            let target = checked(config, &path)?;
+            let relative = Path::new(path.trim_matches('/'));
+            let mut entries = Vec::new();
+            let mut read_dir = tokio::fs::read_dir(&target).await?;
+            while let Some(entry) = read_dir.next_entry().await? {
+                let name = entry.file_name().to_string_lossy().to_string();
+                let path = relative.join(&name);
+                if is_denied(&path, &config.file_deny_list) {
+                    continue;
+                }
+                match real_path(Path::new(&config.dir), &path.to_string_lossy()) {
+                    Ok(real) if !is_denied(&real, &config.file_deny_list) => {}
+                    _ => continue,
+                }
+                let Ok(metadata) = tokio::fs::metadata(entry.path()).await else {
+                    continue;
+                };
+                entries.push(FileEntry {
+                    name,
+                    is_dir: metadata.is_dir(),
+                    size: metadata.len(),
+                });
+            }
+            entries.sort_by_key(|entry| (!entry.is_dir, entry.name.to_lowercase()));
+            Ok(FileResponse::Listing(entries))
+        }
+        FileRequest::Read(path) => {
+            let target = checked(config, &path)?;
+            let metadata = tokio::fs::metadata(&target).await?;
+            if !metadata.is_file() {
+                bail!("{} is not a file", path);
+            }
+            if metadata.len() > MAX_READ {
+                bail!("{} is larger than {} MB", path, MAX_READ / 1024 / 1024);
+            }
+            Ok(FileResponse::Contents(
+                STANDARD.encode(tokio::fs::read(&target).await?),
+            ))
+        }
+        FileRequest::WriteText(path, text) => {
+            let target = checked(config, &path)?;
+            if !target.is_file() {
+                bail!("{} is not a file", path);
+            }
+            write_atomic(&target, text.as_bytes()).await?;
+            Ok(FileResponse::Written(path))
+        }
+        FileRequest::Upload(path, file) => {
+            let mut path = path.trim_matches('/').to_string();
+            if checked(config, &path)?.is_dir() {
+                if file.file_name.contains(['/', '\\']) || file.file_name.starts_with('.') {
+                    bail!("{} is not a valid file name", file.file_name);
+                }
+                path = if path.is_empty() {
+                    file.file_name.clone()
+                } else {
+                    format!("{}/{}", path, file.file_name)
+                };
+            }
+            let target = checked(config, &path)?;
+            let bytes = download(&file.url).await?;
+            write_atomic(&target, &bytes).await?;
+            Ok(FileResponse::Written(path))
+        }
+    }
+}
+
+// Every path has to stay inside the server directory after following symlinks, and neither it
+// nor where a symlink takes it may match the deny list
+pub fn checked(config: &Configs, path: &str) -> Result<PathBuf> {
+    let dir = Path::new(&config.dir);
+    let relative = path.trim_matches('/');
+    if relative.is_empty() || relative == "." {
+        return Ok(dir.to_path_buf());
+    }
+    let real = real_path(dir, relative)?;
+    if is_denied(Path::new(relative), &config.file_deny_list)
+        || is_denied(&real, &config.file_deny_list)
+    {
+        bail!("{} is on the agent's deny list", relative);
+    }
+    Ok(dir.canonicalize()?.join(real))
+}
+
+// The server may read the file at any moment, so it should never see a half written one
+pub async fn write_atomic(target: &Path, bytes: &[u8]) -> Result<()> {
+    let name = target
+        .file_name()
+        .ok_or_else(|| anyhow!("{} has no file name", target.display()))?;
+    let part = target.with_file_name(format!(".{}.part", name.to_string_lossy()));
+    tokio::fs::write(&part, bytes).await?;
+    tokio::fs::rename(&part, target).await?;
+    Ok(())
+}
@@ -0,0 +1,231 @@
+use anyhow::{Result, anyhow, bail};
+use std::collections::HashMap;
+use std::time::Duration;
+use tokio::net::UdpSocket;
+use tokio::time::timeout;
+
+// The GameSpy4 query protocol servers answer on query.port when enable-query is on. Unlike the
+// ping, it lists every player and the plugins the server reports
+
+const QUERY_TIMEOUT: Duration = Duration::from_secs(2);
+const MAGIC: [u8; 2] = [0xFE, 0xFD];
+const HANDSHAKE: u8 = 0x09;
+const STAT: u8 = 0x00;
+// Only the low 4 bits of each byte are read back by the server
+const SESSION_ID: [u8; 4] = [0x01, 0x02, 0x03, 0x04];
+const KEYS_PADDING: &[u8] = b"splitnum\0\x80\0";
+const PLAYERS_PADDING: &[u8] = b"\x01player_\0\0";
+
+#[derive(Debug, Default, Clone)]
+pub struct FullStat {
+    pub values: HashMap<String, String>,
+    pub players: Vec<String>,
+}
+
+impl FullStat {
+    pub fn map(&self) -> Option<String> {
+        self.values
+            .get("map")
+            .filter(|map| !map.is_empty())
+            .cloned()
+    }
+
+    // "Paper on Bukkit 1.21.1: WorldEdit 7.3.4; LuckPerms 5.4" names the software before the
+    // colon and the plugins after it. Vanilla leaves the whole value empty
+    pub fn software(&self) -> Option<String> {
+        let plugins = self.values.get("plugins")?;
+        let software = plugins.split(':').next()?.trim();
+        (!software.is_empty()).then(|| software.to_string())
+    }
+
+    pub fn plugins(&self) -> Option<Vec<String>> {
+        let (_, plugins) = self.values.get("plugins")?.split_once(':')?;
+        let plugins: Vec<String> = plugins
+            .split(';')
+            .map(|plugin| plugin.trim().to_string())
+            .filter(|plugin| !plugin.is_empty())
+            .collect();
+        (!plugins.is_empty()).then_some(plugins)
+    }
+}
+
+pub async fn full_stat(port: u16) -> Result<FullStat> {
+    let socket = UdpSocket::bind("127.0.0.1:0").await?;
+    socket.connect(("127.0.0.1", port)).await?;
+    timeout(QUERY_TIMEOUT, async {
+        let challenge = handshake(&socket).await?;
+        let mut request = packet(STAT);
+        request.extend_from_slice(&challenge.to_be_bytes());
+        // Padding asks for the full stat instead of the basic one
+        request.extend_from_slice(&[0; 4]);
+        socket.send(&request).await?;
+        let response = receive(&socket, STAT).await?;
+        parse_full_stat(&response)
+    })
+    .await
+    .map_err(|_| anyhow!("Query timed out on port {}", port))?
+}
+
+async fn handshake(socket: &UdpSocket) -> Result<i32> {
+    socket.send(&packet(HANDSHAKE)).await?;
+    let response = receive(socket, HANDSHAKE).await?;
+    // The challenge token comes back as a null terminated decimal string
+    let token = response.split(|byte| *byte == 0).next().unwrap_or_default();
+    Ok(std::str::from_utf8(token)?.trim().parse()?)
+}
+
+fn packet(kind: u8) -> Vec<u8> {
+    let mut packet = MAGIC.to_vec();
+    packet.push(kind);
+    packet.extend_from_slice(&SESSION_ID);
+    packet
+}
+
+// Returns the payload after the type and session id
+async fn receive(socket: &UdpSocket, kind: u8) -> Result<Vec<u8>> {
+    let mut buffer = vec![0; 8192];
+    let length = socket.recv(&mut buffer).await?;
+    buffer.truncate(length);
+    if buffer.len() < 5 || buffer[0] != kind || buffer[1..5] != SESSION_ID {
+        bail!("Unexpected query response");
+    }
+    Ok(buffer.split_off(5))
+}
+
+fn parse_full_stat(payload: &[u8]) -> Result<FullStat> {
+    let payload = payload
+        .strip_prefix(KEYS_PADDING)
+        .ok_or_else(|| anyhow!("Malformed full stat response"))?;
+    let mut strings = payload.split(|byte| *byte == 0);
+    let mut stat = FullStat::default();
+    loop {
+        let key = strings
+            .next()
+            .ok_or_else(|| anyhow!("Full stat response ended early"))?;
+        if key.is_empty() {
+            break;
+        }
+        let value = strings.next().unwrap_or_default();
+        stat.values.insert(
+            String::from_utf8_lossy(key).into_owned(),
+            String::from_utf8_lossy(value).into_owned(),
+        );
+    }
+
+    // The player section starts after the key list's closing null
+    let keys_end = payload
+        .windows(PLAYERS_PADDING.len())
+        .position(|window| window == PLAYERS_PADDING)
+        .ok_or_else(|| anyhow!("Full stat response has no player section"))?;
+    stat.players = payload[keys_end + PLAYERS_PADDING.len()..]
+        .split(|byte| *byte == 0)
+        .take_while(|name| !name.is_empty())
+        .map(|name| String::from_utf8_lossy(name).into_owned())
+        .collect();
+    Ok(stat)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The key values and players sent, and the software and plugins read back from them
+    type Case = (
+        &'static [(&'static str, &'static str)],
+        &'static [&'static str],
+        Option<&'static str>,
+        Option<&'static [&'static str]>,
+    );
+
+    // Builds a response payload the way servers send it
+    fn payload(values: &[(&str, &str)], players: &[&str]) -> Vec<u8> {
+        let mut payload = KEYS_PADDING.to_vec();
+        for (key, value) in values {
+            payload.extend_from_slice(key.as_bytes());
+            payload.push(0);
+            payload.extend_from_slice(value.as_bytes());
+            payload.push(0);
+        }
+        payload.push(0);
+        payload.extend_from_slice(PLAYERS_PADDING);
+        for player in players {
+            payload.extend_from_slice(player.as_bytes());
+            payload.push(0);
+        }
+        payload.push(0);
+        payload
+    }
+
+    #[test]
+    fn parses_full_stats() {
+        let cases: &[Case] = &[
+            (
+                &[
+                    ("hostname", "A Minecraft Server"),
+                    ("map", "world"),
+                    ("plugins", ""),
+                ],
+                &["Steve", "Alex"],
+                None,
+                None,
+            ),
+            (
+                &[
+                    ("map", "world"),
+                    (
+                        "plugins",
+                        "Paper on Bukkit 1.21.1: WorldEdit 7.3.4; LuckPerms 5.4",
+                    ),
+                ],
+                &[],
+                Some("Paper on Bukkit 1.21.1"),
+                Some(&["WorldEdit 7.3.4", "LuckPerms 5.4"]),
+            ),
+            (
+                &[("plugins", "Paper on Bukkit 1.21.1: ")],
+                &["Steve"],
+                Some("Paper on Bukkit 1.21.1"),
+                None,
+            ),
+            (&[], &[], None, None),
+        ];
+        for (values, players, software, plugins) in cases {
+            let stat = parse_full_stat(&payload(values, players)).unwrap();
+            for (key, value) in *values {
+                assert_eq!(stat.values.get(*key).map(String::as_str), Some(*value));
+            }
+            assert_eq!(stat.values.len(), values.len());
+            assert_eq!(stat.players, *players);
+            assert_eq!(stat.software().as_deref(), *software, "{values:?}");
+            assert_eq!(
+                stat.plugins(),
+                plugins.map(|plugins| plugins.iter().map(|p| p.to_string()).collect()),
+                "{values:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn maps() {
+        let stat = parse_full_stat(&payload(&[("map", "")], &[])).unwrap();
+        assert_eq!(stat.map(), None);
+        let stat = parse_full_stat(&payload(&[("map", "world")], &[])).unwrap();
+        assert_eq!(stat.map().as_deref(), Some("world"));
+    }
+
+    #[test]
+    fn rejects_malformed_stats() {
+        let full = payload(&[("map", "world")], &["Steve"]);
+        let cases: &[&[u8]] = &[
+            b"",
+            b"map\0world\0\0",
+            // Cut off in the key list
+            &full[..KEYS_PADDING.len() + 4],
+            // Cut off before the player section
+            &full[..full.len() - PLAYERS_PADDING.len() - 7],
+        ];
+        for payload in cases {
+            assert!(parse_full_stat(payload).is_err(), "{payload:?}");
+        }
+    }
+}
@@ -0,0 +1,49 @@
+use crate::mods::configs::Configs;
+use crate::mods::server_process::ProcessHandle;
+use anyhow::{Result, anyhow};
+use std::time::Duration;
+use tokio::sync::mpsc::UnboundedSender;
+use tokio::sync::{oneshot, watch};
+use tokio::time::timeout;
+
+const STOP_TIMEOUT: Duration = Duration::from_secs(120);
+
+// Background jobs (upgrades, installs) can't borrow the ServerHandler while the listener is
+// running, so they ask the listener to act on their behalf through these requests
+pub enum HandlerRequest {
+    // Replies with the exit watcher of the process that was stopped, if there was one
+    Stop(oneshot::Sender<Result<Option<watch::Receiver<bool>>>>),
+    Start(oneshot::Sender<Result<ProcessHandle>>),
+    EditConfig(oneshot::Sender<Result<()>>, Configs),
+    // server.properties changed on disk
+    PropertiesChanged,
+}
+
+// Stops the server and waits for the process to exit. Returns whether it was running
+pub async fn stop(requests: &UnboundedSender<HandlerRequest>) -> Result<bool> {
+    let (sender, receiver) = oneshot::channel();
+    requests.send(HandlerRequest::Stop(sender))?;
+    let Some(mut exited) = receiver.await?? else {
+        return Ok(false);
+    };
+    let was_running = !*exited.borrow();
+    timeout(STOP_TIMEOUT, exited.wait_for(|exited| *exited))
+        .await
+        .map_err(|_| anyhow!("Server did not stop in time"))??;
+    Ok(was_running)
+}
+
+pub async fn start(requests: &UnboundedSender<HandlerRequest>) -> Result<ProcessHandle> {
+    let (sender, receiver) = oneshot::channel();
+    requests.send(HandlerRequest::Start(sender))?;
+    receiver.await?
+}
+
+pub async fn edit_config(
+    requests: &UnboundedSender<HandlerRequest>,
+    config: Configs,
+) -> Result<()> {
+    let (sender, receiver) = oneshot::channel();
+    requests.send(HandlerRequest::EditConfig(sender, config))?;
+    receiver.await?
+}
@@ -0,0 +1,482 @@
+use crate::mods::version_range::{at_least, matches_maven_range, matches_predicate};
+use anyhow::{Result, anyhow};
+use protocol::inventory::{InstalledMod, Inventory, Loader};
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::io::{Cursor, Read, Seek};
+use std::path::Path;
+use zip::ZipArchive;
+
+pub const FOLDERS: [&str; 2] = ["mods", "plugins"];
+pub const DISABLED_SUFFIX: &str = ".disabled";
+
+// Ids that are always present and never show up as a jar
+const BUILT_IN: [&str; 8] = [
+    "minecraft",
+    "java",
+    "fabricloader",
+    "quilt_loader",
+    "forge",
+    "neoforge",
+    "bukkit",
+    "spigot",
+];
+
+struct Metadata {
+    loader: Loader,
+    id: String,
+    name: String,
+    version: String,
+    dependencies: Vec<String>,
+    // Any of these has to match the server's version
+    minecraft: Vec<String>,
+    // Ids other jars can depend on, including mods bundled inside this jar
+    provides: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct FabricMod {
+    id: String,
+    version: String,
+    name: Option<String>,
+    #[serde(default)]
+    depends: HashMap<String, Value>,
+    #[serde(default)]
+    provides: Vec<String>,
+    #[serde(default)]
+    jars: Vec<FabricJar>,
+}
+
+#[derive(Deserialize)]
+struct FabricJar {
+    file: String,
+}
+
+#[derive(Deserialize)]
+struct QuiltMod {
+    quilt_loader: QuiltLoader,
+}
+
+#[derive(Deserialize)]
+struct QuiltLoader {
+    id: String,
+    version: String,
+    metadata: Option<QuiltMetadata>,
+    #[serde(default)]
+    depends: Vec<Value>,
+    #[serde(default)]
+    provides: Vec<Value>,
+    #[serde(default)]
+    jars: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct QuiltMetadata {
+    name: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ForgeMods {
+    mods: Vec<ForgeMod>,
+    #[serde(default)]
+    dependencies: HashMap<String, Vec<ForgeDependency>>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ForgeMod {
+    mod_id: String,
+    version: Option<String>,
+    display_name: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ForgeDependency {
+    mod_id: String,
+    mandatory: Option<bool>,
+    // Replaced mandatory in newer Forge and NeoForge versions
+    #[serde(rename = "type")]
+    kind: Option<String>,
+    version_range: Option<String>,
+    side: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct BukkitPlugin {
+    name: String,
+    version: Value,
+    #[serde(default)]
+    depend: Vec<String>,
+    #[serde(default)]
+    provides: Vec<String>,
+    api_version: Option<Value>,
+}
+
+// Reads the metadata of every jar in mods/ and plugins/ and flags anything that won't load
+pub fn scan(dir: &Path, minecraft_version: Option<String>) -> Inventory {
+    let mut mods = Vec::new();
+    for folder in FOLDERS {
+        let Ok(entries) = std::fs::read_dir(dir.join(folder)) else {
+            continue;
+        };
+        let mut found: Vec<(InstalledMod, Option<Metadata>)> = entries
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| {
+                let file_name = entry.file_name().to_string_lossy().to_string();
+                let enabled = file_name.ends_with(".jar");
+                if !enabled && !file_name.ends_with(&format!(".jar{}", DISABLED_SUFFIX)) {
+                    return None;
+                }
+                let metadata = match read_jar(&entry.path()) {
+                    Ok(metadata) => metadata,
+                    Err(e) => {
+                        tracing::warn!("Failed to read {}: {}", file_name, e);
+                        None
+                    }
+                };
+                Some((
+                    installed(folder, file_name, enabled, metadata.as_ref()),
+                    metadata,
+                ))
+            })
+            .collect();
+
+        // Disabled jars aren't loaded, so they can't satisfy dependencies
+        let provided: HashSet<String> = found
+            .iter()
+            .filter(|(installed, _)| installed.enabled)
+            .filter_map(|(_, metadata)| metadata.as_ref())
+            .flat_map(|metadata| metadata.provides.iter().cloned())
+            .collect();
+        for (installed, metadata) in found.iter_mut() {
+            if let Some(metadata) = metadata
+                && installed.enabled
+            {
+                installed.problems = problems(metadata, &provided, minecraft_version.as_deref());
+            }
+        }
+        found.sort_by_key(|(installed, _)| installed.name.to_lowercase());
+        mods.extend(found.into_iter().map(|(installed, _)| installed));
+    }
+    Inventory {
+        minecraft_version,
+        mods,
+    }
+}
+
+fn installed(
+    folder: &str,
+    file_name: String,
+    enabled: bool,
+    metadata: Option<&Metadata>,
+) -> InstalledMod {
+    match metadata {
+        Some(metadata) => InstalledMod {
+            folder: folder.to_string(),
+            file_name,
+            enabled,
+            loader: Some(metadata.loader),
+            id: metadata.id.clone(),
+            name: metadata.name.clone(),
+            version: metadata.version.clone(),
+            dependencies: metadata.dependencies.clone(),
+            minecraft: (!metadata.minecraft.is_empty()).then(|| metadata.minecraft.join(" || ")),
+            problems: Vec::new(),
+        },
+        None => {
+            let stem = file_name
+                .trim_end_matches(DISABLED_SUFFIX)
+                .trim_end_matches(".jar")
+                .to_string();
+            InstalledMod {
+                folder: folder.to_string(),
+                file_name,
+                enabled,
+                loader: None,
+                id: stem.clone(),
+                name: stem,
+                version: "unknown".to_string(),
+                dependencies: Vec::new(),
+                minecraft: None,
+                problems: Vec::new(),
+            }
+        }
+    }
+}
+
+fn problems(
+    metadata: &Metadata,
+    provided: &HashSet<String>,
+    minecraft_version: Option<&str>,
+) -> Vec<String> {
+    let mut problems: Vec<String> = metadata
+        .dependencies
+        .iter()
+        .filter(|dependency| !provided.contains(*dependency))
+        .map(|dependency| format!("requires {}, which is not installed", dependency))
+        .collect();
+
+    if let Some(version) = minecraft_version
+        && !metadata.minecraft.is_empty()
+    {
+        let results: Vec<Option<bool>> = metadata
+            .minecraft
+            .iter()
+            .map(|range| match metadata.loader {
+                Loader::Fabric | Loader::Quilt => matches_predicate(range, version),
+                Loader::Forge => matches_maven_range(range, version),
+                Loader::Bukkit => at_least(range, version),
+            })
+            .collect();
+        // Only complain when every range was understood and none of them matched
+        if results.iter().all(|result| *result == Some(false)) {
+            problems.push(format!(
+                "made for Minecraft {}, the server runs {}",
+                metadata.minecraft.join(" || "),
+                version
+            ));
+        }
+    }
+    problems
+}
+
+fn read_jar(path: &Path) -> Result<Option<Metadata>> {
+    let mut archive = ZipArchive::new(std::fs::File::open(path)?)?;
+    read_archive(&mut archive)
+}
+
+fn read_archive<R: Read + Seek>(archive: &mut ZipArchive<R>) -> Result<Option<Metadata>> {
+    if let Some(text) = read_entry(archive, "quilt.mod.json")? {
+        return Ok(Some(quilt(archive, &text)?));
+    }
+    if let Some(text) = read_entry(archive, "fabric.mod.json")? {
+        return Ok(Some(fabric(archive, &text)?));
+    }
+    for name in ["META-INF/neoforge.mods.toml", "META-INF/mods.toml"] {
+        if let Some(text) = read_entry(archive, name)? {
+            return Ok(Some(forge(archive, &text)?));
+        }
+    }
+    for name in ["paper-plugin.yml", "plugin.yml"] {
+        if let Some(text) = read_entry(archive, name)? {
+            return Ok(Some(bukkit(&text)?));
+        }
+    }
+    Ok(None)
+}
+
+fn read_entry<R: Read + Seek>(archive: &mut ZipArchive<R>, name: &str) -> Result<Option<String>> {
+    let mut entry = match archive.by_name(name) {
+        Ok(entry) => entry,
+        Err(zip::result::ZipError::FileNotFound) => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    let mut text = String::new();
+    entry.read_to_string(&mut text)?;
+    Ok(Some(text))
+}
+
+// Fabric and Quilt mods bundle libraries and other mods as jars inside their own jar
+fn nested_ids<R: Read + Seek>(archive: &mut ZipArchive<R>, paths: &[String]) -> Vec<String> {
+    let mut ids = Vec::new();
+    for path in paths {
+        let nested = archive
+            .by_name(path)
+            .map_err(anyhow::Error::from)
+            .and_then(|mut entry| {
+                let mut bytes = Vec::new();
+                entry.read_to_end(&mut bytes)?;
+                read_archive(&mut ZipArchive::new(Cursor::new(bytes))?)
+            });
+        match nested {
+            Ok(Some(metadata)) => ids.extend(metadata.provides),
+            Ok(None) => {}
+            Err(e) => tracing::debug!("Failed to read nested jar {}: {}", path, e),
+        }
+    }
+    ids
+}
+
+fn fabric<R: Read + Seek>(archive: &mut ZipArchive<R>, text: &str) -> Result<Metadata> {
+    let fabric: FabricMod = serde_json::from_str(text)?;
+    let mut dependencies = Vec::new();
+    let mut minecraft = Vec::new();
+    for (id, versions) in fabric.depends {
+        if id == "minecraft" {
+            minecraft = string_or_list(&versions);
+        } else if !BUILT_IN.contains(&id.as_str()) {
+            dependencies.push(id);
+        }
+    }
+    dependencies.sort();
+
+    let jars: Vec<String> = fabric.jars.into_iter().map(|jar| jar.file).collect();
+    let mut provides = vec![fabric.id.clone()];
+    provides.extend(fabric.provides);
+    provides.extend(nested_ids(archive, &jars));
+    Ok(Metadata {
+        loader: Loader::Fabric,
+        name: fabric.name.unwrap_or_else(|| fabric.id.clone()),
+        id: fabric.id,
+        version: fabric.version,
+        dependencies,
+        minecraft,
+        provides,
+    })
+}
+
+fn quilt<R: Read + Seek>(archive: &mut ZipArchive<R>, text: &str) -> Result<Metadata> {
+    let quilt: QuiltMod = serde_json::from_str(text)?;
+    let quilt = quilt.quilt_loader;
+    let mut dependencies = Vec::new();
+    let mut minecraft = Vec::new();
+    for dependency in quilt.depends.iter() {
+        // Either a plain id or an object with an id, version range and optional flag
+        let (id, versions) = match dependency {
+            Value::String(id) => (id.as_str(), None),
+            Value::Object(object) => {
+                if object.get("optional").and_then(Value::as_bool) == Some(true) {
+                    continue;
+                }
+                let Some(id) = object.get("id").and_then(Value::as_str) else {
+                    continue;
+                };
+                (id, object.get("versions"))
+            }
+            _ => continue,
+        };
+        if id == "minecraft" {
+            minecraft = versions.map(string_or_list).unwrap_or_default();
+        } else if !BUILT_IN.contains(&id) {
+            dependencies.push(id.to_string());
+        }
+    }
+
+    let mut provides = vec![quilt.id.clone()];
+    provides.extend(quilt.provides.iter().filter_map(|provided| match provided {
+        Value::String(id) => Some(id.clone()),
+        Value::Object(object) => object.get("id").and_then(Value::as_str).map(str::to_string),
+        _ => None,
+    }));
+    provides.extend(nested_ids(archive, &quilt.jars));
+    Ok(Metadata {
+        loader: Loader::Quilt,
+        name: quilt
+            .metadata
+            .and_then(|metadata| metadata.name)
+            .unwrap_or_else(|| quilt.id.clone()),
+        id: quilt.id,
+        version: quilt.version,
+        dependencies,
+        minecraft,
+        provides,
+    })
+}
+
+fn forge<R: Read + Seek>(archive: &mut ZipArchive<R>, text: &str) -> Result<Metadata> {
+    let forge: ForgeMods = toml::from_str(text)?;
+    let first = forge
+        .mods
+        .first()
+        .ok_or_else(|| anyhow!("mods.toml does not declare any mods"))?;
+
+    let mut dependencies = Vec::new();
+    let mut minecraft = Vec::new();
+    for dependency in forge.dependencies.values().flatten() {
+        let required = dependency.mandatory.unwrap_or(false)
+            || dependency
+                .kind
+                .as_deref()
+                .is_some_and(|kind| kind.eq_ignore_ascii_case("required"));
+        let client_only = dependency
+            .side
+            .as_deref()
+            .is_some_and(|side| side.eq_ignore_ascii_case("client"));
+        if !required || client_only {
+            continue;
+        }
+        if dependency.mod_id == "minecraft" {
+            minecraft.extend(dependency.version_range.clone());
+        } else if !BUILT_IN.contains(&dependency.mod_id.as_str())
+            && !dependencies.contains(&dependency.mod_id)
+        {
+            dependencies.push(dependency.mod_id.clone());
+        }
+    }
+
+    // Most mods let the build fill the version into the manifest
+    let mut version = first.version.clone().unwrap_or_default();
+    if version.is_empty() || version.contains("${") {
+        version = read_entry(archive, "META-INF/MANIFEST.MF")?
+            .and_then(|manifest| {
+                manifest.lines().find_map(|line| {
+                    line.strip_prefix("Implementation-Version:")
+                        .map(|version| version.trim().to_string())
+                })
+            })
+            .unwrap_or_else(|| "unknown".to_string());
+    }
+
+    Ok(Metadata {
+        loader: Loader::Forge,
+        id: first.mod_id.clone(),
+        name: first
+            .display_name
+            .clone()
+            .unwrap_or_else(|| first.mod_id.clone()),
+        version,
+        dependencies,
+        minecraft,
+        provides: forge
+            .mods
+            .iter()
+            .map(|forge| forge.mod_id.clone())
+            .collect(),
+    })
+}
+
+fn bukkit(text: &str) -> Result<Metadata> {
+    let plugin: BukkitPlugin = serde_yaml::from_str(text)?;
+    let mut provides = vec![plugin.name.clone()];
+    provides.extend(plugin.provides);
+    Ok(Metadata {
+        loader: Loader::Bukkit,
+        id: plugin.name.clone(),
+        name: plugin.name,
+        version: yaml_string(&plugin.version),
+        dependencies: plugin.depend,
+        minecraft: plugin.api_version.iter().map(yaml_string).collect(),
+        provides,
+    })
+}
+
+fn string_or_list(value: &Value) -> Vec<String> {
+    match value {
+        Value::String(version) => vec![version.clone()],
+        Value::Array(versions) => versions
+            .iter()
+            .filter_map(Value::as_str)
+            .map(str::to_string)
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+// YAML reads unquoted versions like 1.20 as numbers
+fn yaml_string(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        value => value.to_string(),
+    }
+}
+
+// Vanilla and Paper jars carry their Minecraft version in version.json
+pub fn jar_version(jar: &Path) -> Option<String> {
+    let mut archive = ZipArchive::new(std::fs::File::open(jar).ok()?).ok()?;
+    let text = read_entry(&mut archive, "version.json").ok()??;
+    let version: Value = serde_json::from_str(&text).ok()?;
+    version.get("id")?.as_str().map(str::to_string)
+}
@@ -2,6 +2,8 @@ use crate::gui::{
     gui_actions::{ConfigRequest, EditRequestReturn},
     tui::GuiEvents,
 };
+use crate::mods::handler_request::HandlerRequest;
+use crate::mods::{config_files, files};
 use futures_util::Stream;
 use futures_util::{
     sink::{Sink, SinkExt},
@@ -14,10 +16,11 @@ use tokio_tungstenite::tungstenite::Error;
 use tokio_tungstenite::tungstenite::protocol::Message;
 
 use anyhow::{Result, anyhow};
-use protocol::properties::Property;
+use protocol::tasks::Progress;
 
 use crate::mods::server_handler::ServerHandler;
 
+#[allow(clippy::too_many_arguments)]
 pub async fn listen<R, S>(
     mut ws_receiver: R,                            //Websocket receiver
     mut ws_sender: S,                              //Websocket sender
@@ -25,8 +28,9 @@ pub async fn listen<R, S>(
     handler: &mut ServerHandler,
     agent_from_tui: &mut UnboundedReceiver<ConfigRequest>, // Agent receives requests from TUI here
     agent_to_tui: UnboundedSender<GuiEvents>,              // Agent should send to TUI from here
-    forward_receiver: &mut UnboundedReceiver<ServerActions>, // Agent sends server requests here to
-                                                           // be sent over websocket
+    handler_requests: &mut UnboundedReceiver<HandlerRequest>, // Background jobs ask for handler actions here
+    forward_receiver: &mut UnboundedReceiver<ServerActions>,  // Agent sends server requests here to
+                                                              // be sent over websocket
 ) -> Result<()>
 where
     R: Stream<Item = Result<Message, Error>> + Unpin,
@@ -61,6 +65,13 @@ where
 
             }
 
+            next_request = handler_requests.recv() => {
+                if let Some(request) = next_request
+                    && let Err(e) = handler_request(handler, request, &server_sender, &agent_to_tui).await {
+                        tracing::error!("Error handling handler request: {}", e);
+                }
+            }
+
             message = forward_receiver.recv() => {
                 tracing::debug!("Sending server action");
                 ws_sender
@@ -94,141 +105,71 @@ async fn websocket_action(
         }
         AgentActions::SvStart(id) => {
             tracing::info!("Starting server");
-            if handler.start_server(sender.clone()).is_ok() {
-                sender.send(ServerActions::StartResponse(id))?;
-                agent_to_tui.send(GuiEvents::ServerStarted)?;
+            match handler.start_server(sender.clone()) {
+                Ok(()) => {
+                    sender.send(ServerActions::StartResponse(id))?;
+                    agent_to_tui.send(GuiEvents::ServerStarted)?;
+                }
+                Err(e) => sender.send(ServerActions::RequestError(id, e.to_string()))?,
             }
         }
-        AgentActions::SvStop(id) => {
-            if handler.stop_server().await.is_ok() {
+        AgentActions::SvStop(id) => match handler.stop_server().await {
+            Ok(()) => {
                 sender.send(ServerActions::StopResponse(id))?;
                 agent_to_tui.send(GuiEvents::ServerStopped)?;
             }
-        }
-        AgentActions::StartQuery(request_id, options) => {
-            tracing::info!("Received query");
+            Err(e) => sender.send(ServerActions::RequestError(id, e.to_string()))?,
+        },
+        AgentActions::StartQuery(request_id, monitor, options, interval) => {
+            tracing::info!("Received query for monitor {}", monitor);
             if let Err(e) = handler
-                .start_query(options, sender.clone(), request_id)
+                .start_query(monitor, options, interval, sender.clone(), request_id)
                 .await
             {
                 tracing::error!("Error starting query handling: {}", e);
             }
         }
-        AgentActions::StopQuery => {
-            handler.stop_query();
+        AgentActions::StopQuery(monitor) => {
+            handler.stop_query(monitor);
+        }
+        AgentActions::RequestStatus(request_id) => {
+            if let Err(e) = handler.request_status(sender.clone(), request_id).await {
+                tracing::error!("Error answering status request: {}", e);
+            }
         }
         AgentActions::RequestProps(request_id) => {
             tracing::info!("Received request_props action with ID: {}", request_id);
-            let props = handler
+            handler
                 .update_properties()
                 .properties
                 .as_ref()
                 .ok_or_else(|| anyhow!("No properties in the process"))?;
-            props.send_response(sender.clone(), request_id).await?;
+            handler
+                .send_properties_response(sender.clone(), request_id)
+                .await?;
             tracing::info!("Properties response sent successfully");
         }
-        AgentActions::EditProp(request_id, prop) => {
-            match prop {
-                Property::AllowFlight => {
-                    let current = handler.get_property("allow-flight")?.parse::<bool>()?;
-
-                    handler.set("allow-flight", &(!current).to_string())?;
-                    tracing::info!("Properties response sent successfully");
-                }
-                Property::Difficulty => {
-                    let current = handler.get_property("difficulty")?;
-                    let new_difficulty = match current {
-                        "peaceful" => "easy",
-                        "easy" => "normal",
-                        "normal" => "hard",
-                        "hard" => "peaceful",
-                        _ => "easy",
-                    };
-                    handler.set("difficulty", new_difficulty)?;
-                }
-                Property::Hardcore => {
-                    let current = handler.get_property("hardcore")?.parse::<bool>()?;
-
-                    handler.set("hardcore", &(!current).to_string())?;
-                    tracing::info!("Properties response sent successfully");
-                }
-                Property::Whitelist => {
-                    let current = handler.get_property("white-list")?.parse::<bool>()?;
-
-                    handler.set("white-list", &(!current).to_string())?;
-                    tracing::info!("Properties response sent successfully");
-                }
-                Property::PVP => {
-                    let current = handler.get_property("pvp")?.parse::<bool>()?;
-
-                    handler.set("pvp", &(!current).to_string())?;
-                    tracing::info!("Properties response sent successfully");
-                }
-                Property::GenerateStructures => {
-                    let current = handler
-                        .get_property("generate-structures")?
-                        .parse::<bool>()?;
-
-                    handler.set("generate-structures", &(!current).to_string())?;
-                    tracing::info!("Properties response sent successfully");
-                }
-                Property::Gamemode => {
-                    let current = handler.get_property("gamemode")?;
-                    let new_gamemode = match current {
-                        "survival" => "creative",
-                        "creative" => "adventure",
-                        "adventure" => "spectator",
-                        "spectator" => "survival",
-                        _ => "survival",
-                    };
-                    handler.set("gamemode", new_gamemode)?;
-                }
-                Property::MOTD(data) => {
-                    handler.set("motd", &data)?;
-                }
-                Property::MaxPlayers(data) => {
-                    handler.set("max-players", &data.to_string())?;
-                }
-                Property::MaxWorldSize(data) => {
-                    handler.set("max-world-size", &data.to_string())?;
-                }
-                Property::AllowNether => {
-                    let current = handler.get_property("allow-nether")?.parse::<bool>()?;
-
-                    handler.set("allow-nether", &(!current).to_string())?;
-                    tracing::info!("Properties response sent successfully");
-                }
-                Property::SpawnNPC => {
-                    let current = handler.get_property("spawn-npcs")?.parse::<bool>()?;
-
-                    handler.set("spawn-npcs", &(!current).to_string())?;
-                    tracing::info!("Properties response sent successfully");
-                }
-                Property::SpawnAnimals => {
-                    let current = handler.get_property("spawn-animals")?.parse::<bool>()?;
-
-                    handler.set("spawn-animals", &(!current).to_string())?;
-                    tracing::info!("Properties response sent successfully");
-                }
-                Property::SpawnMonsters => {
-                    let current = handler.get_property("spawn-monsters")?.parse::<bool>()?;
-
-                    handler.set("spawn-monsters", &(!current).to_string())?;
-                    tracing::info!("Properties response sent successfully");
+        AgentActions::SetProperty(request_id, key, value) => {
+            tracing::info!("Setting property {}", key);
+            match handler.set_property(&key, &value) {
+                Ok(()) => {
+                    handler
+                        .send_properties_response(sender.clone(), request_id)
+                        .await?
                 }
-                Property::ViewDistance(data) => {
-                    handler.set("view-distance", &data.to_string())?;
-                }
-                Property::SimulationDistance(data) => {
-                    handler.set("simulation-distance", &data.to_string())?;
-                }
-                Property::SpawnProtection(data) => {
-                    handler.set("spawn-protection", &data.to_string())?;
+                Err(e) => sender.send(ServerActions::PropsError(request_id, e.to_string()))?,
+            }
+        }
+        AgentActions::SetProperties(request_id, changes) => {
+            tracing::info!("Setting {} properties", changes.len());
+            match handler.set_properties(changes) {
+                Ok(()) => {
+                    handler
+                        .send_properties_response(sender.clone(), request_id)
+                        .await?
                 }
+                Err(e) => sender.send(ServerActions::PropsError(request_id, e.to_string()))?,
             }
-            handler
-                .send_properties_response(sender.clone(), request_id)
-                .await?;
         }
         AgentActions::StartChatStream(uuid) => {
             handler.start_chat()?;
@@ -251,6 +192,90 @@ async fn websocket_action(
             handler.send_command(command)?;
             sender.send(ServerActions::SendCommandResponse(id))?;
         }
+        AgentActions::Upgrade(id, request) => {
+            tracing::info!("Upgrading server to {}", request.file_name);
+            if let Err(e) = handler.start_upgrade(id, request, sender.clone()) {
+                sender.send(ServerActions::Progress(id, Progress::Failed(e.to_string())))?;
+            }
+        }
+        AgentActions::ListMods(id) => match handler.mod_inventory().await {
+            Ok(inventory) => sender.send(ServerActions::ModsResponse(id, inventory))?,
+            Err(e) => sender.send(ServerActions::RequestError(id, e.to_string()))?,
+        },
+        AgentActions::ChangeMod(id, change) => {
+            tracing::info!("Changing mods: {:?}", change);
+            if let Err(e) = handler.start_mod_change(id, change, sender.clone()) {
+                sender.send(ServerActions::Progress(id, Progress::Failed(e.to_string())))?;
+            }
+        }
+        AgentActions::Restart(id) => {
+            tracing::info!("Restarting server");
+            if let Err(e) = handler.start_restart(id, sender.clone()) {
+                sender.send(ServerActions::Progress(id, Progress::Failed(e.to_string())))?;
+            }
+        }
+        AgentActions::Files(id, request) => {
+            tokio::spawn(files::run(id, handler.config(), request, sender.clone()));
+        }
+        AgentActions::ConfigFile(id, request) => {
+            tokio::spawn(config_files::run(
+                id,
+                handler.config(),
+                request,
+                sender.clone(),
+            ));
+        }
+        AgentActions::InstallModpack(id, pack) => {
+            tracing::info!("Installing modpack {}", pack.file_name);
+            if let Err(e) = handler.start_modpack_install(id, pack, sender.clone()) {
+                sender.send(ServerActions::Progress(id, Progress::Failed(e.to_string())))?;
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn handler_request(
+    handler: &mut ServerHandler,
+    request: HandlerRequest,
+    sender: &UnboundedSender<ServerActions>,
+    agent_to_tui: &UnboundedSender<GuiEvents>,
+) -> Result<()> {
+    match request {
+        HandlerRequest::Stop(reply) => {
+            let exited = handler.process_handle().map(|handle| handle.exited);
+            let result = handler.stop_process().await.map(|_| exited);
+            if result.is_ok() {
+                agent_to_tui.send(GuiEvents::ServerStopped)?;
+            }
+            reply
+                .send(result)
+                .map_err(|_| anyhow!("failed to send stop response"))?;
+        }
+        HandlerRequest::Start(reply) => {
+            let result = handler.start_process(sender.clone()).and_then(|_| {
+                handler
+                    .process_handle()
+                    .ok_or_else(|| anyhow!("Server process missing after start"))
+            });
+            if result.is_ok() {
+                agent_to_tui.send(GuiEvents::ServerStarted)?;
+            }
+            reply
+                .send(result)
+                .map_err(|_| anyhow!("failed to send start response"))?;
+        }
+        HandlerRequest::EditConfig(reply, config) => {
+            reply
+                .send(handler.edit_config(config))
+                .map_err(|_| anyhow!("failed to send config response"))?;
+        }
+        HandlerRequest::PropertiesChanged => {
+            if handler.reload_properties() {
+                tracing::info!("server.properties changed on disk");
+                handler.push_properties(sender)?;
+            }
+        }
     }
     Ok(())
 }
@@ -1,8 +1,22 @@
+pub mod config_files;
 pub mod configs;
 pub mod connect;
+pub mod download;
+pub mod files;
+pub mod full_query;
+pub mod handler_request;
+pub mod inventory;
 pub mod listener;
+pub mod mod_files;
+pub mod modpack;
+pub mod player_events;
+pub mod properties_watcher;
 pub mod query_handler;
+pub mod restart;
+pub mod sandbox;
 pub mod server_handler;
 pub mod server_process;
 pub mod server_properties;
 pub mod stdout_writer;
+pub mod upgrade;
+pub mod version_range;
@@ -0,0 +1,105 @@
+use crate::mods::download::download;
+use crate::mods::inventory::{DISABLED_SUFFIX, FOLDERS};
+use crate::mods::sandbox::resolve;
+use anyhow::{Result, bail};
+use protocol::inventory::ModChange;
+use protocol::serveractions::ServerActions;
+use protocol::tasks::{Progress, RemoteFile};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tokio::sync::mpsc::UnboundedSender;
+use uuid::Uuid;
+
+pub async fn run(
+    request_id: Uuid,
+    dir: PathBuf,
+    change: ModChange,
+    sender: UnboundedSender<ServerActions>,
+    task_running: Arc<AtomicBool>,
+) {
+    let progress = match apply(&dir, change).await {
+        Ok(message) => Progress::Finished(format!(
+            "{}. Restart the server to apply the change",
+            message
+        )),
+        Err(e) => Progress::Failed(e.to_string()),
+    };
+    if let Err(e) = sender.send(ServerActions::Progress(request_id, progress)) {
+        tracing::error!("Failed to report mod change: {}", e);
+    }
+    task_running.store(false, Ordering::SeqCst);
+}
+
+async fn apply(dir: &Path, change: ModChange) -> Result<String> {
+    match change {
+        ModChange::Enable { folder, file_name } => {
+            let jar = jar_name(&file_name)?;
+            let disabled = format!("{}{}", jar, DISABLED_SUFFIX);
+            let from = mod_path(dir, &folder, &disabled)?;
+            if !from.is_file() {
+                bail!("{}/{} is not a disabled jar", folder, jar);
+            }
+            tokio::fs::rename(from, mod_path(dir, &folder, &jar)?).await?;
+            Ok(format!("Enabled {}/{}", folder, jar))
+        }
+        ModChange::Disable { folder, file_name } => {
+            let jar = jar_name(&file_name)?;
+            let from = mod_path(dir, &folder, &jar)?;
+            if !from.is_file() {
+                bail!("{}/{} is not an enabled jar", folder, jar);
+            }
+            let disabled = format!("{}{}", jar, DISABLED_SUFFIX);
+            tokio::fs::rename(from, mod_path(dir, &folder, &disabled)?).await?;
+            Ok(format!("Disabled {}/{}", folder, jar))
+        }
+        ModChange::Delete { folder, file_name } => {
+            let jar = jar_name(&file_name)?;
+            let disabled = format!("{}{}", jar, DISABLED_SUFFIX);
+            for name in [jar.as_str(), disabled.as_str()] {
+                let path = mod_path(dir, &folder, name)?;
+                if path.is_file() {
+                    tokio::fs::remove_file(path).await?;
+                    return Ok(format!("Deleted {}/{}", folder, name));
+                }
+            }
+            bail!("{}/{} does not exist", folder, jar)
+        }
+        ModChange::Upload { folder, file } => upload(dir, &folder, &file).await,
+    }
+}
+
+async fn upload(dir: &Path, folder: &str, file: &RemoteFile) -> Result<String> {
+    let jar = jar_name(&file.file_name)?;
+    if jar != file.file_name {
+        bail!("Only .jar files can be uploaded");
+    }
+    let target = mod_path(dir, folder, &jar)?;
+    if target.exists() || mod_path(dir, folder, &format!("{}{}", jar, DISABLED_SUFFIX))?.exists() {
+        bail!("{}/{} already exists, delete it first", folder, jar);
+    }
+
+    let bytes = download(&file.url).await?;
+    tokio::fs::create_dir_all(dir.join(folder)).await?;
+    // Write next to the target first so the server never sees half a jar
+    let part = mod_path(dir, folder, &format!(".{}.part", jar))?;
+    tokio::fs::write(&part, bytes).await?;
+    tokio::fs::rename(part, target).await?;
+    Ok(format!("Uploaded {}/{}", folder, jar))
+}
+
+// Accepts either name of a jar and returns the enabled one
+fn jar_name(file_name: &str) -> Result<String> {
+    let jar = file_name.strip_suffix(DISABLED_SUFFIX).unwrap_or(file_name);
+    if !jar.ends_with(".jar") || jar.starts_with('.') || jar.contains(['/', '\\']) {
+        bail!("{} is not a jar file name", file_name);
+    }
+    Ok(jar.to_string())
+}
+
+fn mod_path(dir: &Path, folder: &str, file_name: &str) -> Result<PathBuf> {
+    if !FOLDERS.contains(&folder) {
+        bail!("{} is not a mods or plugins folder", folder);
+    }
+    resolve(dir, &format!("{}/{}", folder, file_name))
+}
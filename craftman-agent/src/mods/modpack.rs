@@ -0,0 +1,593 @@
+use crate::mods::configs::{Configs, RunType};
+use crate::mods::download::download;
+use crate::mods::handler_request::{HandlerRequest, edit_config, start, stop};
+use crate::mods::sandbox::join_relative;
+use crate::mods::upgrade::{BACKUP_DIR, prune_backups};
+use anyhow::{Result, anyhow, bail};
+use protocol::serveractions::ServerActions;
+use protocol::tasks::{Progress, RemoteFile};
+use serde::Deserialize;
+use sha1::Sha1;
+use sha2::{Digest, Sha512};
+use std::collections::HashMap;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::process::Command;
+use tokio::sync::mpsc::UnboundedSender;
+use uuid::Uuid;
+use zip::ZipArchive;
+
+const MODRINTH_CDN: &str = "https://cdn.modrinth.com";
+const FABRIC_META: &str = "https://meta.fabricmc.net/v2";
+const QUILT_INSTALLER: &str = "https://quiltmc.org/api/v1/download-latest-installer/java-universal";
+const ALLOWED_HOSTS: [&str; 4] = [
+    "cdn.modrinth.com",
+    "github.com",
+    "raw.githubusercontent.com",
+    "gitlab.com",
+];
+const STAGING_DIR: &str = ".modpack.part";
+const BACKUP_PREFIX: &str = "modpack-";
+
+pub struct ModpackInstall {
+    pub request_id: Uuid,
+    pub pack: RemoteFile,
+    pub config: Configs,
+}
+
+// https://support.modrinth.com/en/articles/8802351-modrinth-modpack-format-mrpack
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PackIndex {
+    game: String,
+    name: String,
+    version_id: String,
+    files: Vec<PackFile>,
+    dependencies: HashMap<String, String>,
+}
+
+#[derive(Deserialize)]
+struct PackFile {
+    path: String,
+    hashes: HashMap<String, String>,
+    env: Option<HashMap<String, String>>,
+    downloads: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct FabricInstaller {
+    version: String,
+    stable: bool,
+}
+
+enum Loader {
+    Fabric { minecraft: String, loader: String },
+    Quilt { minecraft: String, loader: String },
+}
+
+pub async fn run(
+    install: ModpackInstall,
+    requests: UnboundedSender<HandlerRequest>,
+    sender: UnboundedSender<ServerActions>,
+    task_running: Arc<AtomicBool>,
+) {
+    let progress = match install_modpack(&install, &requests, &sender).await {
+        Ok(report) => Progress::Finished(report),
+        Err(e) => Progress::Failed(e.to_string()),
+    };
+    if let Err(e) = sender.send(ServerActions::Progress(install.request_id, progress)) {
+        tracing::error!("Failed to report modpack result: {}", e);
+    }
+    task_running.store(false, Ordering::SeqCst);
+}
+
+async fn install_modpack(
+    install: &ModpackInstall,
+    requests: &UnboundedSender<HandlerRequest>,
+    sender: &UnboundedSender<ServerActions>,
+) -> Result<String> {
+    let dir = PathBuf::from(&install.config.dir);
+    let step = |message: String| -> Result<()> {
+        tracing::info!("Modpack: {}", message);
+        sender.send(ServerActions::Progress(
+            install.request_id,
+            Progress::Step(message),
+        ))?;
+        Ok(())
+    };
+
+    step(format!("Downloading {}", install.pack.file_name))?;
+    let mut archive = ZipArchive::new(Cursor::new(download(&install.pack.url).await?))?;
+    let index: PackIndex = serde_json::from_reader(archive.by_name("modrinth.index.json")?)?;
+    if index.game != "minecraft" {
+        bail!("{} is not a Minecraft modpack", install.pack.file_name);
+    }
+    let loader = loader(&index.dependencies)?;
+
+    // Files are downloaded next to the server before it's stopped, so it stays up as long as
+    // possible and a failed download can't leave it half installed
+    let staging = dir.join(STAGING_DIR);
+    let _ = tokio::fs::remove_dir_all(&staging).await;
+    let files: Vec<&PackFile> = index
+        .files
+        .iter()
+        .filter(|file| {
+            file.env
+                .as_ref()
+                .and_then(|env| env.get("server"))
+                .is_none_or(|server| server != "unsupported")
+        })
+        .collect();
+    step(format!("Downloading {} files", files.len()))?;
+    let mut staged = Vec::new();
+    let mut failed = Vec::new();
+    for file in files {
+        match stage_file(&staging, file, install.config.modrinth_mirror.as_deref()).await {
+            Ok(()) => staged.push(file.path.clone()),
+            Err(e) => {
+                tracing::warn!("Failed to download {}: {}", file.path, e);
+                failed.push(format!("{}: {}", file.path, e));
+            }
+        }
+    }
+
+    step("Stopping server".to_string())?;
+    let was_running = match stop(requests).await {
+        Ok(was_running) => was_running,
+        Err(e) => {
+            let _ = tokio::fs::remove_dir_all(&staging).await;
+            return Err(e);
+        }
+    };
+
+    let seconds = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+    let backup = dir
+        .join(BACKUP_DIR)
+        .join(format!("{}{}", BACKUP_PREFIX, seconds));
+    let mut journal = Journal::new(&dir, &backup);
+    let result = set_up(
+        install,
+        archive,
+        &loader,
+        staged,
+        &mut journal,
+        requests,
+        &step,
+    )
+    .await;
+    let _ = tokio::fs::remove_dir_all(&staging).await;
+    if let Err(reason) = result {
+        step(format!("Install failed ({}). Rolling back", reason))?;
+        tokio::task::spawn_blocking(move || journal.roll_back())
+            .await?
+            .map_err(|e| {
+                anyhow!(
+                    "Installing {} failed: {}. Rolling back failed too ({}), the old files are in {}",
+                    index.name,
+                    reason,
+                    e,
+                    backup.display()
+                )
+            })?;
+        if was_running {
+            step("Restarting server".to_string())?;
+            start(requests).await?;
+        }
+        bail!(
+            "Installing {} failed: {}. The server was put back as it was",
+            index.name,
+            reason
+        );
+    }
+
+    let backups = dir.join(BACKUP_DIR);
+    if let Err(e) =
+        tokio::task::spawn_blocking(move || prune_backups(&backups, BACKUP_PREFIX)).await?
+    {
+        tracing::warn!("Failed to delete old modpack backups: {}", e);
+    }
+
+    // The install itself is done, so a server that won't start is reported rather than undone
+    let mut report = if was_running {
+        step("Starting server".to_string())?;
+        match start(requests).await {
+            Ok(_) => format!(
+                "Installed {} {} and started the server",
+                index.name, index.version_id
+            ),
+            Err(e) => format!(
+                "Installed {} {}, but the server didn't start again: {}",
+                index.name, index.version_id, e
+            ),
+        }
+    } else {
+        format!(
+            "Installed {} {}. Start the server to finish setting it up",
+            index.name, index.version_id
+        )
+    };
+    if !failed.is_empty() {
+        report.push_str(&format!(
+            "\n{} files failed:\n{}",
+            failed.len(),
+            failed.join("\n")
+        ));
+    }
+    Ok(report)
+}
+
+// Everything after the server is stopped. The config is changed last, so until then the server
+// still starts the way it did before
+async fn set_up(
+    install: &ModpackInstall,
+    mut archive: ZipArchive<Cursor<Vec<u8>>>,
+    loader: &Loader,
+    staged: Vec<String>,
+    journal: &mut Journal,
+    requests: &UnboundedSender<HandlerRequest>,
+    step: &impl Fn(String) -> Result<()>,
+) -> Result<()> {
+    let dir = PathBuf::from(&install.config.dir);
+    if dir.join("mods").is_dir() {
+        step(format!("Moving the old mods folder to {}", BACKUP_DIR))?;
+        journal.move_mods()?;
+    }
+
+    step("Applying files and overrides".to_string())?;
+    let staging = dir.join(STAGING_DIR);
+    let mut moved = std::mem::take(journal);
+    let (moved, result) = tokio::task::spawn_blocking(move || {
+        let result = place_files(&staging, &staged, &mut moved)
+            .and_then(|()| apply_overrides(&mut archive, &mut moved));
+        (moved, result)
+    })
+    .await?;
+    *journal = moved;
+    result?;
+
+    let jar = match loader {
+        Loader::Fabric { minecraft, loader } => {
+            step(format!(
+                "Setting up Fabric {} for Minecraft {}",
+                loader, minecraft
+            ))?;
+            install_fabric(journal, minecraft, loader).await?
+        }
+        Loader::Quilt { minecraft, loader } => {
+            step(format!(
+                "Setting up Quilt {} for Minecraft {}",
+                loader, minecraft
+            ))?;
+            install_quilt(journal, minecraft, loader).await?
+        }
+    };
+    let config = install
+        .config
+        .clone()
+        .set_jar(jar.to_string())
+        .set_run_type(RunType::CustomJar(vec![
+            "-jar".to_string(),
+            jar.to_string(),
+            "nogui".to_string(),
+        ]));
+    edit_config(requests, config).await
+}
+
+fn loader(dependencies: &HashMap<String, String>) -> Result<Loader> {
+    let minecraft = dependencies
+        .get("minecraft")
+        .ok_or_else(|| anyhow!("Modpack does not declare a Minecraft version"))?
+        .clone();
+    if let Some(loader) = dependencies.get("fabric-loader") {
+        Ok(Loader::Fabric {
+            minecraft,
+            loader: loader.clone(),
+        })
+    } else if let Some(loader) = dependencies.get("quilt-loader") {
+        Ok(Loader::Quilt {
+            minecraft,
+            loader: loader.clone(),
+        })
+    } else if dependencies.contains_key("forge") || dependencies.contains_key("neoforge") {
+        bail!("Only Fabric and Quilt modpacks can be set up automatically")
+    } else {
+        bail!("Modpack does not declare a mod loader")
+    }
+}
+
+// Mirrors are tried first. A download that fails or doesn't match the hashes moves on to the next
+async fn stage_file(staging: &Path, file: &PackFile, mirror: Option<&str>) -> Result<()> {
+    let target = join_relative(staging, &file.path)?;
+    let mut candidates: Vec<String> = file
+        .downloads
+        .iter()
+        .filter_map(|url| mirrored(url, mirror))
+        .collect();
+    candidates.extend(file.downloads.iter().filter(|url| allowed(url)).cloned());
+
+    let mut last_error = anyhow!("No downloads from an allowed host");
+    for url in candidates {
+        let bytes = match download(&url).await {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                last_error = e;
+                continue;
+            }
+        };
+        if let Err(e) = verify(&bytes, &file.hashes) {
+            last_error = e;
+            continue;
+        }
+        if let Some(parent) = target.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        tokio::fs::write(&target, &bytes).await?;
+        return Ok(());
+    }
+    Err(last_error)
+}
+
+// The hosts the .mrpack format allows. The configured mirror is trusted on its own
+fn allowed(url: &str) -> bool {
+    reqwest::Url::parse(url).is_ok_and(|url| {
+        url.scheme() == "https"
+            && url
+                .host_str()
+                .is_some_and(|host| ALLOWED_HOSTS.contains(&host))
+    })
+}
+
+fn mirrored(url: &str, mirror: Option<&str>) -> Option<String> {
+    let rest = url.strip_prefix(MODRINTH_CDN)?;
+    Some(format!("{}{}", mirror?.trim_end_matches('/'), rest))
+}
+
+fn verify(bytes: &[u8], hashes: &HashMap<String, String>) -> Result<()> {
+    let mut checked = false;
+    if let Some(expected) = hashes.get("sha512") {
+        if !format!("{:x}", Sha512::digest(bytes)).eq_ignore_ascii_case(expected) {
+            bail!("sha512 hash mismatch");
+        }
+        checked = true;
+    }
+    if let Some(expected) = hashes.get("sha1") {
+        if !format!("{:x}", Sha1::digest(bytes)).eq_ignore_ascii_case(expected) {
+            bail!("sha1 hash mismatch");
+        }
+        checked = true;
+    }
+    if !checked {
+        bail!("No sha1 or sha512 hash to verify against");
+    }
+    Ok(())
+}
+
+fn place_files(staging: &Path, staged: &[String], journal: &mut Journal) -> Result<()> {
+    for relative in staged {
+        let target = journal.replace(relative)?;
+        std::fs::rename(join_relative(staging, relative)?, target)?;
+    }
+    Ok(())
+}
+
+// server-overrides is applied after overrides so it wins when both contain the same file
+fn apply_overrides(archive: &mut ZipArchive<Cursor<Vec<u8>>>, journal: &mut Journal) -> Result<()> {
+    for prefix in ["overrides/", "server-overrides/"] {
+        for i in 0..archive.len() {
+            let mut entry = archive.by_index(i)?;
+            let Some(relative) = entry.name().strip_prefix(prefix).map(str::to_string) else {
+                continue;
+            };
+            if relative.is_empty() {
+                continue;
+            }
+            if entry.is_dir() {
+                std::fs::create_dir_all(join_relative(&journal.dir, &relative)?)?;
+                continue;
+            }
+            let target = journal.replace(&relative)?;
+            std::io::copy(&mut entry, &mut std::fs::File::create(&target)?)?;
+        }
+    }
+    Ok(())
+}
+
+// Remembers every file the install writes so a failed install can be undone. Files that were
+// there before are moved into the backup first. Folders it creates are left behind empty
+#[derive(Default)]
+struct Journal {
+    dir: PathBuf,
+    backup: PathBuf,
+    mods_moved: bool,
+    // Each written file and where the file it replaced went
+    written: HashMap<PathBuf, Option<PathBuf>>,
+}
+
+impl Journal {
+    fn new(dir: &Path, backup: &Path) -> Self {
+        Journal {
+            dir: dir.to_path_buf(),
+            backup: backup.to_path_buf(),
+            mods_moved: false,
+            written: HashMap::new(),
+        }
+    }
+
+    // The old mods folder is moved aside whole so none of its mods load next to the pack's
+    fn move_mods(&mut self) -> Result<()> {
+        std::fs::create_dir_all(&self.backup)?;
+        std::fs::rename(self.dir.join("mods"), self.backup.join("mods"))?;
+        self.mods_moved = true;
+        Ok(())
+    }
+
+    // Makes room for a file and returns where to write it
+    fn replace(&mut self, relative: &str) -> Result<PathBuf> {
+        let target = join_relative(&self.dir, relative)?;
+        if !self.written.contains_key(&target) {
+            let replaced = if target.is_file() {
+                let backup = join_relative(&self.backup, relative)?;
+                if let Some(parent) = backup.parent() {
+                    std::fs::create_dir_all(parent)?;
+                }
+                std::fs::rename(&target, &backup)?;
+                Some(backup)
+            } else {
+                None
+            };
+            self.written.insert(target.clone(), replaced);
+        }
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        Ok(target)
+    }
+
+    fn roll_back(self) -> Result<()> {
+        for (target, replaced) in self.written {
+            if let Err(e) = std::fs::remove_file(&target)
+                && e.kind() != std::io::ErrorKind::NotFound
+            {
+                return Err(e.into());
+            }
+            if let Some(replaced) = replaced {
+                std::fs::rename(replaced, target)?;
+            }
+        }
+        if self.mods_moved {
+            if self.dir.join("mods").exists() {
+                std::fs::remove_dir_all(self.dir.join("mods"))?;
+            }
+            std::fs::rename(self.backup.join("mods"), self.dir.join("mods"))?;
+        }
+        Ok(())
+    }
+}
+
+// The launcher from Fabric's meta server downloads the vanilla server and libraries on first start
+async fn install_fabric(
+    journal: &mut Journal,
+    minecraft: &str,
+    loader: &str,
+) -> Result<&'static str> {
+    let jar = "fabric-server-launch.jar";
+    let installers: Vec<FabricInstaller> =
+        serde_json::from_slice(&download(&format!("{}/versions/installer", FABRIC_META)).await?)?;
+    let installer = installers
+        .iter()
+        .find(|installer| installer.stable)
+        .ok_or_else(|| anyhow!("No stable Fabric installer found"))?;
+    let launcher = download(&format!(
+        "{}/versions/loader/{}/{}/{}/server/jar",
+        FABRIC_META, minecraft, loader, installer.version
+    ))
+    .await?;
+    tokio::fs::write(journal.replace(jar)?, launcher).await?;
+    Ok(jar)
+}
+
+// The installer writes the launcher and the vanilla server.jar, which may already be there. The
+// libraries it downloads are left behind on a rollback
+async fn install_quilt(
+    journal: &mut Journal,
+    minecraft: &str,
+    loader: &str,
+) -> Result<&'static str> {
+    let jar = "quilt-server-launch.jar";
+    journal.replace(jar)?;
+    journal.replace("server.jar")?;
+    let dir = journal.dir.clone();
+    let installer = dir.join("quilt-installer.jar");
+    tokio::fs::write(&installer, download(QUILT_INSTALLER).await?).await?;
+    let status = Command::new("java")
+        .current_dir(&dir)
+        .arg("-jar")
+        .arg(&installer)
+        .arg("install")
+        .arg("server")
+        .arg(minecraft)
+        .arg(loader)
+        .arg("--download-server")
+        .arg("--install-dir=.")
+        .status()
+        .await;
+    tokio::fs::remove_file(&installer).await?;
+    if !status?.success() {
+        bail!("Quilt installer failed");
+    }
+    Ok(jar)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_allowed_hosts() {
+        for (url, expected) in [
+            (
+                "https://cdn.modrinth.com/data/AANobbMI/versions/x/sodium.jar",
+                true,
+            ),
+            (
+                "https://github.com/owner/repo/releases/download/v1/mod.jar",
+                true,
+            ),
+            (
+                "https://raw.githubusercontent.com/owner/repo/main/mod.jar",
+                true,
+            ),
+            ("https://gitlab.com/owner/repo/-/raw/main/mod.jar", true),
+            ("http://cdn.modrinth.com/data/x.jar", false),
+            ("https://cdn.modrinth.com.evil.example/x.jar", false),
+            ("https://127.0.0.1/x.jar", false),
+            ("https://evil.example/cdn.modrinth.com/x.jar", false),
+            ("file:///etc/passwd", false),
+            ("not a url", false),
+        ] {
+            assert_eq!(allowed(url), expected, "{}", url);
+        }
+    }
+
+    #[test]
+    fn mirrors_only_the_modrinth_cdn() {
+        let url = "https://cdn.modrinth.com/data/x.jar";
+        assert_eq!(
+            mirrored(url, Some("https://mirror.example/")),
+            Some("https://mirror.example/data/x.jar".to_string())
+        );
+        assert_eq!(mirrored(url, None), None);
+        assert_eq!(
+            mirrored("https://github.com/x.jar", Some("https://mirror.example")),
+            None
+        );
+    }
+
+    #[test]
+    fn rolls_back_written_files() {
+        let dir = std::env::temp_dir().join(format!("craftman-journal-{}", Uuid::new_v4()));
+        std::fs::create_dir_all(dir.join("mods")).unwrap();
+        std::fs::create_dir_all(dir.join("config")).unwrap();
+        std::fs::write(dir.join("mods/old.jar"), "old mod").unwrap();
+        std::fs::write(dir.join("config/a.toml"), "old config").unwrap();
+
+        let mut journal = Journal::new(&dir, &dir.join("backup"));
+        journal.move_mods().unwrap();
+        std::fs::write(journal.replace("mods/new.jar").unwrap(), "new mod").unwrap();
+        std::fs::write(journal.replace("config/a.toml").unwrap(), "overrides").unwrap();
+        // A second write to the same file must not replace the backup of the first
+        std::fs::write(journal.replace("config/a.toml").unwrap(), "server").unwrap();
+        std::fs::write(journal.replace("config/b.toml").unwrap(), "new config").unwrap();
+        journal.roll_back().unwrap();
+
+        let read = |path: &str| std::fs::read_to_string(dir.join(path)).ok();
+        assert_eq!(read("mods/old.jar").as_deref(), Some("old mod"));
+        assert_eq!(read("mods/new.jar"), None);
+        assert_eq!(read("config/a.toml").as_deref(), Some("old config"));
+        assert_eq!(read("config/b.toml"), None);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
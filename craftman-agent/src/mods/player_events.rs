@@ -0,0 +1,205 @@
+use crate::mods::server_process::strip_log_prefix;
+use protocol::player_events::{AdvancementKind, PlayerEvent};
+use protocol::properties::Edition;
+
+// What follows the player's name in Java's death messages. "was " covers most of them, like
+// "was slain by" and "was shot by"
+const DEATHS: [&str; 19] = [
+    "was ",
+    "died",
+    "drowned",
+    "blew up",
+    "burned to death",
+    "fell ",
+    "froze to death",
+    "hit the ground too hard",
+    "starved to death",
+    "suffocated in a wall",
+    "tried to swim in lava",
+    "experienced kinetic energy",
+    "went up in flames",
+    "went off with a bang",
+    "walked into ",
+    "withered away",
+    "discovered the floor was lava",
+    "didn't want to live",
+    "left the confines of this world",
+];
+
+const ADVANCEMENTS: [(&str, AdvancementKind); 3] = [
+    ("has made the advancement [", AdvancementKind::Advancement),
+    ("has reached the goal [", AdvancementKind::Goal),
+    ("has completed the challenge [", AdvancementKind::Challenge),
+];
+
+// Bedrock only logs connects and disconnects, like "Player connected: Steve, xuid: 2535..."
+pub fn parse(line: &str, edition: Edition) -> Option<PlayerEvent> {
+    let message = strip_log_prefix(line);
+    match edition {
+        Edition::Java => java(message),
+        Edition::Bedrock => {
+            let player = |event: &str| {
+                message
+                    .strip_prefix(event)
+                    .and_then(|rest| rest.split_once(','))
+                    .map(|(name, _)| name.trim().to_string())
+                    .filter(|name| !name.is_empty())
+            };
+            player("Player connected:")
+                .map(PlayerEvent::Joined)
+                .or_else(|| player("Player disconnected:").map(PlayerEvent::Left))
+        }
+    }
+}
+
+// Chat lines start with "<Steve>" and "say" with "[Server]", neither of which is a valid name, so
+// players can't fake events by typing them. That relies on strip_log_prefix leaving chat whole
+fn java(message: &str) -> Option<PlayerEvent> {
+    let (player, rest) = message.split_once(' ')?;
+    let valid = (3..=16).contains(&player.len())
+        && player
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if !valid {
+        return None;
+    }
+    let player = player.to_string();
+    match rest {
+        "joined the game" => return Some(PlayerEvent::Joined(player)),
+        "left the game" => return Some(PlayerEvent::Left(player)),
+        _ => {}
+    }
+    for (prefix, kind) in ADVANCEMENTS {
+        if let Some(title) = rest
+            .strip_prefix(prefix)
+            .and_then(|title| title.strip_suffix(']'))
+        {
+            return Some(PlayerEvent::Advancement {
+                player,
+                title: title.to_string(),
+                kind,
+            });
+        }
+    }
+    DEATHS
+        .iter()
+        .any(|death| rest.starts_with(death))
+        .then(|| PlayerEvent::Died {
+            message: message.to_string(),
+            player,
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn died(player: &str, message: &str) -> Option<PlayerEvent> {
+        Some(PlayerEvent::Died {
+            player: player.to_string(),
+            message: message.to_string(),
+        })
+    }
+
+    #[test]
+    fn java_events() {
+        let cases = [
+            (
+                "[12:00:00] [Server thread/INFO]: Steve joined the game",
+                Some(PlayerEvent::Joined("Steve".to_string())),
+            ),
+            (
+                "[12:00:00 INFO]: Steve left the game",
+                Some(PlayerEvent::Left("Steve".to_string())),
+            ),
+            (
+                "[12:00:00] [Server thread/INFO]: Steve was slain by Zombie",
+                died("Steve", "Steve was slain by Zombie"),
+            ),
+            (
+                "[12:00:00] [Server thread/INFO]: Alex_2 fell from a high place",
+                died("Alex_2", "Alex_2 fell from a high place"),
+            ),
+            (
+                "[12:00:00] [Server thread/INFO]: Steve has made the advancement [Stone Age]",
+                Some(PlayerEvent::Advancement {
+                    player: "Steve".to_string(),
+                    title: "Stone Age".to_string(),
+                    kind: AdvancementKind::Advancement,
+                }),
+            ),
+            (
+                "[12:00:00] [Server thread/INFO]: Steve has reached the goal [Sky's the Limit]",
+                Some(PlayerEvent::Advancement {
+                    player: "Steve".to_string(),
+                    title: "Sky's the Limit".to_string(),
+                    kind: AdvancementKind::Goal,
+                }),
+            ),
+            (
+                "[12:00:00] [Server thread/INFO]: Steve has completed the challenge [Arbalistic]",
+                Some(PlayerEvent::Advancement {
+                    player: "Steve".to_string(),
+                    title: "Arbalistic".to_string(),
+                    kind: AdvancementKind::Challenge,
+                }),
+            ),
+            ("[12:00:00] [Server thread/INFO]: Done (3.2s)!", None),
+            (
+                "[12:00:00] [Server thread/INFO]: Steve lost connection: Disconnected",
+                None,
+            ),
+        ];
+        for (line, expected) in cases {
+            assert_eq!(parse(line, Edition::Java), expected, "{line}");
+        }
+    }
+
+    // Chat, /say, /me and names that aren't valid can't pass for events
+    #[test]
+    fn java_spoofs() {
+        let cases = [
+            "[12:00:00] [Server thread/INFO]: <Mallory> Alice joined the game",
+            "[12:00:00] [Server thread/INFO]: <Mallory> a]: Alice joined the game",
+            "[12:00:00 INFO]: <Mallory> [a]: Alice was slain by Zombie",
+            "[12:00:00] [Server thread/INFO]: [Server] Alice left the game",
+            "[12:00:00] [Server thread/INFO]: [Mallory] x] Alice left the game",
+            "[12:00:00] [Server thread/INFO]: * Mallory was here",
+            "[12:00:00] [Server thread/INFO]: Al joined the game",
+            "[12:00:00] [Server thread/INFO]: Mallory_is_far_too_long joined the game",
+            "[12:00:00] [Server thread/INFO]: Mal-lory joined the game",
+            "[12:00:00] [Server thread/INFO]: Steve has made the advancement [Stone Age",
+            "[12:00:00] [Server thread/INFO]: [Not Secure] <Mallory> Alice joined the game",
+        ];
+        for line in cases {
+            assert_eq!(parse(line, Edition::Java), None, "{line}");
+        }
+    }
+
+    #[test]
+    fn bedrock_events() {
+        let cases = [
+            (
+                "[2024-01-01 12:00:00:000 INFO] Player connected: Steve, xuid: 2535",
+                Some(PlayerEvent::Joined("Steve".to_string())),
+            ),
+            (
+                "[2024-01-01 12:00:00:000 INFO] Player disconnected: Two Words, xuid: 2535",
+                Some(PlayerEvent::Left("Two Words".to_string())),
+            ),
+            (
+                "[2024-01-01 12:00:00:000 INFO] Player connected: , xuid: 2535",
+                None,
+            ),
+            (
+                "[2024-01-01 12:00:00:000 INFO] Player connected: Steve",
+                None,
+            ),
+            // Java style lines mean nothing on Bedrock
+            ("[2024-01-01 12:00:00:000 INFO] Steve joined the game", None),
+        ];
+        for (line, expected) in cases {
+            assert_eq!(parse(line, Edition::Bedrock), expected, "{line}");
+        }
+    }
+}
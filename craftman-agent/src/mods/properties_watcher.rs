@@ -0,0 +1,44 @@
+use crate::mods::handler_request::HandlerRequest;
+use anyhow::Result;
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::path::Path;
+use std::time::Duration;
+use tokio::sync::mpsc::{self, UnboundedSender};
+use tokio::time::timeout;
+
+// Editors often write a file in several steps, so changes are only reported once they settle
+const SETTLE: Duration = Duration::from_millis(500);
+
+// Watches the server directory rather than the file, since saving usually replaces the file
+// and a watch on the old one would stop firing. Dropping the watcher stops everything
+pub fn watch(dir: &str, requests: UnboundedSender<HandlerRequest>) -> Result<RecommendedWatcher> {
+    let (events, mut received) = mpsc::unbounded_channel();
+    let mut watcher = notify::recommended_watcher(move |event: notify::Result<Event>| {
+        if let Ok(event) = event
+            && !matches!(event.kind, EventKind::Access(_))
+            && event.paths.iter().any(|path| {
+                path.file_name()
+                    .is_some_and(|name| name == "server.properties")
+            })
+        {
+            let _ = events.send(());
+        }
+    })?;
+    watcher.watch(Path::new(dir), RecursiveMode::NonRecursive)?;
+
+    tokio::spawn(async move {
+        while received.recv().await.is_some() {
+            loop {
+                match timeout(SETTLE, received.recv()).await {
+                    Ok(Some(())) => continue,
+                    Ok(None) => return,
+                    Err(_) => break,
+                }
+            }
+            if requests.send(HandlerRequest::PropertiesChanged).is_err() {
+                return;
+            }
+        }
+    });
+    Ok(watcher)
+}
@@ -1,45 +1,177 @@
+use crate::mods::full_query::{self, FullStat};
+use crate::mods::inventory;
+use crate::mods::server_process::{ProcessHandle, strip_log_prefix};
+use crate::mods::version_range::find_version;
 use anyhow::{Result, anyhow};
 use base64::Engine as _;
 use base64::engine::general_purpose::STANDARD;
-use protocol::query_options::{QueryOptions, QueryStatus, ServerStatus};
+use protocol::inventory::Inventory;
+use protocol::properties::Edition;
+use protocol::query_options::{QueryOptions, QuerySample, QueryStatus, ServerStatus};
 use protocol::serveractions::ServerActions;
-use rust_mc_status::JavaStatus;
 use rust_mc_status::McClient;
 use rust_mc_status::ServerData;
 use rust_mc_status::error::McError;
+use rust_mc_status::{BedrockStatus, JavaStatus};
+use std::path::PathBuf;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::sync::broadcast::error::RecvError;
 use tokio::sync::mpsc::UnboundedSender;
+use tokio::sync::watch;
+use tokio::time::timeout;
 use uuid::Uuid;
 
+// `list` shows up in the console and the chat stream, so it isn't run on every update
+const LIST_REFRESH: Duration = Duration::from_secs(60);
+const LIST_TIMEOUT: Duration = Duration::from_secs(2);
+
 pub struct QueryHandler {
     client: McClient,
     port: u32,
+    edition: Edition,
+    dir: PathBuf,
     options: QueryOptions,
     last_status: Option<ServerStatus>,
+    process_started: watch::Receiver<Option<i64>>,
+    // Start of the current run of successful pings, in unix seconds
+    online_since: Option<i64>,
+    // Set when server.properties turns on enable-query
+    query_port: Option<u16>,
+    process: watch::Receiver<Option<ProcessHandle>>,
+    listed: Option<(Instant, Vec<String>)>,
+    scanned: Option<Scanned>,
+}
+
+// The last scan of the mods and plugins folders, with the folders' modification times and the
+// Minecraft version it was checked against
+struct Scanned {
+    modified: Vec<Option<SystemTime>>,
+    version: Option<String>,
+    inventory: Inventory,
+}
+
+// Java and Bedrock pings brought down to what the monitor shows
+#[derive(Clone)]
+pub struct Ping {
+    pub version: String,
+    pub online: i64,
+    pub max: i64,
+    pub sample: Option<Vec<String>>,
+    pub description: String,
+    pub favicon: Option<String>,
+    pub map: Option<String>,
+    pub gamemode: Option<String>,
+    pub software: Option<String>,
+    pub plugins: Option<Vec<String>>,
+    pub mods: Option<Vec<String>>,
+}
+
+impl From<JavaStatus> for Ping {
+    fn from(status: JavaStatus) -> Self {
+        Ping {
+            version: status.version.name,
+            online: status.players.online,
+            max: status.players.max,
+            sample: status
+                .players
+                .sample
+                .map(|players| players.into_iter().map(|player| player.name).collect()),
+            description: status.description,
+            favicon: status.favicon,
+            map: status.map,
+            gamemode: status.gamemode,
+            software: status.software,
+            plugins: status
+                .plugins
+                .map(|plugins| plugins.into_iter().map(|plugin| plugin.name).collect()),
+            mods: status
+                .mods
+                .map(|mods| mods.into_iter().map(|mcmod| mcmod.modid).collect()),
+        }
+    }
+}
+
+// RakNet pongs carry no favicon, player names, mods or plugins
+impl From<BedrockStatus> for Ping {
+    fn from(status: BedrockStatus) -> Self {
+        Ping {
+            version: status.version,
+            online: status.online_players.parse().unwrap_or(0),
+            max: status.max_players.parse().unwrap_or(0),
+            sample: None,
+            description: status.motd,
+            favicon: None,
+            map: status.map.filter(|map| !map.is_empty()),
+            gamemode: Some(status.game_mode).filter(|gamemode| !gamemode.is_empty()),
+            software: status.software,
+            plugins: None,
+            mods: None,
+        }
+    }
+}
+
+// What the UDP query or the console knows that the ping leaves out
+#[derive(Default)]
+struct Details {
+    players: Option<Vec<String>>,
+    plugins: Option<Vec<String>>,
+    map: Option<String>,
+    software: Option<String>,
+}
+
+impl From<FullStat> for Details {
+    fn from(stat: FullStat) -> Self {
+        Details {
+            map: stat.map(),
+            software: stat.software(),
+            plugins: stat.plugins(),
+            players: Some(stat.players),
+        }
+    }
 }
 
 impl QueryHandler {
-    pub fn new(port: u32, options: QueryOptions) -> Self {
+    pub fn new(
+        port: u32,
+        edition: Edition,
+        dir: PathBuf,
+        options: QueryOptions,
+        process_started: watch::Receiver<Option<i64>>,
+        query_port: Option<u16>,
+        process: watch::Receiver<Option<ProcessHandle>>,
+    ) -> Self {
         QueryHandler {
             client: McClient::new(),
             port,
+            edition,
+            dir,
             options,
             last_status: None,
+            process_started,
+            online_since: None,
+            query_port,
+            process,
+            listed: None,
+            scanned: None,
         }
     }
 
-    pub async fn ping(&self) -> Result<JavaStatus, McError> {
-        if let ServerData::Java(javastatus) = self
-            .client
-            .ping_java(&format!("localhost:{}", self.port))
-            .await?
-            .data
-        {
-            Ok(javastatus)
-        } else {
-            Err(McError::InvalidResponse(
-                "The returned data in the ping function is not a java status. I don't think this is supposed to be possible".to_string()
-            ))
-        }
+    pub async fn ping(&self) -> Result<Ping, McError> {
+        Ok(self.timed_ping().await?.0)
+    }
+
+    // Also returns the latency the library measured for the ping itself, in milliseconds
+    async fn timed_ping(&self) -> Result<(Ping, f64), McError> {
+        let address = format!("localhost:{}", self.port);
+        let status = match self.edition {
+            Edition::Java => self.client.ping_java(&address).await?,
+            Edition::Bedrock => self.client.ping_bedrock(&address).await?,
+        };
+        let ping = match status.data {
+            ServerData::Java(status) => status.into(),
+            ServerData::Bedrock(status) => status.into(),
+        };
+        Ok((ping, status.latency))
     }
 
     pub async fn respond(
@@ -47,9 +179,10 @@ impl QueryHandler {
         sender: UnboundedSender<ServerActions>,
         request_id: Uuid,
     ) -> Result<()> {
-        let status: JavaStatus = match self.ping().await {
+        let status: Ping = match self.ping().await {
             Ok(status) => status,
             Err(_) => {
+                self.online_since = None;
                 sender.send(ServerActions::QueryResponse {
                     uuid: request_id,
                     description: "A minecraft server".to_string(),
@@ -78,7 +211,9 @@ impl QueryHandler {
         }
         tracing::info!("decoded image");
 
-        let query_response = self.query_builder(status.clone());
+        let inventory = self.local_inventory(&status).await;
+        let details = self.details(&status, false).await;
+        let query_response = self.query_builder(status.clone(), inventory, details);
 
         self.last_status = Some(ServerStatus::ServerOnline(query_response.clone()));
 
@@ -91,8 +226,17 @@ impl QueryHandler {
         Ok(())
     }
 
-    pub async fn update(&mut self, sender: UnboundedSender<ServerActions>) -> Result<()> {
-        let server_status = if let Ok(status) = self.ping().await {
+    // Updates are tagged with the monitor so the server knows which message to edit
+    pub async fn update(
+        &mut self,
+        monitor: i64,
+        sender: UnboundedSender<ServerActions>,
+    ) -> Result<()> {
+        let ping = self.ping().await;
+        if ping.is_err() {
+            self.online_since = None;
+        }
+        let server_status = if let Ok(status) = ping {
             if self.last_status == Some(ServerStatus::ServerOffline) {
                 let image_base64 = &status.favicon;
 
@@ -108,11 +252,14 @@ impl QueryHandler {
                     );
                 }
                 sender.send(ServerActions::UpdateQueryHeader {
+                    monitor,
                     description: status.description.clone(),
                     image,
                 })?;
             }
-            ServerStatus::ServerOnline(self.query_builder(status))
+            let inventory = self.local_inventory(&status).await;
+            let details = self.details(&status, true).await;
+            ServerStatus::ServerOnline(self.query_builder(status, inventory, details))
         } else {
             ServerStatus::ServerOffline
         };
@@ -133,6 +280,7 @@ impl QueryHandler {
 
         if self.last_status.is_none() || (&server_status != self.last_status.as_ref().unwrap()) {
             sender.send(ServerActions::UpdateQuery {
+                monitor,
                 status: server_status.clone(),
             })?;
             self.last_status = Some(server_status);
@@ -141,29 +289,147 @@ impl QueryHandler {
         Ok(())
     }
 
-    fn query_builder(&mut self, status: JavaStatus) -> QueryStatus {
+    // One point of history per agent, whether a monitor is open or not
+    pub async fn sample(&self) -> QuerySample {
+        match self.timed_ping().await {
+            Ok((status, latency)) => QuerySample {
+                online: true,
+                players: u32::try_from(status.online).ok(),
+                max_players: u32::try_from(status.max).ok(),
+                latency_ms: (latency.is_finite() && latency >= 0.0).then(|| latency.round() as u32),
+            },
+            Err(_) => QuerySample::default(),
+        }
+    }
+
+    // Most servers leave mods and plugins out of the ping, so fall back to reading the jars
+    async fn local_inventory(&mut self, status: &Ping) -> Option<Inventory> {
+        let wants_mods = self.options.mods() && status.mods.is_none();
+        let wants_plugins = self.options.plugins() && status.plugins.is_none();
+        if !wants_mods && !wants_plugins {
+            return None;
+        }
+        let version = find_version(&status.version);
+        // Adding, removing or renaming a jar changes its folder's modification time, so the last
+        // scan holds until one of them changes
+        let mut modified = Vec::new();
+        for folder in inventory::FOLDERS {
+            let metadata = tokio::fs::metadata(self.dir.join(folder)).await;
+            modified.push(metadata.and_then(|metadata| metadata.modified()).ok());
+        }
+        if let Some(scanned) = &self.scanned
+            && scanned.modified == modified
+            && scanned.version == version
+        {
+            return Some(scanned.inventory.clone());
+        }
+
+        let dir = self.dir.clone();
+        let scan_version = version.clone();
+        match tokio::task::spawn_blocking(move || inventory::scan(&dir, scan_version)).await {
+            Ok(inventory) => {
+                self.scanned = Some(Scanned {
+                    modified,
+                    version,
+                    inventory: inventory.clone(),
+                });
+                Some(inventory)
+            }
+            Err(e) => {
+                tracing::warn!("Failed to scan mods and plugins: {}", e);
+                None
+            }
+        }
+    }
+
+    // The ping only samples up to 12 players and many servers hide even those. The UDP query
+    // has everyone, otherwise the console's `list` does. Only the update loops run `list`, since
+    // requests are answered on the listener and anyone can make one through a status page
+    async fn details(&mut self, status: &Ping, list: bool) -> Details {
+        let wants_players = self.options.player_list();
+        if !wants_players
+            && !self.options.plugins()
+            && !self.options.map()
+            && !self.options.software()
+        {
+            return Details::default();
+        }
+        if let Some(port) = self.query_port {
+            match full_query::full_stat(port).await {
+                Ok(stat) => return stat.into(),
+                Err(e) => tracing::debug!("Full query failed, falling back: {}", e),
+            }
+        }
+        let sampled = status.sample.as_ref().map_or(0, Vec::len);
+        if !list || !wants_players || sampled as i64 >= status.online {
+            return Details::default();
+        }
+        if let Some((at, players)) = &self.listed
+            && at.elapsed() < LIST_REFRESH
+        {
+            return Details {
+                players: Some(players.clone()),
+                ..Default::default()
+            };
+        }
+        let process = self
+            .process
+            .borrow()
+            .as_ref()
+            .map(ProcessHandle::resubscribe);
+        let listed = timeout(LIST_TIMEOUT, list_players(process))
+            .await
+            .unwrap_or_else(|_| Err(anyhow!("No answer to list")));
+        match listed {
+            Ok(players) => {
+                self.listed = Some((Instant::now(), players.clone()));
+                Details {
+                    players: Some(players),
+                    ..Default::default()
+                }
+            }
+            Err(e) => {
+                tracing::debug!("Listing players failed: {}", e);
+                Details::default()
+            }
+        }
+    }
+
+    fn query_builder(
+        &mut self,
+        status: Ping,
+        inventory: Option<Inventory>,
+        details: Details,
+    ) -> QueryStatus {
         let mut query_response = QueryStatus::default();
         if self.options.version() {
             tracing::info!("set version");
-            query_response.set_version(status.version.name);
+            query_response.set_version(status.version);
         }
 
         if self.options.player_count() {
             tracing::info!("set player count");
-            query_response
-                .set_player_count(format!("{}/{}", status.players.online, status.players.max));
+            query_response.set_player_count(format!("{}/{}", status.online, status.max));
         }
 
-        if self.options.player_list()
-            && let Some(players) = status.players.sample
-        {
-            tracing::info!("set player list");
-            query_response.set_player_list(players.into_iter().map(|player| player.name).collect())
+        if self.options.player_list() {
+            if let Some(players) = details.players {
+                tracing::info!("set full player list");
+                query_response.set_player_list(players)
+            } else if let Some(players) = status.sample {
+                tracing::info!("set player list");
+                query_response.set_player_list(players)
+            }
         }
 
         if self.options.map() {
             tracing::info!("set map");
-            query_response.set_map(Some(status.map.unwrap_or("No map found".to_string())));
+            query_response.set_map(Some(
+                status
+                    .map
+                    .or(details.map)
+                    .unwrap_or("No map found".to_string()),
+            ));
         }
 
         if self.options.gamemode() {
@@ -182,25 +448,53 @@ impl QueryHandler {
         if self.options.software() {
             tracing::info!("set software");
             query_response.set_software(Some(
-                status.software.unwrap_or("No software found".to_string()),
+                status
+                    .software
+                    .or(details.software)
+                    .unwrap_or("No software found".to_string()),
             ));
         }
 
         if self.options.plugins() {
             tracing::info!("set plugins");
             if let Some(plugins) = status.plugins {
-                query_response.set_plugins(Some(
-                    plugins.into_iter().map(|plugin| plugin.name).collect(),
-                ));
+                query_response.set_plugins(Some(plugins));
+            } else if let Some(plugins) = details.plugins {
+                query_response.set_plugins(Some(plugins));
+            } else if let Some(plugins) = inventory
+                .as_ref()
+                .map(|inventory| installed_list(inventory, "plugins"))
+                .filter(|plugins| !plugins.is_empty())
+            {
+                query_response.set_plugins(Some(plugins));
             } else {
                 query_response.set_plugins(Some(vec!["No plugins found".to_string()]))
             }
         }
 
+        if self.options.uptime() {
+            tracing::info!("set uptime");
+            let started_at = *self.process_started.borrow();
+            // A server that was already up when the monitor started has been online since the
+            // agent started it, later streaks begin with the first ping that answers
+            let first_ping = self.last_status.is_none();
+            let online_since = *self.online_since.get_or_insert_with(|| match started_at {
+                Some(started_at) if first_ping => started_at,
+                _ => unix_now(),
+            });
+            query_response.set_uptime(started_at, Some(online_since));
+        }
+
         if self.options.mods() {
             tracing::info!("set mods");
             if let Some(mods) = status.mods {
-                query_response.set_mods(Some(mods.into_iter().map(|mcmod| mcmod.modid).collect()));
+                query_response.set_mods(Some(mods));
+            } else if let Some(mods) = inventory
+                .as_ref()
+                .map(|inventory| installed_list(inventory, "mods"))
+                .filter(|mods| !mods.is_empty())
+            {
+                query_response.set_mods(Some(mods));
             } else {
                 query_response.set_mods(Some(vec!["No mods found".to_string()]))
             }
@@ -209,3 +503,62 @@ impl QueryHandler {
         query_response
     }
 }
+
+// Runs `list` and reads the answer off the console. Newer servers put the names on the same line,
+// older ones, Bukkit and Bedrock put them on the next
+async fn list_players(process: Option<ProcessHandle>) -> Result<Vec<String>> {
+    let Some(mut process) = process.filter(|process| !*process.exited.borrow()) else {
+        return Err(anyhow!("No running process"));
+    };
+    process.command("list")?;
+    let mut names_next = false;
+    loop {
+        let line = match process.console.recv().await {
+            Ok(line) => line,
+            Err(RecvError::Lagged(_)) => continue,
+            Err(RecvError::Closed) => return Err(anyhow!("console closed")),
+        };
+        let message = strip_log_prefix(&line);
+        if names_next {
+            return Ok(split_names(message));
+        }
+        if let Some(start) = message.find("There are")
+            && let Some((_, names)) = message[start..].split_once("players online:")
+        {
+            let message = &message[start..];
+            if names.trim().is_empty() && !message.starts_with("There are 0") {
+                names_next = true;
+            } else {
+                return Ok(split_names(names));
+            }
+        }
+    }
+}
+
+fn split_names(names: &str) -> Vec<String> {
+    names
+        .split(',')
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+        .collect()
+}
+
+fn unix_now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs() as i64)
+}
+
+fn installed_list(inventory: &Inventory, folder: &str) -> Vec<String> {
+    inventory
+        .in_folder(folder)
+        .filter(|installed| installed.enabled)
+        .map(|installed| {
+            if installed.problems.is_empty() {
+                format!("{} {}", installed.name, installed.version)
+            } else {
+                format!("{} {} ⚠️", installed.name, installed.version)
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,43 @@
+use crate::mods::handler_request::{HandlerRequest, start, stop};
+use crate::mods::upgrade::wait_for_startup;
+use anyhow::Result;
+use protocol::serveractions::ServerActions;
+use protocol::tasks::Progress;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tokio::sync::mpsc::UnboundedSender;
+use uuid::Uuid;
+
+pub async fn run(
+    request_id: Uuid,
+    requests: UnboundedSender<HandlerRequest>,
+    sender: UnboundedSender<ServerActions>,
+    task_running: Arc<AtomicBool>,
+) {
+    let progress = match restart(request_id, &requests, &sender).await {
+        Ok(()) => Progress::Finished("Server is back up".to_string()),
+        Err(e) => Progress::Failed(e.to_string()),
+    };
+    if let Err(e) = sender.send(ServerActions::Progress(request_id, progress)) {
+        tracing::error!("Failed to report restart result: {}", e);
+    }
+    task_running.store(false, Ordering::SeqCst);
+}
+
+// Lets the server save and exit on its own before starting it again
+async fn restart(
+    request_id: Uuid,
+    requests: &UnboundedSender<HandlerRequest>,
+    sender: &UnboundedSender<ServerActions>,
+) -> Result<()> {
+    sender.send(ServerActions::Progress(
+        request_id,
+        Progress::Step("Stopping server".to_string()),
+    ))?;
+    stop(requests).await?;
+    sender.send(ServerActions::Progress(
+        request_id,
+        Progress::Step("Starting server".to_string()),
+    ))?;
+    wait_for_startup(start(requests).await?).await
+}
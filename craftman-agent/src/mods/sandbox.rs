@@ -0,0 +1,180 @@
+use anyhow::{Result, anyhow, bail};
+use std::path::{Component, Path, PathBuf};
+
+// Joins a path that came from outside the agent onto the server directory, refusing anything
+// that could climb out of it
+pub fn join_relative(dir: &Path, relative: &str) -> Result<PathBuf> {
+    let path = Path::new(relative);
+    if relative.is_empty()
+        || !path
+            .components()
+            .all(|component| matches!(component, Component::Normal(_) | Component::CurDir))
+    {
+        bail!("{} is not a path inside the server directory", relative);
+    }
+    Ok(dir.join(path))
+}
+
+// Like join_relative, but also follows symlinks so a link inside the server directory can't
+// send the agent somewhere else. Returns where the path really leads, so that's what gets opened
+pub fn resolve(dir: &Path, relative: &str) -> Result<PathBuf> {
+    Ok(dir.canonicalize()?.join(real_path(dir, relative)?))
+}
+
+// Where a path inside the server directory really leads once symlinks are followed, relative to
+// the directory. The part that doesn't exist yet is kept as it was given
+pub fn real_path(dir: &Path, relative: &str) -> Result<PathBuf> {
+    let path = join_relative(dir, relative)?;
+    let root = dir.canonicalize()?;
+    let mut existing = path.as_path();
+    while existing.symlink_metadata().is_err() {
+        existing = existing
+            .parent()
+            .ok_or_else(|| anyhow!("{} has no existing parent", relative))?;
+    }
+    let real = existing.canonicalize()?.join(path.strip_prefix(existing)?);
+    match real.strip_prefix(&root) {
+        Ok(real) => Ok(real.to_path_buf()),
+        Err(_) => bail!("{} leads outside the server directory", relative),
+    }
+}
+
+// Checks a path relative to the server directory and every folder above it against the deny list
+pub fn is_denied(relative: &Path, patterns: &[String]) -> bool {
+    let mut prefix = PathBuf::new();
+    for component in relative.components() {
+        prefix.push(component);
+        let name = component.as_os_str().to_string_lossy();
+        let path = prefix.to_string_lossy().replace('\\', "/");
+        let denied = patterns.iter().any(|pattern| {
+            if pattern.contains('/') {
+                glob(pattern.trim_start_matches("./"), &path)
+            } else {
+                glob(pattern, &name)
+            }
+        });
+        if denied {
+            return true;
+        }
+    }
+    false
+}
+
+fn glob(pattern: &str, text: &str) -> bool {
+    match pattern.split_once('*') {
+        None => pattern == text,
+        Some((prefix, rest)) => {
+            let Some(text) = text.strip_prefix(prefix) else {
+                return false;
+            };
+            (0..=text.len())
+                .filter(|i| text.is_char_boundary(*i))
+                .any(|i| glob(rest, &text[i..]))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    #[test]
+    fn joins_only_relative_paths() {
+        let dir = Path::new("/srv/minecraft");
+        let cases = [
+            ("mods/sodium.jar", Some("/srv/minecraft/mods/sodium.jar")),
+            ("./config/a.toml", Some("/srv/minecraft/config/a.toml")),
+            ("..", None),
+            ("mods/../../etc/passwd", None),
+            ("mods/..", None),
+            ("/etc/passwd", None),
+            ("", None),
+        ];
+        for (relative, expected) in cases {
+            assert_eq!(
+                join_relative(dir, relative).ok(),
+                expected.map(PathBuf::from),
+                "{relative}"
+            );
+        }
+    }
+
+    #[test]
+    fn denies_names_paths_and_parents() {
+        let patterns: Vec<String> = [".env", "*.pem", "secrets/*", "plugins/LuckPerms"]
+            .into_iter()
+            .map(str::to_string)
+            .collect();
+        let cases = [
+            (".env", true),
+            ("config/.env", true),
+            ("cert.pem", true),
+            ("a/b/cert.pem", true),
+            ("cert.pem.bak", false),
+            ("secrets/token", true),
+            ("secrets/nested/token", true),
+            ("other/secrets/token", false),
+            ("plugins/LuckPerms/config.yml", true),
+            ("plugins/LuckPermsExtra/config.yml", false),
+            ("server.properties", false),
+        ];
+        for (path, expected) in cases {
+            assert_eq!(is_denied(Path::new(path), &patterns), expected, "{path}");
+        }
+    }
+
+    #[test]
+    fn globs() {
+        let cases = [
+            ("*", "", true),
+            ("*", "anything", true),
+            ("*.pem", ".pem", true),
+            ("*.pem", "a.pem.txt", false),
+            ("a*b*c", "aXbYc", true),
+            ("a*b*c", "aXc", false),
+            ("*é", "caféé", true),
+            ("exact", "exact", true),
+            ("exact", "exactly", false),
+        ];
+        for (pattern, text, expected) in cases {
+            assert_eq!(glob(pattern, text), expected, "{pattern} against {text}");
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn follows_symlinks() {
+        use std::os::unix::fs::symlink;
+
+        let base = std::env::temp_dir().join(format!("craftman-sandbox-{}", Uuid::new_v4()));
+        let dir = base.join("server");
+        std::fs::create_dir_all(dir.join("config")).unwrap();
+        std::fs::create_dir_all(base.join("outside")).unwrap();
+        std::fs::write(dir.join("config/a.toml"), "").unwrap();
+        std::fs::write(dir.join("key.pem"), "").unwrap();
+        symlink(base.join("outside"), dir.join("escape")).unwrap();
+        symlink(dir.join("key.pem"), dir.join("innocent.txt")).unwrap();
+        symlink(dir.join("config"), dir.join("linked")).unwrap();
+
+        let root = dir.canonicalize().unwrap();
+        assert!(real_path(&dir, "escape").is_err());
+        assert!(real_path(&dir, "escape/new.txt").is_err());
+        assert!(resolve(&dir, "escape/new.txt").is_err());
+        assert_eq!(
+            real_path(&dir, "linked/new/b.toml").unwrap(),
+            PathBuf::from("config/new/b.toml")
+        );
+        assert_eq!(
+            resolve(&dir, "linked/a.toml").unwrap(),
+            root.join("config/a.toml")
+        );
+
+        // The link's own name passes the deny list, where it leads doesn't
+        let patterns = vec!["*.pem".to_string()];
+        let real = real_path(&dir, "innocent.txt").unwrap();
+        assert!(!is_denied(Path::new("innocent.txt"), &patterns));
+        assert!(is_denied(&real, &patterns));
+        std::fs::remove_dir_all(&base).unwrap();
+    }
+}
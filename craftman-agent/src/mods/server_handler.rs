@@ -1,39 +1,95 @@
-use crate::mods::configs::{Configs, RunType};
+use crate::mods::configs::{Configs, RunType, is_bedrock_server};
+use crate::mods::handler_request::HandlerRequest;
+use crate::mods::inventory;
+use crate::mods::mod_files;
+use crate::mods::modpack::{self, ModpackInstall};
+use crate::mods::properties_watcher;
 use crate::mods::query_handler::QueryHandler;
-use crate::mods::server_process::ServerProcess;
+use crate::mods::restart;
+use crate::mods::server_process::{ProcessHandle, ServerProcess};
 use crate::mods::server_properties::ServerProperties;
+use crate::mods::upgrade::{self, Upgrade};
+use crate::mods::version_range::find_version;
 use anyhow::{Result, anyhow, bail};
+use notify::RecommendedWatcher;
+use protocol::inventory::{Inventory, ModChange};
+use protocol::properties::{Edition, PendingChange};
 use protocol::query_options::QueryOptions;
 use protocol::server_commands::ServerCommands;
 use protocol::serveractions::ServerActions;
-use std::path::Path;
-use std::time::Duration;
+use protocol::tasks::RemoteFile;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use tokio::sync::mpsc::UnboundedSender;
-use tokio::sync::oneshot;
+use tokio::sync::{oneshot, watch};
 use tokio::time;
 use uuid::Uuid;
 
 pub struct ServerHandler {
     config: Configs,
     pub properties: Option<ServerProperties>,
+    // What the running server read when it started, changes after that need a restart
+    running_properties: Option<HashMap<String, String>>,
     process: Option<ServerProcess>,
-    query_channel: Option<oneshot::Sender<()>>,
+    // Kept alive so changes made outside the agent are picked up
+    properties_watcher: Option<RecommendedWatcher>,
+    // One update loop per monitor message, by the server's monitor id
+    query_channels: HashMap<i64, oneshot::Sender<()>>,
+    // Runs while the agent is connected, monitor or not, for /stats, alerts and playtime
+    sample_channel: Option<oneshot::Sender<()>>,
+    // Unix seconds when the agent last started the server, for the monitor's uptime
+    process_started: watch::Sender<Option<i64>>,
+    // The running process, for monitors that read the player list off the console
+    process_handle: watch::Sender<Option<ProcessHandle>>,
+    requests: UnboundedSender<HandlerRequest>,
+    task_running: Arc<AtomicBool>,
 }
 
 impl ServerHandler {
-    pub fn new(config: Configs) -> Self {
-        let properties = match ServerProperties::new(&config.dir) {
+    pub fn new(config: Configs, requests: UnboundedSender<HandlerRequest>) -> Self {
+        let properties = match ServerProperties::new(&config.dir, config.run_type.edition()) {
             Ok(prop) => Some(prop),
             Err(e) => panic!("{}", e),
         };
+        let properties_watcher = watch_properties(&config.dir, &requests);
         Self {
             properties,
+            running_properties: None,
             config,
             process: None,
-            query_channel: None,
+            properties_watcher,
+            query_channels: HashMap::new(),
+            sample_channel: None,
+            process_started: watch::Sender::new(None),
+            process_handle: watch::Sender::new(None),
+            requests,
+            task_running: Arc::new(AtomicBool::new(false)),
         }
     }
     pub fn start_server(&mut self, ws_sender: UnboundedSender<ServerActions>) -> Result<()> {
+        self.check_no_job()?;
+        self.start_process(ws_sender)
+    }
+
+    pub async fn stop_server(&mut self) -> Result<()> {
+        self.check_no_job()?;
+        self.stop_process().await
+    }
+
+    // An upgrade or install stops and starts the server itself, and changing anything under it
+    // halfway through could leave a half copied backup or a missing jar
+    fn check_no_job(&self) -> Result<()> {
+        if self.task_running.load(Ordering::SeqCst) {
+            bail!("A job is running on this server, try again when it's done");
+        }
+        Ok(())
+    }
+
+    // Unchecked, for the jobs themselves and for the agent shutting down
+    pub fn start_process(&mut self, ws_sender: UnboundedSender<ServerActions>) -> Result<()> {
         self.process = Some(ServerProcess::new(
             self.config.xms,
             self.config.xmx,
@@ -42,30 +98,156 @@ impl ServerHandler {
             ws_sender,
             &self.config.run_type,
         )?);
+        self.update_properties();
+        self.running_properties = self.properties.as_ref().map(|props| props.values().clone());
+        self.process_handle.send_replace(self.process_handle());
+        self.process_started.send_replace(
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .ok()
+                .map(|since| since.as_secs() as i64),
+        );
         tracing::info!("Started server");
         Ok(())
     }
 
-    pub async fn stop_server(&mut self) -> Result<()> {
-        if let Some(process) = self.process.take() {
+    pub async fn stop_process(&mut self) -> Result<()> {
+        self.running_properties = None;
+        self.process_started.send_replace(None);
+        self.process_handle.send_replace(None);
+        if let Some(process) = self.process.take()
+            && !process.has_exited()
+        {
             process.shutdown()?;
         }
         Ok(())
     }
 
+    pub fn process_handle(&self) -> Option<ProcessHandle> {
+        self.process.as_ref().map(|process| process.handle())
+    }
+
+    pub fn start_upgrade(
+        &mut self,
+        request_id: Uuid,
+        request: RemoteFile,
+        sender: UnboundedSender<ServerActions>,
+    ) -> Result<()> {
+        if self.config.run_type.edition() == Edition::Bedrock {
+            bail!("Upgrades only download Java server jars");
+        }
+        // Scripts name their jar themselves, and replacing the script with a jar would lose
+        // whatever else it sets up
+        if self.config.run_type == RunType::Script {
+            bail!("This server starts from a script. Point the script at the new jar instead");
+        }
+        if self.task_running.swap(true, Ordering::SeqCst) {
+            bail!("Another upgrade or install is already running");
+        }
+        self.update_properties();
+        let level_name = self
+            .get_property("level-name")
+            .unwrap_or("world")
+            .to_string();
+        tokio::spawn(upgrade::run(
+            Upgrade {
+                request_id,
+                request,
+                config: self.config.clone(),
+                level_name,
+            },
+            self.requests.clone(),
+            sender,
+            self.task_running.clone(),
+        ));
+        Ok(())
+    }
+
+    pub fn start_modpack_install(
+        &mut self,
+        request_id: Uuid,
+        pack: RemoteFile,
+        sender: UnboundedSender<ServerActions>,
+    ) -> Result<()> {
+        if self.config.run_type.edition() == Edition::Bedrock {
+            bail!("Modpacks can't be installed on a Bedrock server");
+        }
+        if self.task_running.swap(true, Ordering::SeqCst) {
+            bail!("Another upgrade or install is already running");
+        }
+        tokio::spawn(modpack::run(
+            ModpackInstall {
+                request_id,
+                pack,
+                config: self.config.clone(),
+            },
+            self.requests.clone(),
+            sender,
+            self.task_running.clone(),
+        ));
+        Ok(())
+    }
+
+    // Waits its turn like the other jobs, so an upgrade or modpack install doesn't have its
+    // files changed halfway through
+    pub fn start_mod_change(
+        &self,
+        request_id: Uuid,
+        change: ModChange,
+        sender: UnboundedSender<ServerActions>,
+    ) -> Result<()> {
+        // Bedrock add-ons are packs inside the world, not jars in a mods folder
+        if self.config.run_type.edition() == Edition::Bedrock {
+            bail!("Mods and plugins can't be managed on a Bedrock server");
+        }
+        if self.task_running.swap(true, Ordering::SeqCst) {
+            bail!("Another upgrade or install is already running");
+        }
+        tokio::spawn(mod_files::run(
+            request_id,
+            PathBuf::from(&self.config.dir),
+            change,
+            sender,
+            self.task_running.clone(),
+        ));
+        Ok(())
+    }
+
+    pub fn start_restart(
+        &mut self,
+        request_id: Uuid,
+        sender: UnboundedSender<ServerActions>,
+    ) -> Result<()> {
+        if self.task_running.swap(true, Ordering::SeqCst) {
+            bail!("Another upgrade or install is already running");
+        }
+        tokio::spawn(restart::run(
+            request_id,
+            self.requests.clone(),
+            sender,
+            self.task_running.clone(),
+        ));
+        Ok(())
+    }
+
     pub fn update_properties(&mut self) -> &Self {
         let path_str = format!("{}/server.properties", self.config.dir);
         let path = Path::new(&path_str);
 
         if path.exists() {
             match self.properties.as_mut() {
-                Some(props) if props.dir == self.config.dir => {
+                Some(props)
+                    if props.dir == self.config.dir
+                        && props.edition() == self.config.run_type.edition() =>
+                {
                     if let Err(e) = props.update() {
                         tracing::info!("Failed to update server properties: {}", e);
                     }
                 }
                 _ => {
-                    self.properties = ServerProperties::new(&self.config.dir).ok();
+                    self.properties =
+                        ServerProperties::new(&self.config.dir, self.config.run_type.edition())
+                            .ok();
                 }
             }
         } else {
@@ -75,6 +257,25 @@ impl ServerHandler {
         self
     }
 
+    // Re-reads the file and says whether any value changed
+    pub fn reload_properties(&mut self) -> bool {
+        let before = self.properties.as_ref().map(|props| props.values().clone());
+        self.update_properties();
+        before != self.properties.as_ref().map(|props| props.values().clone())
+    }
+
+    // Sent without a request so open settings views can refresh
+    pub fn push_properties(&self, sender: &UnboundedSender<ServerActions>) -> Result<()> {
+        let props = self
+            .properties
+            .as_ref()
+            .ok_or_else(|| anyhow!("Properties not found"))?;
+        sender.send(ServerActions::PropsChanged(
+            props.snapshot(self.pending_changes()),
+        ))?;
+        Ok(())
+    }
+
     pub fn get_property(&mut self, property: &str) -> Result<&str> {
         Ok(self
             .properties
@@ -84,6 +285,37 @@ impl ServerHandler {
             .ok_or_else(|| anyhow!("{} not found in properties", property))?)
     }
 
+    // Checks the value against the property schema before writing it
+    pub fn set_property(&mut self, property: &str, value: &str) -> Result<()> {
+        self.check_no_job()?;
+        let value = self
+            .config
+            .run_type
+            .edition()
+            .validate(property, value)
+            .map_err(|e| anyhow!(e))?;
+        self.set(property, &value)
+    }
+
+    // Every value is checked before any is written, so a bad one leaves the file as it was
+    pub fn set_properties(&mut self, changes: Vec<(String, String)>) -> Result<()> {
+        self.check_no_job()?;
+        let edition = self.config.run_type.edition();
+        let changes = changes
+            .into_iter()
+            .map(|(key, value)| {
+                let value = edition
+                    .validate(&key, &value)
+                    .map_err(|e| anyhow!("{}: {}", key, e))?;
+                Ok((key, value))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        self.properties
+            .as_mut()
+            .ok_or_else(|| anyhow!("Properties not found"))?
+            .set_many(&changes)
+    }
+
     pub fn set(&mut self, property: &str, value: &str) -> Result<()> {
         self.properties
             .as_mut()
@@ -95,43 +327,154 @@ impl ServerHandler {
         sender: UnboundedSender<ServerActions>,
         uuid: Uuid,
     ) -> Result<()> {
+        let pending = self.pending_changes();
         self.properties
             .as_mut()
             .ok_or_else(|| anyhow!("Properties not found"))?
-            .send_response(sender, uuid)
+            .send_response(sender, uuid, pending)
             .await?;
         Ok(())
     }
 
+    fn pending_changes(&self) -> Vec<PendingChange> {
+        let (Some(running), Some(props), Some(process)) =
+            (&self.running_properties, &self.properties, &self.process)
+        else {
+            return Vec::new();
+        };
+        if process.has_exited() {
+            return Vec::new();
+        }
+        let current = props.values();
+        let mut keys: Vec<&String> = running.keys().chain(current.keys()).collect();
+        keys.sort();
+        keys.dedup();
+        keys.into_iter()
+            .filter(|key| running.get(*key) != current.get(*key))
+            .map(|key| PendingChange {
+                key: key.clone(),
+                running: running.get(key).cloned(),
+                file: current.get(key).cloned(),
+            })
+            .collect()
+    }
+
     pub async fn start_query(
         &mut self,
+        monitor: i64,
         options: QueryOptions,
+        interval: u64,
+        sender: UnboundedSender<ServerActions>,
+        request_id: Uuid,
+    ) -> Result<()> {
+        let mut query_handler = self.query_handler(options)?;
+        query_handler.respond(sender.clone(), request_id).await?;
+
+        // Reconnects start every monitor again, so an old loop for the same message is replaced
+        self.stop_query(monitor);
+        let (c_sender, c_receiver) = oneshot::channel();
+        self.query_channels.insert(monitor, c_sender);
+        tokio::spawn(query_loop(
+            query_handler,
+            monitor,
+            Duration::from_secs(interval.max(MIN_QUERY_INTERVAL)),
+            c_receiver,
+            sender.clone(),
+        ));
+
+        // In case server.properties wasn't there yet when the agent connected
+        if self.sample_channel.is_none() {
+            self.start_sampling(sender)?;
+        }
+        Ok(())
+    }
+
+    pub fn start_sampling(&mut self, sender: UnboundedSender<ServerActions>) -> Result<()> {
+        let sampler = self.query_handler(QueryOptions::default())?;
+        let (c_sender, c_receiver) = oneshot::channel();
+        // Replacing the sender drops the old one, which ends its loop
+        self.sample_channel = Some(c_sender);
+        tokio::spawn(sample_loop(sampler, c_receiver, sender));
+        Ok(())
+    }
+
+    // Samples taken while disconnected would all arrive at once on reconnect, stamped with the
+    // wrong time
+    pub fn stop_sampling(&mut self) {
+        if let Some(sender) = self.sample_channel.take() {
+            let _ = sender.send(());
+        }
+    }
+
+    // Status pages ask for this, so it only answers with what the ping and UDP query know and
+    // never runs `list` on the console
+    pub async fn request_status(
+        &mut self,
         sender: UnboundedSender<ServerActions>,
         request_id: Uuid,
     ) -> Result<()> {
+        self.query_handler(QueryOptions::all())?
+            .respond(sender, request_id)
+            .await
+    }
+
+    fn query_handler(&mut self, options: QueryOptions) -> Result<QueryHandler> {
         self.update_properties();
         let Some(props) = &self.properties else {
             bail!("No properties for this server");
         };
 
-        let mut query_handler = QueryHandler::new(
+        Ok(QueryHandler::new(
             props
                 .get("server-port")
                 .ok_or_else(|| anyhow!("No server port found"))?
                 .parse::<u32>()?,
+            self.config.run_type.edition(),
+            PathBuf::from(&self.config.dir),
             options,
-        );
+            self.process_started.subscribe(),
+            query_port(props),
+            self.process_handle.subscribe(),
+        ))
+    }
 
-        query_handler.respond(sender.clone(), request_id).await?;
+    pub async fn mod_inventory(&mut self) -> Result<Inventory> {
+        let version = self.minecraft_version().await;
+        let dir = PathBuf::from(&self.config.dir);
+        Ok(tokio::task::spawn_blocking(move || inventory::scan(&dir, version)).await?)
+    }
 
-        let (c_sender, c_receiver) = oneshot::channel();
-        self.query_channel = Some(c_sender);
-        tokio::spawn(query_loop(query_handler, c_receiver, sender));
-        Ok(())
+    // A running server knows its version best, otherwise look inside the server jar
+    async fn minecraft_version(&mut self) -> Option<String> {
+        self.update_properties();
+        if let Some(port) = self
+            .get_property("server-port")
+            .ok()
+            .and_then(|port| port.parse::<u32>().ok())
+            && let Ok(status) = QueryHandler::new(
+                port,
+                self.config.run_type.edition(),
+                PathBuf::from(&self.config.dir),
+                QueryOptions::default(),
+                self.process_started.subscribe(),
+                None,
+                self.process_handle.subscribe(),
+            )
+            .ping()
+            .await
+            && let Some(version) = find_version(&status.version)
+        {
+            return Some(version);
+        }
+        let jar = Path::new(&self.config.dir).join(&self.config.jar);
+        tokio::task::spawn_blocking(move || inventory::jar_version(&jar))
+            .await
+            .ok()
+            .flatten()
     }
 
-    pub fn stop_query(&mut self) {
-        if let Some(sender) = self.query_channel.take() {
+    pub fn stop_query(&mut self, monitor: i64) {
+        if let Some(sender) = self.query_channels.remove(&monitor) {
             let _ = sender.send(());
         }
     }
@@ -176,27 +519,43 @@ impl ServerHandler {
             config.run_type = RunType::Script;
         }
 
-        if config.jar.ends_with(".jar") && config.run_type == RunType::Script {
+        if config.jar.ends_with(".jar")
+            && matches!(config.run_type, RunType::Script | RunType::Bedrock)
+        {
             config.run_type = RunType::Default;
         }
+
+        if is_bedrock_server(&config.jar) {
+            config.run_type = RunType::Bedrock;
+        }
+        if config.dir != self.config.dir {
+            self.properties_watcher = watch_properties(&config.dir, &self.requests);
+        }
         self.config = config;
         self.config.save();
         Ok(())
     }
 }
 
+// How often the player count and latency history gets a new point
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(60);
+// Each monitor picks its own interval, but pinging more often than this just adds load
+const MIN_QUERY_INTERVAL: u64 = 5;
+
 async fn query_loop(
     mut query_handler: QueryHandler,
+    monitor: i64,
+    period: Duration,
     mut receiver: oneshot::Receiver<()>,
     sender: UnboundedSender<ServerActions>,
 ) -> Result<()> {
-    let mut interval = time::interval(Duration::from_secs(10));
+    let mut interval = time::interval(period);
 
     loop {
-        tracing::debug!("Updating query monitor");
+        tracing::debug!("Updating query monitor {}", monitor);
         tokio::select! {
             _ = interval.tick() => {
-                if let Err(e) = query_handler.update(sender.clone()).await {
+                if let Err(e) = query_handler.update(monitor, sender.clone()).await {
                     tracing::info!("Query update failed: {e}");
                 }
             }
@@ -207,7 +566,59 @@ async fn query_loop(
             }
         }
     }
-    tracing::info!("Exiting update loop");
+    tracing::info!("Exiting update loop for monitor {}", monitor);
 
     Ok(())
 }
+
+async fn sample_loop(
+    query_handler: QueryHandler,
+    mut receiver: oneshot::Receiver<()>,
+    sender: UnboundedSender<ServerActions>,
+) -> Result<()> {
+    let mut sample_interval = time::interval(SAMPLE_INTERVAL);
+    // The first tick fires straight away. Skipping it keeps samples a minute apart when the agent
+    // reconnects quickly
+    sample_interval.tick().await;
+
+    loop {
+        tokio::select! {
+            _ = sample_interval.tick() => {
+                let sample = query_handler.sample().await;
+                if let Err(e) = sender.send(ServerActions::QuerySample(sample)) {
+                    tracing::info!("Sending query sample failed: {e}");
+                }
+            }
+
+            _ = &mut receiver => {
+                break;
+            }
+        }
+    }
+    tracing::info!("Exiting sample loop");
+
+    Ok(())
+}
+
+// Bedrock has no GameSpy query
+fn query_port(props: &ServerProperties) -> Option<u16> {
+    if props.edition() == Edition::Bedrock
+        || props.get("enable-query").map(String::as_str) != Some("true")
+    {
+        return None;
+    }
+    props.get("query.port")?.parse().ok()
+}
+
+fn watch_properties(
+    dir: &str,
+    requests: &UnboundedSender<HandlerRequest>,
+) -> Option<RecommendedWatcher> {
+    match properties_watcher::watch(dir, requests.clone()) {
+        Ok(watcher) => Some(watcher),
+        Err(e) => {
+            tracing::warn!("Not watching server.properties for changes: {}", e);
+            None
+        }
+    }
+}
@@ -2,6 +2,8 @@ use protocol::server_commands::ServerCommands;
 use protocol::serveractions::ServerActions;
 use std::path::Path;
 use std::process::Stdio;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
 use tokio::io::{AsyncWriteExt, Lines};
 use tokio::select;
 use tokio::sync::mpsc::{self, UnboundedReceiver};
@@ -12,14 +14,46 @@ use tokio::process::{ChildStderr, ChildStdin, ChildStdout};
 use tokio::{
     io::BufReader,
     process::Command,
-    sync::{mpsc::UnboundedSender, watch},
+    sync::{broadcast, mpsc::UnboundedSender, watch},
 };
 
 use crate::mods::configs::RunType;
+use crate::mods::player_events;
+use protocol::properties::Edition;
 
 pub struct ServerProcess {
     watch_sender: watch::Sender<bool>,
     command_sender: UnboundedSender<ServerCommands>,
+    console: broadcast::Sender<String>,
+    exited: watch::Receiver<bool>,
+    // Set once the server has been told to stop, so its exit isn't reported as a crash
+    stopping: Arc<AtomicBool>,
+}
+
+// Lets background jobs follow the console and notice when the process goes away without holding
+// on to the ServerHandler
+pub struct ProcessHandle {
+    pub console: broadcast::Receiver<String>,
+    pub exited: watch::Receiver<bool>,
+    commands: UnboundedSender<ServerCommands>,
+}
+
+impl ProcessHandle {
+    // Another subscription to the same process, starting at its next line
+    pub fn resubscribe(&self) -> ProcessHandle {
+        ProcessHandle {
+            console: self.console.resubscribe(),
+            exited: self.exited.clone(),
+            commands: self.commands.clone(),
+        }
+    }
+
+    // Whatever the command prints arrives on this handle's console, since it was subscribed first
+    pub fn command(&self, command: &str) -> Result<()> {
+        self.commands
+            .send(ServerCommands::Command(command.to_string()))?;
+        Ok(())
+    }
 }
 
 impl ServerProcess {
@@ -78,6 +112,14 @@ impl ServerProcess {
                     .stderr(Stdio::piped())
                     .spawn()?
             }
+            // bedrock_server loads the libraries it ships with from its own folder
+            RunType::Bedrock => Command::new(format!("./{}", jar))
+                .current_dir(dir)
+                .env("LD_LIBRARY_PATH", ".")
+                .stdin(Stdio::piped())
+                .stdout(Stdio::piped())
+                .stderr(Stdio::piped())
+                .spawn()?,
         };
         let (watch_sender, watch_receiver) = watch::channel(false);
 
@@ -96,6 +138,21 @@ impl ServerProcess {
             .take()
             .ok_or_else(|| anyhow!("No stdin found for child. Initialization failed"))?;
 
+        let (exit_sender, exited) = watch::channel(false);
+        let stopping = Arc::new(AtomicBool::new(false));
+        let exit_ws_sender = ws_sender.clone();
+        let expected = stopping.clone();
+        tokio::spawn(async move {
+            if let Err(e) = child.wait().await {
+                tracing::error!("Error waiting on server process: {}", e);
+            }
+            let _ = exit_sender.send(true);
+            let _ = exit_ws_sender.send(ServerActions::ServerExited {
+                expected: expected.load(Ordering::SeqCst),
+            });
+        });
+
+        let (console, _) = broadcast::channel::<String>(256);
         let (command_sender, command_receiver) = mpsc::unbounded_channel::<ServerCommands>();
         tokio::spawn(commander(command_receiver, stdin));
         tokio::spawn(chat_listener(
@@ -103,24 +160,48 @@ impl ServerProcess {
             err_lines,
             ws_sender.clone(),
             watch_receiver,
+            console.clone(),
+            run_type.edition(),
         ));
         Ok(ServerProcess {
             watch_sender,
             command_sender,
+            console,
+            exited,
+            stopping,
         })
     }
 
+    pub fn handle(&self) -> ProcessHandle {
+        ProcessHandle {
+            console: self.console.subscribe(),
+            exited: self.exited.clone(),
+            commands: self.command_sender.clone(),
+        }
+    }
+
+    pub fn has_exited(&self) -> bool {
+        *self.exited.borrow()
+    }
+
     pub fn set_chat(&self, on: bool) -> Result<()> {
         self.watch_sender.send(on)?;
         Ok(())
     }
 
     pub fn shutdown(self) -> Result<()> {
+        self.stopping.store(true, Ordering::SeqCst);
         self.command_sender.send(ServerCommands::Stop)?;
         Ok(())
     }
 
     pub fn send_command(&self, command: ServerCommands) -> Result<()> {
+        // Typing stop into the console is as deliberate as /server stop
+        if let ServerCommands::Command(line) = &command
+            && line.trim().trim_start_matches('/') == "stop"
+        {
+            self.stopping.store(true, Ordering::SeqCst);
+        }
         self.command_sender.send(command)?;
         Ok(())
     }
@@ -131,6 +212,8 @@ async fn chat_listener(
     mut err_lines: Lines<BufReader<ChildStderr>>,
     sender: UnboundedSender<ServerActions>,
     mut watcher: watch::Receiver<bool>,
+    console: broadcast::Sender<String>,
+    edition: Edition,
 ) -> Result<()> {
     loop {
         select! {
@@ -138,8 +221,13 @@ async fn chat_listener(
                 match line? {
                     Some(new_message) => {
                         tracing::info!("{}", new_message);
+                        let _ = console.send(new_message.clone());
+                        if let Some(event) = player_events::parse(&new_message, edition) {
+                            sender.send(ServerActions::PlayerEvent(event))?;
+                        }
                         if *watcher.borrow()
-                        {sender.send(ServerActions::ChatMessage(new_message))?;
+                            && let Some(message) = chat_line(new_message, edition)
+                        {sender.send(ServerActions::ChatMessage(message))?;
                     }}
                     None => break, // stdout closed
                 }
@@ -149,6 +237,7 @@ async fn chat_listener(
                 match line? {
                     Some(new_message) => {
                         tracing::info!("{}", new_message);
+                        let _ = console.send(new_message.clone());
                         if *watcher.borrow()
                         {sender.send(ServerActions::ChatMessage(new_message))?;
                     }}
@@ -164,6 +253,52 @@ async fn chat_listener(
     Ok(())
 }
 
+// Bedrock logs joins as "Player connected: Steve, xuid: 2535...". They're rewritten to read like
+// Java's, and the other xuid lines are left out of the chat
+fn chat_line(line: String, edition: Edition) -> Option<String> {
+    if edition == Edition::Java {
+        return Some(line);
+    }
+    let message = strip_log_prefix(&line);
+    let player = |event: &str| {
+        message
+            .strip_prefix(event)
+            .and_then(|rest| rest.split_once(','))
+            .map(|(name, _)| name.trim().to_string())
+    };
+    if let Some(name) = player("Player connected:") {
+        Some(format!("{} joined the game", name))
+    } else if let Some(name) = player("Player disconnected:") {
+        Some(format!("{} left the game", name))
+    } else if message.contains("xuid:") {
+        None
+    } else {
+        Some(message.to_string())
+    }
+}
+
+// Java logs "[12:00:00] [Server thread/INFO]: msg", with more bracketed groups on Forge, and
+// Bedrock "[2024-01-01 12:00:00:000 INFO] msg". Only the groups at the start are the prefix, so a
+// "]: " typed into chat can't move where the message begins
+pub fn strip_log_prefix(line: &str) -> &str {
+    let mut rest = line;
+    while let Some(group) = rest.strip_prefix('[') {
+        let Some(end) = group.find(']') else {
+            break;
+        };
+        rest = &group[end + 1..];
+        if let Some(message) = rest.strip_prefix(": ") {
+            return message;
+        }
+        match rest.strip_prefix(' ') {
+            Some(next) if next.starts_with('[') => rest = next,
+            Some(message) => return message,
+            None => break,
+        }
+    }
+    line
+}
+
 async fn commander(
     mut receiver: UnboundedReceiver<ServerCommands>,
     mut stdin: ChildStdin,
@@ -205,3 +340,95 @@ fn update_user_jvm_args(path: &Path, xms_mb: u32, xmx_mb: u32) -> std::io::Resul
     let output = format!("{}\n", lines.join("\n"));
     std::fs::write(path, output)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strips_log_prefixes() {
+        let cases = [
+            (
+                "[12:00:00] [Server thread/INFO]: Steve joined the game",
+                "Steve joined the game",
+            ),
+            (
+                "[12:00:00 INFO]: Steve joined the game",
+                "Steve joined the game",
+            ),
+            (
+                "[12:00:00] [Server thread/INFO] [minecraft/DedicatedServer]: Steve left the game",
+                "Steve left the game",
+            ),
+            (
+                "[2024-01-01 12:00:00:000 INFO] Player connected: Steve, xuid: 2535",
+                "Player connected: Steve, xuid: 2535",
+            ),
+            ("No prefix at all", "No prefix at all"),
+            ("[unclosed prefix: msg", "[unclosed prefix: msg"),
+        ];
+        for (line, message) in cases {
+            assert_eq!(strip_log_prefix(line), message, "{}", line);
+        }
+    }
+
+    #[test]
+    fn chat_cant_move_the_prefix() {
+        let cases = [
+            (
+                "[12:00:00] [Server thread/INFO]: <Mallory> a]: Alice joined the game",
+                "<Mallory> a]: Alice joined the game",
+            ),
+            (
+                "[12:00:00] [Server thread/INFO]: [Server] x] Alice left the game",
+                "[Server] x] Alice left the game",
+            ),
+            (
+                "[12:00:00 INFO]: <Mallory> [a]: Alice joined the game",
+                "<Mallory> [a]: Alice joined the game",
+            ),
+        ];
+        for (line, message) in cases {
+            assert_eq!(strip_log_prefix(line), message, "{}", line);
+        }
+    }
+
+    #[test]
+    fn bedrock_chat_lines() {
+        let cases = [
+            (
+                "[2024-01-01 12:00:00:000 INFO] Player connected: Steve, xuid: 2535",
+                Some("Steve joined the game"),
+            ),
+            (
+                "[2024-01-01 12:00:00:000 INFO] Player disconnected: Steve, xuid: 2535",
+                Some("Steve left the game"),
+            ),
+            (
+                "[2024-01-01 12:00:00:000 INFO] Player Spawned: Steve xuid: 2535",
+                None,
+            ),
+            (
+                "[2024-01-01 12:00:00:000 INFO] Server started.",
+                Some("Server started."),
+            ),
+        ];
+        for (line, chat) in cases {
+            assert_eq!(
+                chat_line(line.to_string(), Edition::Bedrock).as_deref(),
+                chat,
+                "{}",
+                line
+            );
+        }
+    }
+
+    #[test]
+    fn java_chat_lines_pass_through() {
+        let line = "[12:00:00] [Server thread/INFO]: <Steve> hi";
+        assert_eq!(
+            chat_line(line.to_string(), Edition::Java).as_deref(),
+            Some(line)
+        );
+    }
+}
@@ -1,65 +1,108 @@
 use anyhow::{Ok, Result, bail};
+use protocol::properties::{Edition, PendingChange, Properties};
+use protocol::properties_document::PropertiesDocument;
 use protocol::serveractions::ServerActions;
 use std::collections::HashMap;
-use std::fs::File;
-use std::fs::OpenOptions;
-use std::io::{BufReader, BufWriter, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
 use tokio::sync::mpsc::UnboundedSender;
 use uuid::Uuid;
 
 pub struct ServerProperties {
+    // What the server runs with, the file's values on top of Minecraft's defaults
     properties: HashMap<String, String>,
     pub dir: String,
+    edition: Edition,
 }
 
 impl ServerProperties {
-    pub fn new(dir: &str) -> Result<Self> {
-        let reader = BufReader::new(File::open(format!("{}/server.properties", dir))?);
-        let mut properties = default_properties();
-        properties.extend(java_properties::read(reader)?);
-        Ok(ServerProperties {
-            properties,
+    pub fn new(dir: &str, edition: Edition) -> Result<Self> {
+        let mut properties = ServerProperties {
+            properties: HashMap::new(),
             dir: dir.to_string(),
-        })
+            edition,
+        };
+        properties.update()?;
+        Ok(properties)
     }
     pub fn get(&self, key: &str) -> Option<&String> {
         self.properties.get(key)
     }
     pub fn set(&mut self, key: &str, value: &str) -> Result<()> {
-        if !self.properties.contains_key(key) {
-            bail!("Key {} not found", key);
+        self.set_many(&[(key.to_string(), value.to_string())])
+    }
+    // Only the changed lines are touched. Keys the file doesn't have yet are added at the end
+    pub fn set_many(&mut self, changes: &[(String, String)]) -> Result<()> {
+        for (key, _) in changes {
+            if !self.properties.contains_key(key) && self.edition.schema(key).is_none() {
+                bail!("Key {} not found", key);
+            }
         }
-        let mut file = OpenOptions::new()
-            .read(true)
-            .write(true)
-            .truncate(true)
-            .open(format!("{}/server.properties", self.dir))?;
-
-        {
-            let mut temp = self.properties.clone();
-            temp.insert(key.to_string(), value.to_string());
-            let mut writer = BufWriter::new(&file);
-            java_properties::write(&mut writer, &temp)?
+        // Read again so changes made since the last update aren't lost
+        let mut document = self.read()?;
+        for (key, value) in changes {
+            document.set(key, value);
         }
 
-        file.seek(SeekFrom::Start(0))?;
-        let reader = BufReader::new(file);
-        self.properties = java_properties::read(reader)?;
+        // Written next to the file and renamed over it so the server never reads half a file
+        let path = self.path();
+        let part = Path::new(&self.dir).join(".server.properties.part");
+        std::fs::write(&part, document.to_string())?;
+        std::fs::rename(&part, &path)?;
+
+        self.apply(&document);
         Ok(())
     }
     pub fn update(&mut self) -> Result<()> {
-        let reader = BufReader::new(File::open(format!("{}/server.properties", self.dir))?);
-        self.properties = java_properties::read(reader)?;
+        let document = self.read()?;
+        self.apply(&document);
         Ok(())
     }
+    pub fn values(&self) -> &HashMap<String, String> {
+        &self.properties
+    }
     pub async fn send_response(
         &self,
         sender: UnboundedSender<ServerActions>,
         uuid: Uuid,
+        pending: Vec<PendingChange>,
     ) -> Result<()> {
-        sender.send(ServerActions::PropsResponse(uuid, self.properties.clone()))?;
+        sender.send(ServerActions::PropsResponse(uuid, self.snapshot(pending)))?;
         Ok(())
     }
+    pub fn snapshot(&self, pending: Vec<PendingChange>) -> Properties {
+        Properties {
+            values: self.properties.clone(),
+            pending,
+            edition: self.edition,
+        }
+    }
+
+    fn path(&self) -> PathBuf {
+        Path::new(&self.dir).join("server.properties")
+    }
+
+    fn read(&self) -> Result<PropertiesDocument> {
+        Ok(PropertiesDocument::parse(&std::fs::read_to_string(
+            self.path(),
+        )?))
+    }
+
+    pub fn edition(&self) -> Edition {
+        self.edition
+    }
+
+    fn apply(&mut self, document: &PropertiesDocument) {
+        // Bedrock servers write out every key themselves
+        self.properties = match self.edition {
+            Edition::Java => default_properties(),
+            Edition::Bedrock => HashMap::new(),
+        };
+        self.properties.extend(
+            document
+                .entries()
+                .map(|(key, value)| (key.to_string(), value.to_string())),
+        );
+    }
 }
 
 fn default_properties() -> HashMap<String, String> {
@@ -0,0 +1,428 @@
+use crate::mods::configs::{Configs, RunType};
+use crate::mods::download::download;
+use crate::mods::handler_request::{HandlerRequest, edit_config, start, stop};
+use crate::mods::server_process::ProcessHandle;
+use anyhow::{Result, anyhow, bail};
+use protocol::serveractions::ServerActions;
+use protocol::tasks::{Progress, RemoteFile};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::mpsc::UnboundedSender;
+use tokio::sync::watch;
+use tokio::time::timeout;
+use uuid::Uuid;
+
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(600);
+// Older backups are deleted once a newer upgrade or install succeeds
+const KEEP_BACKUPS: usize = 3;
+pub const BACKUP_DIR: &str = "craftman-backups";
+const BACKUP_PREFIX: &str = "upgrade-";
+
+pub struct Upgrade {
+    pub request_id: Uuid,
+    pub request: RemoteFile,
+    pub config: Configs,
+    pub level_name: String,
+}
+
+struct Backup {
+    dir: PathBuf,
+    jar: PathBuf,
+    worlds: Vec<String>,
+    // Dimension folders that didn't exist yet. The new jar may create or convert them, so they
+    // go again on restore
+    absent: Vec<String>,
+}
+
+pub async fn run(
+    upgrade: Upgrade,
+    requests: UnboundedSender<HandlerRequest>,
+    sender: UnboundedSender<ServerActions>,
+    task_running: Arc<AtomicBool>,
+) {
+    let request_id = upgrade.request_id;
+    let result = upgrade_server(&upgrade, &requests, &sender).await;
+    let progress = match result {
+        Ok(()) => Progress::Finished(format!(
+            "Server is running on {}",
+            upgrade.request.file_name
+        )),
+        Err(e) => Progress::Failed(e.to_string()),
+    };
+    if let Err(e) = sender.send(ServerActions::Progress(request_id, progress)) {
+        tracing::error!("Failed to report upgrade result: {}", e);
+    }
+    task_running.store(false, Ordering::SeqCst);
+}
+
+async fn upgrade_server(
+    upgrade: &Upgrade,
+    requests: &UnboundedSender<HandlerRequest>,
+    sender: &UnboundedSender<ServerActions>,
+) -> Result<()> {
+    let dir = Path::new(&upgrade.config.dir);
+    let file_name = &upgrade.request.file_name;
+    if !file_name.ends_with(".jar") || file_name.contains(['/', '\\']) || file_name.starts_with('.')
+    {
+        bail!("{} is not a valid jar name", file_name);
+    }
+
+    let step = |message: String| -> Result<()> {
+        tracing::info!("Upgrade: {}", message);
+        sender.send(ServerActions::Progress(
+            upgrade.request_id,
+            Progress::Step(message),
+        ))?;
+        Ok(())
+    };
+
+    // Download before stopping anything so the server stays up as long as possible
+    step(format!("Downloading {}", file_name))?;
+    let part = dir.join(format!(".{}.part", file_name));
+    let bytes = download(&upgrade.request.url).await?;
+    tokio::fs::write(&part, &bytes).await?;
+
+    step("Stopping server".to_string())?;
+    let was_running = match stop(requests).await {
+        Ok(was_running) => was_running,
+        Err(e) => {
+            let _ = tokio::fs::remove_file(&part).await;
+            return Err(e);
+        }
+    };
+
+    step(format!(
+        "Backing up {} and {}",
+        upgrade.config.jar, upgrade.level_name
+    ))?;
+    let backup = {
+        let dir = dir.to_path_buf();
+        let jar = upgrade.config.jar.clone();
+        let level_name = upgrade.level_name.clone();
+        tokio::task::spawn_blocking(move || create_backup(&dir, &jar, &level_name)).await?
+    };
+    let backup = match backup {
+        Ok(backup) => backup,
+        Err(e) => {
+            let _ = tokio::fs::remove_file(&part).await;
+            if was_running {
+                start(requests).await?;
+            }
+            return Err(e);
+        }
+    };
+
+    // From here on anything that goes wrong puts the backup back
+    let Err(reason) = install(upgrade, &part, requests, &step).await else {
+        let backups = dir.join(BACKUP_DIR);
+        if let Err(e) =
+            tokio::task::spawn_blocking(move || prune_backups(&backups, BACKUP_PREFIX)).await?
+        {
+            tracing::warn!("Failed to delete old upgrade backups: {}", e);
+        }
+        return Ok(());
+    };
+
+    step(format!("Upgrade failed ({}). Rolling back", reason))?;
+    let _ = tokio::fs::remove_file(&part).await;
+    // Copying the world back under a server that still has it open would corrupt it
+    if let Err(e) = stop(requests).await {
+        bail!(
+            "Upgrade to {} failed: {}. The server didn't stop ({}), so the backup wasn't restored, it's in {}",
+            file_name,
+            reason,
+            e,
+            backup.dir.display()
+        );
+    }
+    {
+        let dir = dir.to_path_buf();
+        let new_jar = file_name.clone();
+        let backup_dir = backup.dir.clone();
+        tokio::task::spawn_blocking(move || restore_backup(&dir, &backup, &new_jar))
+            .await?
+            .map_err(|e| {
+                anyhow!(
+                    "Upgrade to {} failed: {}. Restoring the backup failed too ({}), it's in {}",
+                    file_name,
+                    reason,
+                    e,
+                    backup_dir.display()
+                )
+            })?;
+    }
+    edit_config(requests, upgrade.config.clone()).await?;
+    if was_running {
+        step(format!("Restarting {}", upgrade.config.jar))?;
+        start(requests).await?;
+    }
+
+    bail!(
+        "Upgrade to {} failed: {}. Restored {} and the world from backup",
+        file_name,
+        reason,
+        upgrade.config.jar
+    )
+}
+
+async fn install(
+    upgrade: &Upgrade,
+    part: &Path,
+    requests: &UnboundedSender<HandlerRequest>,
+    step: &impl Fn(String) -> Result<()>,
+) -> Result<()> {
+    let file_name = &upgrade.request.file_name;
+    step(format!("Installing {}", file_name))?;
+    tokio::fs::rename(part, Path::new(&upgrade.config.dir).join(file_name)).await?;
+    edit_config(requests, upgraded_config(&upgrade.config, file_name)).await?;
+
+    step(format!("Starting {}", file_name))?;
+    wait_for_startup(start(requests).await?).await
+}
+
+// The default run type picks the jar up from the config, custom arguments have to have the old
+// jar swapped out by hand. Scripts are refused before getting here
+fn upgraded_config(config: &Configs, file_name: &str) -> Configs {
+    let run_type = match &config.run_type {
+        RunType::CustomJar(args) => RunType::CustomJar(
+            args.iter()
+                .map(|arg| {
+                    if *arg == config.jar {
+                        file_name.to_string()
+                    } else {
+                        arg.clone()
+                    }
+                })
+                .collect(),
+        ),
+        run_type => run_type.clone(),
+    };
+    config
+        .clone()
+        .set_jar(file_name.to_string())
+        .set_run_type(run_type)
+}
+
+pub async fn wait_for_startup(mut handle: ProcessHandle) -> Result<()> {
+    let startup = async {
+        loop {
+            tokio::select! {
+                line = handle.console.recv() => match line {
+                    // Bedrock says "Server started." where Java says "Done (4.2s)!"
+                    Ok(line) if line.contains("Done (") || line.contains("Server started.") => {
+                        return Ok(());
+                    }
+                    Ok(_) | Err(RecvError::Lagged(_)) => {}
+                    Err(RecvError::Closed) => bail!("console closed"),
+                },
+                _ = exited(&mut handle.exited) => bail!("server exited during startup"),
+            }
+        }
+    };
+    timeout(STARTUP_TIMEOUT, startup)
+        .await
+        .map_err(|_| anyhow!("server did not finish starting in time"))?
+}
+
+async fn exited(exited: &mut watch::Receiver<bool>) {
+    if exited.wait_for(|exited| *exited).await.is_err() {
+        std::future::pending::<()>().await;
+    }
+}
+
+fn create_backup(dir: &Path, jar: &str, level_name: &str) -> Result<Backup> {
+    let seconds = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+    let backup_dir = dir
+        .join(BACKUP_DIR)
+        .join(format!("{}{}", BACKUP_PREFIX, seconds));
+    std::fs::create_dir_all(&backup_dir)?;
+
+    let jar_backup = backup_dir.join(jar);
+    std::fs::copy(dir.join(jar), &jar_backup)?;
+
+    // Bukkit based servers keep the other dimensions next to the main world
+    let mut worlds = Vec::new();
+    let mut absent = Vec::new();
+    for world in [
+        level_name.to_string(),
+        format!("{}_nether", level_name),
+        format!("{}_the_end", level_name),
+    ] {
+        if dir.join(&world).is_dir() {
+            copy_dir(&dir.join(&world), &backup_dir.join(&world))?;
+            worlds.push(world);
+        } else {
+            absent.push(world);
+        }
+    }
+
+    Ok(Backup {
+        dir: backup_dir,
+        jar: jar_backup,
+        worlds,
+        absent,
+    })
+}
+
+fn restore_backup(dir: &Path, backup: &Backup, new_jar: &str) -> Result<()> {
+    let old_jar = backup
+        .jar
+        .file_name()
+        .ok_or_else(|| anyhow!("Backup jar has no file name"))?;
+    // The new jar may not have been moved in yet
+    if old_jar != new_jar && dir.join(new_jar).is_file() {
+        std::fs::remove_file(dir.join(new_jar))?;
+    }
+    std::fs::copy(&backup.jar, dir.join(old_jar))?;
+
+    for world in backup.worlds.iter() {
+        let target = dir.join(world);
+        if target.exists() {
+            std::fs::remove_dir_all(&target)?;
+        }
+        copy_dir(&backup.dir.join(world), &target)?;
+    }
+    for world in backup.absent.iter() {
+        let target = dir.join(world);
+        if target.exists() {
+            std::fs::remove_dir_all(&target)?;
+        }
+    }
+    Ok(())
+}
+
+// Backup names are the job's prefix and the unix time they were taken. Only the newest few of
+// each kind are kept
+pub fn prune_backups(backups: &Path, prefix: &str) -> Result<()> {
+    let mut upgrades: Vec<(u64, PathBuf)> = std::fs::read_dir(backups)?
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let seconds = entry
+                .file_name()
+                .to_string_lossy()
+                .strip_prefix(prefix)?
+                .parse()
+                .ok()?;
+            Some((seconds, entry.path()))
+        })
+        .collect();
+    upgrades.sort();
+    for (_, path) in upgrades.iter().rev().skip(KEEP_BACKUPS) {
+        std::fs::remove_dir_all(path)?;
+    }
+    Ok(())
+}
+
+fn copy_dir(from: &Path, to: &Path) -> std::io::Result<()> {
+    std::fs::create_dir_all(to)?;
+    for entry in std::fs::read_dir(from)? {
+        let entry = entry?;
+        let target = to.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir(&entry.path(), &target)?;
+        } else {
+            std::fs::copy(entry.path(), target)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn server_dir() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("craftman-upgrade-{}", Uuid::new_v4()));
+        std::fs::create_dir_all(dir.join("world/region")).unwrap();
+        std::fs::create_dir_all(dir.join("world_nether")).unwrap();
+        std::fs::write(dir.join("server.jar"), "old jar").unwrap();
+        std::fs::write(dir.join("world/level.dat"), "old level").unwrap();
+        std::fs::write(dir.join("world/region/r.0.0.mca"), "old region").unwrap();
+        std::fs::write(dir.join("world_nether/level.dat"), "old nether").unwrap();
+        dir
+    }
+
+    #[test]
+    fn restores_the_backup() {
+        let dir = server_dir();
+        let backup = create_backup(&dir, "server.jar", "world").unwrap();
+        assert_eq!(backup.worlds, ["world", "world_nether"]);
+        assert_eq!(backup.absent, ["world_the_end"]);
+
+        // What a new jar does on its first start
+        std::fs::write(dir.join("paper.jar"), "new jar").unwrap();
+        std::fs::remove_file(dir.join("server.jar")).unwrap();
+        std::fs::write(dir.join("world/level.dat"), "converted").unwrap();
+        std::fs::write(dir.join("world/region/r.1.0.mca"), "new region").unwrap();
+        std::fs::create_dir_all(dir.join("world_the_end")).unwrap();
+        std::fs::write(dir.join("world_the_end/level.dat"), "new end").unwrap();
+
+        restore_backup(&dir, &backup, "paper.jar").unwrap();
+        let read = |path: &str| std::fs::read_to_string(dir.join(path)).ok();
+        assert_eq!(read("server.jar").as_deref(), Some("old jar"));
+        assert_eq!(read("paper.jar"), None);
+        assert_eq!(read("world/level.dat").as_deref(), Some("old level"));
+        assert_eq!(
+            read("world/region/r.0.0.mca").as_deref(),
+            Some("old region")
+        );
+        assert_eq!(read("world/region/r.1.0.mca"), None);
+        assert_eq!(
+            read("world_nether/level.dat").as_deref(),
+            Some("old nether")
+        );
+        assert!(!dir.join("world_the_end").exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn restores_over_the_same_jar_name() {
+        let dir = server_dir();
+        let backup = create_backup(&dir, "server.jar", "world").unwrap();
+        std::fs::write(dir.join("server.jar"), "new jar").unwrap();
+        restore_backup(&dir, &backup, "server.jar").unwrap();
+        assert_eq!(
+            std::fs::read_to_string(dir.join("server.jar")).unwrap(),
+            "old jar"
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn prunes_old_backups() {
+        let dir = std::env::temp_dir().join(format!("craftman-backups-{}", Uuid::new_v4()));
+        let names = [
+            "upgrade-100",
+            "upgrade-20",
+            "upgrade-300",
+            "upgrade-4000",
+            "upgrade-notatime",
+            "modpack-1",
+        ];
+        for name in names {
+            std::fs::create_dir_all(dir.join(name)).unwrap();
+        }
+        prune_backups(&dir, BACKUP_PREFIX).unwrap();
+
+        let mut left: Vec<String> = std::fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        left.sort();
+        // Only the oldest timestamped upgrade goes, other folders aren't touched
+        assert_eq!(
+            left,
+            [
+                "modpack-1",
+                "upgrade-100",
+                "upgrade-300",
+                "upgrade-4000",
+                "upgrade-notatime"
+            ]
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
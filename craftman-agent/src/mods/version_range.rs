@@ -0,0 +1,226 @@
+use std::cmp::Ordering;
+
+// Minecraft release versions as comparable numbers. Snapshots like 24w14a don't parse and every
+// check against them is skipped
+fn parse(version: &str) -> Option<Vec<u64>> {
+    let release = version.split(['-', '+', ' ']).next()?;
+    release
+        .split('.')
+        .map(|part| part.parse::<u64>().ok())
+        .collect()
+}
+
+fn compare(a: &[u64], b: &[u64]) -> Ordering {
+    for i in 0..a.len().max(b.len()) {
+        let ordering = a.get(i).unwrap_or(&0).cmp(b.get(i).unwrap_or(&0));
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+    Ordering::Equal
+}
+
+fn same_prefix(a: &[u64], b: &[u64], len: usize) -> bool {
+    (0..len).all(|i| a.get(i).unwrap_or(&0) == b.get(i).unwrap_or(&0))
+}
+
+// Pulls a version out of what servers report, e.g. "Paper 1.20.4" or "1.20.4"
+pub fn find_version(text: &str) -> Option<String> {
+    text.split_whitespace()
+        .find(|word| word.contains('.') && parse(word).is_some())
+        .map(str::to_string)
+}
+
+// Fabric and Quilt predicates: space separated terms that all have to match, e.g.
+// ">=1.20 <1.21", "~1.20.1", "1.20.x" or "*". None means the predicate couldn't be understood
+pub fn matches_predicate(predicate: &str, version: &str) -> Option<bool> {
+    let version = parse(version)?;
+    let mut matched = true;
+    for term in predicate.split_whitespace() {
+        matched &= matches_term(term, &version)?;
+    }
+    Some(matched)
+}
+
+fn matches_term(term: &str, version: &[u64]) -> Option<bool> {
+    if term == "*" {
+        return Some(true);
+    }
+    let operator_len = term
+        .find(|c: char| c.is_ascii_digit())
+        .filter(|len| *len <= 2)?;
+    let (operator, bound) = term.split_at(operator_len);
+
+    // 1.20.x matches every version starting with 1.20
+    if let Some(prefix) = bound
+        .strip_suffix(".x")
+        .or_else(|| bound.strip_suffix(".X"))
+        .or_else(|| bound.strip_suffix(".*"))
+    {
+        let prefix = parse(prefix)?;
+        return Some(same_prefix(version, &prefix, prefix.len()));
+    }
+
+    let bound = parse(bound)?;
+    let ordering = compare(version, &bound);
+    Some(match operator {
+        "" | "=" => ordering == Ordering::Equal,
+        ">=" => ordering != Ordering::Less,
+        "<=" => ordering != Ordering::Greater,
+        ">" => ordering == Ordering::Greater,
+        "<" => ordering == Ordering::Less,
+        // ~1.20.1 allows patch updates, ^1.20 allows minor updates
+        "~" => ordering != Ordering::Less && same_prefix(version, &bound, bound.len().min(2)),
+        "^" => ordering != Ordering::Less && same_prefix(version, &bound, 1),
+        _ => return None,
+    })
+}
+
+// Forge uses Maven ranges like "[1.20.1,1.21)", "[1.20,)" or "[1.20.1]", optionally several
+// joined with commas. A bare version is only a recommendation and always matches
+pub fn matches_maven_range(range: &str, version: &str) -> Option<bool> {
+    let version = parse(version)?;
+    let range = range.trim();
+    if !range.starts_with(['[', '(']) {
+        return Some(true);
+    }
+
+    let mut rest = range;
+    while let Some(start) = rest.find(['[', '(']) {
+        let end = rest[start..].find([']', ')'])? + start;
+        let inclusive_start = rest[start..].starts_with('[');
+        let inclusive_end = rest[end..].starts_with(']');
+        let inner = &rest[start + 1..end];
+        let (lower, upper) = inner.split_once(',').unwrap_or((inner, inner));
+
+        let above = match lower.trim() {
+            "" => true,
+            lower => match compare(&version, &parse(lower)?) {
+                Ordering::Greater => true,
+                Ordering::Equal => inclusive_start,
+                Ordering::Less => false,
+            },
+        };
+        let below = match upper.trim() {
+            "" => true,
+            upper => match compare(&version, &parse(upper)?) {
+                Ordering::Less => true,
+                Ordering::Equal => inclusive_end,
+                Ordering::Greater => false,
+            },
+        };
+        if above && below {
+            return Some(true);
+        }
+        rest = &rest[end + 1..];
+    }
+    Some(false)
+}
+
+// Bukkit's api-version is the oldest server the plugin supports
+pub fn at_least(minimum: &str, version: &str) -> Option<bool> {
+    Some(compare(&parse(version)?, &parse(minimum)?) != Ordering::Less)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_versions() {
+        let cases = [
+            ("1.20.4", Some("1.20.4")),
+            ("Paper 1.20.4", Some("1.20.4")),
+            (
+                "Velocity 3.3.0-SNAPSHOT (git-1b3b2f3a)",
+                Some("3.3.0-SNAPSHOT"),
+            ),
+            ("24w14a", None),
+            ("Paper", None),
+        ];
+        for (text, expected) in cases {
+            assert_eq!(find_version(text).as_deref(), expected, "{text}");
+        }
+    }
+
+    #[test]
+    fn predicates() {
+        let cases = [
+            ("*", "1.20.1", Some(true)),
+            (">=1.20 <1.21", "1.20.4", Some(true)),
+            (">=1.20 <1.21", "1.21", Some(false)),
+            (">=1.20 <1.21", "1.19.4", Some(false)),
+            ("1.20.1", "1.20.1", Some(true)),
+            ("=1.20.1", "1.20.2", Some(false)),
+            (">1.20", "1.20", Some(false)),
+            ("<=1.20", "1.20.0", Some(true)),
+            ("~1.20.1", "1.20.4", Some(true)),
+            ("~1.20.1", "1.20.0", Some(false)),
+            ("~1.20.1", "1.21", Some(false)),
+            ("^1.20", "1.21.1", Some(true)),
+            ("^1.20", "2.0", Some(false)),
+            ("1.20.x", "1.20.6", Some(true)),
+            ("1.20.x", "1.21", Some(false)),
+            ("1.20.*", "1.20", Some(true)),
+            // Snapshots and terms we don't understand aren't checked
+            (">=1.20", "24w14a", None),
+            ("!=1.20", "1.20", None),
+            ("~>1.20", "1.20", None),
+            (">=latest", "1.20", None),
+        ];
+        for (predicate, version, expected) in cases {
+            assert_eq!(
+                matches_predicate(predicate, version),
+                expected,
+                "{predicate} against {version}"
+            );
+        }
+    }
+
+    #[test]
+    fn maven_ranges() {
+        let cases = [
+            ("[1.20.1,1.21)", "1.20.1", Some(true)),
+            ("[1.20.1,1.21)", "1.20.6", Some(true)),
+            ("[1.20.1,1.21)", "1.21", Some(false)),
+            ("[1.20.1,1.21)", "1.20", Some(false)),
+            ("(1.20,1.21]", "1.20", Some(false)),
+            ("(1.20,1.21]", "1.21", Some(true)),
+            ("[1.20,)", "1.21.4", Some(true)),
+            ("(,1.20)", "1.19.2", Some(true)),
+            ("[1.20.1]", "1.20.1", Some(true)),
+            ("[1.20.1]", "1.20.2", Some(false)),
+            ("[1.18,1.19),[1.20,1.21)", "1.20.1", Some(true)),
+            ("[1.18,1.19),[1.20,1.21)", "1.19.2", Some(false)),
+            // A bare version is a recommendation
+            ("1.20.1", "1.19", Some(true)),
+            ("[1.20,1.21", "1.20", None),
+            ("[1.20,)", "24w14a", None),
+        ];
+        for (range, version, expected) in cases {
+            assert_eq!(
+                matches_maven_range(range, version),
+                expected,
+                "{range} against {version}"
+            );
+        }
+    }
+
+    #[test]
+    fn minimum_versions() {
+        let cases = [
+            ("1.13", "1.20.4", Some(true)),
+            ("1.20", "1.20", Some(true)),
+            ("1.20", "1.20.0", Some(true)),
+            ("1.21", "1.20.4", Some(false)),
+            ("1.13", "24w14a", None),
+        ];
+        for (minimum, version, expected) in cases {
+            assert_eq!(
+                at_least(minimum, version),
+                expected,
+                "{minimum} against {version}"
+            );
+        }
+    }
+}
@@ -0,0 +1,15 @@
+-- Every property edit made from Discord, newest looked up first
+
+CREATE TABLE property_changes (
+	id BIGSERIAL PRIMARY KEY,
+	guild_id BIGINT NOT NULL,
+	user_id BIGINT NOT NULL,
+	agent_id UUID NOT NULL,
+	key TEXT NOT NULL,
+	-- Secrets are stored as NULL and '', so the history only shows that they changed
+	old_value TEXT,
+	new_value TEXT NOT NULL,
+	changed_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX property_changes_agent_idx ON property_changes (agent_id, changed_at DESC);
@@ -0,0 +1,10 @@
+-- Named sets of server.properties values that can be applied in one go
+
+CREATE TABLE property_presets (
+	agent_id UUID NOT NULL,
+	name TEXT NOT NULL,
+	properties JSONB NOT NULL,
+	created_by BIGINT NOT NULL,
+	created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+	PRIMARY KEY (agent_id, name)
+);
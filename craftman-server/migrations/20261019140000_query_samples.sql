@@ -0,0 +1,12 @@
+-- Player count and latency seen by the agent's query loop, pruned after a month
+
+CREATE TABLE query_samples (
+	agent_id UUID NOT NULL,
+	sampled_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+	online BOOLEAN NOT NULL,
+	players INT,
+	max_players INT,
+	latency_ms INT
+);
+
+CREATE INDEX query_samples_agent_idx ON query_samples (agent_id, sampled_at);
@@ -0,0 +1,13 @@
+-- Monitors for servers without an agent, pinged by the central server
+
+CREATE TABLE external_monitors (
+	id BIGSERIAL PRIMARY KEY,
+	guild_id BIGINT NOT NULL,
+	address TEXT NOT NULL,
+	edition TEXT NOT NULL,
+	channel_id BIGINT NOT NULL,
+	message_id BIGINT NOT NULL,
+	created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX external_monitors_guild_idx ON external_monitors (guild_id);
@@ -0,0 +1,25 @@
+-- Any number of monitor messages per agent, each with its own options and refresh interval
+
+CREATE TABLE query_monitors (
+	id BIGSERIAL PRIMARY KEY,
+	agent_id UUID NOT NULL,
+	channel_id BIGINT NOT NULL,
+	message_id BIGINT NOT NULL,
+	options JSONB NOT NULL,
+	interval_secs INT NOT NULL DEFAULT 10,
+	created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX query_monitors_agent_idx ON query_monitors (agent_id);
+
+INSERT INTO query_monitors (agent_id, channel_id, message_id, options)
+SELECT agent_id, query_channel_id, query_message_id, query_monitor_options
+FROM servers
+WHERE query_channel_id IS NOT NULL
+	AND query_message_id IS NOT NULL
+	AND query_monitor_options IS NOT NULL;
+
+ALTER TABLE servers
+	DROP COLUMN query_channel_id,
+	DROP COLUMN query_message_id,
+	DROP COLUMN query_monitor_options;
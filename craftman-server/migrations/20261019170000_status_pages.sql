@@ -0,0 +1,2 @@
+-- Public status pages are opt-in. The slug is what goes in the URL instead of the agent's id
+ALTER TABLE servers ADD COLUMN status_slug TEXT UNIQUE;
@@ -0,0 +1,16 @@
+-- Who to mention, and where, when an agent's server goes down on its own
+
+CREATE TABLE alert_rules (
+	id BIGSERIAL PRIMARY KEY,
+	agent_id UUID NOT NULL,
+	-- offline, disconnected or crash_loop
+	kind TEXT NOT NULL,
+	channel_id BIGINT NOT NULL,
+	role_id BIGINT NOT NULL,
+	-- Minutes for disconnected, crashes for crash_loop
+	threshold INT NOT NULL,
+	cooldown_secs INT NOT NULL,
+	created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX alert_rules_agent_idx ON alert_rules (agent_id);
@@ -0,0 +1,25 @@
+-- Direct messages users asked for about their Discord server's Minecraft server
+
+CREATE TABLE notify_subscriptions (
+	id BIGSERIAL PRIMARY KEY,
+	guild_id BIGINT NOT NULL,
+	user_id BIGINT NOT NULL,
+	-- online, offline or join
+	event TEXT NOT NULL,
+	-- Lowercased player name for join, empty otherwise
+	player TEXT NOT NULL DEFAULT '',
+	created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+	UNIQUE (guild_id, user_id, event, player)
+);
+
+CREATE INDEX notify_subscriptions_event_idx ON notify_subscriptions (guild_id, event, player);
+
+CREATE TABLE notify_quiet_hours (
+	guild_id BIGINT NOT NULL,
+	user_id BIGINT NOT NULL,
+	-- Hours of the day in the user's own time. No DMs from start_hour until end_hour
+	start_hour INT NOT NULL,
+	end_hour INT NOT NULL,
+	utc_offset_minutes INT NOT NULL,
+	PRIMARY KEY (guild_id, user_id)
+);
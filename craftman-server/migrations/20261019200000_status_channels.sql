@@ -0,0 +1,2 @@
+-- A channel whose name is kept showing the server's status and player count
+ALTER TABLE servers ADD COLUMN status_channel_id BIGINT;
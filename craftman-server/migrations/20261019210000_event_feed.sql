@@ -0,0 +1,3 @@
+-- A channel for player joins, leaves, deaths and advancements, separate from the chat stream
+ALTER TABLE servers ADD COLUMN feed_channel_id BIGINT;
+ALTER TABLE servers ADD COLUMN feed_events TEXT[] NOT NULL DEFAULT '{join,leave,death,advancement}';
@@ -0,0 +1,13 @@
+-- One row per stay on the server, read from the console's join and leave lines
+
+CREATE TABLE play_sessions (
+	id BIGSERIAL PRIMARY KEY,
+	agent_id UUID NOT NULL,
+	player TEXT NOT NULL,
+	started_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+	-- Null while the player is still on. Closed by the leave line, or when the server is next seen offline after a crash
+	ended_at TIMESTAMPTZ
+);
+
+CREATE INDEX play_sessions_player_idx ON play_sessions (agent_id, player);
+CREATE INDEX play_sessions_open_idx ON play_sessions (agent_id) WHERE ended_at IS NULL;
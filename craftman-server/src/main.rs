@@ -39,8 +39,19 @@ async fn main() {
     info!("Appstate created");
     app_state.start_clean_task(Duration::from_secs(300), Duration::from_secs(120));
     info!("Cleaning task started");
+    app_state.start_prune_task(Duration::from_secs(3600));
+    info!("Sample pruning task started");
+    app_state.start_external_task(Duration::from_secs(60));
+    info!("External monitor task started");
+    app_state.start_alert_task(Duration::from_secs(60));
+    info!("Alert task started");
+    app_state.start_notify_task(Duration::from_secs(15));
+    info!("Notify task started");
+    app_state.start_status_channel_task(Duration::from_secs(30));
+    info!("Status channel task started");
     let app = Router::new()
         .route("/craftman", get(handler))
+        .merge(status_page::routes())
         .with_state(app_state.clone());
     info!("App created");
 
@@ -1,11 +1,18 @@
+use crate::mods::alerts::{Alerts, OFFLINE_GRACE};
+use crate::mods::bot::server_commands::properties::settingscreen::{SettingScreen, SettingsView};
+use crate::mods::charts::Sample;
+use crate::mods::event_feed::{Feed, FeedEvent};
+use crate::mods::notify::Notifier;
+use crate::mods::status_channel::StatusChannels;
 use anyhow::anyhow;
 use atomic_time::AtomicInstant;
 use dashmap::DashMap;
+use moka::future::Cache;
 use sqlx::PgPool;
 use std::{
     collections::{HashMap, HashSet},
     sync::Arc,
-    time::Duration,
+    time::{Duration, Instant},
 };
 use tokio::sync::oneshot::Sender as OneshotSender;
 use tokio::{sync::oneshot, time::timeout};
@@ -16,12 +23,18 @@ use twilight_model::id::{
 };
 
 use anyhow::{Result, bail};
+use protocol::config_files::{ConfigRequest, ConfigResponse};
+use protocol::files::{FileRequest, FileResponse};
+use protocol::inventory::{Inventory, ModChange};
+use protocol::player_events::PlayerEvent;
+use protocol::properties::{Properties, is_secret};
+use protocol::server_commands::ServerCommands;
 use protocol::serveractions::RequestResponses;
+use protocol::tasks::{Progress, RemoteFile};
 use protocol::{
     agentactions::AgentActions,
-    query_options::{QueryOptions, ServerStatus},
+    query_options::{QueryOptions, QuerySample, ServerStatus},
 };
-use protocol::{properties::Property, server_commands::ServerCommands};
 use tokio::{
     sync::{
         Mutex, RwLock,
@@ -36,21 +49,46 @@ pub struct Agent {
     id: Uuid,
     sender: Mutex<Option<mpsc::UnboundedSender<AgentActions>>>,
     pending_requests: DashMap<Uuid, OneshotSender<RequestResponses>>,
+    progress_channels: DashMap<Uuid, UnboundedSender<Progress>>,
     chat_channel_cache: RwLock<Cached<Option<Id<ChannelMarker>>>>,
-    query_monitor_id_cache: RwLock<Cached<Option<(Id<ChannelMarker>, Id<MessageMarker>)>>>,
-    query_monitor_options_cache: RwLock<Cached<Option<QueryOptions>>>,
+    feed_cache: RwLock<Cached<Option<Feed>>>,
+    query_monitors_cache: RwLock<Cached<Vec<QueryMonitor>>>,
     dbpool: PgPool,
     chat_sender: RwLock<Option<UnboundedSender<String>>>,
     last_seen: Mutex<Option<AtomicInstant>>,
+    // Settings messages by message id, shared by every agent so they outlive a reconnect
+    settings_views: Arc<Cache<u64, SettingsView>>,
+    chart_drawn: Mutex<Option<Instant>>,
+    // When the server was first seen offline, and whether its sessions were closed for it
+    sessions_offline: Mutex<Option<(Instant, bool)>>,
+    alerts: Arc<Alerts>,
+    notifier: Arc<Notifier>,
+    status_channels: Arc<StatusChannels>,
 }
 
 const TTL: Duration = Duration::from_secs(2);
+// Requests that move whole files or read every jar take longer than a quick lookup
+const TRANSFER_TTL: Duration = Duration::from_secs(60);
+// Samples come in every minute, but redrawing the monitor chart that often is wasted work
+const CHART_REFRESH: Duration = Duration::from_secs(15 * 60);
 
 impl Agent {
     pub fn id(&self) -> Uuid {
         self.id
     }
 
+    pub fn alerts(&self) -> &Alerts {
+        &self.alerts
+    }
+
+    pub fn notifier(&self) -> &Notifier {
+        &self.notifier
+    }
+
+    pub fn status_channels(&self) -> &StatusChannels {
+        &self.status_channels
+    }
+
     pub async fn send(&self, message: AgentActions) -> Result<()> {
         self.sender
             .lock()
@@ -61,17 +99,32 @@ impl Agent {
         Ok(())
     }
 
-    pub fn new(id: Uuid, sender: mpsc::UnboundedSender<AgentActions>, dbpool: PgPool) -> Self {
+    pub fn new(
+        id: Uuid,
+        sender: mpsc::UnboundedSender<AgentActions>,
+        dbpool: PgPool,
+        alerts: Arc<Alerts>,
+        notifier: Arc<Notifier>,
+        status_channels: Arc<StatusChannels>,
+        settings_views: Arc<Cache<u64, SettingsView>>,
+    ) -> Self {
         Agent {
             id,
             sender: Mutex::new(Some(sender)),
             pending_requests: DashMap::new(),
+            progress_channels: DashMap::new(),
             chat_channel_cache: RwLock::new(Cached::NotCached),
-            query_monitor_id_cache: RwLock::new(Cached::NotCached),
-            query_monitor_options_cache: RwLock::new(Cached::NotCached),
+            feed_cache: RwLock::new(Cached::NotCached),
+            query_monitors_cache: RwLock::new(Cached::NotCached),
             dbpool,
             chat_sender: RwLock::new(None),
             last_seen: Mutex::new(None),
+            settings_views,
+            chart_drawn: Mutex::new(None),
+            sessions_offline: Mutex::new(None),
+            alerts,
+            notifier,
+            status_channels,
         }
     }
 
@@ -89,7 +142,53 @@ impl Agent {
         Ok(())
     }
 
-    pub async fn request_props(&self) -> Result<HashMap<String, String>> {
+    pub fn report_progress(&self, id: &Uuid, progress: Progress) -> Result<()> {
+        let sender = if progress.is_final() {
+            self.progress_channels.remove(id).map(|(_, sender)| sender)
+        } else {
+            self.progress_channels.get(id).map(|sender| sender.clone())
+        };
+        sender
+            .ok_or_else(|| anyhow!("No task found for this progress report"))?
+            .send(progress)
+            .map_err(|_| anyhow!("Progress receiver dropped"))?;
+        Ok(())
+    }
+
+    async fn start_task(
+        &self,
+        action: impl FnOnce(Uuid) -> AgentActions,
+    ) -> Result<UnboundedReceiver<Progress>> {
+        let (sender, receiver) = mpsc::unbounded_channel::<Progress>();
+        let request_id = Uuid::new_v4();
+        self.progress_channels.insert(request_id, sender);
+        if let Err(e) = self.send(action(request_id)).await {
+            self.progress_channels.remove(&request_id);
+            return Err(e);
+        }
+        Ok(receiver)
+    }
+
+    pub async fn upgrade(&self, jar: RemoteFile) -> Result<UnboundedReceiver<Progress>> {
+        self.start_task(|request_id| AgentActions::Upgrade(request_id, jar))
+            .await
+    }
+
+    pub async fn install_modpack(&self, pack: RemoteFile) -> Result<UnboundedReceiver<Progress>> {
+        self.start_task(|request_id| AgentActions::InstallModpack(request_id, pack))
+            .await
+    }
+
+    pub async fn change_mod(&self, change: ModChange) -> Result<UnboundedReceiver<Progress>> {
+        self.start_task(|request_id| AgentActions::ChangeMod(request_id, change))
+            .await
+    }
+
+    pub async fn restart(&self) -> Result<UnboundedReceiver<Progress>> {
+        self.start_task(AgentActions::Restart).await
+    }
+
+    pub async fn request_props(&self) -> Result<Properties> {
         let (sender, receiver) = oneshot::channel::<RequestResponses>();
         let request_id = Uuid::new_v4();
         self.pending_requests.insert(request_id, sender);
@@ -106,6 +205,88 @@ impl Agent {
         }
     }
 
+    pub async fn track_settings_view(
+        &self,
+        message_id: u64,
+        channel_id: u64,
+        screen: SettingScreen,
+    ) {
+        self.settings_views
+            .insert(
+                message_id,
+                SettingsView {
+                    agent_id: self.id,
+                    channel_id,
+                    screen,
+                },
+            )
+            .await;
+    }
+
+    pub fn settings_views(&self) -> Vec<(u64, SettingsView)> {
+        self.settings_views
+            .iter()
+            .filter(|(_, view)| view.agent_id == self.id)
+            .map(|(message_id, view)| (*message_id, view))
+            .collect()
+    }
+
+    pub async fn forget_settings_view(&self, message_id: u64) {
+        self.settings_views.invalidate(&message_id).await;
+    }
+
+    pub async fn list_mods(&self) -> Result<Inventory> {
+        let (sender, receiver) = oneshot::channel::<RequestResponses>();
+        let request_id = Uuid::new_v4();
+        self.pending_requests.insert(request_id, sender);
+        self.send(AgentActions::ListMods(request_id)).await?;
+        match timeout(TRANSFER_TTL, receiver).await {
+            Ok(Ok(RequestResponses::ModsResponse(inventory))) => Ok(inventory),
+            Ok(Ok(RequestResponses::Error(e))) => bail!(e),
+            Ok(_) => {
+                bail!("Received incorrect response format, or sender was dropped");
+            }
+            Err(_) => {
+                bail!("Request timed out")
+            }
+        }
+    }
+
+    pub async fn files(&self, request: FileRequest) -> Result<FileResponse> {
+        let (sender, receiver) = oneshot::channel::<RequestResponses>();
+        let request_id = Uuid::new_v4();
+        self.pending_requests.insert(request_id, sender);
+        self.send(AgentActions::Files(request_id, request)).await?;
+        match timeout(TRANSFER_TTL, receiver).await {
+            Ok(Ok(RequestResponses::FilesResponse(FileResponse::Error(e)))) => bail!(e),
+            Ok(Ok(RequestResponses::FilesResponse(response))) => Ok(response),
+            Ok(_) => {
+                bail!("Received incorrect response format, or sender was dropped");
+            }
+            Err(_) => {
+                bail!("Request timed out")
+            }
+        }
+    }
+
+    pub async fn config_file(&self, request: ConfigRequest) -> Result<ConfigResponse> {
+        let (sender, receiver) = oneshot::channel::<RequestResponses>();
+        let request_id = Uuid::new_v4();
+        self.pending_requests.insert(request_id, sender);
+        self.send(AgentActions::ConfigFile(request_id, request))
+            .await?;
+        match timeout(TRANSFER_TTL, receiver).await {
+            Ok(Ok(RequestResponses::ConfigFileResponse(ConfigResponse::Error(e)))) => bail!(e),
+            Ok(Ok(RequestResponses::ConfigFileResponse(response))) => Ok(response),
+            Ok(_) => {
+                bail!("Received incorrect response format, or sender was dropped");
+            }
+            Err(_) => {
+                bail!("Request timed out")
+            }
+        }
+    }
+
     pub async fn send_chat(&self, message: String) -> Result<()> {
         if let Some(channel) = self.chat_sender.read().await.clone() {
             channel.send(message)?;
@@ -160,70 +341,489 @@ impl Agent {
         Ok(())
     }
 
-    pub async fn edit_props(&self, prop: Property) -> Result<HashMap<String, String>> {
+    // None until a feed channel is set
+    pub async fn feed(&self) -> Result<Option<Feed>> {
+        if let Cached::Cached(feed) = &*self.feed_cache.read().await {
+            return Ok(feed.clone());
+        }
+        let record = sqlx::query!(
+            "SELECT feed_channel_id, feed_events FROM servers WHERE agent_id = $1",
+            self.id
+        )
+        .fetch_one(&self.dbpool)
+        .await?;
+        let feed = record.feed_channel_id.map(|channel_id| Feed {
+            channel_id: Id::new(channel_id as u64),
+            events: record.feed_events,
+        });
+        *self.feed_cache.write().await = Cached::Cached(feed.clone());
+        Ok(feed)
+    }
+
+    pub async fn set_feed_channel(&self, channel_id: Option<u64>) -> Result<()> {
+        sqlx::query!(
+            "UPDATE servers SET feed_channel_id = $1 WHERE agent_id = $2",
+            channel_id.map(|id| id as i64),
+            self.id
+        )
+        .execute(&self.dbpool)
+        .await?;
+        *self.feed_cache.write().await = Cached::NotCached;
+        Ok(())
+    }
+
+    pub async fn set_feed_event(&self, event: FeedEvent, on: bool) -> Result<()> {
+        if on {
+            sqlx::query!(
+                "UPDATE servers SET feed_events = array_append(array_remove(feed_events, $1), $1) WHERE agent_id = $2",
+                event.name(),
+                self.id
+            )
+            .execute(&self.dbpool)
+            .await?;
+        } else {
+            sqlx::query!(
+                "UPDATE servers SET feed_events = array_remove(feed_events, $1) WHERE agent_id = $2",
+                event.name(),
+                self.id
+            )
+            .execute(&self.dbpool)
+            .await?;
+        }
+        *self.feed_cache.write().await = Cached::NotCached;
+        Ok(())
+    }
+
+    // Records who made the change so it shows up in the history and can be reverted
+    pub async fn set_property(
+        &self,
+        key: String,
+        value: String,
+        guild_id: u64,
+        user_id: u64,
+    ) -> Result<Properties> {
+        let old = self.request_props().await?.values;
+        let props = self
+            .property_request(|id| AgentActions::SetProperty(id, key.clone(), value))
+            .await?;
+        self.record_changes(&old, &props, &[key], guild_id, user_id)
+            .await;
+        Ok(props)
+    }
+
+    // Several edits the agent writes at once, so a preset never ends up half applied
+    pub async fn set_properties(
+        &self,
+        changes: Vec<(String, String)>,
+        guild_id: u64,
+        user_id: u64,
+    ) -> Result<Properties> {
+        let old = self.request_props().await?.values;
+        let keys: Vec<String> = changes.iter().map(|(key, _)| key.clone()).collect();
+        let props = self
+            .property_request(|id| AgentActions::SetProperties(id, changes))
+            .await?;
+        self.record_changes(&old, &props, &keys, guild_id, user_id)
+            .await;
+        Ok(props)
+    }
+
+    async fn property_request(
+        &self,
+        action: impl FnOnce(Uuid) -> AgentActions,
+    ) -> Result<Properties> {
         let (sender, receiver) = oneshot::channel::<RequestResponses>();
         let request_id = Uuid::new_v4();
         self.pending_requests.insert(request_id, sender);
-        self.send(AgentActions::EditProp(request_id, prop)).await?;
+        self.send(action(request_id)).await?;
         match timeout(TTL, receiver).await {
             Ok(Ok(RequestResponses::PropsResponse(props))) => Ok(props),
+            Ok(Ok(RequestResponses::PropsError(e))) => bail!(e),
             Ok(_) => bail!("Received incorrect response format!"),
             Err(_) => bail!("Edit_props timed out"),
         }
     }
 
+    // The edit already went through, so a failed insert shouldn't be reported as a failed edit.
+    // Secrets are recorded without their values, which also leaves them out of reverts
+    async fn record_changes(
+        &self,
+        old: &HashMap<String, String>,
+        props: &Properties,
+        keys: &[String],
+        guild_id: u64,
+        user_id: u64,
+    ) {
+        for key in keys {
+            let new_value = props.values.get(key).cloned().unwrap_or_default();
+            let old_value = old.get(key).cloned();
+            if old_value.as_ref() == Some(&new_value) {
+                continue;
+            }
+            let (old_value, new_value) = if is_secret(key) {
+                (None, String::new())
+            } else {
+                (old_value, new_value)
+            };
+            if let Err(e) = sqlx::query!(
+                "INSERT INTO property_changes (guild_id, user_id, agent_id, key, old_value, new_value) VALUES ($1, $2, $3, $4, $5, $6)",
+                guild_id as i64,
+                user_id as i64,
+                self.id,
+                key,
+                old_value,
+                new_value
+            )
+            .execute(&self.dbpool)
+            .await
+            {
+                tracing::error!("Failed to record property change: {}", e);
+            }
+        }
+    }
+
+    pub async fn property_history(&self, limit: i64) -> Result<Vec<PropertyChange>> {
+        let changes = sqlx::query_as!(
+            PropertyChange,
+            r#"SELECT id, user_id, key, old_value, new_value, EXTRACT(EPOCH FROM changed_at)::BIGINT AS "changed_at!" FROM property_changes WHERE agent_id = $1 ORDER BY changed_at DESC, id DESC LIMIT $2"#,
+            self.id,
+            limit
+        )
+        .fetch_all(&self.dbpool)
+        .await?;
+        Ok(changes)
+    }
+
+    pub async fn property_change(&self, change_id: i64) -> Result<Option<PropertyChange>> {
+        let change = sqlx::query_as!(
+            PropertyChange,
+            r#"SELECT id, user_id, key, old_value, new_value, EXTRACT(EPOCH FROM changed_at)::BIGINT AS "changed_at!" FROM property_changes WHERE id = $1 AND agent_id = $2"#,
+            change_id,
+            self.id
+        )
+        .fetch_optional(&self.dbpool)
+        .await?;
+        Ok(change)
+    }
+
+    // Presets never hold secrets, callers drop them before saving
+    pub async fn save_preset(
+        &self,
+        name: &str,
+        values: &HashMap<String, String>,
+        user_id: u64,
+    ) -> Result<()> {
+        sqlx::query!(
+            "INSERT INTO property_presets (agent_id, name, properties, created_by) VALUES ($1, $2, $3, $4) ON CONFLICT (agent_id, name) DO UPDATE SET properties = $3, created_by = $4, created_at = now()",
+            self.id,
+            name,
+            serde_json::to_value(values)?,
+            user_id as i64
+        )
+        .execute(&self.dbpool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn preset(&self, name: &str) -> Result<Option<HashMap<String, String>>> {
+        let record = sqlx::query!(
+            "SELECT properties FROM property_presets WHERE agent_id = $1 AND name = $2",
+            self.id,
+            name
+        )
+        .fetch_optional(&self.dbpool)
+        .await?;
+        match record {
+            Some(record) => Ok(Some(serde_json::from_value(record.properties)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub async fn preset_names(&self) -> Result<Vec<String>> {
+        let names = sqlx::query_scalar!(
+            "SELECT name FROM property_presets WHERE agent_id = $1 ORDER BY name",
+            self.id
+        )
+        .fetch_all(&self.dbpool)
+        .await?;
+        Ok(names)
+    }
+
     pub async fn new_query(
         &self,
         options: HashSet<String>,
+        interval_secs: u64,
         message_id: Id<MessageMarker>,
         channel_id: Id<ChannelMarker>,
     ) -> Result<(String, Option<Vec<u8>>, ServerStatus)> {
-        let query_options = QueryOptions::new(options);
-        sqlx::query!(
-            "UPDATE servers SET query_channel_id = $1, query_message_id = $2, query_monitor_options =$3 WHERE agent_id = $4",
+        let options = QueryOptions::new(options);
+        let id = sqlx::query_scalar!(
+            "INSERT INTO query_monitors (agent_id, channel_id, message_id, options, interval_secs) VALUES ($1, $2, $3, $4, $5) RETURNING id",
+            self.id,
             channel_id.get() as i64,
             message_id.get() as i64,
-            serde_json::to_value(query_options.clone())?,
+            serde_json::to_value(options.clone())?,
+            interval_secs as i32
+        )
+        .fetch_one(&self.dbpool)
+        .await?;
+        *self.query_monitors_cache.write().await = Cached::NotCached;
+        // The new monitor gets its chart with the next sample
+        *self.chart_drawn.lock().await = None;
+        self.start_query(&QueryMonitor {
+            id,
+            channel_id,
+            message_id,
+            options,
+            interval_secs,
+        })
+        .await
+    }
+
+    pub async fn record_sample(&self, sample: QuerySample) -> Result<()> {
+        sqlx::query!(
+            "INSERT INTO query_samples (agent_id, online, players, max_players, latency_ms) VALUES ($1, $2, $3, $4, $5)",
+            self.id,
+            sample.online,
+            sample.players.map(|players| players as i32),
+            sample.max_players.map(|max| max as i32),
+            sample.latency_ms.map(|latency| latency as i32)
+        )
+        .execute(&self.dbpool)
+        .await?;
+        Ok(())
+    }
+
+    // Oldest first, between two unix timestamps
+    pub async fn samples(&self, from: i64, to: i64) -> Result<Vec<Sample>> {
+        let samples = sqlx::query_as!(
+            Sample,
+            r#"SELECT EXTRACT(EPOCH FROM sampled_at)::BIGINT AS "at!", online, players, max_players, latency_ms FROM query_samples WHERE agent_id = $1 AND sampled_at >= to_timestamp($2::BIGINT) AND sampled_at <= to_timestamp($3::BIGINT) ORDER BY sampled_at"#,
+            self.id,
+            from,
+            to
+        )
+        .fetch_all(&self.dbpool)
+        .await?;
+        Ok(samples)
+    }
+
+    // A join while a session is still open means the leave line was missed, so that one ends here
+    pub async fn record_player_event(&self, event: &PlayerEvent) -> Result<()> {
+        let (PlayerEvent::Joined(player) | PlayerEvent::Left(player)) = event else {
+            return Ok(());
+        };
+        sqlx::query!(
+            "UPDATE play_sessions SET ended_at = now() WHERE agent_id = $1 AND player = $2 AND ended_at IS NULL",
+            self.id,
+            player
+        )
+        .execute(&self.dbpool)
+        .await?;
+        if let PlayerEvent::Joined(player) = event {
+            sqlx::query!(
+                "INSERT INTO play_sessions (agent_id, player) VALUES ($1, $2)",
+                self.id,
+                player
+            )
+            .execute(&self.dbpool)
+            .await?;
+        }
+        Ok(())
+    }
+
+    // A server that hangs without exiting leaves no leave lines behind either, so sessions are
+    // also closed once it has stayed unreachable for OFFLINE_GRACE. A missed ping ends nothing
+    pub async fn sessions_status(&self, online: bool) -> Result<()> {
+        let mut offline = self.sessions_offline.lock().await;
+        if online {
+            *offline = None;
+            return Ok(());
+        }
+        let (since, closed) = offline.get_or_insert_with(|| (Instant::now(), false));
+        if !*closed && since.elapsed() >= OFFLINE_GRACE {
+            self.close_sessions().await?;
+            *closed = true;
+        }
+        Ok(())
+    }
+
+    // A crash leaves no leave lines behind, so whoever was on stops counting once the server exits
+    pub async fn close_sessions(&self) -> Result<()> {
+        sqlx::query!(
+            "UPDATE play_sessions SET ended_at = now() WHERE agent_id = $1 AND ended_at IS NULL",
             self.id
         )
         .execute(&self.dbpool)
         .await?;
+        Ok(())
+    }
+
+    // Since a unix timestamp, 0 for all time. Names are matched regardless of case
+    pub async fn playtime(&self, player: &str, since: i64) -> Result<Option<Playtime>> {
+        let playtime = sqlx::query_as!(
+            Playtime,
+            r#"SELECT player, SUM(EXTRACT(EPOCH FROM COALESCE(ended_at, now()) - GREATEST(started_at, to_timestamp($2::BIGINT))))::BIGINT AS "seconds!", COUNT(*) AS "sessions!", BOOL_OR(ended_at IS NULL) AS "online!" FROM play_sessions WHERE agent_id = $1 AND COALESCE(ended_at, now()) > to_timestamp($2::BIGINT) AND lower(player) = lower($3) GROUP BY player"#,
+            self.id,
+            since,
+            player
+        )
+        .fetch_optional(&self.dbpool)
+        .await?;
+        Ok(playtime)
+    }
+
+    // Most played first
+    pub async fn playtime_leaderboard(&self, since: i64, limit: i64) -> Result<Vec<Playtime>> {
+        let leaderboard = sqlx::query_as!(
+            Playtime,
+            r#"SELECT player, SUM(EXTRACT(EPOCH FROM COALESCE(ended_at, now()) - GREATEST(started_at, to_timestamp($2::BIGINT))))::BIGINT AS "seconds!", COUNT(*) AS "sessions!", BOOL_OR(ended_at IS NULL) AS "online!" FROM play_sessions WHERE agent_id = $1 AND COALESCE(ended_at, now()) > to_timestamp($2::BIGINT) GROUP BY player ORDER BY 2 DESC LIMIT $3"#,
+            self.id,
+            since,
+            limit
+        )
+        .fetch_all(&self.dbpool)
+        .await?;
+        Ok(leaderboard)
+    }
+
+    // Everyone who has played here whose name starts with prefix, for autocomplete
+    pub async fn known_players(&self, prefix: &str) -> Result<Vec<String>> {
+        let players = sqlx::query!(
+            "SELECT DISTINCT player FROM play_sessions WHERE agent_id = $1 AND starts_with(lower(player), lower($2)) ORDER BY player LIMIT 25",
+            self.id,
+            prefix
+        )
+        .fetch_all(&self.dbpool)
+        .await?;
+        Ok(players.into_iter().map(|record| record.player).collect())
+    }
+
+    // True at most once per CHART_REFRESH, the caller is expected to redraw the chart
+    pub async fn chart_due(&self) -> bool {
+        let mut drawn = self.chart_drawn.lock().await;
+        if drawn.is_some_and(|at| at.elapsed() < CHART_REFRESH) {
+            return false;
+        }
+        *drawn = Some(Instant::now());
+        true
+    }
+
+    async fn start_query(
+        &self,
+        monitor: &QueryMonitor,
+    ) -> Result<(String, Option<Vec<u8>>, ServerStatus)> {
         let (sender, receiver) = oneshot::channel::<RequestResponses>();
         let request_id = Uuid::new_v4();
         self.pending_requests.insert(request_id, sender);
-        self.send(AgentActions::StartQuery(request_id, query_options))
-            .await?;
+        self.send(AgentActions::StartQuery(
+            request_id,
+            monitor.id,
+            monitor.options.clone(),
+            monitor.interval_secs,
+        ))
+        .await?;
         match timeout(TTL, receiver).await {
             Ok(Ok(RequestResponses::QueryResponse(description, image_bytes, query))) => {
                 Ok((description, image_bytes, query))
             }
-            Ok(_) => bail!("Received inmproper response format"),
-            Err(_) => bail!("new_query timed out"),
+            Ok(_) => bail!("Received improper response format"),
+            Err(_) => bail!("start_query timed out"),
         }
     }
 
-    pub async fn start_query(&self) -> Result<()> {
-        if let Some(options) = self.query_monitor_options().await?
-            && self.query_ids().await?.is_some()
-        {
-            let (sender, receiver) = oneshot::channel::<RequestResponses>();
-            let request_id = Uuid::new_v4();
-            self.pending_requests.insert(request_id, sender);
-            self.send(AgentActions::StartQuery(request_id, options))
-                .await?;
-            match timeout(TTL, receiver).await {
-                Ok(Ok(RequestResponses::QueryResponse(_, _, _))) => {
-                    tracing::debug!("Query started");
-                    Ok(())
-                }
-                Ok(_) => bail!("Received improper response format"),
-                Err(_) => bail!("start_query timed out"),
+    // One monitor failing to start shouldn't keep the rest from running
+    pub async fn start_queries(&self) -> Result<()> {
+        for monitor in self.query_monitors().await? {
+            match self.start_query(&monitor).await {
+                Ok(_) => tracing::debug!("Query started for monitor {}", monitor.id),
+                Err(e) => tracing::warn!("Failed to start monitor {}: {}", monitor.id, e),
             }
-        } else {
-            bail!("No query monitor found");
+        }
+        Ok(())
+    }
+
+    // Stops the agent's updates too. False when the monitor isn't this agent's
+    pub async fn remove_monitor(&self, monitor: i64) -> Result<bool> {
+        let removed = sqlx::query!(
+            "DELETE FROM query_monitors WHERE id = $1 AND agent_id = $2",
+            monitor,
+            self.id
+        )
+        .execute(&self.dbpool)
+        .await?
+        .rows_affected()
+            > 0;
+        *self.query_monitors_cache.write().await = Cached::NotCached;
+        // Fails while the agent is offline, and reconnecting won't bring a deleted monitor back
+        let _ = self.send(AgentActions::StopQuery(monitor)).await;
+        Ok(removed)
+    }
+
+    // Restarting the agent's loop for the monitor answers with a fresh status
+    pub async fn refresh_monitor(
+        &self,
+        message_id: Id<MessageMarker>,
+    ) -> Result<Option<(String, Option<Vec<u8>>, ServerStatus)>> {
+        let Some(monitor) = self
+            .query_monitors()
+            .await?
+            .into_iter()
+            .find(|monitor| monitor.message_id == message_id)
+        else {
+            return Ok(None);
+        };
+        self.start_query(&monitor).await.map(Some)
+    }
+
+    pub async fn request_status(&self) -> Result<(String, Option<Vec<u8>>, ServerStatus)> {
+        let (sender, receiver) = oneshot::channel::<RequestResponses>();
+        let request_id = Uuid::new_v4();
+        self.pending_requests.insert(request_id, sender);
+        self.send(AgentActions::RequestStatus(request_id)).await?;
+        match timeout(TTL, receiver).await {
+            Ok(Ok(RequestResponses::QueryResponse(description, image_bytes, query))) => {
+                Ok((description, image_bytes, query))
+            }
+            Ok(_) => bail!("Received improper response format"),
+            Err(_) => bail!("request_status timed out"),
         }
     }
 
+    // None while the public status page is turned off
+    pub async fn status_slug(&self) -> Result<Option<String>> {
+        let record = sqlx::query!(
+            "SELECT status_slug FROM servers WHERE agent_id = $1",
+            self.id
+        )
+        .fetch_one(&self.dbpool)
+        .await?;
+        Ok(record.status_slug)
+    }
+
+    pub async fn set_status_channel(&self, channel_id: Option<u64>) -> Result<()> {
+        sqlx::query!(
+            "UPDATE servers SET status_channel_id = $1 WHERE agent_id = $2",
+            channel_id.map(|id| id as i64),
+            self.id
+        )
+        .execute(&self.dbpool)
+        .await?;
+        self.status_channels.reset(self.id);
+        Ok(())
+    }
+
+    pub async fn set_status_slug(&self, slug: Option<&str>) -> Result<()> {
+        sqlx::query!(
+            "UPDATE servers SET status_slug = $1 WHERE agent_id = $2",
+            slug,
+            self.id
+        )
+        .execute(&self.dbpool)
+        .await?;
+        Ok(())
+    }
+
     pub async fn start_chat_loop(&self, client: Arc<Client>) -> Result<()> {
         let (request_sender, request_receiver) = oneshot::channel::<RequestResponses>();
         let (chat_sender, chat_receiver) = mpsc::unbounded_channel::<String>();
@@ -252,6 +852,7 @@ impl Agent {
         self.send(AgentActions::SvStart(request_id)).await?;
         match timeout(TTL, receiver).await {
             Ok(Ok(RequestResponses::StartServerResponse)) => Ok(()),
+            Ok(Ok(RequestResponses::Error(e))) => bail!(e),
             Ok(_) => bail!("Received improper response format"),
             Err(_) => bail!("start_server timed out"),
         }
@@ -264,52 +865,48 @@ impl Agent {
         self.send(AgentActions::SvStop(request_id)).await?;
         match timeout(TTL, receiver).await {
             Ok(Ok(RequestResponses::StopServerResponse)) => Ok(()),
+            Ok(Ok(RequestResponses::Error(e))) => bail!(e),
             Ok(_) => bail!("Received improper response format"),
             Err(_) => bail!("stop_server timed out"),
         }
     }
 
-    pub async fn query_ids(&self) -> Result<Option<(Id<ChannelMarker>, Id<MessageMarker>)>> {
-        if let Cached::Cached(ids) = *self.query_monitor_id_cache.read().await {
-            Ok(ids)
-        } else {
-            let record = sqlx::query!(
-                "SELECT query_channel_id, query_message_id FROM servers WHERE agent_id = $1",
-                self.id
-            )
-            .fetch_one(&self.dbpool)
-            .await?;
-            let ids = if record.query_channel_id.is_some() && record.query_message_id.is_some() {
-                Some((
-                    Id::new(record.query_channel_id.expect("wtf") as u64),
-                    Id::new(record.query_message_id.expect("wtf") as u64),
-                ))
-            } else {
-                None
-            };
-            *self.query_monitor_id_cache.write().await = Cached::Cached(ids);
-            Ok(ids)
+    pub async fn query_monitors(&self) -> Result<Vec<QueryMonitor>> {
+        if let Cached::Cached(monitors) = &*self.query_monitors_cache.read().await {
+            return Ok(monitors.clone());
         }
+        let records = sqlx::query!(
+            "SELECT id, channel_id, message_id, options, interval_secs FROM query_monitors WHERE agent_id = $1 ORDER BY id",
+            self.id
+        )
+        .fetch_all(&self.dbpool)
+        .await?;
+        let monitors = records
+            .into_iter()
+            .map(|record| {
+                Ok(QueryMonitor {
+                    id: record.id,
+                    channel_id: Id::new(record.channel_id as u64),
+                    message_id: Id::new(record.message_id as u64),
+                    options: serde_json::from_value(record.options)?,
+                    interval_secs: record.interval_secs as u64,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        *self.query_monitors_cache.write().await = Cached::Cached(monitors.clone());
+        Ok(monitors)
     }
 
-    pub async fn query_monitor_options(&self) -> Result<Option<QueryOptions>> {
-        if let Cached::Cached(options) = &*self.query_monitor_options_cache.read().await {
-            Ok(options.clone())
-        } else {
-            let record = sqlx::query!(
-                "SELECT query_monitor_options FROM servers WHERE agent_id = $1",
-                self.id
-            )
-            .fetch_one(&self.dbpool)
-            .await?;
-            let options = if let Some(value) = record.query_monitor_options {
-                Some(serde_json::from_value(value)?)
-            } else {
-                None
-            };
-            *self.query_monitor_options_cache.write().await = Cached::Cached(options.clone());
-            Ok(options)
-        }
+    pub async fn query_ids(
+        &self,
+        monitor: i64,
+    ) -> Result<Option<(Id<ChannelMarker>, Id<MessageMarker>)>> {
+        Ok(self
+            .query_monitors()
+            .await?
+            .into_iter()
+            .find(|query_monitor| query_monitor.id == monitor)
+            .map(|query_monitor| (query_monitor.channel_id, query_monitor.message_id)))
     }
 
     pub async fn message_chat(&self, command: ServerCommands) -> Result<()> {
@@ -329,6 +926,13 @@ impl Agent {
     pub async fn lost_connection(&self) {
         *self.last_seen.lock().await = Some(AtomicInstant::now());
         *self.sender.lock().await = None;
+        self.alerts.disconnected(self.id).await;
+        self.progress_channels.retain(|_, sender| {
+            let _ = sender.send(Progress::Failed(
+                "Lost connection to the agent. Check the agent for the outcome".to_string(),
+            ));
+            false
+        });
         info!("Lost Connection, last seen and sender changed!");
     }
 
@@ -336,7 +940,8 @@ impl Agent {
     pub async fn reconnect(&self, sender: mpsc::UnboundedSender<AgentActions>) {
         *self.sender.lock().await = Some(sender);
         *self.last_seen.lock().await = None;
-        let _ = self.start_query().await;
+        self.alerts.connected(self.id).await;
+        let _ = self.start_queries().await;
         debug!("Reconnection complete for {}", self.id());
     }
 
@@ -382,6 +987,33 @@ pub async fn chat_loop(
     Ok(())
 }
 
+#[derive(Clone)]
+pub struct QueryMonitor {
+    pub id: i64,
+    pub channel_id: Id<ChannelMarker>,
+    pub message_id: Id<MessageMarker>,
+    pub options: QueryOptions,
+    pub interval_secs: u64,
+}
+
+// Time played since some point, with sessions that started before it cut short
+pub struct Playtime {
+    pub player: String,
+    pub seconds: i64,
+    pub sessions: i64,
+    pub online: bool,
+}
+
+pub struct PropertyChange {
+    pub id: i64,
+    pub user_id: i64,
+    pub key: String,
+    pub old_value: Option<String>,
+    pub new_value: String,
+    // Unix seconds
+    pub changed_at: i64,
+}
+
 enum Cached<T> {
     NotCached,
     Cached(T),
@@ -0,0 +1,384 @@
+use anyhow::Result;
+use dashmap::DashMap;
+use sqlx::PgPool;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+use tokio::time::sleep;
+use twilight_model::channel::message::AllowedMentions;
+use twilight_model::id::Id;
+use twilight_model::id::marker::{ChannelMarker, MessageMarker, RoleMarker};
+use uuid::Uuid;
+
+// Crashes further apart than this don't count towards a crash loop
+pub const CRASH_WINDOW: Duration = Duration::from_secs(10 * 60);
+// A stop from the console or the TUI only reaches us once the process is gone, which can be after
+// the server already stopped answering pings
+pub const OFFLINE_GRACE: Duration = Duration::from_secs(60);
+pub const MAX_PER_AGENT: i64 = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Kind {
+    Offline,
+    Disconnected,
+    CrashLoop,
+}
+
+impl Kind {
+    pub const ALL: [Kind; 3] = [Kind::Offline, Kind::Disconnected, Kind::CrashLoop];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Kind::Offline => "offline",
+            Kind::Disconnected => "disconnected",
+            Kind::CrashLoop => "crash_loop",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Kind> {
+        Kind::ALL.into_iter().find(|kind| kind.name() == name)
+    }
+
+    pub fn title(self) -> &'static str {
+        match self {
+            Kind::Offline => "Server offline",
+            Kind::Disconnected => "Agent disconnected",
+            Kind::CrashLoop => "Crash loop",
+        }
+    }
+
+    // Minutes without the agent, or crashes within CRASH_WINDOW. Offline has nothing to count
+    pub fn default_threshold(self) -> i32 {
+        match self {
+            Kind::Offline => 0,
+            Kind::Disconnected => 10,
+            Kind::CrashLoop => 3,
+        }
+    }
+}
+
+pub struct AlertRule {
+    pub id: i64,
+    pub kind: String,
+    pub channel_id: i64,
+    pub role_id: i64,
+    pub threshold: i32,
+    pub cooldown_secs: i32,
+}
+
+// An alert that was sent and hasn't been resolved yet
+struct Firing {
+    kind: Kind,
+    channel_id: Id<ChannelMarker>,
+    message_id: Id<MessageMarker>,
+    since: Instant,
+}
+
+#[derive(Default)]
+struct AgentAlerts {
+    online: Option<bool>,
+    // Cleared when the server comes back, so the next time it goes down counts again
+    stopped_by_user: bool,
+    offline_since: Option<Instant>,
+    crashes: VecDeque<Instant>,
+    disconnected_since: Option<Instant>,
+    // By rule id
+    firing: HashMap<i64, Firing>,
+    last_sent: HashMap<i64, Instant>,
+}
+
+// Turns what the agents report into role mentions. Kept outside the Agent because the cleaning
+// task drops agents that have been gone for a while, and that's exactly when alerts matter
+pub struct Alerts {
+    dbpool: PgPool,
+    client: Arc<twilight_http::Client>,
+    agents: DashMap<Uuid, Arc<Mutex<AgentAlerts>>>,
+}
+
+impl Alerts {
+    pub fn new(dbpool: PgPool, client: Arc<twilight_http::Client>) -> Self {
+        Alerts {
+            dbpool,
+            client,
+            agents: DashMap::new(),
+        }
+    }
+
+    fn state(&self, agent_id: Uuid) -> Arc<Mutex<AgentAlerts>> {
+        self.agents.entry(agent_id).or_default().clone()
+    }
+
+    // From monitor updates and samples. Going offline only alerts after OFFLINE_GRACE
+    pub async fn status(&self, agent_id: Uuid, online: bool) -> Result<()> {
+        let state = self.state(agent_id);
+        let mut state = state.lock().await;
+        let was = state.online.replace(online);
+        if online {
+            state.stopped_by_user = false;
+            state.offline_since = None;
+            if was == Some(false) {
+                self.resolve(&mut state, Kind::Offline, "The server is back online")
+                    .await;
+            }
+        } else if was == Some(true) && !state.stopped_by_user {
+            state.offline_since = Some(Instant::now());
+        }
+        Ok(())
+    }
+
+    pub async fn exited(&self, agent_id: Uuid, expected: bool) -> Result<()> {
+        let state = self.state(agent_id);
+        let mut state = state.lock().await;
+        state.online = Some(false);
+        state.offline_since = None;
+        if expected {
+            state.stopped_by_user = true;
+            return Ok(());
+        }
+        let now = Instant::now();
+        state.crashes.push_back(now);
+        while state
+            .crashes
+            .front()
+            .is_some_and(|crash| now.duration_since(*crash) > CRASH_WINDOW)
+        {
+            state.crashes.pop_front();
+        }
+        self.fire(
+            &mut state,
+            agent_id,
+            Kind::Offline,
+            "The server process exited without anyone stopping it",
+            |_| true,
+        )
+        .await?;
+        let crashes = state.crashes.len();
+        self.fire(
+            &mut state,
+            agent_id,
+            Kind::CrashLoop,
+            &format!(
+                "The server crashed {} times in the last {} minutes",
+                crashes,
+                CRASH_WINDOW.as_secs() / 60
+            ),
+            |rule| crashes >= rule.threshold.max(1) as usize,
+        )
+        .await
+    }
+
+    pub async fn disconnected(&self, agent_id: Uuid) {
+        let state = self.state(agent_id);
+        state.lock().await.disconnected_since = Some(Instant::now());
+    }
+
+    pub async fn connected(&self, agent_id: Uuid) {
+        let state = self.state(agent_id);
+        let mut state = state.lock().await;
+        if state.disconnected_since.take().is_some() {
+            self.resolve(&mut state, Kind::Disconnected, "The agent reconnected")
+                .await;
+        }
+    }
+
+    // Whatever depends on time passing rather than on a message from the agent
+    pub async fn check(&self) {
+        let agents: Vec<(Uuid, Arc<Mutex<AgentAlerts>>)> = self
+            .agents
+            .iter()
+            .map(|entry| (*entry.key(), entry.value().clone()))
+            .collect();
+        for (agent_id, state) in agents {
+            if let Err(e) = self.check_agent(agent_id, &mut *state.lock().await).await {
+                tracing::error!("Failed to check alerts for {}: {}", agent_id, e);
+            }
+        }
+    }
+
+    async fn check_agent(&self, agent_id: Uuid, state: &mut AgentAlerts) -> Result<()> {
+        if let Some(since) = state.disconnected_since {
+            let minutes = since.elapsed().as_secs() / 60;
+            self.fire(
+                state,
+                agent_id,
+                Kind::Disconnected,
+                &format!("The agent has been disconnected for {} minutes", minutes),
+                |rule| minutes >= rule.threshold.max(1) as u64,
+            )
+            .await?;
+        }
+        if let Some(since) = state.offline_since
+            && since.elapsed() >= OFFLINE_GRACE
+            && !state.stopped_by_user
+        {
+            state.offline_since = None;
+            self.fire(
+                state,
+                agent_id,
+                Kind::Offline,
+                "The server stopped answering pings without anyone stopping it",
+                |_| true,
+            )
+            .await?;
+        }
+        let settled = state
+            .crashes
+            .back()
+            .is_none_or(|crash| crash.elapsed() > CRASH_WINDOW);
+        if state.online == Some(true) && settled {
+            state.crashes.clear();
+            self.resolve(
+                state,
+                Kind::CrashLoop,
+                "The server has stayed up since its last crash",
+            )
+            .await;
+        }
+        Ok(())
+    }
+
+    pub async fn run(&self, cycle: Duration) {
+        loop {
+            self.check().await;
+            sleep(cycle).await;
+        }
+    }
+
+    // Rules already firing or still cooling down stay quiet
+    async fn fire(
+        &self,
+        state: &mut AgentAlerts,
+        agent_id: Uuid,
+        kind: Kind,
+        reason: &str,
+        applies: impl Fn(&AlertRule) -> bool,
+    ) -> Result<()> {
+        let now = Instant::now();
+        for rule in rules(&self.dbpool, agent_id, kind).await? {
+            let cooling = state.last_sent.get(&rule.id).is_some_and(|sent| {
+                now.duration_since(*sent) < Duration::from_secs(rule.cooldown_secs as u64)
+            });
+            if state.firing.contains_key(&rule.id) || cooling || !applies(&rule) {
+                continue;
+            }
+            let channel_id = Id::new(rule.channel_id as u64);
+            let role_id: Id<RoleMarker> = Id::new(rule.role_id as u64);
+            let mentions = AllowedMentions {
+                roles: vec![role_id],
+                ..Default::default()
+            };
+            let content = format!("<@&{}> 🔴 **{}**\n{}", role_id, kind.title(), reason);
+            match self
+                .client
+                .create_message(channel_id)
+                .content(&content)
+                .allowed_mentions(Some(&mentions))
+                .await
+            {
+                Ok(response) => {
+                    let message = response.model().await?;
+                    state.last_sent.insert(rule.id, now);
+                    state.firing.insert(
+                        rule.id,
+                        Firing {
+                            kind,
+                            channel_id,
+                            message_id: message.id,
+                            since: now,
+                        },
+                    );
+                }
+                Err(e) => tracing::warn!("Failed to send alert {}: {}", rule.id, e),
+            }
+        }
+        Ok(())
+    }
+
+    // Answers every firing alert of this kind, even ones whose rule was removed since
+    async fn resolve(&self, state: &mut AgentAlerts, kind: Kind, note: &str) {
+        let ids: Vec<i64> = state
+            .firing
+            .iter()
+            .filter(|(_, firing)| firing.kind == kind)
+            .map(|(id, _)| *id)
+            .collect();
+        for id in ids {
+            let Some(firing) = state.firing.remove(&id) else {
+                continue;
+            };
+            let content = format!(
+                "✅ **Resolved**: {} after {} minutes",
+                note,
+                firing.since.elapsed().as_secs() / 60
+            );
+            if let Err(e) = self
+                .client
+                .create_message(firing.channel_id)
+                .content(&content)
+                .reply(firing.message_id)
+                .allowed_mentions(Some(&AllowedMentions::default()))
+                .await
+            {
+                tracing::warn!("Failed to resolve alert {}: {}", id, e);
+            }
+        }
+    }
+}
+
+async fn rules(dbpool: &PgPool, agent_id: Uuid, kind: Kind) -> Result<Vec<AlertRule>> {
+    Ok(sqlx::query_as!(
+        AlertRule,
+        "SELECT id, kind, channel_id, role_id, threshold, cooldown_secs FROM alert_rules WHERE agent_id = $1 AND kind = $2",
+        agent_id,
+        kind.name()
+    )
+    .fetch_all(dbpool)
+    .await?)
+}
+
+pub async fn list_rules(dbpool: &PgPool, agent_id: Uuid) -> Result<Vec<AlertRule>> {
+    Ok(sqlx::query_as!(
+        AlertRule,
+        "SELECT id, kind, channel_id, role_id, threshold, cooldown_secs FROM alert_rules WHERE agent_id = $1 ORDER BY id",
+        agent_id
+    )
+    .fetch_all(dbpool)
+    .await?)
+}
+
+pub async fn add_rule(
+    dbpool: &PgPool,
+    agent_id: Uuid,
+    kind: Kind,
+    channel_id: u64,
+    role_id: u64,
+    threshold: i32,
+    cooldown_secs: i32,
+) -> Result<()> {
+    sqlx::query!(
+        "INSERT INTO alert_rules (agent_id, kind, channel_id, role_id, threshold, cooldown_secs) VALUES ($1, $2, $3, $4, $5, $6)",
+        agent_id,
+        kind.name(),
+        channel_id as i64,
+        role_id as i64,
+        threshold,
+        cooldown_secs
+    )
+    .execute(dbpool)
+    .await?;
+    Ok(())
+}
+
+// False when the rule isn't this agent's
+pub async fn remove_rule(dbpool: &PgPool, agent_id: Uuid, id: i64) -> Result<bool> {
+    Ok(sqlx::query!(
+        "DELETE FROM alert_rules WHERE id = $1 AND agent_id = $2",
+        id,
+        agent_id
+    )
+    .execute(dbpool)
+    .await?
+    .rows_affected()
+        > 0)
+}
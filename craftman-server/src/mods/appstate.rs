@@ -1,5 +1,13 @@
 use crate::mods::agents::Agent;
+use crate::mods::alerts::Alerts;
+use crate::mods::bot::config_commands::config_browser::ConfigBrowser;
+use crate::mods::bot::file_commands::browser::FileBrowser;
+use crate::mods::bot::server_commands::properties::settingscreen::SettingsView;
+use crate::mods::external;
 use crate::mods::listener;
+use crate::mods::notify::Notifier;
+use crate::mods::status_channel::StatusChannels;
+use crate::mods::status_page::{STATUS_TTL, StatusPage};
 use anyhow::Result;
 use anyhow::anyhow;
 use axum::extract::ws::WebSocket;
@@ -8,6 +16,7 @@ use futures_util::stream::SplitStream;
 use moka::future::Cache;
 use nanoid::nanoid;
 use protocol::agentactions::AgentActions;
+use protocol::query_options::ServerStatus;
 use sqlx::PgPool;
 use sqlx::query;
 use std::sync::Arc;
@@ -25,20 +34,53 @@ pub struct AppState {
     pub twilight_client: Arc<Client>,
     pub dbpool: PgPool,
     connection_requests: Arc<Cache<String, PendingRequest>>,
+    pub file_browsers: Arc<Cache<u64, FileBrowser>>,
+    pub config_browsers: Arc<Cache<u64, ConfigBrowser>>,
+    // Unknown slugs are kept as None, so guessing them doesn't reach the database every time
+    pub status_pages: Arc<Cache<String, Option<StatusPage>>>,
+    pub alerts: Arc<Alerts>,
+    pub notifier: Arc<Notifier>,
+    pub status_channels: Arc<StatusChannels>,
+    settings_views: Arc<Cache<u64, SettingsView>>,
 }
 
 impl AppState {
     pub fn new(token: String, dbpool: PgPool) -> Self {
+        let twilight_client = Arc::new(Client::new(token));
         AppState {
             connections: Arc::new(DashMap::new()),
             uuid_by_guild: Arc::new(DashMap::new()),
-            twilight_client: Arc::new(Client::new(token)),
+            alerts: Arc::new(Alerts::new(dbpool.clone(), twilight_client.clone())),
+            notifier: Arc::new(Notifier::new(dbpool.clone(), twilight_client.clone())),
+            status_channels: Arc::new(StatusChannels::new(dbpool.clone(), twilight_client.clone())),
+            settings_views: Arc::new(
+                Cache::builder()
+                    .time_to_idle(Duration::from_secs(24 * 60 * 60))
+                    .build(),
+            ),
+            twilight_client,
             dbpool,
             connection_requests: Arc::new(
                 Cache::builder()
                     .time_to_live(Duration::from_secs(300))
                     .build(),
             ),
+            file_browsers: Arc::new(
+                Cache::builder()
+                    .time_to_idle(Duration::from_secs(1800))
+                    .build(),
+            ),
+            config_browsers: Arc::new(
+                Cache::builder()
+                    .time_to_idle(Duration::from_secs(1800))
+                    .build(),
+            ),
+            status_pages: Arc::new(
+                Cache::builder()
+                    .time_to_live(STATUS_TTL)
+                    .max_capacity(10_000)
+                    .build(),
+            ),
         }
     }
 
@@ -75,7 +117,17 @@ impl AppState {
     ) -> Result<()> {
         self.uuid_by_guild.insert(guild_id, id);
         tracing::debug!("Should have inserted guild id: {}", guild_id);
-        let agent = Arc::new(Agent::new(id, sender.clone(), self.dbpool.clone()));
+        let agent = Arc::new(Agent::new(
+            id,
+            sender.clone(),
+            self.dbpool.clone(),
+            self.alerts.clone(),
+            self.notifier.clone(),
+            self.status_channels.clone(),
+            self.settings_views.clone(),
+        ));
+        // An agent dropped by the cleaning task comes back as a new Agent, not through reconnect
+        self.alerts.connected(id).await;
         tokio::spawn(listener::listen(
             receiver,
             agent.clone(),
@@ -122,6 +174,44 @@ impl AppState {
         self.find_connection(self.find_id_by_guild(guild_id)?.as_ref())
     }
 
+    // Public pages are answered from the cache, so visitors can't flood the agent with requests.
+    // None when no agent has this slug. Failures aren't cached, so the next visitor tries again
+    pub async fn status_page(&self, slug: &str) -> Option<StatusPage> {
+        self.status_pages
+            .try_get_with(slug.to_string(), self.fetch_status_page(slug))
+            .await
+            .unwrap_or_else(|e| {
+                tracing::warn!("Failed to build status page {}: {}", slug, e);
+                None
+            })
+    }
+
+    async fn fetch_status_page(&self, slug: &str) -> Result<Option<StatusPage>> {
+        let Some(agent_id) =
+            sqlx::query_scalar!("SELECT agent_id FROM servers WHERE status_slug = $1", slug)
+                .fetch_optional(&self.dbpool)
+                .await?
+        else {
+            return Ok(None);
+        };
+        // A disconnected agent can't tell us anything, so its server reads as offline
+        let offline = || {
+            (
+                "A minecraft server".to_string(),
+                None,
+                ServerStatus::ServerOffline,
+            )
+        };
+        let (description, favicon, status) = match self.find_connection(&agent_id) {
+            Ok(agent) => agent.request_status().await.unwrap_or_else(|e| {
+                tracing::debug!("Status request for page {} failed: {}", slug, e);
+                offline()
+            }),
+            Err(_) => offline(),
+        };
+        Ok(Some(StatusPage::new(&description, favicon, status).await?))
+    }
+
     //It says it's not used but I wanna keep for safety. Future features very well may end up using
     //this
     // pub async fn send_message(&self, id: Uuid, message: AgentActions) -> Result<()> {
@@ -160,6 +250,65 @@ impl AppState {
         let run_this = self.clone();
         tokio::spawn(async move { run_this.clean_connections(limit, cycle_time).await });
     }
+
+    // Query history is only charted for a month at most
+    pub async fn prune_samples(&self, cycle_time: Duration) {
+        loop {
+            if let Err(e) = sqlx::query!(
+                "DELETE FROM query_samples WHERE sampled_at < now() - interval '30 days'"
+            )
+            .execute(&self.dbpool)
+            .await
+            {
+                tracing::error!("Failed to prune query samples: {}", e);
+            }
+            sleep(cycle_time).await;
+        }
+    }
+
+    pub fn start_prune_task(&self, cycle_time: Duration) {
+        let run_this = self.clone();
+        tokio::spawn(async move { run_this.prune_samples(cycle_time).await });
+    }
+
+    // Summed over agents that are connected right now, for the bot's presence
+    pub async fn online_players(&self) -> u32 {
+        let agents: Vec<Arc<Agent>> = self
+            .connections
+            .iter()
+            .map(|entry| entry.value().clone())
+            .collect();
+        let mut players = 0;
+        for agent in agents {
+            if agent.since_last_seen().await.is_none() {
+                players += self.status_channels.players(agent.id());
+            }
+        }
+        players
+    }
+
+    pub fn start_status_channel_task(&self, cycle_time: Duration) {
+        let status_channels = self.status_channels.clone();
+        tokio::spawn(async move { status_channels.run(cycle_time).await });
+    }
+
+    pub fn start_alert_task(&self, cycle_time: Duration) {
+        let alerts = self.alerts.clone();
+        tokio::spawn(async move { alerts.run(cycle_time).await });
+    }
+
+    pub fn start_notify_task(&self, cycle_time: Duration) {
+        let notifier = self.notifier.clone();
+        tokio::spawn(async move { notifier.run(cycle_time).await });
+    }
+
+    pub fn start_external_task(&self, cycle_time: Duration) {
+        tokio::spawn(external::poll(
+            self.dbpool.clone(),
+            self.twilight_client.clone(),
+            cycle_time,
+        ));
+    }
 }
 
 #[derive(Clone)]
@@ -0,0 +1,207 @@
+use crate::appstate::AppState;
+use crate::mods::alerts::{self, CRASH_WINDOW, Kind, MAX_PER_AGENT};
+use crate::mods::bot::get_guild::get_guild;
+use crate::mods::bot::get_option::get_option;
+use anyhow::Result;
+use serenity::all::{ChannelType, CommandDataOptionValue, CommandOptionType, Context, Permissions};
+use serenity::builder::*;
+use serenity::model::application::CommandInteraction;
+
+const DEFAULT_COOLDOWN_MINUTES: i64 = 30;
+
+pub fn register() -> CreateCommand {
+    let mut kind = CreateCommandOption::new(CommandOptionType::String, "kind", "what to alert on")
+        .required(true);
+    for choice in Kind::ALL {
+        kind = kind.add_string_choice(choice.title(), choice.name());
+    }
+    CreateCommand::new("alert")
+        .description("mention a role when the server goes down unexpectedly")
+        .default_member_permissions(Permissions::MANAGE_GUILD)
+        .add_option(
+            CreateCommandOption::new(CommandOptionType::SubCommand, "add", "add an alert rule")
+                .add_sub_option(kind)
+                .add_sub_option(
+                    CreateCommandOption::new(
+                        CommandOptionType::Channel,
+                        "channel",
+                        "where to send the alert",
+                    )
+                    .channel_types(vec![ChannelType::Text])
+                    .required(true),
+                )
+                .add_sub_option(
+                    CreateCommandOption::new(CommandOptionType::Role, "role", "who to mention")
+                        .required(true),
+                )
+                .add_sub_option(
+                    CreateCommandOption::new(
+                        CommandOptionType::Integer,
+                        "threshold",
+                        "minutes disconnected (default 10) or crashes in 10 minutes (default 3)",
+                    )
+                    .min_int_value(1)
+                    .max_int_value(1440),
+                )
+                .add_sub_option(
+                    CreateCommandOption::new(
+                        CommandOptionType::Integer,
+                        "cooldown",
+                        "minutes before the same rule can mention again (default 30)",
+                    )
+                    .min_int_value(0)
+                    .max_int_value(1440),
+                ),
+        )
+        .add_option(CreateCommandOption::new(
+            CommandOptionType::SubCommand,
+            "list",
+            "show this server's alert rules",
+        ))
+        .add_option(
+            CreateCommandOption::new(
+                CommandOptionType::SubCommand,
+                "remove",
+                "remove an alert rule",
+            )
+            .add_sub_option(
+                CreateCommandOption::new(
+                    CommandOptionType::Integer,
+                    "id",
+                    "the rule's id from /alert list",
+                )
+                .required(true),
+            ),
+        )
+}
+
+pub async fn run(
+    ctx: &Context,
+    interaction: &CommandInteraction,
+    appstate: &AppState,
+    subcommand: &str,
+) -> Result<()> {
+    let agent = if let Ok(agent) =
+        appstate.find_connection_by_guild(get_guild(ctx, interaction).await?)
+    {
+        agent
+    } else {
+        let response = CreateInteractionResponseMessage::new();
+        interaction.create_response(&ctx.http, CreateInteractionResponse::Message(response.content("Unable to find agent. Please either start agent or verify a new agent with /verify"))).await?;
+        return Ok(());
+    };
+
+    let content = match subcommand {
+        "add" => add(interaction, appstate, agent.id()).await?,
+        "list" => list(appstate, agent.id()).await?,
+        "remove" => {
+            let Some(CommandDataOptionValue::Integer(id)) = get_option(interaction, "id") else {
+                return Ok(());
+            };
+            if alerts::remove_rule(&appstate.dbpool, agent.id(), *id).await? {
+                format!("Removed alert rule {}", id)
+            } else {
+                format!("There is no alert rule {}", id)
+            }
+        }
+        _ => return Ok(()),
+    };
+    let response = CreateInteractionResponseMessage::new()
+        .content(content)
+        .ephemeral(true);
+    interaction
+        .create_response(&ctx.http, CreateInteractionResponse::Message(response))
+        .await?;
+    Ok(())
+}
+
+async fn add(
+    interaction: &CommandInteraction,
+    appstate: &AppState,
+    agent_id: uuid::Uuid,
+) -> Result<String> {
+    let (
+        Some(CommandDataOptionValue::String(kind)),
+        Some(CommandDataOptionValue::Channel(channel)),
+        Some(CommandDataOptionValue::Role(role)),
+    ) = (
+        get_option(interaction, "kind"),
+        get_option(interaction, "channel"),
+        get_option(interaction, "role"),
+    )
+    else {
+        return Ok("Pick a kind, a channel and a role".to_string());
+    };
+    let Some(kind) = Kind::from_name(kind) else {
+        return Ok(format!("Unknown alert kind {}", kind));
+    };
+    if alerts::list_rules(&appstate.dbpool, agent_id).await?.len() as i64 >= MAX_PER_AGENT {
+        return Ok(format!(
+            "This server already has {} alert rules. Remove one with /alert remove first",
+            MAX_PER_AGENT
+        ));
+    }
+    let threshold = match get_option(interaction, "threshold") {
+        Some(CommandDataOptionValue::Integer(threshold)) if kind != Kind::Offline => {
+            *threshold as i32
+        }
+        _ => kind.default_threshold(),
+    };
+    let cooldown = match get_option(interaction, "cooldown") {
+        Some(CommandDataOptionValue::Integer(cooldown)) => *cooldown,
+        _ => DEFAULT_COOLDOWN_MINUTES,
+    };
+    alerts::add_rule(
+        &appstate.dbpool,
+        agent_id,
+        kind,
+        channel.get(),
+        role.get(),
+        threshold,
+        (cooldown * 60) as i32,
+    )
+    .await?;
+    Ok(format!(
+        "<@&{}> will be mentioned in <#{}> {}",
+        role,
+        channel,
+        describe(kind, threshold)
+    ))
+}
+
+async fn list(appstate: &AppState, agent_id: uuid::Uuid) -> Result<String> {
+    let rules = alerts::list_rules(&appstate.dbpool, agent_id).await?;
+    if rules.is_empty() {
+        return Ok("No alert rules yet. Add one with /alert add".to_string());
+    }
+    Ok(rules
+        .iter()
+        .filter_map(|rule| {
+            let kind = Kind::from_name(&rule.kind)?;
+            Some(format!(
+                "`{}` <@&{}> in <#{}> {}, at most every {} minutes",
+                rule.id,
+                rule.role_id,
+                rule.channel_id,
+                describe(kind, rule.threshold),
+                rule.cooldown_secs / 60
+            ))
+        })
+        .collect::<Vec<_>>()
+        .join("\n"))
+}
+
+fn describe(kind: Kind, threshold: i32) -> String {
+    match kind {
+        Kind::Offline => "when the server goes down without anyone stopping it".to_string(),
+        Kind::Disconnected => format!(
+            "when the agent has been disconnected for {} minutes",
+            threshold
+        ),
+        Kind::CrashLoop => format!(
+            "when the server crashes {} times within {} minutes",
+            threshold,
+            CRASH_WINDOW.as_secs() / 60
+        ),
+    }
+}
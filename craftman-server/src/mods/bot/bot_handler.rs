@@ -1,15 +1,12 @@
 use crate::bot::*;
 use crate::mods::bot::chat_commands::start_chat::start_chat;
 use crate::mods::bot::chat_commands::stop_chat::stop_chat;
-use crate::mods::bot::server_commands::properties::props_modals::props_modal;
-use crate::mods::bot::server_commands::{startserver, stopserver};
+use crate::mods::bot::server_commands::{installmodpack, startserver, stopserver, upgradeserver};
 use anyhow::{Result, anyhow, bail};
 use properties::settingscreen::SettingScreen;
-use protocol::properties::Property;
-use serenity::all::{ActionRowComponent, CreateModal};
 use serenity::async_trait;
 use serenity::builder::{CreateInteractionResponse, CreateInteractionResponseMessage};
-use serenity::model::application::{InputTextStyle, Interaction};
+use serenity::model::application::Interaction;
 use serenity::model::gateway::Ready;
 use serenity::model::id::GuildId;
 use serenity::prelude::*;
@@ -17,6 +14,8 @@ use server_commands::properties;
 use std::env;
 use std::str::FromStr;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
 use tracing::{debug, error, info, instrument, warn};
 use twilight_model::application::interaction::Interaction as TwilightInteraction;
 use twilight_model::application::interaction::InteractionData;
@@ -26,6 +25,7 @@ use uuid::Uuid;
 pub struct Handler {
     pub app_state: crate::appstate::AppState,
     pub twilight_client: Arc<twilight_http::Client>,
+    pub presence_started: AtomicBool,
 }
 
 #[async_trait]
@@ -40,11 +40,31 @@ impl EventHandler for Handler {
     async fn ready(&self, ctx: Context, ready: Ready) {
         info!("{} is connected!", ready.user.name);
 
+        // Ready comes again whenever the gateway reconnects, but one presence loop is enough
+        if !self.presence_started.swap(true, Ordering::SeqCst) {
+            tokio::spawn(presence::run(
+                ctx.clone(),
+                self.app_state.clone(),
+                Duration::from_secs(60),
+            ));
+        }
+
         let create_commands = vec![
             server_commands::server_command::register_server_command(),
             query_monitor::register(),
             chat_commands::chat_commands_register::register_chat_command(),
+            mod_commands::mod_commands_register::register_mod_command(),
+            file_commands::file_commands_register::register_files_command(),
+            config_commands::config_commands_register::register_config_command(),
             connect_to_server::register(),
+            stats::register(),
+            status_page::register(),
+            alerts::register(),
+            notify::register(),
+            status_channel::register(),
+            feed::register(),
+            playtime::register_playtime(),
+            playtime::register_leaderboard(),
         ];
 
         let commands = if cfg!(debug_assertions) {
@@ -78,50 +98,12 @@ fn parse_custom_id(id: &str) -> Option<(ComponentAction, &str)> {
     debug!("Kind: {}", kind);
 
     let action = match kind {
-        "edit" => {
-            let prop = match value {
-                "allowflightbutton" => Property::AllowFlight,
-                "difficultybutton" => Property::Difficulty,
-                "gamemodebutton" => Property::Gamemode,
-                "hardcorebutton" => Property::Hardcore,
-                "whitelistbutton" => Property::Whitelist,
-                "pvpbutton" => Property::PVP,
-                "generatestructuresbutton" => Property::GenerateStructures,
-                "allownetherbutton" => Property::AllowNether,
-                "spawn-npcbutton" => Property::SpawnNPC,
-                "spawn-animalsbutton" => Property::SpawnAnimals,
-                "spawn-monstersbutton" => Property::SpawnMonsters,
-                _ => {
-                    error!("parsing returned none");
-                    return None;
-                }
-            };
-            debug!("Selected prop");
-            ComponentAction::Edit(prop)
-        }
-        "modal" => {
-            let modal = match value {
-                "motdbutton" => {
-                    props_modal("Message Of The Day", server_id, InputTextStyle::Paragraph)
-                }
-                "max-playersbutton" => props_modal("Max Players", server_id, InputTextStyle::Short),
-                "max-worldbutton" => {
-                    props_modal("Max World Size", server_id, InputTextStyle::Short)
-                }
-                "view-distancebutton" => {
-                    props_modal("View Distance", server_id, InputTextStyle::Short)
-                }
-                "simulation-distancebutton" => {
-                    props_modal("Simulation Distance", server_id, InputTextStyle::Short)
-                }
-                "spawn-protectionbutton" => {
-                    props_modal("Spawn Protection", server_id, InputTextStyle::Short)
-                }
-                _ => return None,
-            };
-            debug!("Selected modal");
-            ComponentAction::OpenModal(modal)
-        }
+        "prop" => ComponentAction::Property(value.to_string()),
+        "restart" => ComponentAction::Restart,
+        "revert" => ComponentAction::Revert(value.parse().ok()?),
+        "files" => ComponentAction::Files(value.to_string()),
+        "config" => ComponentAction::Config(value.to_string()),
+        "monitor" => ComponentAction::Monitor(value.to_string()),
         "screen" => {
             let result = SettingScreen::from_str(value);
             match result {
@@ -139,9 +121,13 @@ fn parse_custom_id(id: &str) -> Option<(ComponentAction, &str)> {
 }
 
 enum ComponentAction {
-    Edit(Property),
-    OpenModal(CreateModal),
+    Property(String),
     ChangeScreen(SettingScreen),
+    Restart,
+    Revert(i64),
+    Files(String),
+    Config(String),
+    Monitor(String),
 }
 
 impl Handler {
@@ -159,14 +145,47 @@ impl Handler {
                             stopserver::stop_minecraft_server(&ctx, &command, &self.app_state)
                                 .await?;
                         }
-                        "properties" => {
-                            server_commands::properties::settingsview::run(
-                                &ctx,
-                                &self.twilight_client,
-                                command,
-                                &self.app_state,
-                            )
-                            .await?;
+                        "properties" => match get_option::get_subcommand(&command) {
+                            Some("save") => {
+                                properties::presets::save_preset(&ctx, &command, &self.app_state)
+                                    .await?;
+                            }
+                            Some("apply") => {
+                                properties::presets::apply_preset(&ctx, &command, &self.app_state)
+                                    .await?;
+                            }
+                            Some("export") => {
+                                properties::presets::export(&ctx, &command, &self.app_state)
+                                    .await?;
+                            }
+                            Some("history") => {
+                                properties::history::run(
+                                    &ctx,
+                                    &self.twilight_client,
+                                    &command,
+                                    &self.app_state,
+                                )
+                                .await?;
+                            }
+                            _ => {
+                                properties::settingsview::run(
+                                    &ctx,
+                                    &self.twilight_client,
+                                    command,
+                                    &self.app_state,
+                                )
+                                .await?;
+                            }
+                        },
+                        "upgrade" => {
+                            upgradeserver::upgrade_server(&ctx, &command, &self.app_state).await?;
+                        }
+                        "mods" => {
+                            listmods::list_mods(&ctx, &command, &self.app_state).await?;
+                        }
+                        "modpack" => {
+                            installmodpack::install_modpack(&ctx, &command, &self.app_state)
+                                .await?;
                         }
                         _ => {}
                     },
@@ -202,18 +221,124 @@ impl Handler {
                         }
                         _ => {}
                     },
-                    "monitor" => {
-                        crate::bot::query_monitor::builder_modal(
+                    "mod" => {
+                        mod_commands::change_mod::change_mod(
+                            &ctx,
+                            &command,
+                            &self.app_state,
+                            command.data.options[0].name.as_str(),
+                        )
+                        .await?;
+                    }
+                    "files" => match command.data.options[0].name.as_str() {
+                        "browse" => {
+                            file_commands::browser::run(
+                                &ctx,
+                                &self.twilight_client,
+                                &command,
+                                &self.app_state,
+                            )
+                            .await?;
+                        }
+                        "upload" => {
+                            file_commands::upload::upload_file(&ctx, &command, &self.app_state)
+                                .await?;
+                        }
+                        _ => {}
+                    },
+                    "config" => {
+                        config_commands::config_browser::run(
                             &ctx,
                             &self.twilight_client,
-                            command,
+                            &command,
                             &self.app_state,
                         )
                         .await?;
                     }
+                    "monitor" => match get_option::get_subcommand(&command) {
+                        Some("create") => {
+                            query_monitor::builder_modal(
+                                &ctx,
+                                &self.twilight_client,
+                                command,
+                                &self.app_state,
+                            )
+                            .await?;
+                        }
+                        Some("list") => {
+                            query_monitor::list(&ctx, &command, &self.app_state).await?;
+                        }
+                        Some("remove") => {
+                            query_monitor::remove(&ctx, &command, &self.app_state).await?;
+                        }
+                        Some("add-external") => {
+                            query_monitor::add_external(
+                                &ctx,
+                                &self.twilight_client,
+                                command,
+                                &self.app_state,
+                            )
+                            .await?;
+                        }
+                        _ => {}
+                    },
                     "verify" => {
                         connect_to_server::connect_server(&ctx, &command, &self.app_state).await?;
                     }
+                    "stats" => {
+                        stats::run(&ctx, &command, &self.app_state).await?;
+                    }
+                    "status-page" => {
+                        status_page::run(
+                            &ctx,
+                            &command,
+                            &self.app_state,
+                            command.data.options[0].name.as_str(),
+                        )
+                        .await?;
+                    }
+                    "alert" => {
+                        alerts::run(
+                            &ctx,
+                            &command,
+                            &self.app_state,
+                            command.data.options[0].name.as_str(),
+                        )
+                        .await?;
+                    }
+                    "status-channel" => {
+                        status_channel::run(
+                            &ctx,
+                            &command,
+                            &self.app_state,
+                            command.data.options[0].name.as_str(),
+                        )
+                        .await?;
+                    }
+                    "playtime" => {
+                        playtime::playtime(&ctx, &command, &self.app_state).await?;
+                    }
+                    "leaderboard" => {
+                        playtime::leaderboard(&ctx, &command, &self.app_state).await?;
+                    }
+                    "feed" => {
+                        feed::run(
+                            &ctx,
+                            &command,
+                            &self.app_state,
+                            command.data.options[0].name.as_str(),
+                        )
+                        .await?;
+                    }
+                    "notify" => {
+                        notify::run(
+                            &ctx,
+                            &command,
+                            &self.app_state,
+                            command.data.options[0].name.as_str(),
+                        )
+                        .await?;
+                    }
                     _ => {
                         command
                             .create_response(
@@ -235,37 +360,26 @@ impl Handler {
                 let id = Uuid::from_str(id_str)?;
                 let agent = self.app_state.find_connection(&id)?;
                 match action {
-                    ComponentAction::Edit(property) => {
-                        let props = agent.edit_props(property).await?;
-                        server_commands::properties::settingsview::update_settings_view(
+                    ComponentAction::Property(key) => {
+                        server_commands::properties::settingsview::edit_property(
+                            &ctx,
                             &self.twilight_client,
-                            component.channel_id.get(),
-                            component.message.id.get(),
-                            &props,
+                            &component,
+                            &agent,
+                            &key,
                             id,
-                            None,
-                            &component.message,
                         )
                         .await?;
-                        component
-                            .create_response(&ctx.http, CreateInteractionResponse::Acknowledge)
-                            .await?;
-                    }
-                    ComponentAction::OpenModal(modal) => {
-                        component
-                            .create_response(&ctx.http, CreateInteractionResponse::Modal(modal))
-                            .await?;
                     }
                     ComponentAction::ChangeScreen(screen) => {
                         let props = agent.request_props().await?;
                         server_commands::properties::settingsview::update_settings_view(
                             &self.twilight_client,
+                            &agent,
                             component.channel_id.get(),
                             component.message.id.get(),
                             &props,
-                            id,
-                            Some(&screen),
-                            &component.message,
+                            &screen,
                         )
                         .await?;
                         tracing::debug!("Updated settings view");
@@ -273,6 +387,52 @@ impl Handler {
                             .create_response(ctx.http, CreateInteractionResponse::Acknowledge)
                             .await?;
                     }
+                    ComponentAction::Restart => {
+                        mod_commands::restart::restart_from_button(&ctx, &component, &agent)
+                            .await?;
+                    }
+                    ComponentAction::Revert(change_id) => {
+                        properties::history::revert(
+                            &ctx,
+                            &self.twilight_client,
+                            &component,
+                            &agent,
+                            change_id,
+                        )
+                        .await?;
+                    }
+                    ComponentAction::Files(action) => {
+                        file_commands::browser::handle_component(
+                            &ctx,
+                            &self.twilight_client,
+                            &component,
+                            &self.app_state,
+                            &action,
+                            &agent,
+                        )
+                        .await?;
+                    }
+                    ComponentAction::Config(action) => {
+                        config_commands::config_browser::handle_component(
+                            &ctx,
+                            &self.twilight_client,
+                            &component,
+                            &self.app_state,
+                            &action,
+                            &agent,
+                        )
+                        .await?;
+                    }
+                    ComponentAction::Monitor(action) => {
+                        query_monitor::handle_component(
+                            &ctx,
+                            &self.twilight_client,
+                            &component,
+                            &agent,
+                            &action,
+                        )
+                        .await?;
+                    }
                 }
             }
             Interaction::Modal(modal, raw_json) => {
@@ -282,129 +442,15 @@ impl Handler {
                 tracing::debug!("Title: {}  id: {}", title, id);
                 match action {
                     ModalAction::EditProp => {
-                        tracing::debug!("title: {}, id: {}", title, id);
-                        let ActionRowComponent::InputText(data) =
-                            &modal.data.components[0].components[0]
-                        else {
-                            return Ok(());
-                        };
-                        let input = &data
-                            .value
-                            .as_ref()
-                            .ok_or_else(|| anyhow!("No input text received"))?;
-                        let prop = match title {
-                            "Message Of The Day" => Property::MOTD(input.to_string()),
-                            "Max Players" => {
-                                if let Ok(value) = input.parse::<u32>() {
-                                    Property::MaxPlayers(value)
-                                } else {
-                                    tracing::debug!("Invalid number input");
-                                    modal
-                                        .create_response(
-                                            ctx.http,
-                                            CreateInteractionResponse::Message(
-                                                CreateInteractionResponseMessage::new()
-                                                    .content("Please input a valid number")
-                                                    .ephemeral(true),
-                                            ),
-                                        )
-                                        .await?;
-                                    return Ok(());
-                                }
-                            }
-                            "Max World Size" => {
-                                if let Ok(value) = input.parse::<u32>() {
-                                    Property::MaxWorldSize(value)
-                                } else {
-                                    modal
-                                        .create_response(
-                                            ctx.http,
-                                            CreateInteractionResponse::Message(
-                                                CreateInteractionResponseMessage::new()
-                                                    .content("Please input a valid number")
-                                                    .ephemeral(true),
-                                            ),
-                                        )
-                                        .await?;
-                                    return Ok(());
-                                }
-                            }
-                            "View Distance" => {
-                                if let Ok(value) = input.parse::<u32>() {
-                                    Property::ViewDistance(value)
-                                } else {
-                                    modal
-                                        .create_response(
-                                            ctx.http,
-                                            CreateInteractionResponse::Message(
-                                                CreateInteractionResponseMessage::new()
-                                                    .content("Please input a valid number")
-                                                    .ephemeral(true),
-                                            ),
-                                        )
-                                        .await?;
-                                    return Ok(());
-                                }
-                            }
-                            "Simulation Distance" => {
-                                if let Ok(value) = input.parse::<u32>() {
-                                    Property::SimulationDistance(value)
-                                } else {
-                                    modal
-                                        .create_response(
-                                            ctx.http,
-                                            CreateInteractionResponse::Message(
-                                                CreateInteractionResponseMessage::new()
-                                                    .content("Please input a valid number")
-                                                    .ephemeral(true),
-                                            ),
-                                        )
-                                        .await?;
-                                    return Ok(());
-                                }
-                            }
-                            "Spawn Protection" => {
-                                if let Ok(value) = input.parse::<u32>() {
-                                    Property::SpawnProtection(value)
-                                } else {
-                                    modal
-                                        .create_response(
-                                            ctx.http,
-                                            CreateInteractionResponse::Message(
-                                                CreateInteractionResponseMessage::new()
-                                                    .content("Please input a valid number")
-                                                    .ephemeral(true),
-                                            ),
-                                        )
-                                        .await?;
-                                    return Ok(());
-                                }
-                            }
-                            _ => {
-                                return Ok(());
-                            }
-                        };
-                        let message = modal
-                            .message
-                            .as_ref()
-                            .ok_or_else(|| anyhow!("Message not attatched to any modal"))?;
                         let agent = self.app_state.find_connection(&id)?;
-                        let props = agent.edit_props(prop).await?;
-                        properties::settingsview::update_settings_view(
+                        properties::settingsview::save_property(
+                            &ctx,
                             &self.twilight_client,
-                            modal.channel_id.get(),
-                            message.id.get(),
-                            &props,
-                            id,
-                            None,
-                            message,
+                            &modal,
+                            &agent,
+                            title,
                         )
                         .await?;
-
-                        modal
-                            .create_response(ctx.http, CreateInteractionResponse::Acknowledge)
-                            .await?;
-                        tracing::debug!("Updated settings view");
                     }
                     ModalAction::BuildQuery => {
                         let twilight_interaction: TwilightInteraction =
@@ -427,6 +473,7 @@ impl Handler {
                         };
                         query_monitor::build_view(
                             checkbox_group.values.clone().into_iter().collect(),
+                            title.parse()?,
                             &self.twilight_client,
                             &modal,
                             &self.app_state,
@@ -434,6 +481,34 @@ impl Handler {
                         )
                         .await?;
                     }
+                    ModalAction::EditFile => {
+                        let agent = self.app_state.find_connection(&id)?;
+                        file_commands::browser::save_edit(
+                            &ctx,
+                            &modal,
+                            &self.app_state,
+                            title.parse()?,
+                            &agent,
+                        )
+                        .await?;
+                    }
+                    ModalAction::EditConfig => {
+                        let agent = self.app_state.find_connection(&id)?;
+                        config_commands::config_browser::save_edit(
+                            &ctx,
+                            &self.twilight_client,
+                            &modal,
+                            &self.app_state,
+                            title,
+                            &agent,
+                        )
+                        .await?;
+                    }
+                }
+            }
+            Interaction::Autocomplete(autocomplete) => {
+                if autocomplete.data.name == "playtime" {
+                    playtime::autocomplete(&ctx, &autocomplete, &self.app_state).await?;
                 }
             }
             _ => tracing::error!("Unhandled action..."),
@@ -445,6 +520,8 @@ impl Handler {
 enum ModalAction {
     EditProp,
     BuildQuery,
+    EditFile,
+    EditConfig,
 }
 
 #[instrument]
@@ -465,6 +542,14 @@ fn parse_modal_custom_id(id: &str) -> Option<(ModalAction, &str, &str)> {
             debug!("Build query");
             ModalAction::BuildQuery
         }
+        "edit_file" => {
+            debug!("Edit file");
+            ModalAction::EditFile
+        }
+        "edit_config" => {
+            debug!("Edit config");
+            ModalAction::EditConfig
+        }
         _ => {
             error!("No action found for this parse");
             return None;
@@ -1,4 +1,5 @@
 use std::env;
+use std::sync::atomic::AtomicBool;
 
 use serenity::prelude::*;
 
@@ -17,6 +18,7 @@ pub async fn start_bot(appstate: appstate::AppState) {
         .event_handler(bot_handler::Handler {
             twilight_client: appstate.twilight_client.clone(), //Creates a Twilight HTTP client. Serenity Client is made first so Token needs to be cloned. Then Token is moved into Twilight Client, consuming it.
             app_state: appstate,
+            presence_started: AtomicBool::new(false),
         })
         .await
         .expect("Error creating client");
@@ -0,0 +1,496 @@
+use crate::appstate::AppState;
+use crate::mods::agents::Agent;
+use crate::mods::bot::get_guild::get_guild;
+use crate::mods::bot::get_option::get_option;
+use crate::mods::bot::si2tr::si2tr;
+use anyhow::{Result, anyhow, bail};
+use protocol::config_files::{ConfigEntry, ConfigRequest, ConfigResponse, ValueKind};
+use serenity::all::{
+    ActionRowComponent, CommandDataOptionValue, ComponentInteraction, Context, InputTextStyle,
+    ModalInteraction,
+};
+use serenity::builder::*;
+use serenity::model::application::CommandInteraction;
+use twilight_model::channel::message::component::*;
+use twilight_model::channel::message::{Component, MessageFlags};
+use twilight_model::http::interaction::{
+    InteractionResponse, InteractionResponseData, InteractionResponseType,
+};
+use twilight_model::id::Id;
+use twilight_util::builder::interaction_response::ChannelMessageBuilder;
+use twilight_util::builder::message::*;
+use uuid::Uuid;
+
+const PAGE_SIZE: usize = 8;
+const VALUE_PREVIEW: usize = 100;
+
+// What a /config message is showing. Kept by message id because key paths don't fit in custom ids
+#[derive(Clone)]
+pub struct ConfigBrowser {
+    agent_id: Uuid,
+    files: Vec<String>,
+    file: Option<String>,
+    path: Vec<String>,
+    entries: Vec<ConfigEntry>,
+    page: usize,
+}
+
+impl ConfigBrowser {
+    fn pages(&self) -> usize {
+        let items = match self.file {
+            Some(_) => self.entries.len(),
+            None => self.files.len(),
+        };
+        items.div_ceil(PAGE_SIZE).max(1)
+    }
+
+    async fn list_files(&mut self, agent: &Agent) -> Result<()> {
+        let ConfigResponse::Files(files) = agent.config_file(ConfigRequest::Files).await? else {
+            bail!("Received an unexpected response from the agent");
+        };
+        self.files = files;
+        self.file = None;
+        self.path.clear();
+        self.page = 0;
+        Ok(())
+    }
+
+    async fn open(&mut self, agent: &Agent, file: String, path: Vec<String>) -> Result<()> {
+        let ConfigResponse::Keys(entries) = agent
+            .config_file(ConfigRequest::Keys(file.clone(), path.clone()))
+            .await?
+        else {
+            bail!("Received an unexpected response from the agent");
+        };
+        self.file = Some(file);
+        self.path = path;
+        self.entries = entries;
+        self.page = 0;
+        Ok(())
+    }
+
+    // Keeps the page so the changed key stays in view
+    async fn set(&mut self, agent: &Agent, index: usize, value: String) -> Result<()> {
+        let file = self
+            .file
+            .clone()
+            .ok_or_else(|| anyhow!("No config file is open"))?;
+        let entry = self
+            .entries
+            .get(index)
+            .ok_or_else(|| anyhow!("That key no longer exists"))?;
+        let mut path = self.path.clone();
+        path.push(entry.key.clone());
+        let ConfigResponse::Keys(entries) = agent
+            .config_file(ConfigRequest::Set(file, path, value))
+            .await?
+        else {
+            bail!("Received an unexpected response from the agent");
+        };
+        self.entries = entries;
+        self.page = self.page.min(self.pages() - 1);
+        Ok(())
+    }
+}
+
+pub async fn run(
+    ctx: &Context,
+    client: &twilight_http::Client,
+    interaction: &CommandInteraction,
+    appstate: &AppState,
+) -> Result<()> {
+    let agent = if let Ok(agent) =
+        appstate.find_connection_by_guild(get_guild(ctx, interaction).await?)
+    {
+        agent
+    } else {
+        let response = CreateInteractionResponseMessage::new();
+        interaction.create_response(&ctx.http, CreateInteractionResponse::Message(response.content("Unable to find agent. Please either start agent or verify a new agent with /verify"))).await?;
+        return Ok(());
+    };
+
+    let mut browser = ConfigBrowser {
+        agent_id: agent.id(),
+        files: Vec::new(),
+        file: None,
+        path: Vec::new(),
+        entries: Vec::new(),
+        page: 0,
+    };
+    let result = match get_option(interaction, "file") {
+        Some(CommandDataOptionValue::String(file)) => {
+            let file = file.trim_matches('/').to_string();
+            browser.open(&agent, file, Vec::new()).await
+        }
+        _ => browser.list_files(&agent).await,
+    };
+    if let Err(e) = result {
+        tracing::warn!("Opening config failed: {}", e);
+        interaction
+            .create_response(&ctx.http, ephemeral_message(e.to_string()))
+            .await?;
+        return Ok(());
+    }
+
+    let response = ChannelMessageBuilder::new()
+        .components(build_view(&browser))
+        .flags(MessageFlags::IS_COMPONENTS_V2 | MessageFlags::EPHEMERAL)
+        .build();
+    si2tr(client, interaction, &response).await;
+
+    let message = client
+        .interaction(Id::new(interaction.application_id.get()))
+        .response(&interaction.token)
+        .await?
+        .model()
+        .await?;
+    appstate
+        .config_browsers
+        .insert(message.id.get(), browser)
+        .await;
+    Ok(())
+}
+
+pub async fn handle_component(
+    ctx: &Context,
+    client: &twilight_http::Client,
+    component: &ComponentInteraction,
+    appstate: &AppState,
+    action: &str,
+    agent: &Agent,
+) -> Result<()> {
+    let message_id = component.message.id.get();
+    let Some(mut browser) = appstate.config_browsers.get(&message_id).await else {
+        component
+            .create_response(
+                &ctx.http,
+                ephemeral_message("This config browser expired. Run /config browse again"),
+            )
+            .await?;
+        return Ok(());
+    };
+
+    let (action, index) = match action.split_once('-') {
+        Some((action, index)) => (action, index.parse::<usize>().ok()),
+        None => (action, None),
+    };
+    let result = match (action, index) {
+        ("up", _) => match (&browser.file, browser.path.split_last()) {
+            (Some(file), Some((_, parent))) => {
+                let (file, parent) = (file.clone(), parent.to_vec());
+                browser.open(agent, file, parent).await
+            }
+            _ => browser.list_files(agent).await,
+        },
+        ("prev", _) => {
+            browser.page = browser.page.saturating_sub(1);
+            Ok(())
+        }
+        ("next", _) => {
+            browser.page = (browser.page + 1).min(browser.pages() - 1);
+            Ok(())
+        }
+        ("file", Some(index)) => match browser.files.get(index).cloned() {
+            Some(file) => browser.open(agent, file, Vec::new()).await,
+            None => Err(anyhow!("That file no longer exists")),
+        },
+        ("open", Some(index)) => match (&browser.file, browser.entries.get(index)) {
+            (Some(file), Some(entry)) => {
+                let file = file.clone();
+                let mut path = browser.path.clone();
+                path.push(entry.key.clone());
+                browser.open(agent, file, path).await
+            }
+            _ => Err(anyhow!("That key no longer exists")),
+        },
+        ("toggle", Some(index)) => {
+            let value = browser
+                .entries
+                .get(index)
+                .map(|entry| (entry.value != "true").to_string())
+                .unwrap_or_default();
+            browser.set(agent, index, value).await
+        }
+        ("edit", Some(index)) => return open_editor(ctx, component, &browser, index).await,
+        _ => bail!("Unknown config browser action {}", action),
+    };
+    if let Err(e) = result {
+        component
+            .create_response(&ctx.http, ephemeral_message(e.to_string()))
+            .await?;
+        return Ok(());
+    }
+
+    appstate
+        .config_browsers
+        .insert(message_id, browser.clone())
+        .await;
+    update_view(
+        client,
+        component.application_id.get(),
+        component.id.get(),
+        &component.token,
+        &browser,
+    )
+    .await
+}
+
+async fn open_editor(
+    ctx: &Context,
+    component: &ComponentInteraction,
+    browser: &ConfigBrowser,
+    index: usize,
+) -> Result<()> {
+    let Some(entry) = browser.entries.get(index) else {
+        return Ok(());
+    };
+    let label: String = entry.key.chars().take(45).collect();
+    let mut input = CreateInputText::new(InputTextStyle::Short, label, "value").required(false);
+    if !entry.value.is_empty() {
+        input = input.value(entry.value.clone());
+    }
+    let title: String = format!("Edit {}", entry.key).chars().take(45).collect();
+    let modal = CreateModal::new(
+        format!(
+            "edit_config:{}-{}:{}",
+            component.message.id.get(),
+            index,
+            browser.agent_id
+        ),
+        title,
+    )
+    .components(vec![CreateActionRow::InputText(input)]);
+    component
+        .create_response(&ctx.http, CreateInteractionResponse::Modal(modal))
+        .await?;
+    Ok(())
+}
+
+pub async fn save_edit(
+    ctx: &Context,
+    client: &twilight_http::Client,
+    modal: &ModalInteraction,
+    appstate: &AppState,
+    target: &str,
+    agent: &Agent,
+) -> Result<()> {
+    let (message_id, index) = target
+        .split_once('-')
+        .ok_or_else(|| anyhow!("Edit config modal has no key"))?;
+    let (message_id, index) = (message_id.parse::<u64>()?, index.parse::<usize>()?);
+    let Some(mut browser) = appstate.config_browsers.get(&message_id).await else {
+        modal
+            .create_response(
+                &ctx.http,
+                ephemeral_message("This config browser expired. Run /config browse again"),
+            )
+            .await?;
+        return Ok(());
+    };
+    let Some(ActionRowComponent::InputText(input)) = modal
+        .data
+        .components
+        .first()
+        .and_then(|row| row.components.first())
+    else {
+        bail!("Edit config modal has no text input");
+    };
+    let value = input.value.clone().unwrap_or_default();
+
+    if let Err(e) = browser.set(agent, index, value).await {
+        modal
+            .create_response(&ctx.http, ephemeral_message(e.to_string()))
+            .await?;
+        return Ok(());
+    }
+    appstate
+        .config_browsers
+        .insert(message_id, browser.clone())
+        .await;
+    update_view(
+        client,
+        modal.application_id.get(),
+        modal.id.get(),
+        &modal.token,
+        &browser,
+    )
+    .await
+}
+
+async fn update_view(
+    client: &twilight_http::Client,
+    application_id: u64,
+    interaction_id: u64,
+    token: &str,
+    browser: &ConfigBrowser,
+) -> Result<()> {
+    let response = InteractionResponse {
+        kind: InteractionResponseType::UpdateMessage,
+        data: Some(InteractionResponseData {
+            components: Some(build_view(browser)),
+            flags: Some(MessageFlags::IS_COMPONENTS_V2),
+            ..Default::default()
+        }),
+    };
+    client
+        .interaction(Id::new(application_id))
+        .create_response(Id::new(interaction_id), token, &response)
+        .await?;
+    Ok(())
+}
+
+fn ephemeral_message(content: impl Into<String>) -> CreateInteractionResponse {
+    CreateInteractionResponse::Message(
+        CreateInteractionResponseMessage::new()
+            .content(content)
+            .ephemeral(true),
+    )
+}
+
+fn build_view(browser: &ConfigBrowser) -> Vec<Component> {
+    let id = browser.agent_id.to_string();
+    match &browser.file {
+        Some(file) => keys_view(browser, file, &id),
+        None => files_view(browser, &id),
+    }
+}
+
+fn files_view(browser: &ConfigBrowser, id: &str) -> Vec<Component> {
+    let mut components: Vec<Component> = vec![
+        TextDisplayBuilder::new(format!(
+            "### ⚙️ Config files\nPage {} of {}",
+            browser.page + 1,
+            browser.pages()
+        ))
+        .build()
+        .into(),
+    ];
+    if browser.files.is_empty() {
+        components.push(
+            TextDisplayBuilder::new("*No yaml, toml or json configs found*")
+                .build()
+                .into(),
+        );
+    }
+    for (index, file) in browser
+        .files
+        .iter()
+        .enumerate()
+        .skip(browser.page * PAGE_SIZE)
+        .take(PAGE_SIZE)
+    {
+        let button = ButtonBuilder::new(ButtonStyle::Secondary)
+            .custom_id(format!("config:file-{}:{}", index, id))
+            .label("Open")
+            .build();
+        components.push(
+            SectionBuilder::new(button)
+                .component(TextDisplayBuilder::new(format!("📄 {}", file)).build())
+                .build()
+                .into(),
+        );
+    }
+    components.push(navigation(browser, id, true).into());
+    components
+}
+
+fn keys_view(browser: &ConfigBrowser, file: &str, id: &str) -> Vec<Component> {
+    let mut title = format!("### ⚙️ {}", file);
+    for key in browser.path.iter() {
+        title.push_str(&format!(" › {}", key));
+    }
+    let mut components: Vec<Component> = vec![
+        TextDisplayBuilder::new(format!(
+            "{}\nPage {} of {}",
+            title,
+            browser.page + 1,
+            browser.pages()
+        ))
+        .build()
+        .into(),
+    ];
+    if browser.entries.is_empty() {
+        components.push(
+            TextDisplayBuilder::new("*This section is empty*")
+                .build()
+                .into(),
+        );
+    }
+
+    for (index, entry) in browser
+        .entries
+        .iter()
+        .enumerate()
+        .skip(browser.page * PAGE_SIZE)
+        .take(PAGE_SIZE)
+    {
+        let button = match entry.kind {
+            ValueKind::Section => ButtonBuilder::new(ButtonStyle::Secondary)
+                .custom_id(format!("config:open-{}:{}", index, id))
+                .label("Open"),
+            ValueKind::Boolean => {
+                let (style, label) = if entry.value == "true" {
+                    (ButtonStyle::Success, "On")
+                } else {
+                    (ButtonStyle::Danger, "Off")
+                };
+                ButtonBuilder::new(style)
+                    .custom_id(format!("config:toggle-{}:{}", index, id))
+                    .label(label)
+            }
+            ValueKind::List => ButtonBuilder::new(ButtonStyle::Secondary)
+                .custom_id(format!("config:list-{}:{}", index, id))
+                .label("List")
+                .disabled(true),
+            _ => ButtonBuilder::new(ButtonStyle::Primary)
+                .custom_id(format!("config:edit-{}:{}", index, id))
+                .label("Edit"),
+        };
+        components.push(
+            SectionBuilder::new(button.build())
+                .component(TextDisplayBuilder::new(entry_text(entry)).build())
+                .build()
+                .into(),
+        );
+    }
+    components.push(navigation(browser, id, false).into());
+    components
+}
+
+fn entry_text(entry: &ConfigEntry) -> String {
+    match entry.kind {
+        ValueKind::Section => format!("📁 **{}** · {}", entry.key, entry.value),
+        ValueKind::Boolean => format!("**{}**", entry.key),
+        _ if entry.value.is_empty() => format!("**{}**\n*empty*", entry.key),
+        _ => {
+            let mut value: String = entry.value.replace('`', "'");
+            if value.chars().count() > VALUE_PREVIEW {
+                value = value.chars().take(VALUE_PREVIEW).collect::<String>() + "…";
+            }
+            format!("**{}**\n`{}`", entry.key, value)
+        }
+    }
+}
+
+fn navigation(browser: &ConfigBrowser, id: &str, at_top: bool) -> ActionRow {
+    let up_button = ButtonBuilder::new(ButtonStyle::Secondary)
+        .custom_id(format!("config:up:{}", id))
+        .label("Up")
+        .disabled(at_top)
+        .build();
+    let prev_button = ButtonBuilder::new(ButtonStyle::Secondary)
+        .custom_id(format!("config:prev:{}", id))
+        .label("Previous")
+        .disabled(browser.page == 0)
+        .build();
+    let next_button = ButtonBuilder::new(ButtonStyle::Secondary)
+        .custom_id(format!("config:next:{}", id))
+        .label("Next")
+        .disabled(browser.page + 1 >= browser.pages())
+        .build();
+    ActionRowBuilder::new()
+        .component(up_button)
+        .component(prev_button)
+        .component(next_button)
+        .build()
+}
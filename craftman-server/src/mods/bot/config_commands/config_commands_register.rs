@@ -0,0 +1,19 @@
+use serenity::all::{CommandOptionType, CreateCommand, CreateCommandOption, Permissions};
+
+pub fn register_config_command() -> CreateCommand {
+    CreateCommand::new("config")
+        .description("edit yaml, toml and json configs of your server, plugins and mods")
+        .default_member_permissions(Permissions::MANAGE_GUILD)
+        .add_option(
+            CreateCommandOption::new(
+                CommandOptionType::SubCommand,
+                "browse",
+                "browse the config files and their keys",
+            )
+            .add_sub_option(CreateCommandOption::new(
+                CommandOptionType::String,
+                "file",
+                "the file to open, relative to the server directory. Lists every config if left out",
+            )),
+        )
+}
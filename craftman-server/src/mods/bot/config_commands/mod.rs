@@ -0,0 +1,2 @@
+pub mod config_browser;
+pub mod config_commands_register;
@@ -0,0 +1,135 @@
+use crate::appstate::AppState;
+use crate::mods::bot::get_guild::get_guild;
+use crate::mods::bot::get_option::get_option;
+use crate::mods::event_feed::FeedEvent;
+use anyhow::Result;
+use serenity::all::{ChannelType, CommandDataOptionValue, CommandOptionType, Context, Permissions};
+use serenity::builder::*;
+use serenity::model::application::CommandInteraction;
+
+pub fn register() -> CreateCommand {
+    let mut event =
+        CreateCommandOption::new(CommandOptionType::String, "event", "which kind of event")
+            .required(true);
+    for choice in FeedEvent::ALL {
+        event = event.add_string_choice(choice.title(), choice.name());
+    }
+    CreateCommand::new("feed")
+        .description("post player joins, leaves, deaths and advancements to a channel")
+        .default_member_permissions(Permissions::MANAGE_GUILD)
+        .add_option(
+            CreateCommandOption::new(
+                CommandOptionType::SubCommand,
+                "set",
+                "pick the channel the feed posts to",
+            )
+            .add_sub_option(
+                CreateCommandOption::new(CommandOptionType::Channel, "channel", "the feed channel")
+                    .channel_types(vec![ChannelType::Text])
+                    .required(true),
+            ),
+        )
+        .add_option(
+            CreateCommandOption::new(
+                CommandOptionType::SubCommand,
+                "toggle",
+                "turn one kind of event on or off",
+            )
+            .add_sub_option(event)
+            .add_sub_option(
+                CreateCommandOption::new(
+                    CommandOptionType::Boolean,
+                    "enabled",
+                    "whether to post it",
+                )
+                .required(true),
+            ),
+        )
+        .add_option(CreateCommandOption::new(
+            CommandOptionType::SubCommand,
+            "show",
+            "show the feed channel and which events it posts",
+        ))
+        .add_option(CreateCommandOption::new(
+            CommandOptionType::SubCommand,
+            "disable",
+            "stop posting the feed",
+        ))
+}
+
+pub async fn run(
+    ctx: &Context,
+    interaction: &CommandInteraction,
+    appstate: &AppState,
+    subcommand: &str,
+) -> Result<()> {
+    let agent = if let Ok(agent) =
+        appstate.find_connection_by_guild(get_guild(ctx, interaction).await?)
+    {
+        agent
+    } else {
+        let response = CreateInteractionResponseMessage::new();
+        interaction.create_response(&ctx.http, CreateInteractionResponse::Message(response.content("Unable to find agent. Please either start agent or verify a new agent with /verify"))).await?;
+        return Ok(());
+    };
+
+    let content = match subcommand {
+        "set" => {
+            let Some(CommandDataOptionValue::Channel(channel)) = get_option(interaction, "channel")
+            else {
+                return Ok(());
+            };
+            agent.set_feed_channel(Some(channel.get())).await?;
+            format!("Player events will be posted to <#{}>", channel)
+        }
+        "toggle" => {
+            let (
+                Some(CommandDataOptionValue::String(event)),
+                Some(CommandDataOptionValue::Boolean(enabled)),
+            ) = (
+                get_option(interaction, "event"),
+                get_option(interaction, "enabled"),
+            )
+            else {
+                return Ok(());
+            };
+            let Some(event) = FeedEvent::from_name(event) else {
+                return Ok(());
+            };
+            agent.set_feed_event(event, *enabled).await?;
+            format!(
+                "{} {}",
+                event.title(),
+                if *enabled {
+                    "will be posted"
+                } else {
+                    "won't be posted anymore"
+                }
+            )
+        }
+        "show" => match agent.feed().await? {
+            Some(feed) => {
+                let events: Vec<String> = FeedEvent::ALL
+                    .iter()
+                    .map(|event| {
+                        let on = feed.events.iter().any(|name| name == event.name());
+                        format!("{} {}", if on { "✅" } else { "❌" }, event.title())
+                    })
+                    .collect();
+                format!("Posting to <#{}>\n{}", feed.channel_id, events.join("\n"))
+            }
+            None => "The feed is off. Turn it on with /feed set".to_string(),
+        },
+        _ => {
+            agent.set_feed_channel(None).await?;
+            "The feed is off. Which events it posts is kept for next time".to_string()
+        }
+    };
+    let response = CreateInteractionResponseMessage::new()
+        .content(content)
+        .ephemeral(true);
+    interaction
+        .create_response(&ctx.http, CreateInteractionResponse::Message(response))
+        .await?;
+    Ok(())
+}
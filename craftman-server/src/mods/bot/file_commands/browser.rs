@@ -0,0 +1,464 @@
+use crate::appstate::AppState;
+use crate::mods::agents::Agent;
+use crate::mods::bot::get_guild::get_guild;
+use crate::mods::bot::get_option::get_option;
+use crate::mods::bot::si2tr::si2tr;
+use anyhow::{Result, bail};
+use base64::Engine as _;
+use base64::engine::general_purpose::STANDARD;
+use protocol::files::{FileEntry, FileRequest, FileResponse};
+use serenity::all::{
+    ActionRowComponent, CommandDataOptionValue, ComponentInteraction, Context, InputTextStyle,
+    ModalInteraction,
+};
+use serenity::builder::*;
+use serenity::model::application::CommandInteraction;
+use twilight_model::channel::message::component::*;
+use twilight_model::channel::message::{Component, MessageFlags};
+use twilight_model::http::interaction::{
+    InteractionResponse, InteractionResponseData, InteractionResponseType,
+};
+use twilight_model::id::Id;
+use twilight_util::builder::interaction_response::ChannelMessageBuilder;
+use twilight_util::builder::message::*;
+use uuid::Uuid;
+
+const PAGE_SIZE: usize = 8;
+// Discord's limit for a text input, so bigger files can only be downloaded
+const EDITABLE_CHARS: usize = 4000;
+
+// What a /files message is showing. Kept by message id because paths don't fit in custom ids
+#[derive(Clone)]
+pub struct FileBrowser {
+    agent_id: Uuid,
+    path: String,
+    entries: Vec<FileEntry>,
+    page: usize,
+    selected: Option<FileEntry>,
+}
+
+impl FileBrowser {
+    fn child(&self, name: &str) -> String {
+        if self.path.is_empty() {
+            name.to_string()
+        } else {
+            format!("{}/{}", self.path, name)
+        }
+    }
+
+    fn parent(&self) -> String {
+        self.path
+            .rsplit_once('/')
+            .map(|(parent, _)| parent.to_string())
+            .unwrap_or_default()
+    }
+
+    fn pages(&self) -> usize {
+        self.entries.len().div_ceil(PAGE_SIZE).max(1)
+    }
+
+    async fn open_folder(&mut self, agent: &Agent, path: String) -> Result<()> {
+        let FileResponse::Listing(entries) = agent.files(FileRequest::List(path.clone())).await?
+        else {
+            bail!("Received an unexpected response from the agent");
+        };
+        self.path = path;
+        self.entries = entries;
+        self.page = 0;
+        self.selected = None;
+        Ok(())
+    }
+}
+
+pub async fn run(
+    ctx: &Context,
+    client: &twilight_http::Client,
+    interaction: &CommandInteraction,
+    appstate: &AppState,
+) -> Result<()> {
+    let agent = if let Ok(agent) =
+        appstate.find_connection_by_guild(get_guild(ctx, interaction).await?)
+    {
+        agent
+    } else {
+        let response = CreateInteractionResponseMessage::new();
+        interaction.create_response(&ctx.http, CreateInteractionResponse::Message(response.content("Unable to find agent. Please either start agent or verify a new agent with /verify"))).await?;
+        return Ok(());
+    };
+    let path = match get_option(interaction, "path") {
+        Some(CommandDataOptionValue::String(path)) => path.trim_matches('/').to_string(),
+        _ => String::new(),
+    };
+
+    let mut browser = FileBrowser {
+        agent_id: agent.id(),
+        path: String::new(),
+        entries: Vec::new(),
+        page: 0,
+        selected: None,
+    };
+    if let Err(e) = browser.open_folder(&agent, path).await {
+        tracing::warn!("Listing files failed: {}", e);
+        interaction
+            .create_response(&ctx.http, ephemeral_message(e.to_string()))
+            .await?;
+        return Ok(());
+    }
+
+    // File names can be sensitive, so only the person browsing sees them
+    let response = ChannelMessageBuilder::new()
+        .components(build_view(&browser))
+        .flags(MessageFlags::IS_COMPONENTS_V2 | MessageFlags::EPHEMERAL)
+        .build();
+    si2tr(client, interaction, &response).await;
+
+    let message = client
+        .interaction(Id::new(interaction.application_id.get()))
+        .response(&interaction.token)
+        .await?
+        .model()
+        .await?;
+    appstate
+        .file_browsers
+        .insert(message.id.get(), browser)
+        .await;
+    Ok(())
+}
+
+pub async fn handle_component(
+    ctx: &Context,
+    client: &twilight_http::Client,
+    component: &ComponentInteraction,
+    appstate: &AppState,
+    action: &str,
+    agent: &Agent,
+) -> Result<()> {
+    let message_id = component.message.id.get();
+    let Some(mut browser) = appstate.file_browsers.get(&message_id).await else {
+        component
+            .create_response(
+                &ctx.http,
+                ephemeral_message("This file browser expired. Run /files browse again"),
+            )
+            .await?;
+        return Ok(());
+    };
+
+    let result = match action {
+        "up" => {
+            let parent = browser.parent();
+            browser.open_folder(agent, parent).await
+        }
+        "prev" => {
+            browser.page = browser.page.saturating_sub(1);
+            Ok(())
+        }
+        "next" => {
+            browser.page = (browser.page + 1).min(browser.pages() - 1);
+            Ok(())
+        }
+        "back" => {
+            browser.selected = None;
+            Ok(())
+        }
+        "download" => return download(ctx, component, agent, &browser).await,
+        "edit" => return open_editor(ctx, component, agent, &browser).await,
+        open => {
+            let Some(entry) = open
+                .strip_prefix("open-")
+                .and_then(|index| index.parse::<usize>().ok())
+                .and_then(|index| browser.entries.get(index).cloned())
+            else {
+                bail!("Unknown file browser action {}", open);
+            };
+            if entry.is_dir {
+                let path = browser.child(&entry.name);
+                browser.open_folder(agent, path).await
+            } else {
+                browser.selected = Some(entry);
+                Ok(())
+            }
+        }
+    };
+    if let Err(e) = result {
+        component
+            .create_response(&ctx.http, ephemeral_message(e.to_string()))
+            .await?;
+        return Ok(());
+    }
+
+    appstate
+        .file_browsers
+        .insert(message_id, browser.clone())
+        .await;
+    let response = InteractionResponse {
+        kind: InteractionResponseType::UpdateMessage,
+        data: Some(InteractionResponseData {
+            components: Some(build_view(&browser)),
+            flags: Some(MessageFlags::IS_COMPONENTS_V2),
+            ..Default::default()
+        }),
+    };
+    client
+        .interaction(Id::new(component.application_id.get()))
+        .create_response(Id::new(component.id.get()), &component.token, &response)
+        .await?;
+    Ok(())
+}
+
+async fn download(
+    ctx: &Context,
+    component: &ComponentInteraction,
+    agent: &Agent,
+    browser: &FileBrowser,
+) -> Result<()> {
+    let Some(file) = &browser.selected else {
+        return Ok(());
+    };
+    component
+        .create_response(
+            &ctx.http,
+            CreateInteractionResponse::Defer(
+                CreateInteractionResponseMessage::new().ephemeral(true),
+            ),
+        )
+        .await?;
+    let response = match agent
+        .files(FileRequest::Read(browser.child(&file.name)))
+        .await
+    {
+        Ok(FileResponse::Contents(contents)) => match STANDARD.decode(contents) {
+            Ok(bytes) => EditInteractionResponse::new()
+                .new_attachment(CreateAttachment::bytes(bytes, file.name.clone())),
+            Err(e) => EditInteractionResponse::new().content(e.to_string()),
+        },
+        Ok(_) => {
+            EditInteractionResponse::new().content("Received an unexpected response from the agent")
+        }
+        Err(e) => EditInteractionResponse::new().content(e.to_string()),
+    };
+    component.edit_response(&ctx.http, response).await?;
+    Ok(())
+}
+
+async fn open_editor(
+    ctx: &Context,
+    component: &ComponentInteraction,
+    agent: &Agent,
+    browser: &FileBrowser,
+) -> Result<()> {
+    let Some(file) = &browser.selected else {
+        return Ok(());
+    };
+    let text = match agent
+        .files(FileRequest::Read(browser.child(&file.name)))
+        .await
+    {
+        Ok(FileResponse::Contents(contents)) => STANDARD
+            .decode(contents)
+            .ok()
+            .and_then(|bytes| String::from_utf8(bytes).ok())
+            .filter(|text| text.chars().count() <= EDITABLE_CHARS),
+        Ok(_) => None,
+        Err(e) => {
+            component
+                .create_response(&ctx.http, ephemeral_message(e.to_string()))
+                .await?;
+            return Ok(());
+        }
+    };
+    let Some(text) = text else {
+        component
+            .create_response(
+                &ctx.http,
+                ephemeral_message(format!(
+                    "Only text files up to {} characters can be edited here. Download it and use /files upload instead",
+                    EDITABLE_CHARS
+                )),
+            )
+            .await?;
+        return Ok(());
+    };
+
+    let input = CreateInputText::new(InputTextStyle::Paragraph, "Contents", "contents")
+        .value(text)
+        .max_length(EDITABLE_CHARS as u16)
+        .required(false);
+    let title: String = format!("Edit {}", file.name).chars().take(45).collect();
+    let modal = CreateModal::new(
+        format!(
+            "edit_file:{}:{}",
+            component.message.id.get(),
+            browser.agent_id
+        ),
+        title,
+    )
+    .components(vec![CreateActionRow::InputText(input)]);
+    component
+        .create_response(&ctx.http, CreateInteractionResponse::Modal(modal))
+        .await?;
+    Ok(())
+}
+
+pub async fn save_edit(
+    ctx: &Context,
+    modal: &ModalInteraction,
+    appstate: &AppState,
+    message_id: u64,
+    agent: &Agent,
+) -> Result<()> {
+    let Some(path) = appstate
+        .file_browsers
+        .get(&message_id)
+        .await
+        .and_then(|browser| Some(browser.child(&browser.selected.as_ref()?.name)))
+    else {
+        modal
+            .create_response(
+                &ctx.http,
+                ephemeral_message("This file browser expired. Run /files browse again"),
+            )
+            .await?;
+        return Ok(());
+    };
+    let Some(ActionRowComponent::InputText(input)) = modal
+        .data
+        .components
+        .first()
+        .and_then(|row| row.components.first())
+    else {
+        bail!("Edit file modal has no text input");
+    };
+    let text = input.value.clone().unwrap_or_default();
+
+    let content = match agent.files(FileRequest::WriteText(path, text)).await {
+        Ok(FileResponse::Written(path)) => format!("Saved `{}`", path),
+        Ok(_) => "Received an unexpected response from the agent".to_string(),
+        Err(e) => e.to_string(),
+    };
+    modal
+        .create_response(&ctx.http, ephemeral_message(content))
+        .await?;
+    Ok(())
+}
+
+fn ephemeral_message(content: impl Into<String>) -> CreateInteractionResponse {
+    CreateInteractionResponse::Message(
+        CreateInteractionResponseMessage::new()
+            .content(content)
+            .ephemeral(true),
+    )
+}
+
+fn build_view(browser: &FileBrowser) -> Vec<Component> {
+    let id = browser.agent_id.to_string();
+    match &browser.selected {
+        Some(file) => file_view(browser, file, &id),
+        None => folder_view(browser, &id),
+    }
+}
+
+fn folder_view(browser: &FileBrowser, id: &str) -> Vec<Component> {
+    let mut components: Vec<Component> = vec![
+        TextDisplayBuilder::new(format!(
+            "### 📁 /{}\nPage {} of {}",
+            browser.path,
+            browser.page + 1,
+            browser.pages()
+        ))
+        .build()
+        .into(),
+    ];
+    if browser.entries.is_empty() {
+        components.push(
+            TextDisplayBuilder::new("*This folder is empty*")
+                .build()
+                .into(),
+        );
+    }
+
+    for (index, entry) in browser
+        .entries
+        .iter()
+        .enumerate()
+        .skip(browser.page * PAGE_SIZE)
+        .take(PAGE_SIZE)
+    {
+        let displaytext = if entry.is_dir {
+            format!("📁 **{}**", entry.name)
+        } else {
+            format!("📄 {} · {}", entry.name, format_size(entry.size))
+        };
+        let button = ButtonBuilder::new(ButtonStyle::Secondary)
+            .custom_id(format!("files:open-{}:{}", index, id))
+            .label("Open")
+            .build();
+        components.push(
+            SectionBuilder::new(button)
+                .component(TextDisplayBuilder::new(displaytext).build())
+                .build()
+                .into(),
+        );
+    }
+
+    let up_button = ButtonBuilder::new(ButtonStyle::Secondary)
+        .custom_id(format!("files:up:{}", id))
+        .label("Up")
+        .disabled(browser.path.is_empty())
+        .build();
+    let prev_button = ButtonBuilder::new(ButtonStyle::Secondary)
+        .custom_id(format!("files:prev:{}", id))
+        .label("Previous")
+        .disabled(browser.page == 0)
+        .build();
+    let next_button = ButtonBuilder::new(ButtonStyle::Secondary)
+        .custom_id(format!("files:next:{}", id))
+        .label("Next")
+        .disabled(browser.page + 1 >= browser.pages())
+        .build();
+    let last_row = ActionRowBuilder::new()
+        .component(up_button)
+        .component(prev_button)
+        .component(next_button)
+        .build();
+    components.push(last_row.into());
+    components
+}
+
+fn file_view(browser: &FileBrowser, file: &FileEntry, id: &str) -> Vec<Component> {
+    let details = TextDisplayBuilder::new(format!(
+        "### 📄 /{}\n{}\nUse `/files upload` to replace it",
+        browser.child(&file.name),
+        format_size(file.size)
+    ))
+    .build();
+
+    let download_button = ButtonBuilder::new(ButtonStyle::Primary)
+        .custom_id(format!("files:download:{}", id))
+        .label("Download")
+        .build();
+    let edit_button = ButtonBuilder::new(ButtonStyle::Secondary)
+        .custom_id(format!("files:edit:{}", id))
+        .label("Edit")
+        // Up to four bytes per character, the agent reads it to check the real length
+        .disabled(file.size > EDITABLE_CHARS as u64 * 4)
+        .build();
+    let back_button = ButtonBuilder::new(ButtonStyle::Secondary)
+        .custom_id(format!("files:back:{}", id))
+        .label("Back")
+        .build();
+    let last_row = ActionRowBuilder::new()
+        .component(download_button)
+        .component(edit_button)
+        .component(back_button)
+        .build();
+    vec![details.into(), last_row.into()]
+}
+
+fn format_size(bytes: u64) -> String {
+    match bytes {
+        0..1024 => format!("{} B", bytes),
+        1024..1_048_576 => format!("{:.1} KB", bytes as f64 / 1024.0),
+        _ => format!("{:.1} MB", bytes as f64 / 1_048_576.0),
+    }
+}
@@ -0,0 +1,35 @@
+use serenity::all::{CommandOptionType, CreateCommand, CreateCommandOption, Permissions};
+
+pub fn register_files_command() -> CreateCommand {
+    CreateCommand::new("files")
+        .description("browse and edit the files of your minecraft server")
+        .default_member_permissions(Permissions::MANAGE_GUILD)
+        .add_option(
+            CreateCommandOption::new(
+                CommandOptionType::SubCommand,
+                "browse",
+                "browse the server directory",
+            )
+            .add_sub_option(CreateCommandOption::new(
+                CommandOptionType::String,
+                "path",
+                "the folder to start in, relative to the server directory",
+            )),
+        )
+        .add_option(
+            CreateCommandOption::new(
+                CommandOptionType::SubCommand,
+                "upload",
+                "upload a file, replacing it if it already exists",
+            )
+            .add_sub_option(
+                CreateCommandOption::new(CommandOptionType::Attachment, "file", "the file to upload")
+                    .required(true),
+            )
+            .add_sub_option(CreateCommandOption::new(
+                CommandOptionType::String,
+                "path",
+                "the folder to upload into or the file to replace. Defaults to the server directory",
+            )),
+        )
+}
@@ -0,0 +1,3 @@
+pub mod browser;
+pub mod file_commands_register;
+pub mod upload;
@@ -0,0 +1,56 @@
+use crate::appstate::AppState;
+use crate::mods::bot::get_guild::get_guild;
+use crate::mods::bot::get_option::{get_attachment, get_option};
+use anyhow::Result;
+use protocol::files::{FileRequest, FileResponse};
+use protocol::tasks::RemoteFile;
+use serenity::all::{CommandDataOptionValue, Context};
+use serenity::builder::*;
+use serenity::model::application::CommandInteraction;
+
+pub async fn upload_file(
+    ctx: &Context,
+    interaction: &CommandInteraction,
+    appstate: &AppState,
+) -> Result<()> {
+    let agent = if let Ok(agent) =
+        appstate.find_connection_by_guild(get_guild(ctx, interaction).await?)
+    {
+        agent
+    } else {
+        let response = CreateInteractionResponseMessage::new();
+        interaction.create_response(&ctx.http, CreateInteractionResponse::Message(response.content("Unable to find agent. Please either start agent or verify a new agent with /verify"))).await?;
+        return Ok(());
+    };
+    let Some(file) = get_attachment(interaction, "file") else {
+        return Ok(());
+    };
+    let path = match get_option(interaction, "path") {
+        Some(CommandDataOptionValue::String(path)) => path.clone(),
+        _ => String::new(),
+    };
+
+    let response = CreateInteractionResponseMessage::new().ephemeral(true);
+    interaction
+        .create_response(&ctx.http, CreateInteractionResponse::Defer(response))
+        .await?;
+    let request = FileRequest::Upload(
+        path,
+        RemoteFile {
+            file_name: file.filename.clone(),
+            url: file.url.clone(),
+        },
+    );
+    let content = match agent.files(request).await {
+        Ok(FileResponse::Written(path)) => format!("Saved `{}`", path),
+        Ok(_) => "Received an unexpected response from the agent".to_string(),
+        Err(e) => {
+            tracing::warn!("File upload failed: {}", e);
+            e.to_string()
+        }
+    };
+    interaction
+        .edit_response(&ctx.http, EditInteractionResponse::new().content(content))
+        .await?;
+    Ok(())
+}
@@ -0,0 +1,42 @@
+use serenity::all::{Attachment, CommandDataOption, CommandDataOptionValue, CommandInteraction};
+
+// Looks through subcommands and subcommand groups for an option with this name
+pub fn get_option<'a>(
+    interaction: &'a CommandInteraction,
+    name: &str,
+) -> Option<&'a CommandDataOptionValue> {
+    find_option(&interaction.data.options, name)
+}
+
+fn find_option<'a>(
+    options: &'a [CommandDataOption],
+    name: &str,
+) -> Option<&'a CommandDataOptionValue> {
+    options.iter().find_map(|option| match &option.value {
+        CommandDataOptionValue::SubCommand(options)
+        | CommandDataOptionValue::SubCommandGroup(options) => find_option(options, name),
+        value if option.name == name => Some(value),
+        _ => None,
+    })
+}
+
+// The name of the subcommand that was run, looking inside a subcommand group if there is one
+pub fn get_subcommand(interaction: &CommandInteraction) -> Option<&str> {
+    let option = interaction.data.options.first()?;
+    match &option.value {
+        CommandDataOptionValue::SubCommandGroup(options) => {
+            options.first().map(|option| option.name.as_str())
+        }
+        _ => Some(option.name.as_str()),
+    }
+}
+
+pub fn get_attachment<'a>(
+    interaction: &'a CommandInteraction,
+    name: &str,
+) -> Option<&'a Attachment> {
+    let CommandDataOptionValue::Attachment(id) = get_option(interaction, name)? else {
+        return None;
+    };
+    interaction.data.resolved.attachments.get(id)
+}
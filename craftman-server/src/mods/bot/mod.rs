@@ -1,8 +1,22 @@
+pub mod alerts;
 pub mod bot_handler;
 pub mod bot_start;
 pub mod chat_commands;
+pub mod config_commands;
 pub mod connect_to_server;
+pub mod feed;
+pub mod file_commands;
 pub mod get_guild;
+pub mod get_option;
+pub mod mod_commands;
+pub mod notify;
+pub mod permissions;
+pub mod playtime;
+pub mod presence;
+pub mod progress_message;
 pub mod query_monitor;
 pub mod server_commands;
 pub mod si2tr;
+pub mod stats;
+pub mod status_channel;
+pub mod status_page;
@@ -0,0 +1,95 @@
+use crate::appstate::AppState;
+use crate::mods::bot::get_guild::get_guild;
+use crate::mods::bot::get_option::{get_attachment, get_option};
+use crate::mods::bot::mod_commands::restart::restart_button;
+use crate::mods::bot::progress_message::follow_progress;
+use anyhow::Result;
+use protocol::inventory::ModChange;
+use protocol::tasks::RemoteFile;
+use serenity::all::{CommandDataOptionValue, Context};
+use serenity::builder::*;
+use serenity::model::application::CommandInteraction;
+
+pub async fn change_mod(
+    ctx: &Context,
+    interaction: &CommandInteraction,
+    appstate: &AppState,
+    subcommand: &str,
+) -> Result<()> {
+    let agent = if let Ok(agent) =
+        appstate.find_connection_by_guild(get_guild(ctx, interaction).await?)
+    {
+        agent
+    } else {
+        let response = CreateInteractionResponseMessage::new();
+        interaction.create_response(&ctx.http, CreateInteractionResponse::Message(response.content("Unable to find agent. Please either start agent or verify a new agent with /verify"))).await?;
+        return Ok(());
+    };
+    let response = CreateInteractionResponseMessage::new();
+    let Some(change) = parse_change(interaction, subcommand) else {
+        interaction
+            .create_response(
+                &ctx.http,
+                CreateInteractionResponse::Message(
+                    response.content("Please pick a folder and a .jar file"),
+                ),
+            )
+            .await?;
+        return Ok(());
+    };
+
+    match agent.change_mod(change).await {
+        Ok(progress) => {
+            interaction
+                .create_response(&ctx.http, CreateInteractionResponse::Defer(response))
+                .await?;
+            if follow_progress(ctx, &interaction.token, "Changing mods", progress).await? {
+                interaction
+                    .edit_response(
+                        &ctx.http,
+                        EditInteractionResponse::new().components(vec![restart_button(agent.id())]),
+                    )
+                    .await?;
+            }
+        }
+        Err(e) => {
+            tracing::warn!("Mod change failed to start: {}", e);
+            interaction
+                .create_response(
+                    &ctx.http,
+                    CreateInteractionResponse::Message(response.content(e.to_string())),
+                )
+                .await?;
+        }
+    }
+    Ok(())
+}
+
+fn parse_change(interaction: &CommandInteraction, subcommand: &str) -> Option<ModChange> {
+    let CommandDataOptionValue::String(folder) = get_option(interaction, "folder")? else {
+        return None;
+    };
+    let folder = folder.clone();
+    if subcommand == "upload" {
+        let jar =
+            get_attachment(interaction, "jar").filter(|jar| jar.filename.ends_with(".jar"))?;
+        return Some(ModChange::Upload {
+            folder,
+            file: RemoteFile {
+                file_name: jar.filename.clone(),
+                url: jar.url.clone(),
+            },
+        });
+    }
+
+    let CommandDataOptionValue::String(file_name) = get_option(interaction, "file")? else {
+        return None;
+    };
+    let file_name = file_name.clone();
+    match subcommand {
+        "enable" => Some(ModChange::Enable { folder, file_name }),
+        "disable" => Some(ModChange::Disable { folder, file_name }),
+        "delete" => Some(ModChange::Delete { folder, file_name }),
+        _ => None,
+    }
+}
@@ -0,0 +1,3 @@
+pub mod change_mod;
+pub mod mod_commands_register;
+pub mod restart;
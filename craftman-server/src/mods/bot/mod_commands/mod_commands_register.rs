@@ -0,0 +1,63 @@
+use serenity::all::{CommandOptionType, CreateCommand, CreateCommandOption, Permissions};
+
+fn folder() -> CreateCommandOption {
+    CreateCommandOption::new(
+        CommandOptionType::String,
+        "folder",
+        "whether the jar is a mod or a plugin",
+    )
+    .add_string_choice("mods", "mods")
+    .add_string_choice("plugins", "plugins")
+    .required(true)
+}
+
+fn file() -> CreateCommandOption {
+    CreateCommandOption::new(
+        CommandOptionType::String,
+        "file",
+        "the jar's file name, as shown by /server mods",
+    )
+    .required(true)
+}
+
+pub fn register_mod_command() -> CreateCommand {
+    CreateCommand::new("mod")
+        .description("manage the mods and plugins of your minecraft server")
+        // Uploaded jars run on the host, so only admins get this by default
+        .default_member_permissions(Permissions::MANAGE_GUILD)
+        .add_option(
+            CreateCommandOption::new(
+                CommandOptionType::SubCommand,
+                "enable",
+                "enable a disabled jar",
+            )
+            .add_sub_option(folder())
+            .add_sub_option(file()),
+        )
+        .add_option(
+            CreateCommandOption::new(
+                CommandOptionType::SubCommand,
+                "disable",
+                "disable a jar without deleting it",
+            )
+            .add_sub_option(folder())
+            .add_sub_option(file()),
+        )
+        .add_option(
+            CreateCommandOption::new(CommandOptionType::SubCommand, "delete", "delete a jar")
+                .add_sub_option(folder())
+                .add_sub_option(file()),
+        )
+        .add_option(
+            CreateCommandOption::new(
+                CommandOptionType::SubCommand,
+                "upload",
+                "upload a new jar to your server",
+            )
+            .add_sub_option(folder())
+            .add_sub_option(
+                CreateCommandOption::new(CommandOptionType::Attachment, "jar", "the jar to upload")
+                    .required(true),
+            ),
+        )
+}
@@ -0,0 +1,61 @@
+use crate::mods::agents::Agent;
+use crate::mods::bot::permissions::{MANAGE_ONLY, can_manage};
+use crate::mods::bot::progress_message::follow_progress;
+use anyhow::Result;
+use serenity::all::{ButtonStyle, ComponentInteraction, Context};
+use serenity::builder::*;
+use uuid::Uuid;
+
+pub fn restart_button(agent_id: Uuid) -> CreateActionRow {
+    CreateActionRow::Buttons(vec![
+        CreateButton::new(format!("restart:server:{}", agent_id))
+            .label("Restart now")
+            .style(ButtonStyle::Danger),
+    ])
+}
+
+// Restarts in a new message so the one with the button keeps its result
+pub async fn restart_from_button(
+    ctx: &Context,
+    component: &ComponentInteraction,
+    agent: &Agent,
+) -> Result<()> {
+    if !can_manage(component.member.as_deref()) {
+        component
+            .create_response(
+                &ctx.http,
+                CreateInteractionResponse::Message(
+                    CreateInteractionResponseMessage::new()
+                        .content(MANAGE_ONLY)
+                        .ephemeral(true),
+                ),
+            )
+            .await?;
+        return Ok(());
+    }
+    match agent.restart().await {
+        Ok(progress) => {
+            component
+                .create_response(
+                    &ctx.http,
+                    CreateInteractionResponse::Defer(CreateInteractionResponseMessage::new()),
+                )
+                .await?;
+            follow_progress(ctx, &component.token, "Restarting server", progress).await?;
+        }
+        Err(e) => {
+            tracing::warn!("Restart failed to start: {}", e);
+            component
+                .create_response(
+                    &ctx.http,
+                    CreateInteractionResponse::Message(
+                        CreateInteractionResponseMessage::new()
+                            .content(e.to_string())
+                            .ephemeral(true),
+                    ),
+                )
+                .await?;
+        }
+    }
+    Ok(())
+}
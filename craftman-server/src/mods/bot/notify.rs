@@ -0,0 +1,284 @@
+use crate::appstate::AppState;
+use crate::mods::bot::get_guild::get_guild;
+use crate::mods::bot::get_option::get_option;
+use crate::mods::notify::{self, Event, MAX_PER_USER, QuietHours};
+use anyhow::Result;
+use serenity::all::{CommandDataOptionValue, CommandOptionType, Context};
+use serenity::builder::*;
+use serenity::model::application::CommandInteraction;
+
+pub fn register() -> CreateCommand {
+    let event = || {
+        let mut event =
+            CreateCommandOption::new(CommandOptionType::String, "event", "what to be told about")
+                .required(true);
+        for choice in Event::ALL {
+            event = event.add_string_choice(choice.title(), choice.name());
+        }
+        event
+    };
+    let player = || {
+        CreateCommandOption::new(
+            CommandOptionType::String,
+            "player",
+            "the Minecraft name to watch for, when the event is a player joining",
+        )
+        .max_length(32)
+    };
+    let hour = |name: &'static str, description: &'static str| {
+        CreateCommandOption::new(CommandOptionType::Integer, name, description)
+            .min_int_value(0)
+            .max_int_value(23)
+            .required(true)
+    };
+    CreateCommand::new("notify")
+        .description("get a DM when the Minecraft server comes up, goes down or a friend joins")
+        .add_option(
+            CreateCommandOption::new(CommandOptionType::SubCommand, "add", "subscribe to a DM")
+                .add_sub_option(event())
+                .add_sub_option(player()),
+        )
+        .add_option(
+            CreateCommandOption::new(
+                CommandOptionType::SubCommand,
+                "remove",
+                "stop a DM subscription",
+            )
+            .add_sub_option(event())
+            .add_sub_option(player()),
+        )
+        .add_option(CreateCommandOption::new(
+            CommandOptionType::SubCommand,
+            "list",
+            "show your subscriptions and quiet hours",
+        ))
+        .add_option(
+            CreateCommandOption::new(
+                CommandOptionType::SubCommand,
+                "quiet",
+                "hours of the day to get no DMs",
+            )
+            .add_sub_option(hour("start", "hour quiet time starts, 0 to 23"))
+            .add_sub_option(hour("end", "hour quiet time ends, 0 to 23"))
+            .add_sub_option(CreateCommandOption::new(
+                CommandOptionType::String,
+                "utc-offset",
+                "your time zone as an offset from UTC, like +2 or -5:30 (default 0)",
+            )),
+        )
+        .add_option(CreateCommandOption::new(
+            CommandOptionType::SubCommand,
+            "quiet-off",
+            "get DMs at any hour again",
+        ))
+}
+
+pub async fn run(
+    ctx: &Context,
+    interaction: &CommandInteraction,
+    appstate: &AppState,
+    subcommand: &str,
+) -> Result<()> {
+    let guild_id = get_guild(ctx, interaction).await?;
+    let user_id = interaction.user.id.get();
+    let dbpool = &appstate.dbpool;
+
+    let content = match subcommand {
+        "add" | "remove" => {
+            let (event, player) = match event_option(interaction) {
+                Ok(option) => option,
+                Err(message) => return reply(ctx, interaction, message).await,
+            };
+            if subcommand == "remove" {
+                if notify::unsubscribe(dbpool, guild_id, user_id, event, &player).await? {
+                    format!("Unsubscribed from {}", describe(event, &player))
+                } else {
+                    format!("You weren't subscribed to {}", describe(event, &player))
+                }
+            } else if notify::subscriptions(dbpool, guild_id, user_id)
+                .await?
+                .len()
+                >= MAX_PER_USER
+            {
+                format!(
+                    "You already have {} subscriptions here. Remove one with /notify remove first",
+                    MAX_PER_USER
+                )
+            } else if notify::subscribe(dbpool, guild_id, user_id, event, &player).await? {
+                format!(
+                    "You'll get a DM for {}. Make sure DMs from this server are allowed",
+                    describe(event, &player)
+                )
+            } else {
+                format!("You're already subscribed to {}", describe(event, &player))
+            }
+        }
+        "list" => {
+            let subscriptions = notify::subscriptions(dbpool, guild_id, user_id).await?;
+            let mut lines: Vec<String> = subscriptions
+                .iter()
+                .filter_map(|subscription| {
+                    let event = Event::from_name(&subscription.event)?;
+                    Some(format!("• {}", describe(event, &subscription.player)))
+                })
+                .collect();
+            if lines.is_empty() {
+                lines.push("No subscriptions yet. Add one with /notify add".to_string());
+            }
+            if let Some(quiet) = notify::quiet_hours(dbpool, guild_id, user_id).await? {
+                lines.push(format!(
+                    "Quiet from {}:00 to {}:00 (UTC{})",
+                    quiet.start_hour,
+                    quiet.end_hour,
+                    format_offset(quiet.utc_offset_minutes)
+                ));
+            }
+            lines.join("\n")
+        }
+        "quiet" => {
+            let (
+                Some(CommandDataOptionValue::Integer(start)),
+                Some(CommandDataOptionValue::Integer(end)),
+            ) = (
+                get_option(interaction, "start"),
+                get_option(interaction, "end"),
+            )
+            else {
+                return Ok(());
+            };
+            let offset = match get_option(interaction, "utc-offset") {
+                Some(CommandDataOptionValue::String(offset)) => parse_offset(offset),
+                _ => Some(0),
+            };
+            let Some(utc_offset_minutes) = offset else {
+                return reply(
+                    ctx,
+                    interaction,
+                    "UTC offsets look like `+2`, `-5` or `+5:30`".to_string(),
+                )
+                .await;
+            };
+            if start == end {
+                return reply(
+                    ctx,
+                    interaction,
+                    "Quiet hours need to start and end at different hours".to_string(),
+                )
+                .await;
+            }
+            let quiet = QuietHours {
+                start_hour: *start as i32,
+                end_hour: *end as i32,
+                utc_offset_minutes,
+            };
+            notify::set_quiet_hours(dbpool, guild_id, user_id, Some(&quiet)).await?;
+            format!(
+                "No DMs from {}:00 to {}:00 (UTC{})",
+                start,
+                end,
+                format_offset(utc_offset_minutes)
+            )
+        }
+        "quiet-off" => {
+            notify::set_quiet_hours(dbpool, guild_id, user_id, None).await?;
+            "Quiet hours turned off".to_string()
+        }
+        _ => return Ok(()),
+    };
+    reply(ctx, interaction, content).await
+}
+
+async fn reply(ctx: &Context, interaction: &CommandInteraction, content: String) -> Result<()> {
+    let response = CreateInteractionResponseMessage::new()
+        .content(content)
+        .ephemeral(true);
+    interaction
+        .create_response(&ctx.http, CreateInteractionResponse::Message(response))
+        .await?;
+    Ok(())
+}
+
+// Joins need a player and the server events can't have one
+fn event_option(interaction: &CommandInteraction) -> Result<(Event, String), String> {
+    let Some(CommandDataOptionValue::String(event)) = get_option(interaction, "event") else {
+        return Err("Pick an event".to_string());
+    };
+    let event = Event::from_name(event).ok_or_else(|| format!("Unknown event {}", event))?;
+    let player = match get_option(interaction, "player") {
+        Some(CommandDataOptionValue::String(player)) => player.trim().to_string(),
+        _ => String::new(),
+    };
+    match (event, player.is_empty()) {
+        (Event::Join, true) => Err("Say which player to watch for with `player`".to_string()),
+        (Event::Join, false) => Ok((event, player)),
+        _ => Ok((event, String::new())),
+    }
+}
+
+fn describe(event: Event, player: &str) -> String {
+    match event {
+        Event::Online => "the server coming online".to_string(),
+        Event::Offline => "the server going down".to_string(),
+        Event::Join => format!("**{}** joining", player),
+    }
+}
+
+// "+2", "-5" or "+5:30" to minutes. Real time zones run from UTC-12 to UTC+14
+fn parse_offset(offset: &str) -> Option<i32> {
+    let offset = offset.trim().trim_start_matches("UTC");
+    let (sign, rest) = match offset.strip_prefix('-') {
+        Some(rest) => (-1, rest),
+        None => (1, offset.trim_start_matches('+')),
+    };
+    let (hours, minutes) = rest.split_once(':').unwrap_or((rest, "0"));
+    let (hours, minutes): (i32, i32) = (hours.parse().ok()?, minutes.parse().ok()?);
+    if !(0..60).contains(&minutes) {
+        return None;
+    }
+    let offset = sign * (hours * 60 + minutes);
+    (-12 * 60..=14 * 60).contains(&offset).then_some(offset)
+}
+
+fn format_offset(minutes: i32) -> String {
+    let sign = if minutes < 0 { '-' } else { '+' };
+    match minutes.abs() % 60 {
+        0 => format!("{}{}", sign, minutes.abs() / 60),
+        rest => format!("{}{}:{:02}", sign, minutes.abs() / 60, rest),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_offsets() {
+        for (offset, expected) in [
+            ("0", Some(0)),
+            ("+2", Some(120)),
+            ("2", Some(120)),
+            ("-5", Some(-300)),
+            ("+5:30", Some(330)),
+            ("-0:30", Some(-30)),
+            ("UTC+1", Some(60)),
+            (" -3 ", Some(-180)),
+            ("-12", Some(-720)),
+            ("+14", Some(840)),
+            ("-13", None),
+            ("+15", None),
+            ("+5:60", None),
+            ("+5:", None),
+            ("", None),
+            ("CET", None),
+        ] {
+            assert_eq!(parse_offset(offset), expected, "{:?}", offset);
+        }
+    }
+
+    #[test]
+    fn formats_offsets() {
+        for minutes in [0, 120, -300, 330, -30, 840] {
+            assert_eq!(parse_offset(&format_offset(minutes)), Some(minutes));
+        }
+    }
+}
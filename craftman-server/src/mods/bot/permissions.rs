@@ -0,0 +1,11 @@
+use serenity::all::Member;
+
+pub const MANAGE_ONLY: &str = "You need the Manage Server permission to do this";
+
+// Buttons, and subcommands of commands everyone can use, aren't covered by a command's default
+// permissions, so they check the member themselves
+pub fn can_manage(member: Option<&Member>) -> bool {
+    member
+        .and_then(|member| member.permissions)
+        .is_some_and(|permissions| permissions.manage_guild())
+}
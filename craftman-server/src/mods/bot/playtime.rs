@@ -0,0 +1,176 @@
+use crate::appstate::AppState;
+use crate::mods::bot::get_guild::get_guild;
+use crate::mods::bot::get_option::get_option;
+use crate::mods::duration::duration;
+use anyhow::Result;
+use serenity::all::{CommandDataOptionValue, Context};
+use serenity::builder::*;
+use serenity::model::application::*;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const WEEK: i64 = 7 * 24 * 3600;
+const MONTH: i64 = 30 * 24 * 3600;
+const LEADERBOARD_SIZE: i64 = 10;
+
+pub fn register_playtime() -> CreateCommand {
+    CreateCommand::new("playtime")
+        .description("How long a Minecraft player has spent on the server")
+        .add_option(
+            CreateCommandOption::new(CommandOptionType::String, "player", "Minecraft name")
+                .required(true)
+                .set_autocomplete(true),
+        )
+}
+
+pub fn register_leaderboard() -> CreateCommand {
+    CreateCommand::new("leaderboard")
+        .description("Who plays the most")
+        .add_option(
+            CreateCommandOption::new(
+                CommandOptionType::SubCommand,
+                "playtime",
+                "Most time spent on the server",
+            )
+            .add_sub_option(
+                CreateCommandOption::new(
+                    CommandOptionType::String,
+                    "period",
+                    "How far back to count (default all time)",
+                )
+                .add_string_choice("Last 7 days", "week")
+                .add_string_choice("Last 30 days", "month")
+                .add_string_choice("All time", "all"),
+            ),
+        )
+}
+
+pub async fn playtime(
+    ctx: &Context,
+    interaction: &CommandInteraction,
+    appstate: &AppState,
+) -> Result<()> {
+    let agent = if let Ok(agent) =
+        appstate.find_connection_by_guild(get_guild(ctx, interaction).await?)
+    {
+        agent
+    } else {
+        let response = CreateInteractionResponseMessage::new();
+        interaction.create_response(&ctx.http, CreateInteractionResponse::Message(response.content("Unable to find agent. Please either start agent or verify a new agent with /verify"))).await?;
+        return Ok(());
+    };
+    let Some(CommandDataOptionValue::String(player)) = get_option(interaction, "player") else {
+        return Ok(());
+    };
+
+    let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() as i64;
+    let content = match agent.playtime(player, 0).await? {
+        Some(all) => {
+            let week = agent.playtime(player, now - WEEK).await?;
+            let month = agent.playtime(player, now - MONTH).await?;
+            format!(
+                "**`{}`**{}\nAll time: {} over {} session{}\nLast 30 days: {}\nLast 7 days: {}",
+                all.player,
+                if all.online { " 🟢 on now" } else { "" },
+                duration(all.seconds),
+                all.sessions,
+                if all.sessions == 1 { "" } else { "s" },
+                duration(month.map_or(0, |month| month.seconds)),
+                duration(week.map_or(0, |week| week.seconds))
+            )
+        }
+        None => format!("`{}` hasn't played here yet", player),
+    };
+    let response = CreateInteractionResponseMessage::new().content(content);
+    interaction
+        .create_response(&ctx.http, CreateInteractionResponse::Message(response))
+        .await?;
+    Ok(())
+}
+
+pub async fn leaderboard(
+    ctx: &Context,
+    interaction: &CommandInteraction,
+    appstate: &AppState,
+) -> Result<()> {
+    let agent = if let Ok(agent) =
+        appstate.find_connection_by_guild(get_guild(ctx, interaction).await?)
+    {
+        agent
+    } else {
+        let response = CreateInteractionResponseMessage::new();
+        interaction.create_response(&ctx.http, CreateInteractionResponse::Message(response.content("Unable to find agent. Please either start agent or verify a new agent with /verify"))).await?;
+        return Ok(());
+    };
+
+    let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() as i64;
+    let (since, title) = match get_option(interaction, "period") {
+        Some(CommandDataOptionValue::String(period)) if period == "week" => {
+            (now - WEEK, "the last 7 days")
+        }
+        Some(CommandDataOptionValue::String(period)) if period == "month" => {
+            (now - MONTH, "the last 30 days")
+        }
+        _ => (0, "all time"),
+    };
+    let leaderboard = agent.playtime_leaderboard(since, LEADERBOARD_SIZE).await?;
+    let content = if leaderboard.is_empty() {
+        format!("Nobody has played in {}", title)
+    } else {
+        let lines: Vec<String> = leaderboard
+            .iter()
+            .enumerate()
+            .map(|(place, playtime)| {
+                let place = match place {
+                    0 => "🥇".to_string(),
+                    1 => "🥈".to_string(),
+                    2 => "🥉".to_string(),
+                    place => format!("{}.", place + 1),
+                };
+                format!(
+                    "{} `{}` {}{}",
+                    place,
+                    playtime.player,
+                    duration(playtime.seconds),
+                    if playtime.online { " 🟢" } else { "" }
+                )
+            })
+            .collect();
+        format!("**Most playtime in {}**\n{}", title, lines.join("\n"))
+    };
+    let response = CreateInteractionResponseMessage::new().content(content);
+    interaction
+        .create_response(&ctx.http, CreateInteractionResponse::Message(response))
+        .await?;
+    Ok(())
+}
+
+// Suggests names the server has seen join. Autocomplete can't be answered with a message, so a
+// missing agent just gets no suggestions
+pub async fn autocomplete(
+    ctx: &Context,
+    interaction: &CommandInteraction,
+    appstate: &AppState,
+) -> Result<()> {
+    let players = match interaction
+        .guild_id
+        .and_then(|guild_id| appstate.find_connection_by_guild(guild_id.get()).ok())
+    {
+        Some(agent) => {
+            let prefix = interaction
+                .data
+                .autocomplete()
+                .map(|option| option.value)
+                .unwrap_or_default();
+            agent.known_players(prefix).await?
+        }
+        None => Vec::new(),
+    };
+    let mut response = CreateAutocompleteResponse::new();
+    for player in players {
+        response = response.add_string_choice(player.clone(), player);
+    }
+    interaction
+        .create_response(&ctx.http, CreateInteractionResponse::Autocomplete(response))
+        .await?;
+    Ok(())
+}
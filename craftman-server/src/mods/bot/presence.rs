@@ -0,0 +1,22 @@
+use crate::appstate::AppState;
+use serenity::all::{ActivityData, Context};
+use std::time::Duration;
+use tokio::time::sleep;
+
+// Presence updates go over the gateway, which is rate limited as well, so it's only sent when the
+// count changes
+pub async fn run(ctx: Context, appstate: AppState, cycle: Duration) {
+    let mut shown = None;
+    loop {
+        let players = appstate.online_players().await;
+        if shown != Some(players) {
+            let activity = match players {
+                1 => "1 player online".to_string(),
+                players => format!("{} players online", players),
+            };
+            ctx.set_activity(Some(ActivityData::watching(activity)));
+            shown = Some(players);
+        }
+        sleep(cycle).await;
+    }
+}
@@ -0,0 +1,62 @@
+use anyhow::Result;
+use protocol::tasks::Progress;
+use serenity::all::{Context, EditInteractionResponse};
+use serenity::builder::Builder;
+use tokio::sync::mpsc::UnboundedReceiver;
+
+const MAX_CONTENT: usize = 2000;
+
+// Edits the deferred response of an interaction with each step an agent task reports until the
+// task finishes or fails. Takes the interaction token so commands and buttons can both use it.
+// Returns whether the task finished successfully
+pub async fn follow_progress(
+    ctx: &Context,
+    token: &str,
+    title: &str,
+    mut receiver: UnboundedReceiver<Progress>,
+) -> Result<bool> {
+    let mut steps: Vec<(&str, String)> = Vec::new();
+    loop {
+        let progress = receiver
+            .recv()
+            .await
+            .unwrap_or_else(|| Progress::Failed("Lost track of the agent task".to_string()));
+        let finished = progress.is_final();
+        let succeeded = matches!(progress, Progress::Finished(_));
+        if let Some(last) = steps.last_mut()
+            && last.0 == "⏳"
+        {
+            last.0 = if matches!(progress, Progress::Failed(_)) {
+                "❌"
+            } else {
+                "✅"
+            };
+        }
+        match progress {
+            Progress::Step(step) => steps.push(("⏳", step)),
+            Progress::Finished(message) => steps.push(("✅", message)),
+            Progress::Failed(message) => steps.push(("❌", message)),
+        }
+        EditInteractionResponse::new()
+            .content(render(title, &steps))
+            .execute(&ctx.http, token)
+            .await?;
+        if finished {
+            return Ok(succeeded);
+        }
+    }
+}
+
+fn render(title: &str, steps: &[(&str, String)]) -> String {
+    let mut lines: Vec<String> = steps
+        .iter()
+        .map(|(icon, step)| format!("{} {}", icon, step))
+        .collect();
+    loop {
+        let content = format!("**{}**\n{}", title, lines.join("\n"));
+        if content.chars().count() <= MAX_CONTENT || lines.len() <= 1 {
+            return content.chars().take(MAX_CONTENT).collect();
+        }
+        lines.remove(0);
+    }
+}
@@ -1,31 +1,55 @@
 use crate::appstate::AppState;
+use crate::mods::agents::Agent;
 use crate::mods::bot::get_guild::get_guild;
+use crate::mods::bot::get_option::get_option;
+use crate::mods::bot::mod_commands::restart::restart_from_button;
+use crate::mods::bot::permissions::{MANAGE_ONLY, can_manage};
+use crate::mods::bot::server_commands::properties::settingsview::open_settings_view;
+use crate::mods::bot::server_commands::{startserver, stopserver};
 use crate::mods::bot::si2tr::si2tr;
+use crate::mods::external::{self, MAX_PER_GUILD, parse_address, resolve_public};
 use anyhow::Result;
+use protocol::properties::Edition;
 use protocol::query_options::{QueryStatus, ServerStatus};
+use rust_mc_status::McClient;
+use serenity::all::CommandDataOptionValue;
+use serenity::all::CommandOptionType;
+use serenity::all::ComponentInteraction;
 use serenity::all::Context;
 use serenity::all::CreateInteractionResponse;
+use serenity::all::CreateInteractionResponseFollowup;
 use serenity::all::CreateInteractionResponseMessage;
-use serenity::builder::CreateCommand;
+use serenity::builder::{CreateCommand, CreateCommandOption};
+use serenity::model::application::CommandInteraction;
 use std::collections::HashSet;
+use twilight_http::error::ErrorType;
 use twilight_model::channel::message::Component;
 use twilight_model::channel::message::MessageFlags;
-use twilight_model::channel::message::component::{TextDisplay, UnfurledMediaItem};
+use twilight_model::channel::message::component::{
+    ButtonStyle, MediaGallery, MediaGalleryItem, TextDisplay, UnfurledMediaItem,
+};
+use twilight_model::channel::{Attachment as MessageAttachment, Message};
 use twilight_model::http::attachment::Attachment;
 use twilight_model::http::interaction::InteractionResponseType;
 use twilight_model::http::interaction::*;
 use twilight_model::id::{
     Id,
-    marker::{ChannelMarker, MessageMarker},
+    marker::{AttachmentMarker, ChannelMarker, MessageMarker},
 };
 use twilight_util::builder::interaction_response::ModalBuilder;
+use twilight_util::builder::message::{
+    ActionRowBuilder, ButtonBuilder, LabelBuilder, SectionBuilder, ThumbnailBuilder,
+};
 use twilight_util::builder::message::{
     CheckboxGroupBuilder, CheckboxGroupOptionBuilder, TextDisplayBuilder,
 };
-use twilight_util::builder::message::{LabelBuilder, SectionBuilder, ThumbnailBuilder};
 use uuid::Uuid;
 
 static DEFAULT_ICON: &[u8] = include_bytes!("../../../assets/default_icon.png");
+const CHART_DESCRIPTION: &str = "Player chart";
+const DEFAULT_INTERVAL: u64 = 10;
+const MIN_INTERVAL: u64 = 5;
+const MAX_INTERVAL: u64 = 3600;
 
 pub async fn builder_modal(
     ctx: &Context,
@@ -43,8 +67,12 @@ pub async fn builder_modal(
     };
 
     let id = appstate.find_id_by_guild(get_guild(ctx, &serenity_interaction).await?)?;
+    let interval = match get_option(&serenity_interaction, "interval") {
+        Some(CommandDataOptionValue::Integer(interval)) => *interval as u64,
+        _ => DEFAULT_INTERVAL,
+    };
 
-    let response = build_monitor(id);
+    let response = build_monitor(id, interval);
 
     si2tr(client, &serenity_interaction, &response).await;
 
@@ -52,11 +80,222 @@ pub async fn builder_modal(
 }
 
 pub fn register() -> CreateCommand {
-    CreateCommand::new("monitor").description("see the header")
+    let address = CreateCommandOption::new(
+        CommandOptionType::String,
+        "address",
+        "host or host:port of the server",
+    )
+    .required(true);
+    let edition = CreateCommandOption::new(
+        CommandOptionType::String,
+        "edition",
+        "Java or Bedrock (default Java)",
+    )
+    .add_string_choice("Java", "java")
+    .add_string_choice("Bedrock", "bedrock");
+    let interval = CreateCommandOption::new(
+        CommandOptionType::Integer,
+        "interval",
+        "seconds between updates (default 10)",
+    )
+    .min_int_value(MIN_INTERVAL)
+    .max_int_value(MAX_INTERVAL);
+    let monitor = CreateCommandOption::new(
+        CommandOptionType::Integer,
+        "monitor",
+        "the monitor's number from /monitor list",
+    )
+    .required(true);
+    CreateCommand::new("monitor")
+        .description("live status monitors")
+        .add_option(
+            CreateCommandOption::new(
+                CommandOptionType::SubCommand,
+                "create",
+                "build a monitor for the server your agent runs",
+            )
+            .add_sub_option(interval),
+        )
+        .add_option(CreateCommandOption::new(
+            CommandOptionType::SubCommand,
+            "list",
+            "list this server's monitors",
+        ))
+        .add_option(
+            CreateCommandOption::new(
+                CommandOptionType::SubCommand,
+                "remove",
+                "stop a monitor from updating",
+            )
+            .add_sub_option(monitor),
+        )
+        .add_option(
+            CreateCommandOption::new(
+                CommandOptionType::SubCommand,
+                "add-external",
+                "monitor a server without an agent by its address",
+            )
+            .add_sub_option(address)
+            .add_sub_option(edition),
+        )
+}
+
+// Pinged by the central server on an interval. Deleting the message removes the monitor
+pub async fn add_external(
+    ctx: &Context,
+    client: &twilight_http::Client,
+    serenity_interaction: CommandInteraction,
+    appstate: &AppState,
+) -> Result<()> {
+    if !can_manage(serenity_interaction.member.as_deref()) {
+        return reply(ctx, &serenity_interaction, MANAGE_ONLY).await;
+    }
+    let guild_id = get_guild(ctx, &serenity_interaction).await?;
+    let edition = match get_option(&serenity_interaction, "edition") {
+        Some(CommandDataOptionValue::String(edition)) if edition == "bedrock" => Edition::Bedrock,
+        _ => Edition::Java,
+    };
+    let address = match get_option(&serenity_interaction, "address") {
+        Some(CommandDataOptionValue::String(address)) => parse_address(address, edition),
+        _ => None,
+    };
+    let Some(address) = address else {
+        return reply(
+            ctx,
+            &serenity_interaction,
+            "Addresses look like `play.example.com` or `play.example.com:25566`",
+        )
+        .await;
+    };
+    if let Err(e) = resolve_public(&address, edition).await {
+        return reply(ctx, &serenity_interaction, &e.to_string()).await;
+    }
+    if external::monitor_count(&appstate.dbpool, guild_id).await? >= MAX_PER_GUILD {
+        let content = format!(
+            "This server already has {} external monitors. Delete one of their messages to make room",
+            MAX_PER_GUILD
+        );
+        return reply(ctx, &serenity_interaction, &content).await;
+    }
+
+    let application_id = Id::new(serenity_interaction.application_id.get());
+    let interaction_client = client.interaction(application_id);
+    let response = InteractionResponse {
+        kind: InteractionResponseType::DeferredChannelMessageWithSource,
+        data: Some(InteractionResponseData {
+            flags: Some(MessageFlags::IS_COMPONENTS_V2),
+            ..Default::default()
+        }),
+    };
+    si2tr(client, &serenity_interaction, &response).await;
+
+    let (description, image, status) =
+        match external::ping(&McClient::new(), &address, edition).await {
+            Ok(pong) => (
+                pong.description,
+                pong.favicon,
+                ServerStatus::ServerOnline(pong.status),
+            ),
+            Err(e) => {
+                tracing::debug!("First ping to {} failed: {}", address, e);
+                (
+                    "A minecraft server".to_string(),
+                    None,
+                    ServerStatus::ServerOffline,
+                )
+            }
+        };
+    let header = format!(
+        "# Message Of The Day:\n{}\n-# External server `{}`, watched without an agent",
+        description, address
+    );
+    let (components, attachment) = monitor_message(header, image, status)?;
+    let message = interaction_client
+        .update_response(&serenity_interaction.token)
+        .components(Some(&components))
+        .flags(MessageFlags::IS_COMPONENTS_V2)
+        .attachments(&[attachment])
+        .await?
+        .model()
+        .await?;
+    external::add_monitor(
+        &appstate.dbpool,
+        guild_id,
+        &address,
+        edition,
+        message.channel_id.get(),
+        message.id.get(),
+    )
+    .await
+}
+
+pub async fn list(
+    ctx: &Context,
+    interaction: &CommandInteraction,
+    appstate: &AppState,
+) -> Result<()> {
+    let guild_id = get_guild(ctx, interaction).await?;
+    let Ok(agent) = appstate.find_connection_by_guild(guild_id) else {
+        return reply(
+            ctx,
+            interaction,
+            "Unable to find agent. Please either start agent or verify a new agent with /verify",
+        )
+        .await;
+    };
+    let monitors = agent.query_monitors().await?;
+    if monitors.is_empty() {
+        return reply(
+            ctx,
+            interaction,
+            "No monitors yet. Build one with /monitor create",
+        )
+        .await;
+    }
+    let content = monitors
+        .iter()
+        .map(|monitor| {
+            format!(
+                "**{}** https://discord.com/channels/{}/{}/{} every {}s",
+                monitor.id, guild_id, monitor.channel_id, monitor.message_id, monitor.interval_secs
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    reply(ctx, interaction, &content).await
+}
+
+// The message is left in place, it just stops changing
+pub async fn remove(
+    ctx: &Context,
+    interaction: &CommandInteraction,
+    appstate: &AppState,
+) -> Result<()> {
+    if !can_manage(interaction.member.as_deref()) {
+        return reply(ctx, interaction, MANAGE_ONLY).await;
+    }
+    let Ok(agent) = appstate.find_connection_by_guild(get_guild(ctx, interaction).await?) else {
+        return reply(
+            ctx,
+            interaction,
+            "Unable to find agent. Please either start agent or verify a new agent with /verify",
+        )
+        .await;
+    };
+    let Some(CommandDataOptionValue::Integer(monitor)) = get_option(interaction, "monitor") else {
+        return reply(ctx, interaction, "Pick a monitor from /monitor list").await;
+    };
+    let content = if agent.remove_monitor(*monitor).await? {
+        format!("Monitor {} removed", monitor)
+    } else {
+        format!("No monitor {} on this server", monitor)
+    };
+    reply(ctx, interaction, &content).await
 }
 
 pub async fn build_view(
     options: HashSet<String>,
+    interval: u64,
     client: &twilight_http::Client,
     serenity_interaction: &serenity::model::application::ModalInteraction,
     appstate: &AppState,
@@ -93,7 +332,32 @@ pub async fn build_view(
 
     let agent = appstate.find_connection(&uuid)?;
 
-    let (description, image, status) = agent.new_query(options, message_id, channel_id).await?;
+    let interval = interval.clamp(MIN_INTERVAL, MAX_INTERVAL);
+    let (description, image, status) = agent
+        .new_query(options, interval, message_id, channel_id)
+        .await?;
+    let online = matches!(status, ServerStatus::ServerOnline(_));
+    let (mut components, attachment) = monitor_message(
+        format!("# Message Of The Day:\n{}", description),
+        image,
+        status,
+    )?;
+    components.push(monitor_buttons(uuid, online));
+    interaction_client
+        .update_response(&serenity_interaction.token)
+        .components(Some(&components))
+        .flags(MessageFlags::IS_COMPONENTS_V2)
+        .attachments(&[attachment])
+        .await?;
+    Ok(())
+}
+
+// Status, then the favicon next to the header text, then whatever the status has to show
+fn monitor_message(
+    header: String,
+    image: Option<Vec<u8>>,
+    status: ServerStatus,
+) -> Result<(Vec<Component>, Attachment)> {
     let filename = format!("{}.png", uuid::Uuid::new_v4());
     let mut attachment =
         Attachment::from_bytes(filename.clone(), image.unwrap_or(DEFAULT_ICON.to_vec()), 1);
@@ -107,7 +371,7 @@ pub async fn build_view(
     };
     let displaytext = TextDisplay {
         id: None,
-        content: format!("# Message Of The Day:\n{}", description),
+        content: header,
     };
     let thumbnail = ThumbnailBuilder::new(mediaitem).build();
     let header = SectionBuilder::new(thumbnail)
@@ -119,27 +383,14 @@ pub async fn build_view(
         TextDisplayBuilder::new("🟢 **Online**").build()
     };
     let mut components = vec![statustext.into(), header.into()];
-
     if let ServerStatus::ServerOnline(query) = status {
         components.append(&mut build_monitor_display(query)?);
-        interaction_client
-            .update_response(&serenity_interaction.token)
-            .components(Some(&components))
-            .flags(MessageFlags::IS_COMPONENTS_V2)
-            .attachments(&[attachment])
-            .await?;
-    } else {
-        interaction_client
-            .update_response(&serenity_interaction.token)
-            .components(Some(&components))
-            .flags(MessageFlags::IS_COMPONENTS_V2)
-            .attachments(&[attachment])
-            .await?;
     }
-    Ok(())
+    Ok((components, attachment))
 }
 
-pub fn build_monitor(uuid: Uuid) -> InteractionResponse {
+// The interval rides along in the modal's id until the monitor is saved
+pub fn build_monitor(uuid: Uuid, interval: u64) -> InteractionResponse {
     let id = &uuid.to_string();
     let version = CheckboxGroupOptionBuilder::new("version", "Version")
         .description("What Minecraft version is this server")
@@ -173,6 +424,10 @@ pub fn build_monitor(uuid: Uuid) -> InteractionResponse {
         .description("What mods are loaded on the server")
         .build();
 
+    let uptime = CheckboxGroupOptionBuilder::new("uptime", "Uptime")
+        .description("How long the server has been running and online")
+        .build();
+
     let checkboxgroup = CheckboxGroupBuilder::new("checkboxgroupid")
         .option(version)
         .option(player_count)
@@ -182,6 +437,7 @@ pub fn build_monitor(uuid: Uuid) -> InteractionResponse {
         .option(software)
         .option(plugins)
         .option(mods)
+        .option(uptime)
         .required(true)
         .build();
 
@@ -189,54 +445,141 @@ pub fn build_monitor(uuid: Uuid) -> InteractionResponse {
     let label = LabelBuilder::new("Options: ", checkboxgroup.into()).build();
     let components: Vec<Component> = vec![text.into(), label.into()];
     ModalBuilder::new(
-        format!("build_query:checkbox_test:{}", id),
+        format!("build_query:{}:{}", interval, id),
         "Build Monitor",
         components,
     )
     .build()
 }
 
+// Agent monitors pass their agent's id to keep the buttons in step with the status. External
+// monitors have nothing to control and pass None
 pub async fn update_monitor(
     channel_id: Id<ChannelMarker>,
     message_id: Id<MessageMarker>,
     status: ServerStatus,
+    buttons: Option<Uuid>,
     client: &twilight_http::Client,
 ) -> Result<()> {
-    if let ServerStatus::ServerOnline(query) = status {
-        let message = client
-            .message(channel_id, message_id)
-            .await?
-            .model()
-            .await?;
+    let message = client
+        .message(channel_id, message_id)
+        .await?
+        .model()
+        .await?;
+    let online = matches!(status, ServerStatus::ServerOnline(_));
+    let mut components = if let ServerStatus::ServerOnline(query) = status {
         let mut components = vec![
             TextDisplayBuilder::new("🟢 **Online**").build().into(),
             message.components[1].clone(),
         ];
         components.append(&mut build_monitor_display(query)?);
-
-        client
-            .update_message(channel_id, message_id)
-            .components(Some(&components))
-            .await?;
+        components
     } else {
-        let message = client
-            .message(channel_id, message_id)
-            .await?
-            .model()
-            .await?;
-        let components = vec![
+        vec![
             TextDisplayBuilder::new("🔴 **Offline**").build().into(),
             message.components[1].clone(),
-        ];
+        ]
+    };
+    components.extend(buttons.map(|id| monitor_buttons(id, online)));
+    components.extend(chart_component(&message));
 
-        client
-            .update_message(channel_id, message_id)
-            .components(Some(&components))
-            .await?;
-    }
+    client
+        .update_message(channel_id, message_id)
+        .components(Some(&components))
+        .await?;
     Ok(())
 }
 
+// Start is only offered while the server is offline, Stop and Restart while it's online
+fn monitor_buttons(id: Uuid, online: bool) -> Component {
+    let button = |action: &str, label: &str, style: ButtonStyle, disabled: bool| {
+        ButtonBuilder::new(style)
+            .custom_id(format!("monitor:{}:{}", action, id))
+            .label(label)
+            .disabled(disabled)
+            .build()
+    };
+    ActionRowBuilder::new()
+        .component(button("start", "Start", ButtonStyle::Success, online))
+        .component(button("stop", "Stop", ButtonStyle::Danger, !online))
+        .component(button(
+            "restart",
+            "Restart",
+            ButtonStyle::Secondary,
+            !online,
+        ))
+        .component(button("refresh", "Refresh", ButtonStyle::Secondary, false))
+        .component(button(
+            "props",
+            "Open properties",
+            ButtonStyle::Primary,
+            false,
+        ))
+        .build()
+        .into()
+}
+
+// The monitor is public, so everything but Refresh needs Manage Server like /files and /config
+pub async fn handle_component(
+    ctx: &Context,
+    client: &twilight_http::Client,
+    component: &ComponentInteraction,
+    agent: &Agent,
+    action: &str,
+) -> Result<()> {
+    if action != "refresh" && !can_manage(component.member.as_deref()) {
+        return component_reply(
+            ctx,
+            component,
+            "You need the Manage Server permission to control the server from a monitor",
+        )
+        .await;
+    }
+    match action {
+        "start" => component_reply(ctx, component, &startserver::run(agent).await).await,
+        "stop" => component_reply(ctx, component, &stopserver::run(agent).await).await,
+        "restart" => restart_from_button(ctx, component, agent).await,
+        "refresh" => refresh(ctx, client, component, agent).await,
+        "props" => {
+            open_settings_view(
+                client,
+                agent,
+                component.application_id.get(),
+                component.id.get(),
+                &component.token,
+            )
+            .await
+        }
+        _ => Ok(()),
+    }
+}
+
+async fn refresh(
+    ctx: &Context,
+    client: &twilight_http::Client,
+    component: &ComponentInteraction,
+    agent: &Agent,
+) -> Result<()> {
+    component
+        .create_response(&ctx.http, CreateInteractionResponse::Acknowledge)
+        .await?;
+    let channel_id = Id::new(component.channel_id.get());
+    let message_id = Id::new(component.message.id.get());
+    let Some((description, image, status)) = agent.refresh_monitor(message_id).await? else {
+        component
+            .create_followup(
+                &ctx.http,
+                CreateInteractionResponseFollowup::new()
+                    .content("This monitor was removed. Build a new one with /monitor create")
+                    .ephemeral(true),
+            )
+            .await?;
+        return Ok(());
+    };
+    update_header(message_id, channel_id, description, image, client).await?;
+    update_monitor(channel_id, message_id, status, Some(agent.id()), client).await
+}
+
 pub fn build_monitor_display(query: QueryStatus) -> Result<Vec<Component>> {
     let mut components = Vec::new();
     if let Some(version) = query.version() {
@@ -247,6 +590,18 @@ pub fn build_monitor_display(query: QueryStatus) -> Result<Vec<Component>> {
         );
     }
 
+    // Relative timestamps keep counting up on their own without editing the message
+    if let Some(online_since) = query.online_since() {
+        let content = match query.started_at() {
+            Some(started_at) => format!(
+                "# Uptime:\nStarted <t:{}:R>\nOnline since <t:{}:R>",
+                started_at, online_since
+            ),
+            None => format!("# Uptime:\nOnline since <t:{}:R>", online_since),
+        };
+        components.push(TextDisplayBuilder::new(content).build().into());
+    }
+
     if let Some(player_count) = query.player_count() {
         components.push(
             TextDisplayBuilder::new(format!("# Player Count:\n{}", player_count))
@@ -341,12 +696,120 @@ pub async fn update_header(
     let header = SectionBuilder::new(thumbnail)
         .component(displaytext)
         .build();
+    let keep = kept_attachments(&message.attachments, |description| {
+        description == Some(CHART_DESCRIPTION)
+    });
     let mut components = message.components;
     components[1] = header.into();
     client
         .update_message(channel_id, message_id)
         .components(Some(&components))
         .attachments(&[attachment])
+        .keep_attachment_ids(&keep)
+        .await?;
+    Ok(())
+}
+
+// The chart is always the last component, after whatever the monitor shows
+pub async fn update_chart(
+    channel_id: Id<ChannelMarker>,
+    message_id: Id<MessageMarker>,
+    png: Vec<u8>,
+    client: &twilight_http::Client,
+) -> Result<()> {
+    let message = client
+        .message(channel_id, message_id)
+        .await?
+        .model()
+        .await?;
+    let filename = format!("chart-{}.png", uuid::Uuid::new_v4());
+    let mut attachment = Attachment::from_bytes(filename.clone(), png, 1);
+    attachment.description(CHART_DESCRIPTION.to_string());
+    let gallery = MediaGallery {
+        id: None,
+        items: vec![MediaGalleryItem {
+            media: UnfurledMediaItem {
+                url: format!("attachment://{}", filename),
+                proxy_url: None,
+                height: None,
+                width: None,
+                content_type: None,
+            },
+            description: Some(CHART_DESCRIPTION.to_string()),
+            spoiler: None,
+        }],
+    };
+    let keep = kept_attachments(&message.attachments, |description| {
+        description != Some(CHART_DESCRIPTION)
+    });
+    let mut components = message.components;
+    if matches!(components.last(), Some(Component::MediaGallery(_))) {
+        components.pop();
+    }
+    components.push(gallery.into());
+    client
+        .update_message(channel_id, message_id)
+        .components(Some(&components))
+        .attachments(&[attachment])
+        .keep_attachment_ids(&keep)
+        .await?;
+    Ok(())
+}
+
+fn chart_component(message: &Message) -> Option<Component> {
+    message
+        .components
+        .last()
+        .filter(|component| matches!(component, Component::MediaGallery(_)))
+        .cloned()
+}
+
+// Editing a deleted message is how a removed monitor shows up
+pub fn message_gone(e: &anyhow::Error) -> bool {
+    e.downcast_ref::<twilight_http::Error>().is_some_and(
+        |e| matches!(e.kind(), ErrorType::Response { status, .. } if status.get() == 404),
+    )
+}
+
+fn kept_attachments(
+    attachments: &[MessageAttachment],
+    keep: impl Fn(Option<&str>) -> bool,
+) -> Vec<Id<AttachmentMarker>> {
+    attachments
+        .iter()
+        .filter(|attachment| keep(attachment.description.as_deref()))
+        .map(|attachment| attachment.id)
+        .collect()
+}
+
+async fn component_reply(
+    ctx: &Context,
+    component: &ComponentInteraction,
+    content: &str,
+) -> Result<()> {
+    component
+        .create_response(
+            &ctx.http,
+            CreateInteractionResponse::Message(
+                CreateInteractionResponseMessage::new()
+                    .content(content)
+                    .ephemeral(true),
+            ),
+        )
+        .await?;
+    Ok(())
+}
+
+async fn reply(ctx: &Context, interaction: &CommandInteraction, content: &str) -> Result<()> {
+    interaction
+        .create_response(
+            &ctx.http,
+            CreateInteractionResponse::Message(
+                CreateInteractionResponseMessage::new()
+                    .content(content)
+                    .ephemeral(true),
+            ),
+        )
         .await?;
     Ok(())
 }
@@ -0,0 +1,79 @@
+use crate::appstate::AppState;
+use crate::mods::bot::get_guild::get_guild;
+use crate::mods::bot::get_option::get_attachment;
+use crate::mods::bot::permissions::{MANAGE_ONLY, can_manage};
+use crate::mods::bot::progress_message::follow_progress;
+use anyhow::Result;
+use protocol::tasks::RemoteFile;
+use serenity::all::Context;
+use serenity::builder::*;
+use serenity::model::application::CommandInteraction;
+
+pub async fn install_modpack(
+    ctx: &Context,
+    interaction: &CommandInteraction,
+    appstate: &AppState,
+) -> Result<()> {
+    // Like upgrades, a modpack puts code on the server that runs on the next start
+    if !can_manage(interaction.member.as_deref()) {
+        let response = CreateInteractionResponseMessage::new()
+            .content(MANAGE_ONLY)
+            .ephemeral(true);
+        interaction
+            .create_response(&ctx.http, CreateInteractionResponse::Message(response))
+            .await?;
+        return Ok(());
+    }
+    let agent = if let Ok(agent) =
+        appstate.find_connection_by_guild(get_guild(ctx, interaction).await?)
+    {
+        agent
+    } else {
+        let response = CreateInteractionResponseMessage::new();
+        interaction.create_response(&ctx.http, CreateInteractionResponse::Message(response.content("Unable to find agent. Please either start agent or verify a new agent with /verify"))).await?;
+        return Ok(());
+    };
+    let response = CreateInteractionResponseMessage::new();
+    let Some(pack) =
+        get_attachment(interaction, "pack").filter(|pack| pack.filename.ends_with(".mrpack"))
+    else {
+        interaction
+            .create_response(
+                &ctx.http,
+                CreateInteractionResponse::Message(
+                    response.content("Please attach a Modrinth .mrpack file"),
+                ),
+            )
+            .await?;
+        return Ok(());
+    };
+
+    let request = RemoteFile {
+        file_name: pack.filename.clone(),
+        url: pack.url.clone(),
+    };
+    match agent.install_modpack(request).await {
+        Ok(progress) => {
+            interaction
+                .create_response(&ctx.http, CreateInteractionResponse::Defer(response))
+                .await?;
+            follow_progress(
+                ctx,
+                &interaction.token,
+                &format!("Installing {}", pack.filename),
+                progress,
+            )
+            .await?;
+        }
+        Err(e) => {
+            tracing::warn!("Modpack install failed to start: {}", e);
+            interaction
+                .create_response(
+                    &ctx.http,
+                    CreateInteractionResponse::Message(response.content(e.to_string())),
+                )
+                .await?;
+        }
+    }
+    Ok(())
+}
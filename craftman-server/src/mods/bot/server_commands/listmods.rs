@@ -0,0 +1,104 @@
+use crate::appstate::AppState;
+use crate::mods::bot::get_guild::get_guild;
+use anyhow::Result;
+use protocol::inventory::{InstalledMod, Inventory};
+use serenity::all::Context;
+use serenity::builder::*;
+use serenity::model::application::CommandInteraction;
+
+const MESSAGE_LIMIT: usize = 2000;
+
+pub async fn list_mods(
+    ctx: &Context,
+    interaction: &CommandInteraction,
+    appstate: &AppState,
+) -> Result<()> {
+    let agent = if let Ok(agent) =
+        appstate.find_connection_by_guild(get_guild(ctx, interaction).await?)
+    {
+        agent
+    } else {
+        let response = CreateInteractionResponseMessage::new();
+        interaction.create_response(&ctx.http, CreateInteractionResponse::Message(response.content("Unable to find agent. Please either start agent or verify a new agent with /verify"))).await?;
+        return Ok(());
+    };
+    let response = CreateInteractionResponseMessage::new();
+    interaction
+        .create_response(&ctx.http, CreateInteractionResponse::Defer(response))
+        .await?;
+    let content = match agent.list_mods().await {
+        Ok(inventory) => inventory_message(&inventory),
+        Err(e) => {
+            tracing::warn!("Listing mods failed: {}", e);
+            e.to_string()
+        }
+    };
+    interaction
+        .edit_response(&ctx.http, EditInteractionResponse::new().content(content))
+        .await?;
+    Ok(())
+}
+
+fn inventory_message(inventory: &Inventory) -> String {
+    if inventory.mods.is_empty() {
+        return "No jars found in mods/ or plugins/".to_string();
+    }
+
+    let mut header = format!(
+        "**Minecraft {}**",
+        inventory
+            .minecraft_version
+            .as_deref()
+            .unwrap_or("version unknown")
+    );
+    let problems = inventory.problem_count();
+    if problems > 0 {
+        header.push_str(&format!(" · ⚠️ {} problems", problems));
+    }
+
+    let mut lines = Vec::new();
+    if problems > 0 {
+        lines.push("**Problems**".to_string());
+        for installed in inventory.mods.iter() {
+            for problem in installed.problems.iter() {
+                lines.push(format!("⚠️ {} {}", installed.name, problem));
+            }
+        }
+    }
+    for (folder, title) in [("mods", "Mods"), ("plugins", "Plugins")] {
+        let installed: Vec<&InstalledMod> = inventory.in_folder(folder).collect();
+        if installed.is_empty() {
+            continue;
+        }
+        lines.push(format!("**{}** ({})", title, installed.len()));
+        lines.extend(installed.into_iter().map(mod_line));
+    }
+
+    let mut message = header;
+    for (shown, line) in lines.iter().enumerate() {
+        let more = format!("\n…and {} more", lines.len() - shown);
+        if message.len() + line.len() + 1 + more.len() > MESSAGE_LIMIT {
+            message.push_str(&more);
+            break;
+        }
+        message.push('\n');
+        message.push_str(line);
+    }
+    message
+}
+
+fn mod_line(installed: &InstalledMod) -> String {
+    let mut line = format!(
+        "`{}` {} {}",
+        installed.id, installed.name, installed.version
+    );
+    if let Some(minecraft) = &installed.minecraft {
+        line.push_str(&format!(" · MC {}", minecraft));
+    }
+    if !installed.enabled {
+        line = format!("~~{}~~ (disabled)", line);
+    } else if !installed.problems.is_empty() {
+        line.push_str(" ⚠️");
+    }
+    line
+}
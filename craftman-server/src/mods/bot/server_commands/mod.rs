@@ -1,4 +1,7 @@
+pub mod installmodpack;
+pub mod listmods;
 pub mod properties;
 pub mod server_command;
 pub mod startserver;
 pub mod stopserver;
+pub mod upgradeserver;
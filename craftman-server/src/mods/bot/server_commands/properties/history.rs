@@ -0,0 +1,182 @@
+use crate::appstate::AppState;
+use crate::mods::agents::{Agent, PropertyChange};
+use crate::mods::bot::get_guild::get_guild;
+use crate::mods::bot::permissions::{MANAGE_ONLY, can_manage};
+use crate::mods::bot::server_commands::properties::settingsview::short_value;
+use crate::mods::bot::si2tr::si2tr;
+use anyhow::{Result, anyhow};
+use protocol::properties::{display_name, is_secret};
+use serenity::all::{
+    ComponentInteraction, Context, CreateInteractionResponse, CreateInteractionResponseMessage,
+};
+use serenity::model::application::CommandInteraction;
+use twilight_model::channel::message::component::*;
+use twilight_model::channel::message::{Component, MessageFlags};
+use twilight_model::http::interaction::{
+    InteractionResponse, InteractionResponseData, InteractionResponseType,
+};
+use twilight_model::id::Id;
+use twilight_util::builder::interaction_response::ChannelMessageBuilder;
+use twilight_util::builder::message::*;
+use uuid::Uuid;
+
+const HISTORY_SIZE: i64 = 10;
+
+pub async fn run(
+    ctx: &Context,
+    client: &twilight_http::Client,
+    interaction: &CommandInteraction,
+    appstate: &AppState,
+) -> Result<()> {
+    let agent = if let Ok(agent) =
+        appstate.find_connection_by_guild(get_guild(ctx, interaction).await?)
+    {
+        agent
+    } else {
+        let response = CreateInteractionResponseMessage::new();
+        interaction.create_response(&ctx.http, CreateInteractionResponse::Message(response.content("Unable to find agent. Please either start agent or verify a new agent with /verify"))).await?;
+        return Ok(());
+    };
+    let changes = agent.property_history(HISTORY_SIZE).await?;
+    let response = ChannelMessageBuilder::new()
+        .components(build_history_view(&changes, agent.id()))
+        .flags(MessageFlags::IS_COMPONENTS_V2)
+        .build();
+    si2tr(client, interaction, &response).await;
+    Ok(())
+}
+
+pub fn build_history_view(changes: &[PropertyChange], uuid: Uuid) -> Vec<Component> {
+    let id = &uuid.to_string();
+    let mut message: Vec<Component> = vec![
+        TextDisplayBuilder::new("### 🕓 Property history")
+            .build()
+            .into(),
+    ];
+    if changes.is_empty() {
+        message.push(
+            TextDisplayBuilder::new("*No changes recorded yet*")
+                .build()
+                .into(),
+        );
+    }
+    for change in changes {
+        message.push(change_section(change, id).into());
+    }
+    message
+}
+
+fn change_section(change: &PropertyChange, id: &str) -> Section {
+    let mut content = format!("**{}**", display_name(&change.key));
+    if is_secret(&change.key) {
+        content.push_str(" changed");
+    } else {
+        content.push_str(&format!(
+            " {} → {}",
+            short_value(change.old_value.as_deref()),
+            short_value(Some(&change.new_value))
+        ));
+    }
+    content.push_str(&format!(
+        "\n-# <@{}> <t:{}:R>",
+        change.user_id, change.changed_at
+    ));
+
+    // Keys that weren't in the file before can't be removed again through the agent, and secrets
+    // are recorded without an old value
+    let button = ButtonBuilder::new(ButtonStyle::Secondary)
+        .custom_id(format!("revert:{}:{}", change.id, id))
+        .label("Revert")
+        .disabled(change.old_value.is_none())
+        .build();
+    SectionBuilder::new(button)
+        .component(TextDisplay { id: None, content })
+        .build()
+}
+
+// Sends the old value back to the agent, which is recorded as a change of its own. Only while the
+// property still holds the value the change set, so later edits aren't silently undone
+pub async fn revert(
+    ctx: &Context,
+    client: &twilight_http::Client,
+    component: &ComponentInteraction,
+    agent: &Agent,
+    change_id: i64,
+) -> Result<()> {
+    let guild_id = component
+        .guild_id
+        .ok_or_else(|| anyhow!("Revert used outside of a server"))?;
+    if !can_manage(component.member.as_deref()) {
+        component
+            .create_response(&ctx.http, error_message(MANAGE_ONLY))
+            .await?;
+        return Ok(());
+    }
+    let Some(change) = agent.property_change(change_id).await? else {
+        component
+            .create_response(
+                &ctx.http,
+                error_message("That change is no longer recorded"),
+            )
+            .await?;
+        return Ok(());
+    };
+    let Some(old_value) = change.old_value else {
+        component
+            .create_response(
+                &ctx.http,
+                error_message("The property wasn't set before this change"),
+            )
+            .await?;
+        return Ok(());
+    };
+    let current = agent.request_props().await?.values.remove(&change.key);
+    if current.as_ref() != Some(&change.new_value) {
+        component
+            .create_response(
+                &ctx.http,
+                error_message(
+                    "The property has been changed again since, revert that change first",
+                ),
+            )
+            .await?;
+        return Ok(());
+    }
+    if let Err(e) = agent
+        .set_property(
+            change.key,
+            old_value,
+            guild_id.get(),
+            component.user.id.get(),
+        )
+        .await
+    {
+        component
+            .create_response(&ctx.http, error_message(&e.to_string()))
+            .await?;
+        return Ok(());
+    }
+
+    let changes = agent.property_history(HISTORY_SIZE).await?;
+    let response = InteractionResponse {
+        kind: InteractionResponseType::UpdateMessage,
+        data: Some(InteractionResponseData {
+            components: Some(build_history_view(&changes, agent.id())),
+            flags: Some(MessageFlags::IS_COMPONENTS_V2),
+            ..Default::default()
+        }),
+    };
+    client
+        .interaction(Id::new(component.application_id.get()))
+        .create_response(Id::new(component.id.get()), &component.token, &response)
+        .await?;
+    Ok(())
+}
+
+fn error_message(content: &str) -> CreateInteractionResponse {
+    CreateInteractionResponse::Message(
+        CreateInteractionResponseMessage::new()
+            .content(content)
+            .ephemeral(true),
+    )
+}
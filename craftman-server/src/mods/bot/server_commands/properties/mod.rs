@@ -1,3 +1,5 @@
+pub mod history;
+pub mod presets;
 pub mod props_modals;
 pub mod settingscreen;
 pub mod settingsview;
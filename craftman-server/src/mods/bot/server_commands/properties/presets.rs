@@ -0,0 +1,250 @@
+use crate::appstate::AppState;
+use crate::mods::agents::Agent;
+use crate::mods::bot::get_guild::get_guild;
+use crate::mods::bot::get_option::{get_attachment, get_option};
+use crate::mods::bot::mod_commands::restart::restart_button;
+use crate::mods::bot::permissions::{MANAGE_ONLY, can_manage};
+use anyhow::Result;
+use protocol::properties::{PropertyType, schema};
+use protocol::properties_document::PropertiesDocument;
+use serenity::all::{CommandDataOptionValue, Context};
+use serenity::builder::*;
+use serenity::model::application::CommandInteraction;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+const MAX_NAME: usize = 32;
+const MAX_IMPORT_SIZE: u32 = 64 * 1024;
+
+// Saves the current properties, or the ones in an attached file, under a name. Saving can replace
+// a preset someone else applies later, so it needs the same permission as applying
+pub async fn save_preset(
+    ctx: &Context,
+    interaction: &CommandInteraction,
+    appstate: &AppState,
+) -> Result<()> {
+    if !can_manage(interaction.member.as_deref()) {
+        return reply(ctx, interaction, MANAGE_ONLY).await;
+    }
+    let Some(agent) = find_agent(ctx, interaction, appstate).await? else {
+        return Ok(());
+    };
+    let Some(name) = preset_name(interaction, "name") else {
+        return reply(ctx, interaction, "Preset names are 1 to 32 characters").await;
+    };
+
+    let values = match get_attachment(interaction, "file") {
+        Some(file) => {
+            if !file.filename.ends_with(".properties") || file.size > MAX_IMPORT_SIZE {
+                return reply(
+                    ctx,
+                    interaction,
+                    "Please attach a .properties file under 64KB",
+                )
+                .await;
+            }
+            let text = String::from_utf8(file.download().await?)?;
+            let document = PropertiesDocument::parse(&text);
+            let edition = agent.request_props().await?.edition;
+            let mut values = HashMap::new();
+            for (key, value) in document.entries() {
+                match edition.validate(key, value) {
+                    Ok(value) => values.insert(key.to_string(), value),
+                    Err(e) => {
+                        let content = format!("`{}` can't be used: {}", key, e);
+                        return reply(ctx, interaction, &content).await;
+                    }
+                };
+            }
+            values
+        }
+        None => agent.request_props().await?.values,
+    };
+    let values = without_secrets(values);
+    if values.is_empty() {
+        return reply(ctx, interaction, "There are no properties to save").await;
+    }
+
+    agent
+        .save_preset(&name, &values, interaction.user.id.get())
+        .await?;
+    let content = format!("Saved preset **{}** with {} properties", name, values.len());
+    reply(ctx, interaction, &content).await
+}
+
+// Only the keys that differ are sent, and the agent writes them all at once
+pub async fn apply_preset(
+    ctx: &Context,
+    interaction: &CommandInteraction,
+    appstate: &AppState,
+) -> Result<()> {
+    if !can_manage(interaction.member.as_deref()) {
+        return reply(ctx, interaction, MANAGE_ONLY).await;
+    }
+    let Some(agent) = find_agent(ctx, interaction, appstate).await? else {
+        return Ok(());
+    };
+    let Some(name) = preset_name(interaction, "name") else {
+        return reply(ctx, interaction, "Preset names are 1 to 32 characters").await;
+    };
+    let Some(preset) = agent.preset(&name).await? else {
+        let names = agent.preset_names().await?;
+        let content = if names.is_empty() {
+            "There are no presets yet, save one with `/server properties save`".to_string()
+        } else {
+            format!(
+                "No preset called **{}**. Saved presets: {}",
+                name,
+                names.join(", ")
+            )
+        };
+        return reply(ctx, interaction, &content).await;
+    };
+
+    // Keys this server doesn't have and doesn't know about, like another edition's, are left out
+    // rather than failing the whole preset
+    let current = agent.request_props().await?;
+    let (mut changes, mut skipped): (Vec<(String, String)>, Vec<(String, String)>) = preset
+        .into_iter()
+        .filter(|(key, value)| current.values.get(key) != Some(value))
+        .partition(|(key, _)| {
+            current.values.contains_key(key) || current.edition.schema(key).is_some()
+        });
+    changes.sort();
+    skipped.sort();
+    let skipped = if skipped.is_empty() {
+        String::new()
+    } else {
+        let keys: Vec<String> = skipped
+            .iter()
+            .map(|(key, _)| format!("`{}`", key))
+            .collect();
+        format!(
+            "\nSkipped keys this server doesn't have: {}",
+            keys.join(", ")
+        )
+    };
+    if changes.is_empty() {
+        let content = format!("The server already matches preset **{}**{}", name, skipped);
+        return reply(ctx, interaction, &content).await;
+    }
+
+    let count = changes.len();
+    let guild_id = get_guild(ctx, interaction).await?;
+    let props = match agent
+        .set_properties(changes, guild_id, interaction.user.id.get())
+        .await
+    {
+        Ok(props) => props,
+        Err(e) => {
+            let content = format!("Preset **{}** was not applied: {}", name, e);
+            return reply(ctx, interaction, &content).await;
+        }
+    };
+
+    let mut response = CreateInteractionResponseMessage::new().content(format!(
+        "Applied preset **{}**, {} {} changed{}",
+        name,
+        count,
+        if count == 1 { "property" } else { "properties" },
+        skipped
+    ));
+    if !props.pending.is_empty() {
+        response = response.components(vec![restart_button(agent.id())]);
+    }
+    interaction
+        .create_response(&ctx.http, CreateInteractionResponse::Message(response))
+        .await?;
+    Ok(())
+}
+
+// Sends the current properties, or a saved preset, as a .properties file
+pub async fn export(
+    ctx: &Context,
+    interaction: &CommandInteraction,
+    appstate: &AppState,
+) -> Result<()> {
+    let Some(agent) = find_agent(ctx, interaction, appstate).await? else {
+        return Ok(());
+    };
+    let (file_name, values) = match preset_name(interaction, "preset") {
+        Some(name) => match agent.preset(&name).await? {
+            Some(values) => (format!("{}.properties", name), values),
+            None => {
+                let content = format!("No preset called **{}**", name);
+                return reply(ctx, interaction, &content).await;
+            }
+        },
+        None => (
+            "server.properties".to_string(),
+            agent.request_props().await?.values,
+        ),
+    };
+
+    let mut values: Vec<(String, String)> = without_secrets(values).into_iter().collect();
+    values.sort();
+    let mut document = PropertiesDocument::parse("#Minecraft server properties\n");
+    for (key, value) in values.iter() {
+        document.set(key, value);
+    }
+
+    let response = CreateInteractionResponseMessage::new()
+        .add_file(CreateAttachment::bytes(
+            document.to_string().into_bytes(),
+            file_name,
+        ))
+        .ephemeral(true);
+    interaction
+        .create_response(&ctx.http, CreateInteractionResponse::Message(response))
+        .await?;
+    Ok(())
+}
+
+async fn find_agent(
+    ctx: &Context,
+    interaction: &CommandInteraction,
+    appstate: &AppState,
+) -> Result<Option<Arc<Agent>>> {
+    if let Ok(agent) = appstate.find_connection_by_guild(get_guild(ctx, interaction).await?) {
+        Ok(Some(agent))
+    } else {
+        let response = CreateInteractionResponseMessage::new();
+        interaction.create_response(&ctx.http, CreateInteractionResponse::Message(response.content("Unable to find agent. Please either start agent or verify a new agent with /verify"))).await?;
+        Ok(None)
+    }
+}
+
+fn preset_name(interaction: &CommandInteraction, option: &str) -> Option<String> {
+    let Some(CommandDataOptionValue::String(name)) = get_option(interaction, option) else {
+        return None;
+    };
+    let name = name.trim();
+    (!name.is_empty() && name.chars().count() <= MAX_NAME).then(|| name.to_string())
+}
+
+// Passwords stay in server.properties and never end up in the database or a channel
+fn without_secrets(values: HashMap<String, String>) -> HashMap<String, String> {
+    values
+        .into_iter()
+        .filter(|(key, _)| {
+            !matches!(
+                schema(key).map(|property| property.kind),
+                Some(PropertyType::Secret)
+            )
+        })
+        .collect()
+}
+
+async fn reply(ctx: &Context, interaction: &CommandInteraction, content: &str) -> Result<()> {
+    interaction
+        .create_response(
+            &ctx.http,
+            CreateInteractionResponse::Message(
+                CreateInteractionResponseMessage::new()
+                    .content(content)
+                    .ephemeral(true),
+            ),
+        )
+        .await?;
+    Ok(())
+}
@@ -1,11 +1,31 @@
+use crate::mods::bot::server_commands::properties::settingsview::key_id;
+use protocol::properties::{Edition, PropertyType, display_name};
 use serenity::builder::{CreateActionRow, CreateInputText, CreateModal};
 use serenity::model::application::InputTextStyle;
 
-pub fn props_modal(title: &str, id: &str, style: InputTextStyle) -> CreateModal {
-    let input = CreateActionRow::InputText(CreateInputText::new(
-        style,
-        format!("Edit {}", title),
-        format!("input:{}", title),
-    ));
-    CreateModal::new(format!("edit_props:{}:{}", title, id), title).components(vec![input])
+pub fn props_modal(key: &str, current: &str, id: &str, edition: Edition) -> CreateModal {
+    let title: String = display_name(key).chars().take(45).collect();
+    let kind = edition.schema(key).map(|property| property.kind);
+    let (label, value) = match kind {
+        Some(PropertyType::Integer { min, max }) => {
+            (format!("A number from {} to {}", min, max), current)
+        }
+        // Secrets are only ever replaced so they never reach Discord
+        Some(PropertyType::Secret) => ("New value".to_string(), ""),
+        _ => (format!("Edit {}", title), current),
+    };
+    let mut input = CreateInputText::new(
+        InputTextStyle::Short,
+        label.chars().take(45).collect::<String>(),
+        "value",
+    )
+    .required(false);
+    if !value.is_empty() {
+        input = input.value(value);
+    }
+    if kind == Some(PropertyType::Secret) {
+        input = input.placeholder("Leave empty to keep the current value");
+    }
+    CreateModal::new(format!("edit_props:{}:{}", key_id(key), id), title)
+        .components(vec![CreateActionRow::InputText(input)])
 }
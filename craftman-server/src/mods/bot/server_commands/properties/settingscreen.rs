@@ -1,21 +1,31 @@
+use protocol::properties::Category;
 use std::fmt;
 use std::str::FromStr;
+use uuid::Uuid;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum SettingScreen {
-    WorldGeneration,
-    Gameplay,
-    Admin,
+pub struct SettingScreen {
+    pub category: Category,
+    pub page: usize,
+}
+
+// A settings message the bot keeps up to date when the properties change on disk
+#[derive(Debug, Clone, Copy)]
+pub struct SettingsView {
+    pub agent_id: Uuid,
+    pub channel_id: u64,
+    pub screen: SettingScreen,
+}
+
+impl SettingScreen {
+    pub fn new(category: Category) -> Self {
+        SettingScreen { category, page: 0 }
+    }
 }
 
 impl fmt::Display for SettingScreen {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let s = match self {
-            SettingScreen::WorldGeneration => "world",
-            SettingScreen::Gameplay => "gameplay",
-            SettingScreen::Admin => "admin",
-        };
-        write!(f, "{s}")
+        write!(f, "{}-{}", self.category, self.page)
     }
 }
 
@@ -23,11 +33,10 @@ impl FromStr for SettingScreen {
     type Err = ();
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s {
-            "world" => Ok(SettingScreen::WorldGeneration),
-            "gameplay" => Ok(SettingScreen::Gameplay),
-            "admin" => Ok(SettingScreen::Admin),
-            _ => Err(()),
-        }
+        let (category, page) = s.rsplit_once('-').ok_or(())?;
+        Ok(SettingScreen {
+            category: category.parse()?,
+            page: page.parse().map_err(|_| ())?,
+        })
     }
 }
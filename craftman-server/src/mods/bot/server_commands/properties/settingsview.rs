@@ -1,14 +1,20 @@
 use crate::appstate::AppState;
+use crate::mods::agents::Agent;
 use crate::mods::bot::get_guild::get_guild;
+use crate::mods::bot::permissions::{MANAGE_ONLY, can_manage};
+use crate::mods::bot::server_commands::properties::props_modals::props_modal;
 use crate::mods::bot::server_commands::properties::settingscreen::SettingScreen;
-use crate::mods::bot::si2tr::si2tr;
 use anyhow::Result;
 use anyhow::anyhow;
+use protocol::properties::{
+    Category, Edition, PendingChange, Properties, PropertyType, display_name, is_secret, schema,
+};
 use serenity::all::ActionRowComponent;
+use serenity::all::ComponentInteraction;
 use serenity::all::Context;
 use serenity::all::CreateInteractionResponse;
 use serenity::all::CreateInteractionResponseMessage;
-use std::collections::HashMap;
+use serenity::all::ModalInteraction;
 use twilight_model::channel::message::MessageFlags;
 use twilight_model::channel::message::component::*;
 use twilight_model::id::Id;
@@ -22,7 +28,6 @@ pub async fn run(
     serenity_interaction: serenity::model::application::CommandInteraction,
     appstate: &AppState,
 ) -> Result<()> {
-    let id = appstate.find_id_by_guild(get_guild(ctx, &serenity_interaction).await?)?;
     let agent = if let Ok(agent) =
         appstate.find_connection_by_guild(get_guild(ctx, &serenity_interaction).await?)
     {
@@ -32,486 +37,527 @@ pub async fn run(
         serenity_interaction.create_response(&ctx.http, CreateInteractionResponse::Message(response.content("Unable to find agent. Please either start agent or verify a new agent with /verify"))).await?;
         return Ok(());
     };
+    open_settings_view(
+        client,
+        &agent,
+        serenity_interaction.application_id.get(),
+        serenity_interaction.id.get(),
+        &serenity_interaction.token,
+    )
+    .await
+}
+
+// Posts a new panel. Also answers the Open properties button on a monitor
+pub async fn open_settings_view(
+    client: &twilight_http::Client,
+    agent: &Agent,
+    application_id: u64,
+    interaction_id: u64,
+    token: &str,
+) -> Result<()> {
     let props = agent.request_props().await?;
+    let screen = SettingScreen::new(Category::Gameplay);
     let response = ChannelMessageBuilder::new()
-        .components(build_settings_view(&props, id, &SettingScreen::Gameplay)?)
+        .components(build_settings_view(&props, agent.id(), &screen))
         .flags(MessageFlags::IS_COMPONENTS_V2)
         .build();
 
-    si2tr(client, &serenity_interaction, &response).await;
-
+    let interaction_client = client.interaction(Id::new(application_id));
+    interaction_client
+        .create_response(Id::new(interaction_id), token, &response)
+        .await?;
+    let message = interaction_client.response(token).await?.model().await?;
+    agent
+        .track_settings_view(message.id.get(), message.channel_id.get(), screen)
+        .await;
     Ok(())
 }
 
-fn screen_from_message(message: &serenity::model::channel::Message) -> Option<&SettingScreen> {
-    let last_row = message.components.last()?;
-
-    let components = &last_row.components;
-
-    for component in components {
-        if let ActionRowComponent::Button(button) = component
-            && button.disabled
-        {
-            return match button.label.as_deref()? {
-                "World Generation" => Some(&SettingScreen::WorldGeneration),
-                "Gameplay" => Some(&SettingScreen::Gameplay),
-                "Admin" => Some(&SettingScreen::Admin),
-                _ => None,
-            };
-        }
+const PAGE_SIZE: usize = 8;
+const VALUE_PREVIEW: usize = 100;
+const PENDING_PREVIEW: usize = 5;
+const SHORT_VALUE_PREVIEW: usize = 30;
+// Leaves room for "edit_props:", the agent id and two separators
+const MAX_KEY_ID: usize = 40;
+const GONE: &str = "That property isn't in server.properties anymore";
+
+// Keys of a category in schema order, with keys the schema doesn't know sorted by name
+fn keys_in(category: Category, props: &Properties) -> Vec<&str> {
+    if category == Category::Other {
+        let mut keys: Vec<&str> = props
+            .values
+            .keys()
+            .map(String::as_str)
+            .filter(|key| props.edition.schema(key).is_none())
+            .collect();
+        keys.sort();
+        return keys;
     }
-
-    None
+    props
+        .edition
+        .properties()
+        .iter()
+        .filter(|property| property.category == category && props.values.contains_key(property.key))
+        .map(|property| property.key)
+        .collect()
 }
 
-pub fn allow_flight(allow: bool, id: &str) -> Section {
-    let displaytext = TextDisplay {
-        id: None,
-        content: "Allow Flight: ".to_string(),
-    };
-
-    let (style, label) = if allow {
-        (ButtonStyle::Success, "On")
-    } else {
-        (ButtonStyle::Danger, "Off")
-    };
-
-    let button = ButtonBuilder::new(style)
-        .custom_id(format!("edit:allowflightbutton:{}", id))
-        .label(label)
-        .build();
-
-    SectionBuilder::new(button).component(displaytext).build()
+// The screen a key is on, so the view stays put after editing it
+fn screen_for(key: &str, props: &Properties) -> SettingScreen {
+    let category = props.edition.category(key);
+    let index = keys_in(category, props)
+        .iter()
+        .position(|other| *other == key)
+        .unwrap_or(0);
+    SettingScreen {
+        category,
+        page: index / PAGE_SIZE,
+    }
 }
 
 pub fn build_settings_view(
-    props: &HashMap<String, String>,
+    props: &Properties,
     uuid: Uuid,
     screen: &SettingScreen,
-) -> Result<Vec<Component>> {
+) -> Vec<Component> {
     let id = &uuid.to_string();
-    let mut properties_message = Vec::<Component>::new();
-
-    match screen {
-        SettingScreen::WorldGeneration => {
-            if let Some(generate_value) = props.get("generate-structure") {
-                properties_message
-                    .push(generate_structures(generate_value.parse::<bool>()?, id).into());
-            } else if let Some(generate_value) = props.get("generate-structures") {
-                properties_message
-                    .push(generate_structures(generate_value.parse::<bool>()?, id).into());
-            }
-
-            if let Some(max_world_value) = props.get("max-world-size") {
-                properties_message.push(max_world_size(max_world_value.parse::<u32>()?, id).into());
-            }
-
-            if let Some(allow_nether_value) = props.get("allow-nether") {
-                properties_message
-                    .push(allow_nether(allow_nether_value.parse::<bool>()?, id).into());
-            }
-
-            if let Some(spawn_npc_value) = props.get("spawn-npcs") {
-                properties_message.push(spawn_npcs(spawn_npc_value.parse::<bool>()?, id).into());
-            }
-
-            if let Some(spawn_animals_value) = props.get("spawn-animals") {
-                properties_message
-                    .push(spawn_animals(spawn_animals_value.parse::<bool>()?, id).into());
-            }
-
-            if let Some(spawn_monsters_value) = props.get("spawn-monsters") {
-                properties_message
-                    .push(spawn_monsters(spawn_monsters_value.parse::<bool>()?, id).into());
-            }
-        }
-
-        SettingScreen::Gameplay => {
-            if let Some(allow_flight_value) = props.get("allow-flight") {
-                properties_message
-                    .push(allow_flight(allow_flight_value.parse::<bool>()?, id).into());
-            }
-
-            if let Some(difficulty_value) = props.get("difficulty") {
-                properties_message.push(difficulty(difficulty_value, id).into());
-            }
-
-            if let Some(gamemode_value) = props.get("gamemode") {
-                properties_message.push(gamemode(gamemode_value, id).into());
-            }
-
-            if let Some(hardcore_value) = props.get("hardcore") {
-                properties_message.push(hardcore(hardcore_value.parse::<bool>()?, id).into());
-            }
-
-            if let Some(spawn_protection_value) = props.get("spawn-protection") {
-                properties_message
-                    .push(spawn_protection(spawn_protection_value.parse::<u32>()?, id).into());
-            }
-
-            if let Some(pvp_value) = props.get("pvp") {
-                properties_message.push(pvp(pvp_value.parse::<bool>()?, id).into());
-            }
-        }
-
-        SettingScreen::Admin => {
-            if let Some(whitelist_value) = props.get("white-list") {
-                properties_message.push(whitelist(whitelist_value.parse::<bool>()?, id).into());
-            }
-
-            if let Some(motd_value) = props.get("motd") {
-                properties_message.push(motd(motd_value, id).into());
-            }
-
-            if let Some(max_players_value) = props.get("max-players") {
-                properties_message.push(max_players(max_players_value.parse::<u32>()?, id).into());
-            }
-
-            if let Some(view_distance_value) = props.get("view-distance") {
-                properties_message
-                    .push(view_distance(view_distance_value.parse::<u32>()?, id).into());
-            }
+    let keys = keys_in(screen.category, props);
+    let pages = keys.len().div_ceil(PAGE_SIZE).max(1);
+    let page = screen.page.min(pages - 1);
+
+    let mut properties_message: Vec<Component> = vec![
+        TextDisplayBuilder::new(format!(
+            "### ⚙️ {}\nPage {} of {}",
+            screen.category.label(),
+            page + 1,
+            pages
+        ))
+        .build()
+        .into(),
+    ];
+    if !props.pending.is_empty() {
+        properties_message.push(pending_section(&props.pending, id).into());
+    }
+    if keys.is_empty() {
+        properties_message.push(
+            TextDisplayBuilder::new("*No properties in this category*")
+                .build()
+                .into(),
+        );
+    }
+    for key in keys.iter().skip(page * PAGE_SIZE).take(PAGE_SIZE) {
+        properties_message
+            .push(property_section(key, &props.values[*key], id, props.edition).into());
+    }
 
-            if let Some(simulation_distance_value) = props.get("simulation-distance") {
-                properties_message.push(
-                    simulation_distance(simulation_distance_value.parse::<u32>()?, id).into(),
-                );
-            }
-        }
+    let mut category_row = ActionRowBuilder::new();
+    for category in Category::ALL.into_iter().filter(|c| *c != Category::Other) {
+        category_row = category_row.component(category_button(category, screen, id));
     }
-    let world_button = ButtonBuilder::new(ButtonStyle::Secondary)
-        .custom_id(format!("screen:world:{}", id))
-        .label("World Generation")
-        .disabled(screen == &SettingScreen::WorldGeneration)
-        .build();
-    let gameplay_button = ButtonBuilder::new(ButtonStyle::Secondary)
-        .custom_id(format!("screen:gameplay:{}", id))
-        .label("Gameplay")
-        .disabled(screen == &SettingScreen::Gameplay)
+    properties_message.push(category_row.build().into());
+
+    let prev_button = ButtonBuilder::new(ButtonStyle::Secondary)
+        .custom_id(format!(
+            "screen:{}:{}",
+            SettingScreen {
+                category: screen.category,
+                page: page.saturating_sub(1)
+            },
+            id
+        ))
+        .label("Previous")
+        .disabled(page == 0)
         .build();
-    let admin_button = ButtonBuilder::new(ButtonStyle::Secondary)
-        .custom_id(format!("screen:admin:{}", id))
-        .label("Admin")
-        .disabled(screen == &SettingScreen::Admin)
+    let next_button = ButtonBuilder::new(ButtonStyle::Secondary)
+        .custom_id(format!(
+            "screen:{}:{}",
+            SettingScreen {
+                category: screen.category,
+                page: page + 1
+            },
+            id
+        ))
+        .label("Next")
+        .disabled(page + 1 >= pages)
         .build();
     let last_row = ActionRowBuilder::new()
-        .component(world_button)
-        .component(gameplay_button)
-        .component(admin_button)
+        .component(prev_button)
+        .component(next_button)
+        .component(category_button(Category::Other, screen, id))
         .build();
     properties_message.push(last_row.into());
 
-    Ok(properties_message)
+    properties_message
 }
 
-pub async fn update_settings_view(
-    client: &twilight_http::Client,
-    channel_id: u64,
-    message_id: u64,
-    props: &HashMap<String, String>,
-    id: Uuid,
-    screen: Option<&SettingScreen>,
-    message: &serenity::model::channel::Message,
-) -> Result<()> {
-    let new_screen = (screen.or_else(|| screen_from_message(message).or(None)))
-        .ok_or_else(|| anyhow!("Tried to get screen from message but failed: "))?;
-    let components = build_settings_view(props, id, new_screen);
-
-    client
-        .update_message(Id::new(channel_id), Id::new(message_id))
-        .components(Some(&components?))
-        .await?;
-    Ok(())
+fn category_button(category: Category, screen: &SettingScreen, id: &str) -> Button {
+    ButtonBuilder::new(ButtonStyle::Secondary)
+        .custom_id(format!("screen:{}:{}", SettingScreen::new(category), id))
+        .label(category.label())
+        .disabled(screen.category == category)
+        .build()
 }
 
-pub fn difficulty(difficulty: &str, id: &str) -> Section {
-    let displaytext = TextDisplay {
-        id: None,
-        content: "Difficulty: ".to_string(),
+// Changes saved to the file that the running server hasn't picked up yet
+fn pending_section(pending: &[PendingChange], id: &str) -> Section {
+    let mut content = match pending.len() {
+        1 => "⚠️ **1 change pending restart**".to_string(),
+        n => format!("⚠️ **{} changes pending restart**", n),
     };
+    for change in pending.iter().take(PENDING_PREVIEW) {
+        let secret = matches!(
+            schema(&change.key).map(|property| property.kind),
+            Some(PropertyType::Secret)
+        );
+        if secret {
+            content.push_str(&format!("\n-# `{}` changed", change.key));
+        } else {
+            content.push_str(&format!(
+                "\n-# `{}` {} → {}",
+                change.key,
+                short_value(change.running.as_deref()),
+                short_value(change.file.as_deref())
+            ));
+        }
+    }
+    if pending.len() > PENDING_PREVIEW {
+        content.push_str(&format!(
+            "\n-# and {} more",
+            pending.len() - PENDING_PREVIEW
+        ));
+    }
 
-    let button = ButtonBuilder::new(ButtonStyle::Primary)
-        .custom_id(format!("edit:difficultybutton:{}", id))
-        .label(capitalize(difficulty))
+    let button = ButtonBuilder::new(ButtonStyle::Danger)
+        .custom_id(format!("restart:server:{}", id))
+        .label("Apply (restart)")
         .build();
-
-    SectionBuilder::new(button).component(displaytext).build()
+    SectionBuilder::new(button)
+        .component(TextDisplay { id: None, content })
+        .build()
 }
 
-fn capitalize(s: &str) -> String {
-    let mut c = s.chars();
-    match c.next() {
-        None => String::new(),
-        Some(f) => f.to_uppercase().chain(c).collect(),
+// A value short enough to sit on one line next to another
+pub fn short_value(value: Option<&str>) -> String {
+    match value {
+        None => "*unset*".to_string(),
+        Some("") => "*empty*".to_string(),
+        Some(value) => {
+            let mut value = value.replace('`', "'");
+            if value.chars().count() > SHORT_VALUE_PREVIEW {
+                value = value.chars().take(SHORT_VALUE_PREVIEW).collect::<String>() + "…";
+            }
+            format!("`{}`", value)
+        }
     }
 }
 
-pub fn gamemode(gamemode: &str, id: &str) -> Section {
-    let displaytext = TextDisplay {
-        id: None,
-        content: "Gamemode: ".to_string(),
-    };
-
-    let button = ButtonBuilder::new(ButtonStyle::Primary)
-        .custom_id(format!("edit:gamemodebutton:{}", id))
-        .label(capitalize(gamemode))
-        .build();
-
-    SectionBuilder::new(button).component(displaytext).build()
-}
-
-pub fn hardcore(is_hard: bool, id: &str) -> Section {
-    let displaytext = TextDisplay {
-        id: None,
-        content: "Hardcore: ".to_string(),
-    };
+fn property_section(key: &str, value: &str, id: &str, edition: Edition) -> Section {
+    let property = edition.schema(key);
+    let kind = property.map_or(PropertyType::Text, |property| property.kind);
+
+    let mut content = format!("**{}**", display_name(key));
+    match kind {
+        PropertyType::Boolean | PropertyType::Choice(_) => {}
+        PropertyType::Secret if value.is_empty() => content.push_str(": *not set*"),
+        PropertyType::Secret => content.push_str(": ••••••"),
+        _ if value.is_empty() => content.push_str(": *empty*"),
+        _ => {
+            let mut value = value.replace('`', "'");
+            if value.chars().count() > VALUE_PREVIEW {
+                value = value.chars().take(VALUE_PREVIEW).collect::<String>() + "…";
+            }
+            content.push_str(&format!(": `{}`", value));
+        }
+    }
+    match property {
+        Some(property) => content.push_str(&format!("\n-# {}", property.description)),
+        None => content.push_str(&format!("\n-# {}", key)),
+    }
+    let displaytext = TextDisplay { id: None, content };
 
-    let (style, label) = if is_hard {
-        (ButtonStyle::Danger, "On")
-    } else {
-        (ButtonStyle::Primary, "Off")
+    let (style, label) = match kind {
+        PropertyType::Boolean if value == "true" => (ButtonStyle::Success, "On".to_string()),
+        PropertyType::Boolean => (ButtonStyle::Danger, "Off".to_string()),
+        PropertyType::Choice(_) if value.is_empty() => {
+            (ButtonStyle::Primary, "Not set".to_string())
+        }
+        PropertyType::Choice(_) => (ButtonStyle::Primary, capitalize(value)),
+        _ => (ButtonStyle::Primary, "Edit".to_string()),
     };
-
     let button = ButtonBuilder::new(style)
-        .custom_id(format!("edit:hardcorebutton:{}", id))
+        .custom_id(format!("prop:{}:{}", key_id(key), id))
         .label(label)
         .build();
 
     SectionBuilder::new(button).component(displaytext).build()
 }
 
-pub fn allow_nether(allow: bool, id: &str) -> Section {
-    let displaytext = TextDisplay {
-        id: None,
-        content: "Allow Nether: ".to_string(),
-    };
-
-    let (style, label) = if allow {
-        (ButtonStyle::Success, "On")
-    } else {
-        (ButtonStyle::Danger, "Off")
-    };
-
-    let button = ButtonBuilder::new(style)
-        .custom_id(format!("edit:allownetherbutton:{}", id))
-        .label(label)
-        .build();
+pub async fn update_settings_view(
+    client: &twilight_http::Client,
+    agent: &Agent,
+    channel_id: u64,
+    message_id: u64,
+    props: &Properties,
+    screen: &SettingScreen,
+) -> Result<()> {
+    let components = build_settings_view(props, agent.id(), screen);
 
-    SectionBuilder::new(button).component(displaytext).build()
+    client
+        .update_message(Id::new(channel_id), Id::new(message_id))
+        .components(Some(&components))
+        .await?;
+    agent
+        .track_settings_view(message_id, channel_id, *screen)
+        .await;
+    Ok(())
 }
 
-pub fn whitelist(whitelist: bool, id: &str) -> Section {
-    let displaytext = TextDisplay {
-        id: None,
-        content: "White-list: ".to_string(),
-    };
-
-    let (style, label) = if whitelist {
-        (ButtonStyle::Success, "On")
-    } else {
-        (ButtonStyle::Danger, "Off")
-    };
-
-    let button = ButtonBuilder::new(style)
-        .custom_id(format!("edit:whitelistbutton:{}", id))
-        .label(label)
-        .build();
-
-    SectionBuilder::new(button).component(displaytext).build()
+// Redraws every settings message of the agent after the file changed on disk. Messages that
+// can't be edited any more were most likely deleted, so they stop being tracked
+pub async fn refresh_settings_views(
+    client: &twilight_http::Client,
+    agent: &Agent,
+    props: &Properties,
+) {
+    for (message_id, view) in agent.settings_views() {
+        let components = build_settings_view(props, agent.id(), &view.screen);
+        if let Err(e) = client
+            .update_message(Id::new(view.channel_id), Id::new(message_id))
+            .components(Some(&components))
+            .await
+        {
+            tracing::debug!("Dropping settings view {}: {}", message_id, e);
+            agent.forget_settings_view(message_id).await;
+        }
+    }
 }
 
-pub fn pvp(pvp: bool, id: &str) -> Section {
-    let displaytext = TextDisplay {
-        id: None,
-        content: "PVP: ".to_string(),
-    };
-
-    let (style, label) = if pvp {
-        (ButtonStyle::Danger, "On")
-    } else {
-        (ButtonStyle::Success, "Off")
+// Booleans and choices change straight away, everything else opens a modal
+pub async fn edit_property(
+    ctx: &Context,
+    client: &twilight_http::Client,
+    component: &ComponentInteraction,
+    agent: &Agent,
+    key_id: &str,
+    id: Uuid,
+) -> Result<()> {
+    // Anyone who can see the view can press its buttons
+    if !can_manage(component.member.as_deref()) {
+        component
+            .create_response(&ctx.http, error_message(MANAGE_ONLY.to_string()))
+            .await?;
+        return Ok(());
+    }
+    let props = agent.request_props().await?;
+    let Some(key) = key_from_id(key_id, &props) else {
+        component
+            .create_response(&ctx.http, error_message(GONE.to_string()))
+            .await?;
+        return Ok(());
     };
-
-    let button = ButtonBuilder::new(style)
-        .custom_id(format!("edit:pvpbutton:{}", id))
-        .label(label)
-        .build();
-
-    SectionBuilder::new(button).component(displaytext).build()
-}
-
-pub fn generate_structures(generate: bool, id: &str) -> Section {
-    let displaytext = TextDisplay {
-        id: None,
-        content: "Generate Structures: ".to_string(),
+    let key = key.as_str();
+    let current = props
+        .values
+        .get(key)
+        .map(String::as_str)
+        .unwrap_or_default();
+    let Some(next) = props
+        .edition
+        .schema(key)
+        .and_then(|property| property.kind.next(current))
+    else {
+        let modal = props_modal(key, current, &id.to_string(), props.edition);
+        component
+            .create_response(&ctx.http, CreateInteractionResponse::Modal(modal))
+            .await?;
+        return Ok(());
     };
 
-    let (style, label) = if generate {
-        (ButtonStyle::Success, "On")
-    } else {
-        (ButtonStyle::Danger, "Off")
+    let guild_id = component
+        .guild_id
+        .ok_or_else(|| anyhow!("Property edited outside of a server"))?;
+    let props = match agent
+        .set_property(
+            key.to_string(),
+            next,
+            guild_id.get(),
+            component.user.id.get(),
+        )
+        .await
+    {
+        Ok(props) => props,
+        Err(e) => {
+            component
+                .create_response(&ctx.http, error_message(e.to_string()))
+                .await?;
+            return Ok(());
+        }
     };
-
-    let button = ButtonBuilder::new(style)
-        .custom_id(format!("edit:generatestructuresbutton:{}", id))
-        .label(label)
-        .build();
-
-    SectionBuilder::new(button).component(displaytext).build()
+    update_settings_view(
+        client,
+        agent,
+        component.channel_id.get(),
+        component.message.id.get(),
+        &props,
+        &screen_for(key, &props),
+    )
+    .await?;
+    component
+        .create_response(&ctx.http, CreateInteractionResponse::Acknowledge)
+        .await?;
+    Ok(())
 }
 
-pub fn max_players(max: u32, id: &str) -> Section {
-    let displaytext = TextDisplay {
-        id: None,
-        content: format!("Max Players: {}", max),
+pub async fn save_property(
+    ctx: &Context,
+    client: &twilight_http::Client,
+    modal: &ModalInteraction,
+    agent: &Agent,
+    key_id: &str,
+) -> Result<()> {
+    if !can_manage(modal.member.as_deref()) {
+        modal
+            .create_response(&ctx.http, error_message(MANAGE_ONLY.to_string()))
+            .await?;
+        return Ok(());
+    }
+    let Some(ActionRowComponent::InputText(data)) = modal
+        .data
+        .components
+        .first()
+        .and_then(|row| row.components.first())
+    else {
+        return Ok(());
     };
-
-    let (style, label) = (ButtonStyle::Primary, "Edit");
-
-    let button = ButtonBuilder::new(style)
-        .custom_id(format!("modal:max-playersbutton:{}", id))
-        .label(label)
-        .build();
-
-    SectionBuilder::new(button).component(displaytext).build()
-}
-
-pub fn motd(message: &str, id: &str) -> Section {
-    let displaytext = TextDisplay {
-        id: None,
-        content: format!("Message Of The Day: {}", message),
+    let input = data.value.clone().unwrap_or_default();
+    let key = if key_id.starts_with('#') {
+        key_from_id(key_id, &agent.request_props().await?)
+    } else {
+        Some(key_id.to_string())
     };
-
-    let (style, label) = (ButtonStyle::Primary, "Edit");
-
-    let button = ButtonBuilder::new(style)
-        .custom_id(format!("modal:motdbutton:{}", id))
-        .label(label)
-        .build();
-
-    SectionBuilder::new(button).component(displaytext).build()
-}
-
-pub fn max_world_size(size: u32, id: &str) -> Section {
-    let displaytext = TextDisplay {
-        id: None,
-        content: format!("Max World Size: {}", size),
+    let Some(key) = key else {
+        modal
+            .create_response(&ctx.http, error_message(GONE.to_string()))
+            .await?;
+        return Ok(());
     };
+    let key = key.as_str();
+    // The secret's field starts out empty, so leaving it that way keeps the current value
+    if is_secret(key) && input.trim().is_empty() {
+        modal
+            .create_response(&ctx.http, CreateInteractionResponse::Acknowledge)
+            .await?;
+        return Ok(());
+    }
 
-    let (style, label) = (ButtonStyle::Primary, "Edit");
-
-    let button = ButtonBuilder::new(style)
-        .custom_id(format!("modal:max-worldbutton:{}", id))
-        .label(label)
-        .build();
-
-    SectionBuilder::new(button).component(displaytext).build()
-}
-
-pub fn view_distance(distance: u32, id: &str) -> Section {
-    let displaytext = TextDisplay {
-        id: None,
-        content: format!("View Distance: {}", distance),
+    let message = modal
+        .message
+        .as_ref()
+        .ok_or_else(|| anyhow!("Message not attatched to any modal"))?;
+    let guild_id = modal
+        .guild_id
+        .ok_or_else(|| anyhow!("Property edited outside of a server"))?;
+    // The agent checks the value against its own edition's schema, and a bad one comes back as
+    // the error
+    let props = match agent
+        .set_property(key.to_string(), input, guild_id.get(), modal.user.id.get())
+        .await
+    {
+        Ok(props) => props,
+        Err(e) => {
+            modal
+                .create_response(&ctx.http, error_message(e.to_string()))
+                .await?;
+            return Ok(());
+        }
     };
-
-    let (style, label) = (ButtonStyle::Primary, "Edit");
-
-    let button = ButtonBuilder::new(style)
-        .custom_id(format!("modal:view-distancebutton:{}", id))
-        .label(label)
-        .build();
-
-    SectionBuilder::new(button).component(displaytext).build()
+    update_settings_view(
+        client,
+        agent,
+        modal.channel_id.get(),
+        message.id.get(),
+        &props,
+        &screen_for(key, &props),
+    )
+    .await?;
+    modal
+        .create_response(&ctx.http, CreateInteractionResponse::Acknowledge)
+        .await?;
+    Ok(())
 }
 
-pub fn simulation_distance(distance: u32, id: &str) -> Section {
-    let displaytext = TextDisplay {
-        id: None,
-        content: format!("Simulation Distance: {}", distance),
-    };
-
-    let (style, label) = (ButtonStyle::Primary, "Edit");
-
-    let button = ButtonBuilder::new(style)
-        .custom_id(format!("modal:simulation-distancebutton:{}", id))
-        .label(label)
-        .build();
-
-    SectionBuilder::new(button).component(displaytext).build()
+// Custom ids are capped at 100 characters and split on ':', so keys too long for one or with a ':'
+// in them go out as a hash and are looked up again when the button or modal comes back
+pub fn key_id(key: &str) -> String {
+    if key.len() <= MAX_KEY_ID && !key.contains(':') && !key.starts_with('#') {
+        return key.to_string();
+    }
+    // FNV-1a, which unlike std's hasher gives the same hash in every build
+    let hash = key.bytes().fold(0xcbf29ce484222325u64, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    });
+    format!("#{:016x}", hash)
 }
 
-pub fn spawn_protection(distance: u32, id: &str) -> Section {
-    let displaytext = TextDisplay {
-        id: None,
-        content: format!("Spawn Protection: {}", distance),
-    };
-
-    let (style, label) = (ButtonStyle::Primary, "Edit");
-
-    let button = ButtonBuilder::new(style)
-        .custom_id(format!("modal:spawn-protectionbutton:{}", id))
-        .label(label)
-        .build();
-
-    SectionBuilder::new(button).component(displaytext).build()
+fn key_from_id(id: &str, props: &Properties) -> Option<String> {
+    if !id.starts_with('#') {
+        return Some(id.to_string());
+    }
+    props.values.keys().find(|key| key_id(key) == id).cloned()
 }
 
-pub fn spawn_npcs(spawn: bool, id: &str) -> Section {
-    let displaytext = TextDisplay {
-        id: None,
-        content: "Spawn NPC's: ".to_string(),
-    };
-
-    let (style, label) = if spawn {
-        (ButtonStyle::Success, "On")
-    } else {
-        (ButtonStyle::Danger, "Off")
-    };
-
-    let button = ButtonBuilder::new(style)
-        .custom_id(format!("edit:spawn-npcbutton:{}", id))
-        .label(label)
-        .build();
-
-    SectionBuilder::new(button).component(displaytext).build()
+fn error_message(content: String) -> CreateInteractionResponse {
+    CreateInteractionResponse::Message(
+        CreateInteractionResponseMessage::new()
+            .content(content)
+            .ephemeral(true),
+    )
 }
 
-pub fn spawn_animals(spawn: bool, id: &str) -> Section {
-    let displaytext = TextDisplay {
-        id: None,
-        content: "Spawn animals: ".to_string(),
-    };
-
-    let (style, label) = if spawn {
-        (ButtonStyle::Success, "On")
-    } else {
-        (ButtonStyle::Danger, "Off")
-    };
-
-    let button = ButtonBuilder::new(style)
-        .custom_id(format!("edit:spawn-animalsbutton:{}", id))
-        .label(label)
-        .build();
-
-    SectionBuilder::new(button).component(displaytext).build()
+fn capitalize(s: &str) -> String {
+    let mut c = s.chars();
+    match c.next() {
+        None => String::new(),
+        Some(f) => f.to_uppercase().chain(c).collect(),
+    }
 }
 
-pub fn spawn_monsters(spawn: bool, id: &str) -> Section {
-    let displaytext = TextDisplay {
-        id: None,
-        content: "Spawn Monsters: ".to_string(),
-    };
-
-    let (style, label) = if spawn {
-        (ButtonStyle::Success, "On")
-    } else {
-        (ButtonStyle::Danger, "Off")
-    };
-
-    let button = ButtonBuilder::new(style)
-        .custom_id(format!("edit:spawn-monstersbutton:{}", id))
-        .label(label)
-        .build();
-
-    SectionBuilder::new(button).component(displaytext).build()
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn key_ids_fit_custom_ids() {
+        let long = "a.very.long.property.key.that.some.mod.loader.might.write.out".to_string();
+        let keys = [
+            "view-distance".to_string(),
+            "rcon.password".to_string(),
+            "with:colon".to_string(),
+            "#hash".to_string(),
+            long.clone(),
+        ];
+        let props = Properties {
+            values: keys
+                .iter()
+                .map(|key| (key.clone(), String::new()))
+                .collect::<HashMap<_, _>>(),
+            pending: Vec::new(),
+            edition: Edition::Java,
+        };
+        assert_eq!(key_id("view-distance"), "view-distance");
+        let agent = Uuid::new_v4();
+        for key in &keys {
+            let id = key_id(key);
+            assert!(!id.contains(':'), "{}", id);
+            assert!(
+                format!("edit_props:{}:{}", id, agent).len() <= 100,
+                "{}",
+                key
+            );
+            assert_eq!(key_from_id(&id, &props).as_ref(), Some(key));
+        }
+        assert_ne!(key_id(&long), key_id(&format!("{}s", long)));
+        assert_eq!(key_from_id("#0000000000000000", &props), None);
+    }
 }
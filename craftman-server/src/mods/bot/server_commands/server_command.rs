@@ -13,9 +13,102 @@ pub fn register_server_command() -> CreateCommand {
             "stop",
             "stop your minecraft server",
         ))
+        .add_option(
+            CreateCommandOption::new(
+                CommandOptionType::SubCommandGroup,
+                "properties",
+                "view and edit properties of your minecraft server",
+            )
+            .add_sub_option(CreateCommandOption::new(
+                CommandOptionType::SubCommand,
+                "edit",
+                "view and edit properties of your minecraft server",
+            ))
+            .add_sub_option(CreateCommandOption::new(
+                CommandOptionType::SubCommand,
+                "history",
+                "see recent property changes and who made them, and revert them",
+            ))
+            .add_sub_option(
+                CreateCommandOption::new(
+                    CommandOptionType::SubCommand,
+                    "save",
+                    "save the current properties, or an attached file, as a named preset",
+                )
+                .add_sub_option(
+                    CreateCommandOption::new(CommandOptionType::String, "name", "preset name")
+                        .required(true),
+                )
+                .add_sub_option(CreateCommandOption::new(
+                    CommandOptionType::Attachment,
+                    "file",
+                    "a .properties file to import instead of the current properties",
+                )),
+            )
+            .add_sub_option(
+                CreateCommandOption::new(
+                    CommandOptionType::SubCommand,
+                    "apply",
+                    "apply a saved preset in one edit",
+                )
+                .add_sub_option(
+                    CreateCommandOption::new(CommandOptionType::String, "name", "preset name")
+                        .required(true),
+                ),
+            )
+            .add_sub_option(
+                CreateCommandOption::new(
+                    CommandOptionType::SubCommand,
+                    "export",
+                    "download the current properties, or a preset, as a file",
+                )
+                .add_sub_option(CreateCommandOption::new(
+                    CommandOptionType::String,
+                    "preset",
+                    "the preset to export instead of the current properties",
+                )),
+            ),
+        )
+        .add_option(
+            CreateCommandOption::new(
+                CommandOptionType::SubCommand,
+                "upgrade",
+                "swap in a new server jar, rolling back if it fails to start",
+            )
+            .add_sub_option(
+                CreateCommandOption::new(
+                    CommandOptionType::Attachment,
+                    "jar",
+                    "the new server jar",
+                )
+                .required(true),
+            ),
+        )
         .add_option(CreateCommandOption::new(
             CommandOptionType::SubCommand,
-            "properties",
-            "view and edit properties of your minecraft server",
+            "mods",
+            "list installed mods and plugins and any problems loading them",
         ))
+        .add_option(
+            CreateCommandOption::new(
+                CommandOptionType::SubCommandGroup,
+                "modpack",
+                "manage the modpack of your minecraft server",
+            )
+            .add_sub_option(
+                CreateCommandOption::new(
+                    CommandOptionType::SubCommand,
+                    "install",
+                    "install a modrinth modpack, replacing the current mods folder",
+                )
+                .add_sub_option(
+                    CreateCommandOption::new(
+                        CommandOptionType::Attachment,
+                        "pack",
+                        "the .mrpack file to install",
+                    )
+                    .required(true),
+                ),
+            ),
+        )
 }
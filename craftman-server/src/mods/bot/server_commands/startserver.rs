@@ -1,4 +1,5 @@
 use crate::appstate::AppState;
+use crate::mods::agents::Agent;
 use crate::mods::bot::get_guild::get_guild;
 use anyhow::Result;
 use serenity::all::Context;
@@ -20,23 +21,20 @@ pub async fn start_mc_server(
         return Ok(());
     };
 
-    let response = CreateInteractionResponseMessage::new();
-    if let Err(e) = agent.start_server().await {
-        tracing::warn!("Start Server Failed: {}", e);
-        interaction
-            .create_response(
-                &ctx.http,
-                CreateInteractionResponse::Message(response.content(e.to_string())),
-            )
-            .await?;
-        tracing::debug!("Is it getting to here???");
-    } else {
-        interaction
-            .create_response(
-                &ctx.http,
-                CreateInteractionResponse::Message(response.content("Successfully started server")),
-            )
-            .await?;
-    }
+    let response = CreateInteractionResponseMessage::new().content(run(&agent).await);
+    interaction
+        .create_response(&ctx.http, CreateInteractionResponse::Message(response))
+        .await?;
     Ok(())
 }
+
+// Shared with the monitor's Start button
+pub async fn run(agent: &Agent) -> String {
+    match agent.start_server().await {
+        Ok(()) => "Successfully started server".to_string(),
+        Err(e) => {
+            tracing::warn!("Start Server Failed: {}", e);
+            e.to_string()
+        }
+    }
+}
@@ -1,4 +1,5 @@
 use crate::appstate::AppState;
+use crate::mods::agents::Agent;
 use crate::mods::bot::get_guild::get_guild;
 use anyhow::Result;
 use serenity::all::Context;
@@ -19,22 +20,20 @@ pub async fn stop_minecraft_server(
         interaction.create_response(&ctx.http, CreateInteractionResponse::Message(response.content("Unable to find agent. Please either start agent or verify a new agent with /verify"))).await?;
         return Ok(());
     };
-    let response = CreateInteractionResponseMessage::new();
-    if let Err(e) = agent.stop_server().await {
-        tracing::warn!("Stop server failed: {}", e);
-        interaction
-            .create_response(
-                &ctx.http,
-                CreateInteractionResponse::Message(response.content(e.to_string())),
-            )
-            .await?;
-    } else {
-        interaction
-            .create_response(
-                &ctx.http,
-                CreateInteractionResponse::Message(response.content("Successfully stopped server")),
-            )
-            .await?;
-    }
+    let response = CreateInteractionResponseMessage::new().content(run(&agent).await);
+    interaction
+        .create_response(&ctx.http, CreateInteractionResponse::Message(response))
+        .await?;
     Ok(())
 }
+
+// Shared with the monitor's Stop button
+pub async fn run(agent: &Agent) -> String {
+    match agent.stop_server().await {
+        Ok(()) => "Successfully stopped server".to_string(),
+        Err(e) => {
+            tracing::warn!("Stop server failed: {}", e);
+            e.to_string()
+        }
+    }
+}
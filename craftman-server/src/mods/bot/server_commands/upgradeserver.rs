@@ -0,0 +1,78 @@
+use crate::appstate::AppState;
+use crate::mods::bot::get_guild::get_guild;
+use crate::mods::bot::get_option::get_attachment;
+use crate::mods::bot::permissions::{MANAGE_ONLY, can_manage};
+use crate::mods::bot::progress_message::follow_progress;
+use anyhow::Result;
+use protocol::tasks::RemoteFile;
+use serenity::all::Context;
+use serenity::builder::*;
+use serenity::model::application::CommandInteraction;
+
+pub async fn upgrade_server(
+    ctx: &Context,
+    interaction: &CommandInteraction,
+    appstate: &AppState,
+) -> Result<()> {
+    // The rest of /server is open to everyone, but a new jar runs whatever it contains
+    if !can_manage(interaction.member.as_deref()) {
+        let response = CreateInteractionResponseMessage::new()
+            .content(MANAGE_ONLY)
+            .ephemeral(true);
+        interaction
+            .create_response(&ctx.http, CreateInteractionResponse::Message(response))
+            .await?;
+        return Ok(());
+    }
+    let agent = if let Ok(agent) =
+        appstate.find_connection_by_guild(get_guild(ctx, interaction).await?)
+    {
+        agent
+    } else {
+        let response = CreateInteractionResponseMessage::new();
+        interaction.create_response(&ctx.http, CreateInteractionResponse::Message(response.content("Unable to find agent. Please either start agent or verify a new agent with /verify"))).await?;
+        return Ok(());
+    };
+    let response = CreateInteractionResponseMessage::new();
+    let Some(jar) = get_attachment(interaction, "jar").filter(|jar| jar.filename.ends_with(".jar"))
+    else {
+        interaction
+            .create_response(
+                &ctx.http,
+                CreateInteractionResponse::Message(
+                    response.content("Please attach the new server .jar file"),
+                ),
+            )
+            .await?;
+        return Ok(());
+    };
+
+    let request = RemoteFile {
+        file_name: jar.filename.clone(),
+        url: jar.url.clone(),
+    };
+    match agent.upgrade(request).await {
+        Ok(progress) => {
+            interaction
+                .create_response(&ctx.http, CreateInteractionResponse::Defer(response))
+                .await?;
+            follow_progress(
+                ctx,
+                &interaction.token,
+                &format!("Upgrading to {}", jar.filename),
+                progress,
+            )
+            .await?;
+        }
+        Err(e) => {
+            tracing::warn!("Upgrade failed to start: {}", e);
+            interaction
+                .create_response(
+                    &ctx.http,
+                    CreateInteractionResponse::Message(response.content(e.to_string())),
+                )
+                .await?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,149 @@
+use crate::appstate::AppState;
+use crate::mods::bot::get_guild::get_guild;
+use crate::mods::bot::get_option::get_option;
+use crate::mods::charts::{Sample, stats_chart};
+use anyhow::Result;
+use serenity::all::{CommandDataOptionValue, Context};
+use serenity::builder::*;
+use serenity::model::application::*;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const DEFAULT_RANGE: i64 = 24 * 3600;
+// Samples are pruned after 30 days, so there is nothing older to show
+const MAX_RANGE: i64 = 30 * 24 * 3600;
+
+pub async fn run(
+    ctx: &Context,
+    interaction: &CommandInteraction,
+    appstate: &AppState,
+) -> Result<()> {
+    let agent = if let Ok(agent) =
+        appstate.find_connection_by_guild(get_guild(ctx, interaction).await?)
+    {
+        agent
+    } else {
+        let response = CreateInteractionResponseMessage::new();
+        interaction.create_response(&ctx.http, CreateInteractionResponse::Message(response.content("Unable to find agent. Please either start agent or verify a new agent with /verify"))).await?;
+        return Ok(());
+    };
+    let range = match get_option(interaction, "range") {
+        Some(CommandDataOptionValue::String(range)) => parse_range(range),
+        _ => Some(DEFAULT_RANGE),
+    };
+    let Some(range) = range.filter(|range| (3600..=MAX_RANGE).contains(range)) else {
+        let response = CreateInteractionResponseMessage::new()
+            .content("Ranges look like `12h`, `3d` or `2w`, from 1 hour up to 30 days")
+            .ephemeral(true);
+        interaction
+            .create_response(&ctx.http, CreateInteractionResponse::Message(response))
+            .await?;
+        return Ok(());
+    };
+
+    let response = CreateInteractionResponseMessage::new();
+    interaction
+        .create_response(&ctx.http, CreateInteractionResponse::Defer(response))
+        .await?;
+    let to = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() as i64;
+    let from = to - range;
+    let samples = agent.samples(from, to).await?;
+    if samples.is_empty() {
+        interaction
+            .edit_response(
+                &ctx.http,
+                EditInteractionResponse::new().content(
+                    "No history yet for this range. The agent records a sample every minute while it's connected",
+                ),
+            )
+            .await?;
+        return Ok(());
+    }
+
+    let content = summary(&samples, from);
+    let png = tokio::task::spawn_blocking(move || stats_chart(&samples, from, to)).await??;
+    interaction
+        .edit_response(
+            &ctx.http,
+            EditInteractionResponse::new()
+                .content(content)
+                .new_attachment(CreateAttachment::bytes(png, "stats.png")),
+        )
+        .await?;
+    Ok(())
+}
+
+pub fn register() -> CreateCommand {
+    let range = CreateCommandOption::new(
+        CommandOptionType::String,
+        "range",
+        "How far back to look, like 12h, 3d or 2w (default 24h)",
+    );
+    CreateCommand::new("stats")
+        .description("Chart player counts and latency")
+        .add_option(range)
+}
+
+// "12h", "3d", "2w" into seconds
+fn parse_range(range: &str) -> Option<i64> {
+    let range = range.trim().to_lowercase();
+    let unit = match range.chars().last()? {
+        'h' => 3600,
+        'd' => 24 * 3600,
+        'w' => 7 * 24 * 3600,
+        _ => return None,
+    };
+    let count: i64 = range[..range.len() - 1].parse().ok()?;
+    count.checked_mul(unit)
+}
+
+fn summary(samples: &[Sample], from: i64) -> String {
+    let peak = samples.iter().filter_map(|sample| sample.players).max();
+    let latencies: Vec<i64> = samples
+        .iter()
+        .filter_map(|sample| sample.latency_ms.map(i64::from))
+        .collect();
+    let online = samples.iter().filter(|sample| sample.online).count();
+    let mut content = format!("**Server stats since <t:{}:f>**", from);
+    if let Some(peak) = peak {
+        content.push_str(&format!("\nPeak players: {}", peak));
+    }
+    if !latencies.is_empty() {
+        content.push_str(&format!(
+            "\nAverage latency: {}ms",
+            latencies.iter().sum::<i64>() / latencies.len() as i64
+        ));
+    }
+    content.push_str(&format!(
+        "\nUptime: {:.1}%",
+        online as f64 * 100.0 / samples.len() as f64
+    ));
+    content
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_ranges() {
+        let cases = [
+            ("12h", Some(12 * 3600)),
+            ("3d", Some(3 * 24 * 3600)),
+            ("2w", Some(2 * 7 * 24 * 3600)),
+            (" 1D ", Some(24 * 3600)),
+            // Out of range values parse and are turned away by the caller
+            ("0h", Some(0)),
+            ("-3d", Some(-3 * 24 * 3600)),
+            ("3", None),
+            ("h", None),
+            ("3m", None),
+            ("1.5d", None),
+            ("3 d", None),
+            ("", None),
+            ("99999999999999999w", None),
+        ];
+        for (range, expected) in cases {
+            assert_eq!(parse_range(range), expected, "{range:?}");
+        }
+    }
+}
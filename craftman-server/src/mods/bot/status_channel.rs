@@ -0,0 +1,72 @@
+use crate::appstate::AppState;
+use crate::mods::bot::get_guild::get_guild;
+use crate::mods::bot::get_option::get_option;
+use anyhow::Result;
+use serenity::all::{ChannelType, CommandDataOptionValue, CommandOptionType, Context, Permissions};
+use serenity::builder::*;
+use serenity::model::application::CommandInteraction;
+
+pub fn register() -> CreateCommand {
+    CreateCommand::new("status-channel")
+        .description("keep a channel's name showing whether the server is up and who's on")
+        .default_member_permissions(Permissions::MANAGE_CHANNELS)
+        .add_option(
+            CreateCommandOption::new(
+                CommandOptionType::SubCommand,
+                "set",
+                "pick the channel to rename",
+            )
+            .add_sub_option(
+                CreateCommandOption::new(
+                    CommandOptionType::Channel,
+                    "channel",
+                    "a voice channel works best, since nobody needs to open it",
+                )
+                .channel_types(vec![ChannelType::Voice, ChannelType::Text])
+                .required(true),
+            ),
+        )
+        .add_option(CreateCommandOption::new(
+            CommandOptionType::SubCommand,
+            "clear",
+            "stop renaming the channel. It keeps its last name",
+        ))
+}
+
+pub async fn run(
+    ctx: &Context,
+    interaction: &CommandInteraction,
+    appstate: &AppState,
+    subcommand: &str,
+) -> Result<()> {
+    let agent = if let Ok(agent) =
+        appstate.find_connection_by_guild(get_guild(ctx, interaction).await?)
+    {
+        agent
+    } else {
+        let response = CreateInteractionResponseMessage::new();
+        interaction.create_response(&ctx.http, CreateInteractionResponse::Message(response.content("Unable to find agent. Please either start agent or verify a new agent with /verify"))).await?;
+        return Ok(());
+    };
+
+    let content = match (subcommand, get_option(interaction, "channel")) {
+        ("set", Some(CommandDataOptionValue::Channel(channel))) => {
+            agent.set_status_channel(Some(channel.get())).await?;
+            format!(
+                "<#{}> will be renamed with the server's status on the next monitor update. The bot needs the Manage Channels permission there, and Discord only allows a rename every 5 minutes",
+                channel
+            )
+        }
+        _ => {
+            agent.set_status_channel(None).await?;
+            "The status channel won't be renamed anymore".to_string()
+        }
+    };
+    let response = CreateInteractionResponseMessage::new()
+        .content(content)
+        .ephemeral(true);
+    interaction
+        .create_response(&ctx.http, CreateInteractionResponse::Message(response))
+        .await?;
+    Ok(())
+}
@@ -0,0 +1,73 @@
+use crate::appstate::AppState;
+use crate::mods::bot::get_guild::get_guild;
+use anyhow::Result;
+use nanoid::nanoid;
+use serenity::all::{CommandOptionType, Context, Permissions};
+use serenity::builder::*;
+use serenity::model::application::CommandInteraction;
+use std::env;
+
+pub fn register() -> CreateCommand {
+    CreateCommand::new("status-page")
+        .description("a public web page and banner showing your server's status")
+        .default_member_permissions(Permissions::MANAGE_GUILD)
+        .add_option(CreateCommandOption::new(
+            CommandOptionType::SubCommand,
+            "enable",
+            "turn the status page on, or show its links if it already is",
+        ))
+        .add_option(CreateCommandOption::new(
+            CommandOptionType::SubCommand,
+            "disable",
+            "turn the status page off. Enabling it again gives it a new link",
+        ))
+}
+
+pub async fn run(
+    ctx: &Context,
+    interaction: &CommandInteraction,
+    appstate: &AppState,
+    subcommand: &str,
+) -> Result<()> {
+    let agent = if let Ok(agent) =
+        appstate.find_connection_by_guild(get_guild(ctx, interaction).await?)
+    {
+        agent
+    } else {
+        let response = CreateInteractionResponseMessage::new();
+        interaction.create_response(&ctx.http, CreateInteractionResponse::Message(response.content("Unable to find agent. Please either start agent or verify a new agent with /verify"))).await?;
+        return Ok(());
+    };
+
+    let content = match (subcommand, agent.status_slug().await?) {
+        ("enable", Some(slug)) => links(&slug),
+        ("enable", None) => {
+            let slug = nanoid!(12);
+            agent.set_status_slug(Some(&slug)).await?;
+            appstate.status_pages.invalidate(&slug).await;
+            links(&slug)
+        }
+        (_, Some(slug)) => {
+            agent.set_status_slug(None).await?;
+            appstate.status_pages.invalidate(&slug).await;
+            "Status page turned off. Its links now show nothing".to_string()
+        }
+        (_, None) => "The status page is already off".to_string(),
+    };
+    let response = CreateInteractionResponseMessage::new()
+        .content(content)
+        .ephemeral(true);
+    interaction
+        .create_response(&ctx.http, CreateInteractionResponse::Message(response))
+        .await?;
+    Ok(())
+}
+
+// PUBLIC_URL is where the central server can be reached from outside
+fn links(slug: &str) -> String {
+    let base = env::var("PUBLIC_URL").unwrap_or_else(|_| "http://localhost:3000".to_string());
+    let base = base.trim_end_matches('/');
+    format!(
+        "Page: {base}/status/{slug}\nJSON: {base}/status/{slug}/json\nBanner: {base}/status/{slug}/banner.png\nAnyone with these links can see the server's status, player list and mods"
+    )
+}
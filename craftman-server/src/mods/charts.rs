@@ -0,0 +1,320 @@
+use anyhow::Result;
+use image::codecs::png::PngEncoder;
+use image::imageops::FilterType;
+use image::{DynamicImage, ImageEncoder, ImageFormat};
+use plotters::coord::Shift;
+use plotters::coord::types::RangedCoordf64;
+use plotters::prelude::*;
+use plotters::style::{FontStyle, register_font};
+use std::sync::Once;
+
+static FONT: &[u8] = include_bytes!("../../assets/DejaVuSans.ttf");
+static REGISTER_FONT: Once = Once::new();
+
+const WIDTH: u32 = 900;
+const PANEL_HEIGHT: u32 = 260;
+const BANNER_WIDTH: u32 = 600;
+const BANNER_HEIGHT: u32 = 100;
+const ICON_SIZE: u32 = 64;
+const ICON_MARGIN: u32 = 18;
+// A longer gap than this means nothing was watching the server, so the line is broken there
+const MAX_GAP: i64 = 5 * 60;
+
+const BACKGROUND: RGBColor = RGBColor(43, 45, 49);
+const FOREGROUND: RGBColor = RGBColor(219, 222, 225);
+const GRID: RGBColor = RGBColor(64, 66, 73);
+const PLAYERS: RGBColor = RGBColor(88, 101, 242);
+const LATENCY: RGBColor = RGBColor(254, 231, 92);
+const OFFLINE: RGBColor = RGBColor(237, 66, 69);
+const ONLINE: RGBColor = RGBColor(87, 242, 135);
+
+// One point of the stored query history
+#[derive(Debug, Clone, Copy)]
+pub struct Sample {
+    // Unix seconds
+    pub at: i64,
+    pub online: bool,
+    pub players: Option<i32>,
+    pub max_players: Option<i32>,
+    pub latency_ms: Option<i32>,
+}
+
+// Player counts over the last day and the last week, for the query monitor
+pub fn monitor_chart(samples: &[Sample], now: i64) -> Result<Vec<u8>> {
+    render(2, |panels| {
+        draw_players(
+            &panels[0],
+            samples,
+            now - 24 * 3600,
+            now,
+            "Players, last 24 hours",
+        )?;
+        draw_players(
+            &panels[1],
+            samples,
+            now - 7 * 24 * 3600,
+            now,
+            "Players, last 7 days",
+        )
+    })
+}
+
+// Player counts and latency over any range, for /stats
+pub fn stats_chart(samples: &[Sample], from: i64, to: i64) -> Result<Vec<u8>> {
+    render(2, |panels| {
+        draw_players(&panels[0], samples, from, to, "Players")?;
+        draw_latency(&panels[1], samples, from, to, "Latency (ms)")
+    })
+}
+
+// A strip for the public status page: favicon, up to two lines of MOTD and whatever details
+// there are, like the player count
+pub fn status_banner(
+    motd: &str,
+    favicon: &[u8],
+    online: bool,
+    details: &[&str],
+) -> Result<Vec<u8>> {
+    register_fonts();
+    let mut buffer = vec![0; (BANNER_WIDTH * BANNER_HEIGHT * 3) as usize];
+    {
+        let root = BitMapBackend::with_buffer(&mut buffer, (BANNER_WIDTH, BANNER_HEIGHT))
+            .into_drawing_area();
+        root.fill(&BACKGROUND)?;
+        let x = (ICON_MARGIN * 2 + ICON_SIZE) as i32;
+        let mut lines = motd.lines().map(str::trim).filter(|line| !line.is_empty());
+        if let Some(line) = lines.next() {
+            root.draw(&Text::new(
+                truncate(line, 44),
+                (x, 14),
+                ("sans-serif", 20).into_font().color(&FOREGROUND),
+            ))?;
+        }
+        if let Some(line) = lines.next() {
+            root.draw(&Text::new(
+                truncate(line, 56),
+                (x, 40),
+                ("sans-serif", 15).into_font().color(&FOREGROUND.mix(0.7)),
+            ))?;
+        }
+        let (dot, label) = if online {
+            (ONLINE, "Online")
+        } else {
+            (OFFLINE, "Offline")
+        };
+        root.draw(&Circle::new((x + 6, 76), 6, dot.filled()))?;
+        let status = std::iter::once(label)
+            .chain(details.iter().copied())
+            .collect::<Vec<_>>()
+            .join("  ·  ");
+        root.draw(&Text::new(
+            truncate(&status, 60),
+            (x + 20, 69),
+            ("sans-serif", 16).into_font().color(&FOREGROUND),
+        ))?;
+        root.present()?;
+    }
+    if let Ok(icon) = image::load_from_memory_with_format(favicon, ImageFormat::Png) {
+        paste_icon(&mut buffer, icon);
+    }
+    encode(&buffer, BANNER_WIDTH, BANNER_HEIGHT)
+}
+
+// Blended over the background plotters already filled in, since favicons are often transparent
+fn paste_icon(buffer: &mut [u8], icon: DynamicImage) {
+    let icon = icon
+        .resize_exact(ICON_SIZE, ICON_SIZE, FilterType::Triangle)
+        .to_rgba8();
+    for (x, y, pixel) in icon.enumerate_pixels() {
+        let i = (((ICON_MARGIN + y) * BANNER_WIDTH + ICON_MARGIN + x) * 3) as usize;
+        let alpha = pixel[3] as u32;
+        for c in 0..3 {
+            buffer[i + c] =
+                ((pixel[c] as u32 * alpha + buffer[i + c] as u32 * (255 - alpha)) / 255) as u8;
+        }
+    }
+}
+
+fn truncate(text: &str, max: usize) -> String {
+    if text.chars().count() > max {
+        format!("{}…", text.chars().take(max - 1).collect::<String>())
+    } else {
+        text.to_string()
+    }
+}
+
+fn register_fonts() {
+    REGISTER_FONT.call_once(|| {
+        if register_font("sans-serif", FontStyle::Normal, FONT).is_err() {
+            tracing::error!("Failed to load the chart font");
+        }
+    });
+}
+
+fn encode(buffer: &[u8], width: u32, height: u32) -> Result<Vec<u8>> {
+    let mut png = Vec::new();
+    PngEncoder::new(&mut png).write_image(buffer, width, height, image::ExtendedColorType::Rgb8)?;
+    Ok(png)
+}
+
+fn render(
+    panels: usize,
+    draw: impl FnOnce(&[DrawingArea<BitMapBackend, Shift>]) -> Result<()>,
+) -> Result<Vec<u8>> {
+    register_fonts();
+    let height = PANEL_HEIGHT * panels as u32;
+    let mut buffer = vec![0; (WIDTH * height * 3) as usize];
+    {
+        let root = BitMapBackend::with_buffer(&mut buffer, (WIDTH, height)).into_drawing_area();
+        root.fill(&BACKGROUND)?;
+        draw(&root.split_evenly((panels, 1)))?;
+        root.present()?;
+    }
+    encode(&buffer, WIDTH, height)
+}
+
+fn draw_players(
+    area: &DrawingArea<BitMapBackend, Shift>,
+    samples: &[Sample],
+    from: i64,
+    to: i64,
+    title: &str,
+) -> Result<()> {
+    let samples = in_range(samples, from, to);
+    let top = samples
+        .iter()
+        .filter_map(|sample| sample.max_players.max(sample.players))
+        .max()
+        .unwrap_or(10)
+        .max(1);
+    let mut chart = chart(area, from, to, title, 0.0..top as f64 + 1.0)?;
+    shade_offline(&mut chart, samples, to)?;
+    for segment in segments(samples, to, |sample| sample.players) {
+        chart.draw_series(
+            AreaSeries::new(segment.iter().copied(), 0.0, PLAYERS.mix(0.25))
+                .border_style(PLAYERS.stroke_width(2)),
+        )?;
+    }
+    Ok(())
+}
+
+fn draw_latency(
+    area: &DrawingArea<BitMapBackend, Shift>,
+    samples: &[Sample],
+    from: i64,
+    to: i64,
+    title: &str,
+) -> Result<()> {
+    let samples = in_range(samples, from, to);
+    let top = samples
+        .iter()
+        .filter_map(|sample| sample.latency_ms)
+        .max()
+        .unwrap_or(100)
+        .max(1);
+    let mut chart = chart(area, from, to, title, 0.0..top as f64 * 1.1)?;
+    shade_offline(&mut chart, samples, to)?;
+    for segment in segments(samples, to, |sample| sample.latency_ms) {
+        chart.draw_series(LineSeries::new(segment, LATENCY.stroke_width(2)))?;
+    }
+    Ok(())
+}
+
+type Chart<'a, 'b> =
+    ChartContext<'a, BitMapBackend<'b>, Cartesian2d<RangedCoordf64, RangedCoordf64>>;
+
+// The x axis is in hours before `to`, so labels read as "how long ago"
+fn chart<'a, 'b>(
+    area: &'a DrawingArea<BitMapBackend<'b>, Shift>,
+    from: i64,
+    to: i64,
+    title: &str,
+    y_range: std::ops::Range<f64>,
+) -> Result<Chart<'a, 'b>> {
+    let hours = (to - from) as f64 / 3600.0;
+    let mut chart = ChartBuilder::on(area)
+        .caption(title, ("sans-serif", 20).into_font().color(&FOREGROUND))
+        .margin(12)
+        .x_label_area_size(28)
+        .y_label_area_size(48)
+        .build_cartesian_2d(-hours..0.0, y_range)?;
+    chart
+        .configure_mesh()
+        .axis_style(GRID)
+        .bold_line_style(GRID)
+        .light_line_style(BACKGROUND)
+        .label_style(("sans-serif", 14).into_font().color(&FOREGROUND))
+        .x_labels(8)
+        .y_labels(6)
+        .x_label_formatter(&|hours| ago(*hours, to - from))
+        .y_label_formatter(&|value| format!("{}", value.round() as i64))
+        .draw()?;
+    Ok(chart)
+}
+
+fn ago(hours: f64, span: i64) -> String {
+    if hours.abs() < f64::EPSILON {
+        "now".to_string()
+    } else if span > 2 * 24 * 3600 {
+        format!("{:.0}d ago", -hours / 24.0)
+    } else {
+        format!("{:.0}h ago", -hours)
+    }
+}
+
+// Red bands wherever the server was seen offline
+fn shade_offline(chart: &mut Chart, samples: &[Sample], to: i64) -> Result<()> {
+    let y_range = chart.y_range();
+    let bands = samples
+        .iter()
+        .enumerate()
+        .filter(|(_, sample)| !sample.online)
+        .map(|(i, sample)| {
+            let end = samples
+                .get(i + 1)
+                .map_or(to, |next| next.at)
+                .min(sample.at + MAX_GAP);
+            Rectangle::new(
+                [
+                    (hours_before(sample.at, to), y_range.start),
+                    (hours_before(end, to), y_range.end),
+                ],
+                OFFLINE.mix(0.3).filled(),
+            )
+        });
+    chart.draw_series(bands)?;
+    Ok(())
+}
+
+// Runs of samples that have a value, split where the history has holes
+fn segments(
+    samples: &[Sample],
+    to: i64,
+    value: impl Fn(&Sample) -> Option<i32>,
+) -> Vec<Vec<(f64, f64)>> {
+    let mut segments: Vec<Vec<(f64, f64)>> = Vec::new();
+    let mut last_at: Option<i64> = None;
+    for sample in samples {
+        let Some(y) = value(sample) else {
+            last_at = None;
+            continue;
+        };
+        let point = (hours_before(sample.at, to), y as f64);
+        match (segments.last_mut(), last_at) {
+            (Some(segment), Some(last)) if sample.at - last <= MAX_GAP => segment.push(point),
+            _ => segments.push(vec![point]),
+        }
+        last_at = Some(sample.at);
+    }
+    segments
+}
+
+fn in_range(samples: &[Sample], from: i64, to: i64) -> &[Sample] {
+    let start = samples.partition_point(|sample| sample.at < from);
+    let end = samples.partition_point(|sample| sample.at <= to);
+    &samples[start..end]
+}
+
+fn hours_before(at: i64, to: i64) -> f64 {
+    (at - to) as f64 / 3600.0
+}
@@ -0,0 +1,33 @@
+// The two largest units, like "3d 4h" or "2h 15m"
+pub fn duration(secs: i64) -> String {
+    let (days, hours, minutes) = (secs / 86400, secs % 86400 / 3600, secs % 3600 / 60);
+    if days > 0 {
+        format!("{}d {}h", days, hours)
+    } else if hours > 0 {
+        format!("{}h {}m", hours, minutes)
+    } else {
+        format!("{}m", minutes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_durations() {
+        for (secs, expected) in [
+            (0, "0m"),
+            (59, "0m"),
+            (60, "1m"),
+            (3599, "59m"),
+            (3600, "1h 0m"),
+            (2 * 3600 + 15 * 60, "2h 15m"),
+            (86399, "23h 59m"),
+            (86400, "1d 0h"),
+            (3 * 86400 + 4 * 3600 + 59 * 60, "3d 4h"),
+        ] {
+            assert_eq!(duration(secs), expected, "{}", secs);
+        }
+    }
+}
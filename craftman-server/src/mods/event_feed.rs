@@ -0,0 +1,115 @@
+use anyhow::Result;
+use protocol::player_events::{AdvancementKind, PlayerEvent};
+use twilight_model::id::{Id, marker::ChannelMarker};
+use twilight_util::builder::embed::EmbedBuilder;
+
+#[derive(Clone)]
+pub struct Feed {
+    pub channel_id: Id<ChannelMarker>,
+    // Names of the FeedEvents that are turned on
+    pub events: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FeedEvent {
+    Join,
+    Leave,
+    Death,
+    Advancement,
+}
+
+impl FeedEvent {
+    pub const ALL: [FeedEvent; 4] = [
+        FeedEvent::Join,
+        FeedEvent::Leave,
+        FeedEvent::Death,
+        FeedEvent::Advancement,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            FeedEvent::Join => "join",
+            FeedEvent::Leave => "leave",
+            FeedEvent::Death => "death",
+            FeedEvent::Advancement => "advancement",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<FeedEvent> {
+        FeedEvent::ALL
+            .into_iter()
+            .find(|event| event.name() == name)
+    }
+
+    pub fn title(self) -> &'static str {
+        match self {
+            FeedEvent::Join => "Joins",
+            FeedEvent::Leave => "Leaves",
+            FeedEvent::Death => "Deaths",
+            FeedEvent::Advancement => "Advancements",
+        }
+    }
+
+    fn of(event: &PlayerEvent) -> FeedEvent {
+        match event {
+            PlayerEvent::Joined(_) => FeedEvent::Join,
+            PlayerEvent::Left(_) => FeedEvent::Leave,
+            PlayerEvent::Died { .. } => FeedEvent::Death,
+            PlayerEvent::Advancement { .. } => FeedEvent::Advancement,
+        }
+    }
+}
+
+pub async fn post(client: &twilight_http::Client, feed: &Feed, event: &PlayerEvent) -> Result<()> {
+    if !feed
+        .events
+        .iter()
+        .any(|name| name == FeedEvent::of(event).name())
+    {
+        return Ok(());
+    }
+    let player = format!("**{}**", escape(event.player()));
+    let (color, description) = match event {
+        PlayerEvent::Joined(_) => (0x57f287, format!("📥 {} joined the game", player)),
+        PlayerEvent::Left(_) => (0x949ba4, format!("📤 {} left the game", player)),
+        PlayerEvent::Died {
+            player: name,
+            message,
+        } => (
+            0xed4245,
+            format!("💀 {}", escape(message).replacen(&escape(name), &player, 1)),
+        ),
+        PlayerEvent::Advancement { title, kind, .. } => {
+            let (color, emoji, verb) = match kind {
+                AdvancementKind::Advancement => (0xfee75c, "🏆", "made the advancement"),
+                AdvancementKind::Goal => (0x5865f2, "🎯", "reached the goal"),
+                AdvancementKind::Challenge => (0x9b59b6, "🏅", "completed the challenge"),
+            };
+            (
+                color,
+                format!("{} {} {} **{}**", emoji, player, verb, escape(title)),
+            )
+        }
+    };
+    let embed = EmbedBuilder::new()
+        .color(color)
+        .description(description)
+        .build();
+    client
+        .create_message(feed.channel_id)
+        .embeds(&[embed])
+        .await?;
+    Ok(())
+}
+
+// Names like "Steve_the_Great" would otherwise turn into italics
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '_' | '*' | '~' | '`' | '|' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
@@ -0,0 +1,445 @@
+use crate::mods::bot::query_monitor::{message_gone, update_monitor};
+use crate::mods::java_ping;
+use anyhow::{Result, anyhow, bail};
+use base64::Engine as _;
+use base64::engine::general_purpose::STANDARD;
+use futures_util::future::join_all;
+use protocol::properties::Edition;
+use protocol::query_options::{QueryStatus, ServerStatus};
+use rust_mc_status::{McClient, ServerData};
+use sqlx::PgPool;
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv6Addr, SocketAddr};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::net::lookup_host;
+use tokio::time::{sleep, timeout};
+use trust_dns_resolver::TokioAsyncResolver;
+use twilight_model::id::Id;
+
+// Servers nobody can run an agent on are pinged from here by address. They can only be watched,
+// so there's nothing to start, stop or chat with
+
+const PING_TIMEOUT: Duration = Duration::from_secs(5);
+// Short enough to check an address before Discord gives up on the interaction
+const RESOLVE_TIMEOUT: Duration = Duration::from_secs(2);
+pub const MAX_PER_GUILD: i64 = 10;
+const JAVA_PORT: u16 = 25565;
+
+pub struct ExternalMonitor {
+    pub id: i64,
+    pub address: String,
+    pub edition: String,
+    pub channel_id: i64,
+    pub message_id: i64,
+}
+
+pub struct Pong {
+    pub description: String,
+    pub favicon: Option<Vec<u8>>,
+    pub status: QueryStatus,
+}
+
+pub fn edition_name(edition: Edition) -> &'static str {
+    match edition {
+        Edition::Java => "java",
+        Edition::Bedrock => "bedrock",
+    }
+}
+
+fn edition_from(name: &str) -> Edition {
+    if name == "bedrock" {
+        Edition::Bedrock
+    } else {
+        Edition::Java
+    }
+}
+
+// "play.example.com", "play.example.com:25566", "2001:db8::1" or "[2001:db8::1]:25566", filling
+// in the edition's default port
+pub fn parse_address(address: &str, edition: Edition) -> Option<String> {
+    let address = address.trim();
+    let default_port = match edition {
+        Edition::Java => JAVA_PORT,
+        Edition::Bedrock => 19132,
+    };
+    let (host, port) = if let Some(rest) = address.strip_prefix('[') {
+        let (host, port) = rest.split_once(']')?;
+        match port {
+            "" => (host, default_port),
+            port => (host, port.strip_prefix(':')?.parse::<u16>().ok()?),
+        }
+    } else if address.matches(':').count() > 1 {
+        // Without brackets there's no telling an IPv6 address's last group from a port
+        (address, default_port)
+    } else {
+        match address.split_once(':') {
+            Some((host, port)) => (host, port.parse::<u16>().ok()?),
+            None => (address, default_port),
+        }
+    };
+    if host.is_empty() || host.contains(char::is_whitespace) || port == 0 {
+        return None;
+    }
+    // Brackets are only for IPv6
+    if host.contains(':') || address.starts_with('[') {
+        return Some(format!("[{}]:{}", host.parse::<Ipv6Addr>().ok()?, port));
+    }
+    Some(format!("{}:{}", host, port))
+}
+
+// Where to ping, as long as everything the host resolves to is on the public internet. Otherwise
+// a monitor could have the central server probe its own network. Resolved again before every
+// ping, since DNS can change after the monitor was added
+pub struct Target {
+    pub addr: SocketAddr,
+    // What was typed in, for the Java handshake
+    pub host: String,
+    pub port: u16,
+}
+
+pub async fn resolve_public(address: &str, edition: Edition) -> Result<Target> {
+    let (host, port) = address
+        .rsplit_once(':')
+        .and_then(|(host, port)| Some((host.trim_matches(['[', ']']), port.parse::<u16>().ok()?)))
+        .ok_or_else(|| anyhow!("{} is not an address", address))?;
+    let resolved = timeout(RESOLVE_TIMEOUT, lookup(host, port, edition))
+        .await
+        .map_err(|_| anyhow!("{} didn't resolve in time", address))??;
+    match resolved.first() {
+        None => bail!("{} doesn't resolve to anything", address),
+        Some(_) if !resolved.iter().all(|resolved| is_public(resolved.ip())) => {
+            bail!("{} is not a public address", address)
+        }
+        Some(first) => Ok(Target {
+            addr: *first,
+            host: host.to_string(),
+            port,
+        }),
+    }
+}
+
+// Java clients look for a _minecraft._tcp SRV record when no port is given. Addresses are stored
+// with the port filled in, so the default port stands for none, and a host without a record is
+// looked up directly like the client does. Every target has to be public, not just the one
+// pinged, and the preferred one comes first
+async fn lookup(host: &str, port: u16, edition: Edition) -> Result<Vec<SocketAddr>> {
+    let mut targets = match edition {
+        Edition::Java if port == JAVA_PORT && host.parse::<IpAddr>().is_err() => {
+            srv_records(host).await
+        }
+        _ => Vec::new(),
+    };
+    if targets.is_empty() {
+        targets.push((host.to_string(), port));
+    }
+    let mut resolved = Vec::new();
+    for (host, port) in &targets {
+        resolved.extend(lookup_host((host.as_str(), *port)).await?);
+    }
+    Ok(resolved)
+}
+
+async fn srv_records(host: &str) -> Vec<(String, u16)> {
+    let Ok(resolver) = TokioAsyncResolver::tokio_from_system_conf() else {
+        return Vec::new();
+    };
+    let Ok(records) = resolver
+        .srv_lookup(format!("_minecraft._tcp.{}", host))
+        .await
+    else {
+        return Vec::new();
+    };
+    let mut records: Vec<_> = records.iter().collect();
+    records.sort_by_key(|record| (record.priority(), u16::MAX - record.weight()));
+    records
+        .into_iter()
+        .map(|record| {
+            let target = record.target().to_utf8();
+            (target.trim_end_matches('.').to_string(), record.port())
+        })
+        .collect()
+}
+
+fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+            !(ip.is_private()
+                || ip.is_loopback()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || ip.is_documentation()
+                || ip.is_multicast()
+                || a == 0
+                || a >= 240
+                // Carrier-grade NAT and benchmarking
+                || (a == 100 && (64..128).contains(&b))
+                || (a == 198 && (b == 18 || b == 19)))
+        }
+        IpAddr::V6(ip) => {
+            if let Some(ip) = ip.to_ipv4_mapped() {
+                return is_public(IpAddr::V4(ip));
+            }
+            let [first, second, ..] = ip.segments();
+            !(ip.is_loopback()
+                || ip.is_unspecified()
+                || ip.is_multicast()
+                // Unique local, link local and documentation
+                || (first & 0xfe00) == 0xfc00
+                || (first & 0xffc0) == 0xfe80
+                || (first == 0x2001 && second == 0xdb8))
+        }
+    }
+}
+
+// The checked address is what gets pinged, so a lookup by the ping library can't land somewhere
+// resolve_public didn't check
+pub async fn ping(client: &McClient, address: &str, edition: Edition) -> Result<Pong> {
+    let target = resolve_public(address, edition).await?;
+    let mut status = QueryStatus::default();
+    let pong = match edition {
+        Edition::Java => {
+            let java = timeout(
+                PING_TIMEOUT,
+                java_ping::ping(target.addr, &target.host, target.port),
+            )
+            .await
+            .map_err(|_| anyhow!("{} didn't answer", address))??;
+            status.set_version(java.version);
+            status.set_player_count(format!("{}/{}", java.online, java.max));
+            if let Some(sample) = java.sample {
+                status.set_player_list(sample);
+            }
+            status.set_mods(java.mods);
+            let favicon = java.favicon.and_then(|favicon| {
+                STANDARD
+                    .decode(favicon.strip_prefix("data:image/png;base64,")?)
+                    .ok()
+            });
+            Pong {
+                description: java.description,
+                favicon,
+                status,
+            }
+        }
+        Edition::Bedrock => {
+            let data = timeout(PING_TIMEOUT, client.ping_bedrock(&target.addr.to_string()))
+                .await
+                .map_err(|_| anyhow!("{} didn't answer", address))??
+                .data;
+            let ServerData::Bedrock(bedrock) = data else {
+                bail!("{} didn't answer as a Bedrock server", address);
+            };
+            status.set_version(bedrock.version);
+            status.set_player_count(format!(
+                "{}/{}",
+                bedrock.online_players, bedrock.max_players
+            ));
+            status.set_map(bedrock.map.filter(|map| !map.is_empty()));
+            status.set_gamemode(Some(bedrock.game_mode).filter(|gamemode| !gamemode.is_empty()));
+            status.set_software(bedrock.software);
+            Pong {
+                description: bedrock.motd,
+                favicon: None,
+                status,
+            }
+        }
+    };
+    Ok(pong)
+}
+
+pub async fn monitor_count(dbpool: &PgPool, guild_id: u64) -> Result<i64> {
+    let count = sqlx::query_scalar!(
+        r#"SELECT COUNT(*) AS "count!" FROM external_monitors WHERE guild_id = $1"#,
+        guild_id as i64
+    )
+    .fetch_one(dbpool)
+    .await?;
+    Ok(count)
+}
+
+pub async fn add_monitor(
+    dbpool: &PgPool,
+    guild_id: u64,
+    address: &str,
+    edition: Edition,
+    channel_id: u64,
+    message_id: u64,
+) -> Result<()> {
+    sqlx::query!(
+        "INSERT INTO external_monitors (guild_id, address, edition, channel_id, message_id) VALUES ($1, $2, $3, $4, $5)",
+        guild_id as i64,
+        address,
+        edition_name(edition),
+        channel_id as i64,
+        message_id as i64
+    )
+    .execute(dbpool)
+    .await?;
+    Ok(())
+}
+
+// Pings every external monitor each cycle and edits the messages whose status changed. Deleting
+// the message is how a monitor is removed
+pub async fn poll(dbpool: PgPool, client: Arc<twilight_http::Client>, cycle: Duration) {
+    let mc_client = McClient::new();
+    let mut last_status: HashMap<i64, ServerStatus> = HashMap::new();
+    let mut online_since: HashMap<i64, i64> = HashMap::new();
+    loop {
+        let monitors = match sqlx::query_as!(
+            ExternalMonitor,
+            "SELECT id, address, edition, channel_id, message_id FROM external_monitors"
+        )
+        .fetch_all(&dbpool)
+        .await
+        {
+            Ok(monitors) => monitors,
+            Err(e) => {
+                tracing::error!("Failed to load external monitors: {}", e);
+                sleep(cycle).await;
+                continue;
+            }
+        };
+
+        let pongs = join_all(
+            monitors
+                .iter()
+                .map(|monitor| ping(&mc_client, &monitor.address, edition_from(&monitor.edition))),
+        )
+        .await;
+
+        for (monitor, pong) in monitors.iter().zip(pongs) {
+            let status = match pong {
+                Ok(mut pong) => {
+                    let since = *online_since.entry(monitor.id).or_insert_with(unix_now);
+                    pong.status.set_uptime(None, Some(since));
+                    ServerStatus::ServerOnline(pong.status)
+                }
+                Err(e) => {
+                    tracing::debug!("External ping to {} failed: {}", monitor.address, e);
+                    online_since.remove(&monitor.id);
+                    ServerStatus::ServerOffline
+                }
+            };
+            if last_status.get(&monitor.id) == Some(&status) {
+                continue;
+            }
+            match update_monitor(
+                Id::new(monitor.channel_id as u64),
+                Id::new(monitor.message_id as u64),
+                status.clone(),
+                None,
+                &client,
+            )
+            .await
+            {
+                Ok(()) => {
+                    last_status.insert(monitor.id, status);
+                }
+                Err(e) if message_gone(&e) => {
+                    tracing::info!("Monitor message for {} was deleted", monitor.address);
+                    if let Err(e) =
+                        sqlx::query!("DELETE FROM external_monitors WHERE id = $1", monitor.id)
+                            .execute(&dbpool)
+                            .await
+                    {
+                        tracing::error!("Failed to remove external monitor: {}", e);
+                    }
+                    last_status.remove(&monitor.id);
+                    online_since.remove(&monitor.id);
+                }
+                Err(e) => tracing::warn!("Failed to update monitor for {}: {}", monitor.address, e),
+            }
+        }
+        sleep(cycle).await;
+    }
+}
+
+fn unix_now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs() as i64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_addresses() {
+        let cases = [
+            (
+                "play.example.com",
+                Edition::Java,
+                Some("play.example.com:25565"),
+            ),
+            (
+                "play.example.com",
+                Edition::Bedrock,
+                Some("play.example.com:19132"),
+            ),
+            (
+                " play.example.com:25566 ",
+                Edition::Java,
+                Some("play.example.com:25566"),
+            ),
+            ("1.2.3.4:25566", Edition::Java, Some("1.2.3.4:25566")),
+            (
+                "[2001:db8::1]:25566",
+                Edition::Java,
+                Some("[2001:db8::1]:25566"),
+            ),
+            (
+                "[2001:db8::1]",
+                Edition::Bedrock,
+                Some("[2001:db8::1]:19132"),
+            ),
+            ("2001:db8::1", Edition::Java, Some("[2001:db8::1]:25565")),
+            ("::1", Edition::Java, Some("[::1]:25565")),
+            ("", Edition::Java, None),
+            (":25565", Edition::Java, None),
+            ("play.example.com:", Edition::Java, None),
+            ("play.example.com:0", Edition::Java, None),
+            ("play.example.com:70000", Edition::Java, None),
+            ("play example.com", Edition::Java, None),
+            ("[2001:db8::1]25566", Edition::Java, None),
+            ("[not-ipv6]:25565", Edition::Java, None),
+            ("a:b:c", Edition::Java, None),
+        ];
+        for (address, edition, parsed) in cases {
+            assert_eq!(
+                parse_address(address, edition).as_deref(),
+                parsed,
+                "{}",
+                address
+            );
+        }
+    }
+
+    #[test]
+    fn only_public_addresses() {
+        let cases = [
+            ("1.1.1.1", true),
+            ("2606:4700:4700::1111", true),
+            ("127.0.0.1", false),
+            ("10.0.0.1", false),
+            ("172.16.0.1", false),
+            ("192.168.1.1", false),
+            ("169.254.169.254", false),
+            ("100.64.0.1", false),
+            ("0.0.0.0", false),
+            ("255.255.255.255", false),
+            ("::1", false),
+            ("::", false),
+            ("fd00::1", false),
+            ("fe80::1", false),
+            ("::ffff:127.0.0.1", false),
+            ("::ffff:8.8.8.8", true),
+        ];
+        for (ip, public) in cases {
+            assert_eq!(is_public(ip.parse().unwrap()), public, "{}", ip);
+        }
+    }
+}
@@ -0,0 +1,224 @@
+use anyhow::{Result, anyhow, bail};
+use serde_json::Value;
+use std::net::SocketAddr;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+// The Java server list ping, done here so the connection goes to an address external.rs already
+// checked while the handshake still names the host that was typed in. Proxies like Velocity and
+// TCPShield pick the backend by that name
+// https://minecraft.wiki/w/Java_Edition_protocol/Server_List_Ping
+
+// Clients that don't know the server's version yet send -1
+const PROTOCOL_VERSION: i32 = -1;
+// The favicon is most of the response, and it's a 64x64 PNG
+const MAX_RESPONSE: usize = 1 << 20;
+
+pub struct JavaStatus {
+    pub version: String,
+    pub online: i64,
+    pub max: i64,
+    pub sample: Option<Vec<String>>,
+    pub description: String,
+    pub favicon: Option<String>,
+    pub mods: Option<Vec<String>>,
+}
+
+pub async fn ping(addr: SocketAddr, host: &str, port: u16) -> Result<JavaStatus> {
+    let mut stream = TcpStream::connect(addr).await?;
+    let mut handshake = Vec::new();
+    write_varint(&mut handshake, 0x00);
+    write_varint(&mut handshake, PROTOCOL_VERSION);
+    write_string(&mut handshake, host);
+    handshake.extend_from_slice(&port.to_be_bytes());
+    // Next state: status
+    write_varint(&mut handshake, 1);
+    stream.write_all(&packet(&handshake)).await?;
+    // The status request is an empty packet with id 0
+    stream.write_all(&packet(&[0x00])).await?;
+
+    let length = usize::try_from(read_varint(&mut stream).await?)?;
+    if length > MAX_RESPONSE {
+        bail!("Status response is too large");
+    }
+    let mut body = vec![0; length];
+    stream.read_exact(&mut body).await?;
+    let mut body = body.as_slice();
+    if read_varint(&mut body).await? != 0x00 {
+        bail!("Unexpected status response");
+    }
+    let json_length = usize::try_from(read_varint(&mut body).await?)?;
+    let json = body
+        .get(..json_length)
+        .ok_or_else(|| anyhow!("Status response was cut short"))?;
+    parse_status(&serde_json::from_slice(json)?)
+}
+
+fn parse_status(status: &Value) -> Result<JavaStatus> {
+    let players = &status["players"];
+    Ok(JavaStatus {
+        version: status["version"]["name"]
+            .as_str()
+            .unwrap_or_default()
+            .to_string(),
+        online: players["online"]
+            .as_i64()
+            .ok_or_else(|| anyhow!("Status response has no player count"))?,
+        max: players["max"].as_i64().unwrap_or_default(),
+        sample: players["sample"].as_array().map(|sample| {
+            sample
+                .iter()
+                .filter_map(|player| player["name"].as_str())
+                .map(str::to_string)
+                .collect()
+        }),
+        description: text(&status["description"]),
+        favicon: status["favicon"].as_str().map(str::to_string),
+        mods: mods(status),
+    })
+}
+
+// Descriptions are either plain strings or chat components with nested "extra" parts
+fn text(component: &Value) -> String {
+    match component {
+        Value::String(text) => text.clone(),
+        Value::Array(parts) => parts.iter().map(text).collect(),
+        Value::Object(fields) => {
+            let mut result = fields.get("text").map(text).unwrap_or_default();
+            if let Some(extra) = fields.get("extra") {
+                result.push_str(&text(extra));
+            }
+            result
+        }
+        _ => String::new(),
+    }
+}
+
+// Forge lists its mods under forgeData since 1.13 and under modinfo before that
+fn mods(status: &Value) -> Option<Vec<String>> {
+    let (list, id) = match status["forgeData"]["mods"].as_array() {
+        Some(list) => (list, "modId"),
+        None => (status["modinfo"]["modList"].as_array()?, "modid"),
+    };
+    Some(
+        list.iter()
+            .filter_map(|entry| entry[id].as_str())
+            .map(str::to_string)
+            .collect(),
+    )
+}
+
+fn packet(data: &[u8]) -> Vec<u8> {
+    let mut packet = Vec::with_capacity(data.len() + 5);
+    write_varint(&mut packet, data.len() as i32);
+    packet.extend_from_slice(data);
+    packet
+}
+
+fn write_string(buffer: &mut Vec<u8>, text: &str) {
+    write_varint(buffer, text.len() as i32);
+    buffer.extend_from_slice(text.as_bytes());
+}
+
+fn write_varint(buffer: &mut Vec<u8>, value: i32) {
+    let mut value = value as u32;
+    loop {
+        let byte = (value & 0x7F) as u8;
+        value >>= 7;
+        if value == 0 {
+            buffer.push(byte);
+            return;
+        }
+        buffer.push(byte | 0x80);
+    }
+}
+
+async fn read_varint(reader: &mut (impl AsyncRead + Unpin)) -> Result<i32> {
+    let mut value = 0u32;
+    for i in 0..5 {
+        let byte = reader.read_u8().await?;
+        value |= u32::from(byte & 0x7F) << (7 * i);
+        if byte & 0x80 == 0 {
+            return Ok(value as i32);
+        }
+    }
+    bail!("VarInt is longer than 5 bytes")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn varints_round_trip() {
+        let cases: [(i32, &[u8]); 6] = [
+            (0, &[0x00]),
+            (1, &[0x01]),
+            (127, &[0x7F]),
+            (128, &[0x80, 0x01]),
+            (25565, &[0xDD, 0xC7, 0x01]),
+            (-1, &[0xFF, 0xFF, 0xFF, 0xFF, 0x0F]),
+        ];
+        for (value, bytes) in cases {
+            let mut buffer = Vec::new();
+            write_varint(&mut buffer, value);
+            assert_eq!(buffer, bytes, "{value}");
+            assert_eq!(read_varint(&mut &buffer[..]).await.unwrap(), value);
+        }
+        let too_long: &[u8] = &[0xFF; 6];
+        assert!(read_varint(&mut &too_long[..]).await.is_err());
+    }
+
+    #[test]
+    fn reads_descriptions() {
+        let cases = [
+            (r#""A Minecraft Server""#, "A Minecraft Server"),
+            (r#"{"text": "§aGreen"}"#, "§aGreen"),
+            (
+                r#"{"text": "", "extra": [{"text": "Hello "}, {"text": "world", "bold": true}]}"#,
+                "Hello world",
+            ),
+            (r#"[{"text": "a"}, "b", {"extra": ["c"]}]"#, "abc"),
+            ("null", ""),
+        ];
+        for (json, expected) in cases {
+            let component: Value = serde_json::from_str(json).unwrap();
+            assert_eq!(text(&component), expected, "{json}");
+        }
+    }
+
+    #[test]
+    fn parses_statuses() {
+        let status: Value = serde_json::from_str(
+            r#"{
+                "version": {"name": "Paper 1.21.1", "protocol": 767},
+                "players": {"max": 20, "online": 2, "sample": [{"name": "Steve", "id": "x"}, {"name": "Alex", "id": "y"}]},
+                "description": {"text": "Welcome"},
+                "favicon": "data:image/png;base64,AAAA",
+                "forgeData": {"mods": [{"modId": "forge"}, {"modId": "jei"}]}
+            }"#,
+        )
+        .unwrap();
+        let status = parse_status(&status).unwrap();
+        assert_eq!(status.version, "Paper 1.21.1");
+        assert_eq!((status.online, status.max), (2, 20));
+        assert_eq!(status.sample.unwrap(), ["Steve", "Alex"]);
+        assert_eq!(status.description, "Welcome");
+        assert_eq!(
+            status.favicon.as_deref(),
+            Some("data:image/png;base64,AAAA")
+        );
+        assert_eq!(status.mods.unwrap(), ["forge", "jei"]);
+
+        let legacy: Value = serde_json::from_str(
+            r#"{"players": {"max": 10, "online": 0}, "modinfo": {"modList": [{"modid": "mcp"}]}}"#,
+        )
+        .unwrap();
+        let legacy = parse_status(&legacy).unwrap();
+        assert_eq!(legacy.sample, None);
+        assert_eq!(legacy.mods.unwrap(), ["mcp"]);
+
+        let no_players: Value = serde_json::from_str(r#"{"version": {"name": "x"}}"#).unwrap();
+        assert!(parse_status(&no_players).is_err());
+    }
+}
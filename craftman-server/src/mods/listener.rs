@@ -1,5 +1,8 @@
-use crate::mods::agents::Agent;
-use crate::mods::bot::query_monitor::{update_header, update_monitor};
+use crate::mods::agents::{Agent, QueryMonitor};
+use crate::mods::bot::query_monitor::{message_gone, update_chart, update_header, update_monitor};
+use crate::mods::bot::server_commands::properties::settingsview::refresh_settings_views;
+use crate::mods::charts::monitor_chart;
+use crate::mods::event_feed;
 use anyhow::Result;
 use anyhow::bail;
 use axum::Error;
@@ -7,8 +10,10 @@ use axum::extract::ws::Message;
 use futures_util::Stream;
 use futures_util::stream::StreamExt;
 use protocol::agentactions::AgentActions;
+use protocol::player_events::PlayerEvent;
 use protocol::serveractions::{RequestResponses, ServerActions};
 use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
 use tracing::{debug, error, instrument, warn};
 
 #[instrument(skip(receiver, agent, twilight_client))]
@@ -48,6 +53,19 @@ async fn handle_message(
                 .complete_request(&id, RequestResponses::PropsResponse(props))
                 .await?;
         }
+        // Editing every open panel can take a while, and the agent's other messages shouldn't wait
+        ServerActions::PropsChanged(props) => {
+            debug!("Refreshing settings views");
+            tokio::spawn(async move {
+                refresh_settings_views(&twilight_client, &agent, &props).await;
+            });
+        }
+        ServerActions::PropsError(id, e) => {
+            debug!("Handling props error");
+            agent
+                .complete_request(&id, RequestResponses::PropsError(e))
+                .await?;
+        }
         ServerActions::QueryResponse {
             uuid: id,
             description,
@@ -62,22 +80,74 @@ async fn handle_message(
                 )
                 .await?;
         }
-        ServerActions::UpdateQuery { status } => {
+        ServerActions::UpdateQuery { monitor, status } => {
             debug!("Handling update query");
-            if let Some((channel_id, message_id)) = agent.query_ids().await? {
-                update_monitor(channel_id, message_id, status, &twilight_client).await?;
+            if let Some((channel_id, message_id)) = agent.query_ids(monitor).await? {
+                let result = update_monitor(
+                    channel_id,
+                    message_id,
+                    status,
+                    Some(agent.id()),
+                    &twilight_client,
+                )
+                .await;
+                forget_deleted(&agent, monitor, result).await?;
             } else {
                 warn!("Update query received without an associated monitor");
-                agent.send(AgentActions::StopQuery).await?;
+                agent.send(AgentActions::StopQuery(monitor)).await?;
             }
         }
-        ServerActions::UpdateQueryHeader { description, image } => {
+        ServerActions::UpdateQueryHeader {
+            monitor,
+            description,
+            image,
+        } => {
             debug!("Handling update query header");
-            if let Some((channel_id, message_id)) = agent.query_ids().await? {
-                update_header(message_id, channel_id, description, image, &twilight_client).await?;
+            if let Some((channel_id, message_id)) = agent.query_ids(monitor).await? {
+                let result =
+                    update_header(message_id, channel_id, description, image, &twilight_client)
+                        .await;
+                forget_deleted(&agent, monitor, result).await?;
             } else {
                 warn!("Update query header received without an associated monitor");
-                agent.send(AgentActions::StopQuery).await?;
+                agent.send(AgentActions::StopQuery(monitor)).await?;
+            }
+        }
+        // Samples come once a minute per agent however many monitors it has, so everything that
+        // follows the server's status runs from here rather than from monitor updates
+        ServerActions::QuerySample(sample) => {
+            debug!("Handling query sample");
+            if let Err(e) = agent.alerts().status(agent.id(), sample.online).await {
+                warn!("Failed to check alerts: {}", e);
+            }
+            agent.notifier().status(agent.id(), sample.online);
+            if let Err(e) = agent.status_channels().update(agent.id(), &sample).await {
+                warn!("Failed to rename status channel: {}", e);
+            }
+            agent.sessions_status(sample.online).await?;
+            agent.record_sample(sample).await?;
+            let monitors = agent.query_monitors().await?;
+            if !monitors.is_empty() && agent.chart_due().await {
+                tokio::spawn(async move {
+                    if let Err(e) = redraw_charts(&agent, monitors, &twilight_client).await {
+                        warn!("Failed to redraw monitor charts: {}", e);
+                    }
+                });
+            }
+        }
+        ServerActions::ServerExited { expected } => {
+            debug!("Handling server exit");
+            agent.close_sessions().await?;
+            agent.alerts().exited(agent.id(), expected).await?;
+        }
+        ServerActions::PlayerEvent(event) => {
+            debug!("Handling player event");
+            agent.record_player_event(&event).await?;
+            if let PlayerEvent::Joined(player) = &event {
+                agent.notifier().joined(agent.id(), player);
+            }
+            if let Some(feed) = agent.feed().await? {
+                event_feed::post(&twilight_client, &feed, &event).await?;
             }
         }
         ServerActions::ChatMessage(message) => {
@@ -96,6 +166,12 @@ async fn handle_message(
                 .complete_request(&id, RequestResponses::StopServerResponse)
                 .await?;
         }
+        ServerActions::RequestError(id, e) => {
+            debug!("Handling request error");
+            agent
+                .complete_request(&id, RequestResponses::Error(e))
+                .await?;
+        }
         ServerActions::ConnectAgent(_) => {
             bail!("Agent already connected")
         }
@@ -117,6 +193,61 @@ async fn handle_message(
                 .complete_request(&id, RequestResponses::CommandResponse)
                 .await?;
         }
+        ServerActions::Progress(id, progress) => {
+            debug!("Handling progress report");
+            agent.report_progress(&id, progress)?;
+        }
+        ServerActions::ModsResponse(id, inventory) => {
+            debug!("Handling mods response");
+            agent
+                .complete_request(&id, RequestResponses::ModsResponse(inventory))
+                .await?;
+        }
+        ServerActions::FilesResponse(id, response) => {
+            debug!("Handling files response");
+            agent
+                .complete_request(&id, RequestResponses::FilesResponse(response))
+                .await?;
+        }
+        ServerActions::ConfigFileResponse(id, response) => {
+            debug!("Handling config file response");
+            agent
+                .complete_request(&id, RequestResponses::ConfigFileResponse(response))
+                .await?;
+        }
     }
     Ok(())
 }
+
+async fn redraw_charts(
+    agent: &Agent,
+    monitors: Vec<QueryMonitor>,
+    twilight_client: &twilight_http::Client,
+) -> Result<()> {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() as i64;
+    let samples = agent.samples(now - 7 * 24 * 3600, now).await?;
+    let png = tokio::task::spawn_blocking(move || monitor_chart(&samples, now)).await??;
+    for monitor in monitors {
+        let result = update_chart(
+            monitor.channel_id,
+            monitor.message_id,
+            png.clone(),
+            twilight_client,
+        )
+        .await;
+        forget_deleted(agent, monitor.id, result).await?;
+    }
+    Ok(())
+}
+
+// A monitor whose message was deleted is removed, which also stops the agent's loop for it
+async fn forget_deleted(agent: &Agent, monitor: i64, result: Result<()>) -> Result<()> {
+    match result {
+        Err(e) if message_gone(&e) => {
+            tracing::info!("Monitor {} was deleted, removing it", monitor);
+            agent.remove_monitor(monitor).await?;
+            Ok(())
+        }
+        result => result,
+    }
+}
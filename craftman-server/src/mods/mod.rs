@@ -1,7 +1,16 @@
 pub mod agents;
+pub mod alerts;
 pub mod appstate;
 pub mod bot;
+pub mod charts;
+pub mod duration;
+pub mod event_feed;
+pub mod external;
 pub mod init_tracing;
+pub mod java_ping;
 pub mod listener;
+pub mod notify;
+pub mod status_channel;
+pub mod status_page;
 
 pub use bot::bot_handler;
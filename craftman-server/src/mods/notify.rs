@@ -0,0 +1,358 @@
+use crate::mods::alerts::OFFLINE_GRACE;
+use anyhow::Result;
+use dashmap::DashMap;
+use sqlx::PgPool;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::time::sleep;
+use twilight_model::id::Id;
+use uuid::Uuid;
+
+pub const MAX_PER_USER: usize = 25;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Event {
+    Online,
+    Offline,
+    Join,
+}
+
+impl Event {
+    pub const ALL: [Event; 3] = [Event::Online, Event::Offline, Event::Join];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Event::Online => "online",
+            Event::Offline => "offline",
+            Event::Join => "join",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Event> {
+        Event::ALL.into_iter().find(|event| event.name() == name)
+    }
+
+    pub fn title(self) -> &'static str {
+        match self {
+            Event::Online => "Server comes online",
+            Event::Offline => "Server goes down",
+            Event::Join => "A player joins",
+        }
+    }
+}
+
+pub struct Subscription {
+    pub event: String,
+    pub player: String,
+}
+
+pub struct QuietHours {
+    pub start_hour: i32,
+    pub end_hour: i32,
+    pub utc_offset_minutes: i32,
+}
+
+impl QuietHours {
+    // now is seconds since the Unix epoch
+    pub fn active(&self, now: i64) -> bool {
+        let hour = ((now / 60 + self.utc_offset_minutes as i64).rem_euclid(24 * 60) / 60) as i32;
+        if self.start_hour <= self.end_hour {
+            (self.start_hour..self.end_hour).contains(&hour)
+        } else {
+            hour >= self.start_hour || hour < self.end_hour
+        }
+    }
+}
+
+#[derive(Default)]
+struct AgentState {
+    // What subscribers were last told, None until the first status
+    online: Option<bool>,
+    offline_since: Option<Instant>,
+}
+
+// Sends the DMs users subscribed to with /notify. Like alerts it outlives the Agent, so a server
+// that changed state while its agent was away is still noticed when it comes back
+pub struct Notifier {
+    dbpool: PgPool,
+    client: Arc<twilight_http::Client>,
+    agents: DashMap<Uuid, AgentState>,
+}
+
+impl Notifier {
+    pub fn new(dbpool: PgPool, client: Arc<twilight_http::Client>) -> Self {
+        Notifier {
+            dbpool,
+            client,
+            agents: DashMap::new(),
+        }
+    }
+
+    // The first status after the server starts tracking an agent sets the baseline. Going down
+    // is only sent once the server stayed down for OFFLINE_GRACE, so a missed ping or a quick
+    // restart doesn't DM anyone
+    pub fn status(&self, agent_id: Uuid, online: bool) {
+        let mut state = self.agents.entry(agent_id).or_default();
+        match (state.online, online) {
+            (None, _) => state.online = Some(online),
+            (Some(true), true) => state.offline_since = None,
+            (Some(true), false) => {
+                state.offline_since.get_or_insert_with(Instant::now);
+            }
+            (Some(false), true) => {
+                state.online = Some(true);
+                self.send(agent_id, Event::Online, String::new(), |guild| {
+                    format!("🟢 The Minecraft server on **{}** is online", guild)
+                });
+            }
+            (Some(false), false) => {}
+        }
+    }
+
+    pub fn joined(&self, agent_id: Uuid, player: &str) {
+        let name = player.to_string();
+        self.send(agent_id, Event::Join, player.to_lowercase(), move |guild| {
+            format!(
+                "👋 **{}** joined the Minecraft server on **{}**",
+                name, guild
+            )
+        });
+    }
+
+    pub async fn run(&self, cycle: Duration) {
+        loop {
+            for mut state in self.agents.iter_mut() {
+                if state
+                    .offline_since
+                    .is_some_and(|since| since.elapsed() >= OFFLINE_GRACE)
+                {
+                    state.offline_since = None;
+                    state.online = Some(false);
+                    self.send(*state.key(), Event::Offline, String::new(), |guild| {
+                        format!("🔴 The Minecraft server on **{}** went down", guild)
+                    });
+                }
+            }
+            sleep(cycle).await;
+        }
+    }
+
+    // DMs go out one by one, so they're sent in the background instead of holding up the agent's
+    // messages
+    fn send(
+        &self,
+        agent_id: Uuid,
+        event: Event,
+        player: String,
+        content: impl Fn(&str) -> String + Send + 'static,
+    ) {
+        let dbpool = self.dbpool.clone();
+        let client = self.client.clone();
+        tokio::spawn(async move {
+            if let Err(e) = deliver(&dbpool, &client, agent_id, event, &player, content).await {
+                tracing::warn!("Failed to send notifications: {}", e);
+            }
+        });
+    }
+}
+
+// Users in their quiet hours are skipped, the DM isn't kept for later
+async fn deliver(
+    dbpool: &PgPool,
+    client: &twilight_http::Client,
+    agent_id: Uuid,
+    event: Event,
+    player: &str,
+    content: impl Fn(&str) -> String,
+) -> Result<()> {
+    let recipients = sqlx::query!(
+        "SELECT n.guild_id, n.user_id, q.start_hour, q.end_hour, q.utc_offset_minutes FROM notify_subscriptions n JOIN servers s ON s.guild_id = n.guild_id LEFT JOIN notify_quiet_hours q ON q.guild_id = n.guild_id AND q.user_id = n.user_id WHERE s.agent_id = $1 AND n.event = $2 AND n.player = $3",
+        agent_id,
+        event.name(),
+        player
+    )
+    .fetch_all(dbpool)
+    .await?;
+    let Some(first) = recipients.first() else {
+        return Ok(());
+    };
+    let guild = match client.guild(Id::new(first.guild_id as u64)).await {
+        Ok(response) => response.model().await?.name,
+        Err(_) => "your Discord server".to_string(),
+    };
+    let content = content(&guild);
+    let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() as i64;
+    for recipient in recipients {
+        if let (Some(start_hour), Some(end_hour), Some(utc_offset_minutes)) = (
+            recipient.start_hour,
+            recipient.end_hour,
+            recipient.utc_offset_minutes,
+        ) && (QuietHours {
+            start_hour,
+            end_hour,
+            utc_offset_minutes,
+        })
+        .active(now)
+        {
+            continue;
+        }
+        // Users who closed their DMs to the server make this fail, which isn't ours to fix
+        if let Err(e) = dm(client, recipient.user_id as u64, &content).await {
+            tracing::debug!("Failed to DM {}: {}", recipient.user_id, e);
+        }
+    }
+    Ok(())
+}
+
+async fn dm(client: &twilight_http::Client, user_id: u64, content: &str) -> Result<()> {
+    let channel = client
+        .create_private_channel(Id::new(user_id))
+        .await?
+        .model()
+        .await?;
+    client.create_message(channel.id).content(content).await?;
+    Ok(())
+}
+
+pub async fn subscribe(
+    dbpool: &PgPool,
+    guild_id: u64,
+    user_id: u64,
+    event: Event,
+    player: &str,
+) -> Result<bool> {
+    Ok(sqlx::query!(
+        "INSERT INTO notify_subscriptions (guild_id, user_id, event, player) VALUES ($1, $2, $3, $4) ON CONFLICT DO NOTHING",
+        guild_id as i64,
+        user_id as i64,
+        event.name(),
+        player.to_lowercase()
+    )
+    .execute(dbpool)
+    .await?
+    .rows_affected()
+        > 0)
+}
+
+pub async fn unsubscribe(
+    dbpool: &PgPool,
+    guild_id: u64,
+    user_id: u64,
+    event: Event,
+    player: &str,
+) -> Result<bool> {
+    Ok(sqlx::query!(
+        "DELETE FROM notify_subscriptions WHERE guild_id = $1 AND user_id = $2 AND event = $3 AND player = $4",
+        guild_id as i64,
+        user_id as i64,
+        event.name(),
+        player.to_lowercase()
+    )
+    .execute(dbpool)
+    .await?
+    .rows_affected()
+        > 0)
+}
+
+pub async fn subscriptions(
+    dbpool: &PgPool,
+    guild_id: u64,
+    user_id: u64,
+) -> Result<Vec<Subscription>> {
+    Ok(sqlx::query_as!(
+        Subscription,
+        "SELECT event, player FROM notify_subscriptions WHERE guild_id = $1 AND user_id = $2 ORDER BY id",
+        guild_id as i64,
+        user_id as i64
+    )
+    .fetch_all(dbpool)
+    .await?)
+}
+
+pub async fn quiet_hours(
+    dbpool: &PgPool,
+    guild_id: u64,
+    user_id: u64,
+) -> Result<Option<QuietHours>> {
+    Ok(sqlx::query_as!(
+        QuietHours,
+        "SELECT start_hour, end_hour, utc_offset_minutes FROM notify_quiet_hours WHERE guild_id = $1 AND user_id = $2",
+        guild_id as i64,
+        user_id as i64
+    )
+    .fetch_optional(dbpool)
+    .await?)
+}
+
+pub async fn set_quiet_hours(
+    dbpool: &PgPool,
+    guild_id: u64,
+    user_id: u64,
+    quiet: Option<&QuietHours>,
+) -> Result<()> {
+    match quiet {
+        Some(quiet) => {
+            sqlx::query!(
+                "INSERT INTO notify_quiet_hours (guild_id, user_id, start_hour, end_hour, utc_offset_minutes) VALUES ($1, $2, $3, $4, $5) ON CONFLICT (guild_id, user_id) DO UPDATE SET start_hour = $3, end_hour = $4, utc_offset_minutes = $5",
+                guild_id as i64,
+                user_id as i64,
+                quiet.start_hour,
+                quiet.end_hour,
+                quiet.utc_offset_minutes
+            )
+            .execute(dbpool)
+            .await?;
+        }
+        None => {
+            sqlx::query!(
+                "DELETE FROM notify_quiet_hours WHERE guild_id = $1 AND user_id = $2",
+                guild_id as i64,
+                user_id as i64
+            )
+            .execute(dbpool)
+            .await?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quiet_hours() {
+        let hour = 3600;
+        for (start_hour, end_hour, utc_offset_minutes, now, expected) in [
+            (22, 7, 0, 23 * hour, true),
+            (22, 7, 0, 22 * hour, true),
+            (22, 7, 0, 3 * hour, true),
+            (22, 7, 0, 7 * hour, false),
+            (22, 7, 0, 12 * hour, false),
+            (9, 17, 0, 9 * hour, true),
+            (9, 17, 0, 17 * hour, false),
+            (9, 17, 120, 8 * hour, true),
+            (9, 17, 120, 15 * hour, false),
+            (20, 23, -300, 2 * hour, true),
+            (20, 23, -300, 20 * hour, false),
+            (0, 6, 330, 19 * hour, true),
+            // A day later is the same hour
+            (22, 7, 0, 24 * hour + 23 * hour, true),
+        ] {
+            let quiet = QuietHours {
+                start_hour,
+                end_hour,
+                utc_offset_minutes,
+            };
+            assert_eq!(
+                quiet.active(now),
+                expected,
+                "{}-{} UTC{:+} at {}",
+                start_hour,
+                end_hour,
+                utc_offset_minutes,
+                now / hour
+            );
+        }
+    }
+}
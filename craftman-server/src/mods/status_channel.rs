@@ -0,0 +1,118 @@
+use anyhow::Result;
+use dashmap::DashMap;
+use protocol::query_options::QuerySample;
+use sqlx::PgPool;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::time::sleep;
+use twilight_model::id::Id;
+use uuid::Uuid;
+
+// Discord lets a channel be renamed twice every 10 minutes
+const RENAME_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+#[derive(Default)]
+struct ChannelState {
+    name: String,
+    renamed: Option<String>,
+    renamed_at: Option<Instant>,
+    players: u32,
+}
+
+impl ChannelState {
+    fn due(&self) -> bool {
+        self.renamed.as_ref() != Some(&self.name)
+            && self
+                .renamed_at
+                .is_none_or(|renamed_at| renamed_at.elapsed() >= RENAME_INTERVAL)
+    }
+}
+
+// Keeps each agent's status channel named after its latest sample. Changes that come in
+// faster than Discord allows renames wait for the next run, and only the newest name is applied
+pub struct StatusChannels {
+    dbpool: PgPool,
+    client: Arc<twilight_http::Client>,
+    agents: DashMap<Uuid, ChannelState>,
+}
+
+impl StatusChannels {
+    pub fn new(dbpool: PgPool, client: Arc<twilight_http::Client>) -> Self {
+        StatusChannels {
+            dbpool,
+            client,
+            agents: DashMap::new(),
+        }
+    }
+
+    pub async fn update(&self, agent_id: Uuid, sample: &QuerySample) -> Result<()> {
+        {
+            let mut state = self.agents.entry(agent_id).or_default();
+            state.players = sample.players.filter(|_| sample.online).unwrap_or(0);
+            state.name = match (sample.online, sample.players, sample.max_players) {
+                (true, Some(players), Some(max)) => format!("🟢 {}/{} online", players, max),
+                (true, Some(players), None) => format!("🟢 {} online", players),
+                (true, None, _) => "🟢 Online".to_string(),
+                (false, _, _) => "🔴 Offline".to_string(),
+            };
+        }
+        self.rename(agent_id).await
+    }
+
+    // Players on the servers whose last update said they were online
+    pub fn players(&self, agent_id: Uuid) -> u32 {
+        self.agents
+            .get(&agent_id)
+            .map(|state| state.players)
+            .unwrap_or(0)
+    }
+
+    // After the channel changes, so the next update renames it straight away
+    pub fn reset(&self, agent_id: Uuid) {
+        if let Some(mut state) = self.agents.get_mut(&agent_id) {
+            state.renamed = None;
+            state.renamed_at = None;
+        }
+    }
+
+    pub async fn run(&self, cycle: Duration) {
+        loop {
+            let agents: Vec<Uuid> = self.agents.iter().map(|entry| *entry.key()).collect();
+            for agent_id in agents {
+                if let Err(e) = self.rename(agent_id).await {
+                    tracing::warn!("Failed to rename status channel for {}: {}", agent_id, e);
+                }
+            }
+            sleep(cycle).await;
+        }
+    }
+
+    // The slot is claimed before renaming so the run loop and an update can't both use it
+    async fn rename(&self, agent_id: Uuid) -> Result<()> {
+        let name = match self.agents.get_mut(&agent_id) {
+            Some(mut state) if state.due() => {
+                state.renamed_at = Some(Instant::now());
+                state.name.clone()
+            }
+            _ => return Ok(()),
+        };
+        let record = sqlx::query!(
+            "SELECT status_channel_id FROM servers WHERE agent_id = $1",
+            agent_id
+        )
+        .fetch_optional(&self.dbpool)
+        .await?;
+        // Without a channel there's nothing to rename, but the name still counts as applied so the
+        // database isn't asked again until it changes
+        if let Some(channel_id) = record.and_then(|record| record.status_channel_id) {
+            self.client
+                .update_channel(Id::new(channel_id as u64))
+                .name(&name)
+                .await?;
+        }
+        if let Some(mut state) = self.agents.get_mut(&agent_id) {
+            state.renamed = Some(name);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,272 @@
+use crate::appstate::AppState;
+use crate::mods::charts::status_banner;
+use crate::mods::duration::duration;
+use anyhow::Result;
+use axum::Json;
+use axum::Router;
+use axum::body::Bytes;
+use axum::extract::{Path, State};
+use axum::http::{StatusCode, header};
+use axum::response::{Html, IntoResponse, Response};
+use axum::routing::get;
+use base64::Engine as _;
+use base64::engine::general_purpose::STANDARD;
+use protocol::query_options::{QueryStatus, ServerStatus};
+use serde::Serialize;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+static DEFAULT_ICON: &[u8] = include_bytes!("../../assets/default_icon.png");
+
+// Pages are answered from the cache for this long, and browsers and CDNs may keep them as long
+pub const STATUS_TTL: Duration = Duration::from_secs(30);
+const CACHE_CONTROL: &str = "public, max-age=30";
+
+// Everything the three routes show, fetched from the agent once per STATUS_TTL
+#[derive(Clone)]
+pub struct StatusPage {
+    motd: String,
+    favicon: Option<String>,
+    status: ServerStatus,
+    banner: Bytes,
+    updated_at: i64,
+}
+
+#[derive(Serialize)]
+struct StatusDocument<'a> {
+    online: bool,
+    motd: &'a str,
+    // A data URL, the same way servers hand out their favicon
+    favicon: Option<&'a str>,
+    #[serde(flatten)]
+    status: Option<&'a QueryStatus>,
+    updated_at: i64,
+}
+
+impl StatusPage {
+    pub async fn new(motd: &str, favicon: Option<Vec<u8>>, status: ServerStatus) -> Result<Self> {
+        let motd = strip_formatting(motd);
+        let online = matches!(status, ServerStatus::ServerOnline(_));
+        let details: Vec<String> = match &status {
+            ServerStatus::ServerOnline(query) => [
+                query
+                    .player_count()
+                    .map(|count| format!("{} players", count)),
+                query.version().cloned(),
+            ]
+            .into_iter()
+            .flatten()
+            .collect(),
+            ServerStatus::ServerOffline => Vec::new(),
+        };
+        let banner = {
+            let motd = motd.clone();
+            let icon = favicon.clone().unwrap_or(DEFAULT_ICON.to_vec());
+            tokio::task::spawn_blocking(move || {
+                let details: Vec<&str> = details.iter().map(String::as_str).collect();
+                status_banner(&motd, &icon, online, &details)
+            })
+            .await??
+        };
+        Ok(StatusPage {
+            motd,
+            favicon: favicon
+                .map(|favicon| format!("data:image/png;base64,{}", STANDARD.encode(favicon))),
+            status,
+            banner: banner.into(),
+            updated_at: SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() as i64,
+        })
+    }
+
+    fn document(&self) -> StatusDocument<'_> {
+        StatusDocument {
+            online: matches!(self.status, ServerStatus::ServerOnline(_)),
+            motd: &self.motd,
+            favicon: self.favicon.as_deref(),
+            status: match &self.status {
+                ServerStatus::ServerOnline(query) => Some(query),
+                ServerStatus::ServerOffline => None,
+            },
+            updated_at: self.updated_at,
+        }
+    }
+
+    fn html(&self) -> String {
+        let title = escape(self.motd.lines().next().unwrap_or("Minecraft server"));
+        let motd = self
+            .motd
+            .lines()
+            .map(escape)
+            .collect::<Vec<_>>()
+            .join("<br>");
+        let icon = match &self.favicon {
+            Some(favicon) => favicon.clone(),
+            None => format!("data:image/png;base64,{}", STANDARD.encode(DEFAULT_ICON)),
+        };
+        let mut rows = String::new();
+        let state = match &self.status {
+            ServerStatus::ServerOnline(query) => {
+                let mut row = |label: &str, value: String| {
+                    rows.push_str(&format!("<dt>{}</dt><dd>{}</dd>", label, value));
+                };
+                if let Some(version) = query.version() {
+                    row("Version", escape(version));
+                }
+                if let Some(player_count) = query.player_count() {
+                    row("Players", escape(player_count));
+                }
+                if let Some(player_list) = query.player_list().filter(|list| !list.is_empty()) {
+                    row("Online now", escape(&player_list.join(", ")));
+                }
+                if let Some(online_since) = query.online_since() {
+                    row("Up for", duration(self.updated_at - online_since));
+                }
+                if let Some(software) = query.software() {
+                    row("Software", escape(software));
+                }
+                if let Some(map) = query.map() {
+                    row("Map", escape(map));
+                }
+                if let Some(gamemode) = query.gamemode() {
+                    row("Gamemode", escape(gamemode));
+                }
+                "<span class=\"dot online\"></span>Online"
+            }
+            ServerStatus::ServerOffline => "<span class=\"dot offline\"></span>Offline",
+        };
+        format!(
+            r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<meta http-equiv="refresh" content="60">
+<title>{title}</title>
+<style>
+body {{ background: #2b2d31; color: #dbdee1; font-family: sans-serif; margin: 0; padding: 2em; }}
+main {{ max-width: 40em; margin: auto; }}
+header {{ display: flex; gap: 1em; align-items: center; }}
+header img {{ width: 64px; height: 64px; image-rendering: pixelated; }}
+.dot {{ display: inline-block; width: .7em; height: .7em; border-radius: 50%; margin-right: .4em; }}
+.online {{ background: #57f287; }}
+.offline {{ background: #ed4245; }}
+dl {{ display: grid; grid-template-columns: max-content auto; gap: .4em 1.5em; }}
+dt {{ color: #949ba4; }}
+dd {{ margin: 0; }}
+</style>
+</head>
+<body>
+<main>
+<header><img src="{icon}" alt=""><div>{motd}</div></header>
+<h2>{state}</h2>
+<dl>{rows}</dl>
+</main>
+</body>
+</html>
+"#
+        )
+    }
+}
+
+// Next to /craftman. Unknown or disabled slugs are a plain 404 so pages can't be guessed
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/status/{slug}", get(html))
+        .route("/status/{slug}/json", get(json))
+        .route("/status/{slug}/banner.png", get(banner))
+}
+
+async fn html(State(app_state): State<AppState>, Path(slug): Path<String>) -> Response {
+    let Some(page) = app_state.status_page(&slug).await else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+    ([(header::CACHE_CONTROL, CACHE_CONTROL)], Html(page.html())).into_response()
+}
+
+// Other sites fetch this from the browser, so any origin may read it
+async fn json(State(app_state): State<AppState>, Path(slug): Path<String>) -> Response {
+    let Some(page) = app_state.status_page(&slug).await else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+    (
+        [
+            (header::CACHE_CONTROL, CACHE_CONTROL),
+            (header::ACCESS_CONTROL_ALLOW_ORIGIN, "*"),
+        ],
+        Json(page.document()),
+    )
+        .into_response()
+}
+
+async fn banner(State(app_state): State<AppState>, Path(slug): Path<String>) -> Response {
+    let Some(page) = app_state.status_page(&slug).await else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+    (
+        [
+            (header::CONTENT_TYPE, "image/png"),
+            (header::CACHE_CONTROL, CACHE_CONTROL),
+        ],
+        page.banner,
+    )
+        .into_response()
+}
+
+// MOTDs carry colour codes like "§a", which only the game understands
+fn strip_formatting(text: &str) -> String {
+    let mut stripped = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '§' {
+            chars.next();
+        } else {
+            stripped.push(c);
+        }
+    }
+    stripped
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strips_formatting() {
+        let cases = [
+            ("A Minecraft Server", "A Minecraft Server"),
+            ("§aGreen §lbold§r plain", "Green bold plain"),
+            ("§x§f§f§0§0§0§0Hex", "Hex"),
+            ("Line one\n§bLine two", "Line one\nLine two"),
+            ("Trailing §", "Trailing "),
+            ("§§a", "a"),
+            ("Snow ☃ §eman", "Snow ☃ man"),
+        ];
+        for (motd, expected) in cases {
+            assert_eq!(strip_formatting(motd), expected, "{motd:?}");
+        }
+    }
+
+    #[test]
+    fn escapes_html() {
+        let cases = [
+            ("plain", "plain"),
+            (
+                "<script>alert(1)</script>",
+                "&lt;script&gt;alert(1)&lt;/script&gt;",
+            ),
+            ("\"><img src=x>", "&quot;&gt;&lt;img src=x&gt;"),
+            ("Tom & Jerry", "Tom &amp; Jerry"),
+            // Already escaped text is escaped again, not passed through
+            ("&lt;", "&amp;lt;"),
+        ];
+        for (text, expected) in cases {
+            assert_eq!(escape(text), expected, "{text:?}");
+        }
+    }
+}
@@ -1,5 +1,9 @@
+use crate::config_files::ConfigRequest;
+use crate::files::FileRequest;
+use crate::inventory::ModChange;
 use crate::query_options::QueryOptions;
-use crate::{properties::Property, server_commands::ServerCommands};
+use crate::server_commands::ServerCommands;
+use crate::tasks::RemoteFile;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
@@ -9,12 +13,24 @@ pub enum AgentActions {
     SvStop(Uuid),
     Message(String),
     RequestProps(Uuid),
-    EditProp(Uuid, Property),
-    StartQuery(Uuid, QueryOptions),
-    StopQuery,
+    SetProperty(Uuid, String, String),
+    // Applied together in a single write, or not at all
+    SetProperties(Uuid, Vec<(String, String)>),
+    // Monitor id, its options and how many seconds between updates
+    StartQuery(Uuid, i64, QueryOptions, u64),
+    StopQuery(i64),
+    // A single status with every field, answered like StartQuery but without starting a loop
+    RequestStatus(Uuid),
     StartChatStream(Uuid),
     StopChatStream(Uuid),
     ValidationToken(String),
     Validate,
     ServerCommand(Uuid, ServerCommands),
+    Upgrade(Uuid, RemoteFile),
+    InstallModpack(Uuid, RemoteFile),
+    ListMods(Uuid),
+    ChangeMod(Uuid, ModChange),
+    Restart(Uuid),
+    Files(Uuid, FileRequest),
+    ConfigFile(Uuid, ConfigRequest),
 }
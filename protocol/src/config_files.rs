@@ -0,0 +1,61 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum ConfigFormat {
+    Yaml,
+    Toml,
+    Json,
+}
+
+impl ConfigFormat {
+    pub fn from_path(path: &str) -> Option<Self> {
+        let (_, extension) = path.rsplit_once('.')?;
+        match extension.to_ascii_lowercase().as_str() {
+            "yml" | "yaml" => Some(ConfigFormat::Yaml),
+            "toml" => Some(ConfigFormat::Toml),
+            "json" => Some(ConfigFormat::Json),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum ValueKind {
+    Section,
+    List,
+    Text,
+    Integer,
+    Float,
+    Boolean,
+    Null,
+}
+
+impl ValueKind {
+    pub fn is_editable(&self) -> bool {
+        !matches!(self, ValueKind::Section | ValueKind::List)
+    }
+}
+
+// One key of a section. Sections show how many keys they hold, lists are shown inline
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ConfigEntry {
+    pub key: String,
+    pub kind: ValueKind,
+    pub value: String,
+}
+
+// Files are relative to the server directory, key paths start at the top of the file
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub enum ConfigRequest {
+    Files,
+    Keys(String, Vec<String>),
+    // Answers with the keys of the section that holds the changed key
+    Set(String, Vec<String>, String),
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub enum ConfigResponse {
+    Files(Vec<String>),
+    Keys(Vec<ConfigEntry>),
+    Error(String),
+}
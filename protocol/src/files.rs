@@ -0,0 +1,28 @@
+use crate::tasks::RemoteFile;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct FileEntry {
+    pub name: String,
+    pub is_dir: bool,
+    pub size: u64,
+}
+
+// Paths are relative to the server directory, an empty path is the directory itself
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub enum FileRequest {
+    List(String),
+    Read(String),
+    WriteText(String, String),
+    // Replaces the file at the path, or adds the file to the path if it's a folder
+    Upload(String, RemoteFile),
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub enum FileResponse {
+    Listing(Vec<FileEntry>),
+    // base64, since JSON would send the bytes as an array of numbers
+    Contents(String),
+    Written(String),
+    Error(String),
+}
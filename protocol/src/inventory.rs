@@ -0,0 +1,58 @@
+use crate::tasks::RemoteFile;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum Loader {
+    Fabric,
+    Quilt,
+    Forge,
+    Bukkit,
+}
+
+// A jar found in mods/ or plugins/, described by the metadata file inside it
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct InstalledMod {
+    pub folder: String,
+    pub file_name: String,
+    pub enabled: bool,
+    // None when the jar has no metadata we understand
+    pub loader: Option<Loader>,
+    pub id: String,
+    pub name: String,
+    pub version: String,
+    pub dependencies: Vec<String>,
+    // The Minecraft version range exactly as the jar declares it
+    pub minecraft: Option<String>,
+    // Missing dependencies and version incompatibilities found by the agent
+    pub problems: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct Inventory {
+    pub minecraft_version: Option<String>,
+    pub mods: Vec<InstalledMod>,
+}
+
+impl Inventory {
+    pub fn in_folder<'a>(&'a self, folder: &'a str) -> impl Iterator<Item = &'a InstalledMod> {
+        self.mods
+            .iter()
+            .filter(move |installed| installed.folder == folder)
+    }
+
+    pub fn problem_count(&self) -> usize {
+        self.mods
+            .iter()
+            .map(|installed| installed.problems.len())
+            .sum()
+    }
+}
+
+// Changes to a single jar in mods/ or plugins/. The agent only touches files inside those folders
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub enum ModChange {
+    Enable { folder: String, file_name: String },
+    Disable { folder: String, file_name: String },
+    Delete { folder: String, file_name: String },
+    Upload { folder: String, file: RemoteFile },
+}
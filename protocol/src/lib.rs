@@ -1,8 +1,14 @@
 pub mod agentactions;
+pub mod config_files;
+pub mod files;
+pub mod inventory;
+pub mod player_events;
 pub mod properties;
+pub mod properties_document;
 pub mod query_options;
 pub mod server_commands;
 pub mod serveractions;
+pub mod tasks;
 
 pub fn add(left: u64, right: u64) -> u64 {
     left + right
@@ -0,0 +1,34 @@
+use serde::{Deserialize, Serialize};
+
+// Read from the server console by the agent, whether or not the chat stream is on
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub enum PlayerEvent {
+    Joined(String),
+    Left(String),
+    // The message already names the player, like "Steve was slain by Zombie"
+    Died {
+        player: String,
+        message: String,
+    },
+    Advancement {
+        player: String,
+        title: String,
+        kind: AdvancementKind,
+    },
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum AdvancementKind {
+    Advancement,
+    Goal,
+    Challenge,
+}
+
+impl PlayerEvent {
+    pub fn player(&self) -> &str {
+        match self {
+            PlayerEvent::Joined(player) | PlayerEvent::Left(player) => player,
+            PlayerEvent::Died { player, .. } | PlayerEvent::Advancement { player, .. } => player,
+        }
+    }
+}
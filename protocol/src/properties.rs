@@ -1,22 +1,919 @@
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
 
-#[derive(Debug, Serialize, Deserialize)]
-pub enum Property {
-    AllowFlight,
-    Difficulty,
-    Gamemode,
-    Hardcore,
-    Whitelist,
-    PVP,
-    GenerateStructures,
-    MOTD(String),
-    MaxPlayers(u32),
-    AllowNether,
-    MaxWorldSize(u32),
-    ViewDistance(u32),
-    SimulationDistance(u32),
-    SpawnProtection(u32),
-    SpawnNPC,
-    SpawnAnimals,
-    SpawnMonsters,
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Properties {
+    pub values: HashMap<String, String>,
+    // Differences between the file and what the running server started with
+    pub pending: Vec<PendingChange>,
+    #[serde(default)]
+    pub edition: Edition,
+}
+
+// Java and Bedrock servers both use server.properties, but with their own keys
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Edition {
+    #[default]
+    Java,
+    Bedrock,
+}
+
+impl Edition {
+    pub fn properties(&self) -> &'static [PropertySchema] {
+        match self {
+            Edition::Java => PROPERTIES,
+            Edition::Bedrock => BEDROCK_PROPERTIES,
+        }
+    }
+
+    pub fn schema(&self, key: &str) -> Option<&'static PropertySchema> {
+        self.properties()
+            .iter()
+            .find(|property| property.key == key)
+    }
+
+    pub fn category(&self, key: &str) -> Category {
+        self.schema(key)
+            .map_or(Category::Other, |property| property.category)
+    }
+
+    pub fn validate(&self, key: &str, value: &str) -> Result<String, String> {
+        match self.schema(key) {
+            Some(property) => property.validate(value),
+            None => validate_text(key, value.trim()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PendingChange {
+    pub key: String,
+    pub running: Option<String>,
+    pub file: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Category {
+    Gameplay,
+    World,
+    Players,
+    Network,
+    Admin,
+    // Keys the schema doesn't know, like ones added by newer versions or server forks
+    Other,
+}
+
+impl Category {
+    pub const ALL: [Category; 6] = [
+        Category::Gameplay,
+        Category::World,
+        Category::Players,
+        Category::Network,
+        Category::Admin,
+        Category::Other,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Category::Gameplay => "Gameplay",
+            Category::World => "World",
+            Category::Players => "Players",
+            Category::Network => "Network",
+            Category::Admin => "Admin",
+            Category::Other => "Other",
+        }
+    }
+}
+
+impl fmt::Display for Category {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.label().to_lowercase())
+    }
+}
+
+impl FromStr for Category {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Category::ALL
+            .into_iter()
+            .find(|category| category.to_string() == s)
+            .ok_or(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PropertyType {
+    Boolean,
+    Integer { min: i64, max: i64 },
+    Choice(&'static [&'static str]),
+    Text,
+    // Never shown in Discord, only replaced
+    Secret,
+}
+
+impl PropertyType {
+    // Booleans flip and choices cycle, everything else needs a new value typed in
+    pub fn next(&self, current: &str) -> Option<String> {
+        match self {
+            PropertyType::Boolean => Some((current != "true").to_string()),
+            PropertyType::Choice(choices) => {
+                let index = choices.iter().position(|choice| *choice == current);
+                let next = index.map_or(0, |index| (index + 1) % choices.len());
+                Some(choices[next].to_string())
+            }
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PropertySchema {
+    pub key: &'static str,
+    pub kind: PropertyType,
+    pub category: Category,
+    pub description: &'static str,
+}
+
+impl PropertySchema {
+    // Returns the value as it should be written to server.properties
+    pub fn validate(&self, value: &str) -> Result<String, String> {
+        let value = value.trim();
+        match self.kind {
+            PropertyType::Boolean => match value.to_lowercase().as_str() {
+                "true" | "on" => Ok("true".to_string()),
+                "false" | "off" => Ok("false".to_string()),
+                _ => Err(format!("{} must be true or false", self.key)),
+            },
+            PropertyType::Integer { min, max } => match value.parse::<i64>() {
+                Ok(number) if (min..=max).contains(&number) => Ok(number.to_string()),
+                _ => Err(format!(
+                    "{} must be a whole number between {} and {}",
+                    self.key, min, max
+                )),
+            },
+            PropertyType::Choice(choices) => choices
+                .iter()
+                .find(|choice| choice.eq_ignore_ascii_case(value))
+                .map(|choice| choice.to_string())
+                .ok_or_else(|| format!("{} must be one of {}", self.key, choices.join(", "))),
+            PropertyType::Text | PropertyType::Secret => validate_text(self.key, value),
+        }
+    }
+}
+
+// Java keys, which is also where the only secrets live
+pub fn schema(key: &str) -> Option<&'static PropertySchema> {
+    Edition::Java.schema(key)
+}
+
+// Secret values are never shown or kept anywhere outside server.properties
+pub fn is_secret(key: &str) -> bool {
+    schema(key).is_some_and(|property| property.kind == PropertyType::Secret)
+}
+
+pub fn category(key: &str) -> Category {
+    Edition::Java.category(key)
+}
+
+// Unknown keys are kept as plain strings
+pub fn validate(key: &str, value: &str) -> Result<String, String> {
+    Edition::Java.validate(key, value)
+}
+
+// "view-distance" becomes "View distance"
+pub fn display_name(key: &str) -> String {
+    let name = key.replace(['-', '.', '_'], " ");
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => name,
+    }
+}
+
+fn validate_text(key: &str, value: &str) -> Result<String, String> {
+    if value.contains(['\n', '\r']) {
+        return Err(format!("{} must fit on one line", key));
+    }
+    Ok(value.to_string())
+}
+
+const MAX_INT: i64 = i32::MAX as i64;
+
+pub const PROPERTIES: &[PropertySchema] = &[
+    // Gameplay
+    PropertySchema {
+        key: "difficulty",
+        kind: PropertyType::Choice(&["peaceful", "easy", "normal", "hard"]),
+        category: Category::Gameplay,
+        description: "How dangerous mobs and hunger are",
+    },
+    PropertySchema {
+        key: "gamemode",
+        kind: PropertyType::Choice(&["survival", "creative", "adventure", "spectator"]),
+        category: Category::Gameplay,
+        description: "Game mode for new players",
+    },
+    PropertySchema {
+        key: "force-gamemode",
+        kind: PropertyType::Boolean,
+        category: Category::Gameplay,
+        description: "Puts players back in the default game mode when they join",
+    },
+    PropertySchema {
+        key: "hardcore",
+        kind: PropertyType::Boolean,
+        category: Category::Gameplay,
+        description: "Players become spectators when they die",
+    },
+    PropertySchema {
+        key: "pvp",
+        kind: PropertyType::Boolean,
+        category: Category::Gameplay,
+        description: "Lets players damage each other",
+    },
+    PropertySchema {
+        key: "allow-flight",
+        kind: PropertyType::Boolean,
+        category: Category::Gameplay,
+        description: "Stops kicking players for flying, needed by some mods",
+    },
+    PropertySchema {
+        key: "spawn-protection",
+        kind: PropertyType::Integer {
+            min: 0,
+            max: MAX_INT,
+        },
+        category: Category::Gameplay,
+        description: "Radius around spawn only operators can build in",
+    },
+    PropertySchema {
+        key: "enable-command-block",
+        kind: PropertyType::Boolean,
+        category: Category::Gameplay,
+        description: "Lets command blocks run",
+    },
+    // World
+    PropertySchema {
+        key: "level-name",
+        kind: PropertyType::Text,
+        category: Category::World,
+        description: "Folder the world is loaded from",
+    },
+    PropertySchema {
+        key: "level-seed",
+        kind: PropertyType::Text,
+        category: Category::World,
+        description: "Seed for new worlds, random when empty",
+    },
+    PropertySchema {
+        key: "level-type",
+        kind: PropertyType::Text,
+        category: Category::World,
+        description: "World preset for new worlds, such as minecraft:flat",
+    },
+    PropertySchema {
+        key: "generator-settings",
+        kind: PropertyType::Text,
+        category: Category::World,
+        description: "JSON settings for the world preset",
+    },
+    PropertySchema {
+        key: "generate-structures",
+        kind: PropertyType::Boolean,
+        category: Category::World,
+        description: "Generates villages and other structures",
+    },
+    PropertySchema {
+        key: "allow-nether",
+        kind: PropertyType::Boolean,
+        category: Category::World,
+        description: "Lets players travel to the Nether",
+    },
+    PropertySchema {
+        key: "max-world-size",
+        kind: PropertyType::Integer {
+            min: 1,
+            max: 29999984,
+        },
+        category: Category::World,
+        description: "Radius of the world border in blocks",
+    },
+    PropertySchema {
+        key: "spawn-animals",
+        kind: PropertyType::Boolean,
+        category: Category::World,
+        description: "Spawns animals",
+    },
+    PropertySchema {
+        key: "spawn-monsters",
+        kind: PropertyType::Boolean,
+        category: Category::World,
+        description: "Spawns hostile mobs",
+    },
+    PropertySchema {
+        key: "spawn-npcs",
+        kind: PropertyType::Boolean,
+        category: Category::World,
+        description: "Spawns villagers",
+    },
+    PropertySchema {
+        key: "view-distance",
+        kind: PropertyType::Integer { min: 3, max: 32 },
+        category: Category::World,
+        description: "Chunks sent to players in each direction",
+    },
+    PropertySchema {
+        key: "simulation-distance",
+        kind: PropertyType::Integer { min: 3, max: 32 },
+        category: Category::World,
+        description: "Chunks around players that keep ticking",
+    },
+    PropertySchema {
+        key: "entity-broadcast-range-percentage",
+        kind: PropertyType::Integer { min: 10, max: 1000 },
+        category: Category::World,
+        description: "How far away entities are sent to players, in percent",
+    },
+    PropertySchema {
+        key: "sync-chunk-writes",
+        kind: PropertyType::Boolean,
+        category: Category::World,
+        description: "Writes chunks synchronously, safer but slower",
+    },
+    PropertySchema {
+        key: "region-file-compression",
+        kind: PropertyType::Choice(&["deflate", "lz4", "none"]),
+        category: Category::World,
+        description: "Compression used for region files",
+    },
+    PropertySchema {
+        key: "max-chained-neighbor-updates",
+        kind: PropertyType::Integer {
+            min: -1,
+            max: MAX_INT,
+        },
+        category: Category::World,
+        description: "Limit of chained block updates, -1 for no limit",
+    },
+    PropertySchema {
+        key: "initial-enabled-packs",
+        kind: PropertyType::Text,
+        category: Category::World,
+        description: "Data packs enabled when the world is created",
+    },
+    PropertySchema {
+        key: "initial-disabled-packs",
+        kind: PropertyType::Text,
+        category: Category::World,
+        description: "Data packs disabled when the world is created",
+    },
+    // Players
+    PropertySchema {
+        key: "motd",
+        kind: PropertyType::Text,
+        category: Category::Players,
+        description: "Message shown in the server list",
+    },
+    PropertySchema {
+        key: "max-players",
+        kind: PropertyType::Integer {
+            min: 0,
+            max: MAX_INT,
+        },
+        category: Category::Players,
+        description: "Players allowed online at once",
+    },
+    PropertySchema {
+        key: "white-list",
+        kind: PropertyType::Boolean,
+        category: Category::Players,
+        description: "Only lets whitelisted players join",
+    },
+    PropertySchema {
+        key: "enforce-whitelist",
+        kind: PropertyType::Boolean,
+        category: Category::Players,
+        description: "Kicks players that are removed from the whitelist",
+    },
+    PropertySchema {
+        key: "online-mode",
+        kind: PropertyType::Boolean,
+        category: Category::Players,
+        description: "Checks accounts with Mojang, turn off only behind a proxy",
+    },
+    PropertySchema {
+        key: "enforce-secure-profile",
+        kind: PropertyType::Boolean,
+        category: Category::Players,
+        description: "Requires signed chat from players",
+    },
+    PropertySchema {
+        key: "player-idle-timeout",
+        kind: PropertyType::Integer {
+            min: 0,
+            max: MAX_INT,
+        },
+        category: Category::Players,
+        description: "Minutes before idle players are kicked, 0 never kicks",
+    },
+    PropertySchema {
+        key: "hide-online-players",
+        kind: PropertyType::Boolean,
+        category: Category::Players,
+        description: "Hides the player list in the server list",
+    },
+    PropertySchema {
+        key: "log-ips",
+        kind: PropertyType::Boolean,
+        category: Category::Players,
+        description: "Writes player IP addresses to the log",
+    },
+    PropertySchema {
+        key: "prevent-proxy-connections",
+        kind: PropertyType::Boolean,
+        category: Category::Players,
+        description: "Kicks players connecting through a VPN or proxy",
+    },
+    PropertySchema {
+        key: "accepts-transfers",
+        kind: PropertyType::Boolean,
+        category: Category::Players,
+        description: "Accepts players transferred from other servers",
+    },
+    PropertySchema {
+        key: "resource-pack",
+        kind: PropertyType::Text,
+        category: Category::Players,
+        description: "URL of the server resource pack",
+    },
+    PropertySchema {
+        key: "resource-pack-id",
+        kind: PropertyType::Text,
+        category: Category::Players,
+        description: "UUID of the server resource pack",
+    },
+    PropertySchema {
+        key: "resource-pack-sha1",
+        kind: PropertyType::Text,
+        category: Category::Players,
+        description: "SHA-1 hash of the server resource pack",
+    },
+    PropertySchema {
+        key: "resource-pack-prompt",
+        kind: PropertyType::Text,
+        category: Category::Players,
+        description: "Message shown when asking to use the resource pack",
+    },
+    PropertySchema {
+        key: "require-resource-pack",
+        kind: PropertyType::Boolean,
+        category: Category::Players,
+        description: "Kicks players that decline the resource pack",
+    },
+    // Network
+    PropertySchema {
+        key: "server-ip",
+        kind: PropertyType::Text,
+        category: Category::Network,
+        description: "Address to listen on, empty listens on all",
+    },
+    PropertySchema {
+        key: "server-port",
+        kind: PropertyType::Integer { min: 1, max: 65535 },
+        category: Category::Network,
+        description: "Port players connect to",
+    },
+    PropertySchema {
+        key: "network-compression-threshold",
+        kind: PropertyType::Integer {
+            min: -1,
+            max: MAX_INT,
+        },
+        category: Category::Network,
+        description: "Smallest packet size that gets compressed, -1 turns compression off",
+    },
+    PropertySchema {
+        key: "rate-limit",
+        kind: PropertyType::Integer {
+            min: 0,
+            max: MAX_INT,
+        },
+        category: Category::Network,
+        description: "Packets per second before a player is kicked, 0 for no limit",
+    },
+    PropertySchema {
+        key: "use-native-transport",
+        kind: PropertyType::Boolean,
+        category: Category::Network,
+        description: "Uses faster Linux networking when available",
+    },
+    PropertySchema {
+        key: "enable-status",
+        kind: PropertyType::Boolean,
+        category: Category::Network,
+        description: "Shows the server as online in the server list",
+    },
+    PropertySchema {
+        key: "enable-query",
+        kind: PropertyType::Boolean,
+        category: Category::Network,
+        description: "Answers GameSpy4 query requests",
+    },
+    PropertySchema {
+        key: "query.port",
+        kind: PropertyType::Integer { min: 1, max: 65535 },
+        category: Category::Network,
+        description: "Port for query requests",
+    },
+    PropertySchema {
+        key: "enable-rcon",
+        kind: PropertyType::Boolean,
+        category: Category::Network,
+        description: "Allows remote console connections",
+    },
+    PropertySchema {
+        key: "rcon.port",
+        kind: PropertyType::Integer { min: 1, max: 65535 },
+        category: Category::Network,
+        description: "Port for remote console connections",
+    },
+    PropertySchema {
+        key: "rcon.password",
+        kind: PropertyType::Secret,
+        category: Category::Network,
+        description: "Password for remote console connections",
+    },
+    PropertySchema {
+        key: "broadcast-rcon-to-ops",
+        kind: PropertyType::Boolean,
+        category: Category::Network,
+        description: "Shows remote console commands to online operators",
+    },
+    // Admin
+    PropertySchema {
+        key: "op-permission-level",
+        kind: PropertyType::Integer { min: 0, max: 4 },
+        category: Category::Admin,
+        description: "Permission level given by /op",
+    },
+    PropertySchema {
+        key: "function-permission-level",
+        kind: PropertyType::Integer { min: 1, max: 4 },
+        category: Category::Admin,
+        description: "Permission level functions run with",
+    },
+    PropertySchema {
+        key: "broadcast-console-to-ops",
+        kind: PropertyType::Boolean,
+        category: Category::Admin,
+        description: "Shows console commands to online operators",
+    },
+    PropertySchema {
+        key: "max-tick-time",
+        kind: PropertyType::Integer {
+            min: -1,
+            max: i64::MAX,
+        },
+        category: Category::Admin,
+        description: "Milliseconds a tick may take before the watchdog stops the server, -1 turns it off",
+    },
+    PropertySchema {
+        key: "pause-when-empty-seconds",
+        kind: PropertyType::Integer {
+            min: 0,
+            max: MAX_INT,
+        },
+        category: Category::Admin,
+        description: "Seconds without players before the server pauses, 0 never pauses",
+    },
+    PropertySchema {
+        key: "enable-jmx-monitoring",
+        kind: PropertyType::Boolean,
+        category: Category::Admin,
+        description: "Exposes tick times over JMX",
+    },
+    PropertySchema {
+        key: "text-filtering-config",
+        kind: PropertyType::Text,
+        category: Category::Admin,
+        description: "Chat filtering service settings",
+    },
+    PropertySchema {
+        key: "bug-report-link",
+        kind: PropertyType::Text,
+        category: Category::Admin,
+        description: "Link shown to players for reporting bugs",
+    },
+];
+
+// Bedrock Dedicated Server keys. Some share a name with Java but allow different values
+pub const BEDROCK_PROPERTIES: &[PropertySchema] = &[
+    // Gameplay
+    PropertySchema {
+        key: "difficulty",
+        kind: PropertyType::Choice(&["peaceful", "easy", "normal", "hard"]),
+        category: Category::Gameplay,
+        description: "How dangerous mobs and hunger are",
+    },
+    PropertySchema {
+        key: "gamemode",
+        kind: PropertyType::Choice(&["survival", "creative", "adventure"]),
+        category: Category::Gameplay,
+        description: "Game mode for new players",
+    },
+    PropertySchema {
+        key: "force-gamemode",
+        kind: PropertyType::Boolean,
+        category: Category::Gameplay,
+        description: "Puts players back in the default game mode when they join",
+    },
+    PropertySchema {
+        key: "allow-cheats",
+        kind: PropertyType::Boolean,
+        category: Category::Gameplay,
+        description: "Lets players use commands, also turns off achievements",
+    },
+    // World
+    PropertySchema {
+        key: "level-name",
+        kind: PropertyType::Text,
+        category: Category::World,
+        description: "Folder in worlds/ the world is loaded from",
+    },
+    PropertySchema {
+        key: "level-seed",
+        kind: PropertyType::Text,
+        category: Category::World,
+        description: "Seed for new worlds, random when empty",
+    },
+    PropertySchema {
+        key: "view-distance",
+        kind: PropertyType::Integer {
+            min: 5,
+            max: MAX_INT,
+        },
+        category: Category::World,
+        description: "Chunks sent to players in each direction",
+    },
+    PropertySchema {
+        key: "tick-distance",
+        kind: PropertyType::Integer { min: 4, max: 12 },
+        category: Category::World,
+        description: "Chunks around players that keep ticking",
+    },
+    PropertySchema {
+        key: "client-side-chunk-generation-enabled",
+        kind: PropertyType::Boolean,
+        category: Category::World,
+        description: "Lets clients generate distant terrain themselves",
+    },
+    // Players
+    PropertySchema {
+        key: "server-name",
+        kind: PropertyType::Text,
+        category: Category::Players,
+        description: "Name shown in the server list",
+    },
+    PropertySchema {
+        key: "max-players",
+        kind: PropertyType::Integer {
+            min: 1,
+            max: MAX_INT,
+        },
+        category: Category::Players,
+        description: "Players allowed online at once",
+    },
+    PropertySchema {
+        key: "allow-list",
+        kind: PropertyType::Boolean,
+        category: Category::Players,
+        description: "Only lets players in allowlist.json join",
+    },
+    PropertySchema {
+        key: "online-mode",
+        kind: PropertyType::Boolean,
+        category: Category::Players,
+        description: "Requires players to sign in with a Microsoft account",
+    },
+    PropertySchema {
+        key: "default-player-permission-level",
+        kind: PropertyType::Choice(&["visitor", "member", "operator"]),
+        category: Category::Players,
+        description: "Permission level of players joining for the first time",
+    },
+    PropertySchema {
+        key: "player-idle-timeout",
+        kind: PropertyType::Integer {
+            min: 0,
+            max: MAX_INT,
+        },
+        category: Category::Players,
+        description: "Minutes before idle players are kicked, 0 never kicks",
+    },
+    PropertySchema {
+        key: "texturepack-required",
+        kind: PropertyType::Boolean,
+        category: Category::Players,
+        description: "Makes players use the world's resource packs",
+    },
+    PropertySchema {
+        key: "chat-restriction",
+        kind: PropertyType::Choice(&["None", "Dropped", "Disabled"]),
+        category: Category::Players,
+        description: "Limits or turns off chat between players",
+    },
+    PropertySchema {
+        key: "disable-player-interaction",
+        kind: PropertyType::Boolean,
+        category: Category::Players,
+        description: "Stops players from interacting with each other",
+    },
+    PropertySchema {
+        key: "disable-custom-skins",
+        kind: PropertyType::Boolean,
+        category: Category::Players,
+        description: "Only allows skins from the Marketplace and the default ones",
+    },
+    // Network
+    PropertySchema {
+        key: "server-port",
+        kind: PropertyType::Integer { min: 1, max: 65535 },
+        category: Category::Network,
+        description: "IPv4 port players connect to",
+    },
+    PropertySchema {
+        key: "server-portv6",
+        kind: PropertyType::Integer { min: 1, max: 65535 },
+        category: Category::Network,
+        description: "IPv6 port players connect to",
+    },
+    PropertySchema {
+        key: "enable-lan-visibility",
+        kind: PropertyType::Boolean,
+        category: Category::Network,
+        description: "Shows the server to players on the same network",
+    },
+    PropertySchema {
+        key: "compression-threshold",
+        kind: PropertyType::Integer { min: 0, max: 65535 },
+        category: Category::Network,
+        description: "Smallest packet size that gets compressed",
+    },
+    PropertySchema {
+        key: "compression-algorithm",
+        kind: PropertyType::Choice(&["zlib", "snappy"]),
+        category: Category::Network,
+        description: "Compression used for network packets",
+    },
+    PropertySchema {
+        key: "max-threads",
+        kind: PropertyType::Integer {
+            min: 0,
+            max: MAX_INT,
+        },
+        category: Category::Network,
+        description: "Threads the server may use, 0 uses as many as it can",
+    },
+    // Admin
+    PropertySchema {
+        key: "content-log-file-enabled",
+        kind: PropertyType::Boolean,
+        category: Category::Admin,
+        description: "Writes content errors to a log file",
+    },
+    PropertySchema {
+        key: "server-authoritative-movement",
+        kind: PropertyType::Choice(&["client-auth", "server-auth", "server-auth-with-rewind"]),
+        category: Category::Admin,
+        description: "Whether the server checks player movement",
+    },
+    PropertySchema {
+        key: "correct-player-movement",
+        kind: PropertyType::Boolean,
+        category: Category::Admin,
+        description: "Moves players back when their movement doesn't add up",
+    },
+    PropertySchema {
+        key: "server-authoritative-block-breaking",
+        kind: PropertyType::Boolean,
+        category: Category::Admin,
+        description: "Checks block breaking on the server",
+    },
+    PropertySchema {
+        key: "emit-server-telemetry",
+        kind: PropertyType::Boolean,
+        category: Category::Admin,
+        description: "Sends usage data to Mojang",
+    },
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validates_values() {
+        let cases = [
+            (Edition::Java, "pvp", " On ", Ok("true")),
+            (Edition::Java, "pvp", "FALSE", Ok("false")),
+            (
+                Edition::Java,
+                "pvp",
+                "yes",
+                Err("pvp must be true or false"),
+            ),
+            (Edition::Java, "view-distance", " 12 ", Ok("12")),
+            (Edition::Java, "view-distance", "3", Ok("3")),
+            (
+                Edition::Java,
+                "view-distance",
+                "33",
+                Err("view-distance must be a whole number between 3 and 32"),
+            ),
+            (
+                Edition::Java,
+                "view-distance",
+                "ten",
+                Err("view-distance must be a whole number between 3 and 32"),
+            ),
+            (Edition::Java, "difficulty", "Hard", Ok("hard")),
+            (
+                Edition::Java,
+                "difficulty",
+                "brutal",
+                Err("difficulty must be one of peaceful, easy, normal, hard"),
+            ),
+            (Edition::Java, "gamemode", "spectator", Ok("spectator")),
+            (
+                Edition::Bedrock,
+                "gamemode",
+                "spectator",
+                Err("gamemode must be one of survival, creative, adventure"),
+            ),
+            (
+                Edition::Bedrock,
+                "tick-distance",
+                "13",
+                Err("tick-distance must be a whole number between 4 and 12"),
+            ),
+            (Edition::Java, "motd", " Hello ", Ok("Hello")),
+            (
+                Edition::Java,
+                "motd",
+                "two\nlines",
+                Err("motd must fit on one line"),
+            ),
+            (Edition::Java, "rcon.password", "hunter2", Ok("hunter2")),
+            // Unknown keys are plain text
+            (Edition::Java, "some-fork-key", " anything ", Ok("anything")),
+            (
+                Edition::Bedrock,
+                "some-fork-key",
+                "a\rb",
+                Err("some-fork-key must fit on one line"),
+            ),
+        ];
+        for (edition, key, value, expected) in cases {
+            let expected = expected.map(str::to_string).map_err(str::to_string);
+            assert_eq!(
+                edition.validate(key, value),
+                expected,
+                "{edition:?} {key}={value:?}"
+            );
+        }
+        assert_eq!(validate("pvp", "on"), Ok("true".to_string()));
+    }
+
+    #[test]
+    fn next_values() {
+        let difficulty = PropertyType::Choice(&["peaceful", "easy", "normal", "hard"]);
+        let cases = [
+            (PropertyType::Boolean, "true", Some("false")),
+            (PropertyType::Boolean, "false", Some("true")),
+            (PropertyType::Boolean, "", Some("true")),
+            (difficulty, "easy", Some("normal")),
+            (difficulty, "hard", Some("peaceful")),
+            (difficulty, "unknown", Some("peaceful")),
+            (PropertyType::Integer { min: 0, max: 10 }, "5", None),
+            (PropertyType::Text, "motd", None),
+            (PropertyType::Secret, "hunter2", None),
+        ];
+        for (kind, current, expected) in cases {
+            assert_eq!(
+                kind.next(current).as_deref(),
+                expected,
+                "{kind:?} after {current:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn secrets() {
+        assert!(is_secret("rcon.password"));
+        assert!(!is_secret("motd"));
+        assert!(!is_secret("some-fork-key"));
+    }
 }
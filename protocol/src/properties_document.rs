@@ -0,0 +1,295 @@
+use std::fmt;
+
+// A .properties file kept line for line, so edits leave comments, blank lines and key order alone
+#[derive(Debug, Clone, Default)]
+pub struct PropertiesDocument {
+    lines: Vec<Line>,
+}
+
+#[derive(Debug, Clone)]
+enum Line {
+    // Comments, blank lines and anything else without a key, written back untouched
+    Other(String),
+    Entry {
+        key: String,
+        value: String,
+        // Everything up to and including the separator, as it was in the file
+        prefix: String,
+        // The original text, which can span several lines with continuations
+        raw: String,
+    },
+}
+
+impl PropertiesDocument {
+    pub fn parse(text: &str) -> Self {
+        let mut lines = Vec::new();
+        let mut physical = text.lines();
+        while let Some(first) = physical.next() {
+            let trimmed = first.trim_start();
+            if trimmed.is_empty() || trimmed.starts_with('#') || trimmed.starts_with('!') {
+                lines.push(Line::Other(first.to_string()));
+                continue;
+            }
+
+            let mut raw = first.to_string();
+            let mut logical = trimmed.to_string();
+            while continues(&logical) {
+                logical.pop();
+                let Some(next) = physical.next() else {
+                    break;
+                };
+                raw.push('\n');
+                raw.push_str(next);
+                logical.push_str(next.trim_start());
+            }
+
+            let (key, separator_end) = split_key(&logical);
+            let indent = &first[..first.len() - trimmed.len()];
+            // The prefix is only reused for single line entries, longer ones are rewritten
+            let prefix = if raw.contains('\n') {
+                format!("{}{}=", indent, escape(&unescape(key), true))
+            } else {
+                format!("{}{}", indent, &logical[..separator_end])
+            };
+            lines.push(Line::Entry {
+                key: unescape(key),
+                value: unescape(&logical[separator_end..]),
+                prefix,
+                raw,
+            });
+        }
+        PropertiesDocument { lines }
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.entries()
+            .find(|(entry, _)| *entry == key)
+            .map(|(_, value)| value)
+    }
+
+    // Changes the value in place, or adds the key at the end if the file doesn't have it
+    pub fn set(&mut self, key: &str, value: &str) {
+        for line in self.lines.iter_mut() {
+            if let Line::Entry {
+                key: entry,
+                value: current,
+                prefix,
+                raw,
+            } = line
+                && entry == key
+            {
+                *current = value.to_string();
+                *raw = format!("{}{}", prefix, escape(value, false));
+                return;
+            }
+        }
+        let prefix = format!("{}=", escape(key, true));
+        self.lines.push(Line::Entry {
+            key: key.to_string(),
+            value: value.to_string(),
+            raw: format!("{}{}", prefix, escape(value, false)),
+            prefix,
+        });
+    }
+
+    pub fn entries(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.lines.iter().filter_map(|line| match line {
+            Line::Entry { key, value, .. } => Some((key.as_str(), value.as_str())),
+            Line::Other(_) => None,
+        })
+    }
+}
+
+impl fmt::Display for PropertiesDocument {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for line in self.lines.iter() {
+            match line {
+                Line::Other(text) => writeln!(f, "{}", text)?,
+                Line::Entry { raw, .. } => writeln!(f, "{}", raw)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+// A line continues when it ends in an odd number of backslashes
+fn continues(line: &str) -> bool {
+    line.chars().rev().take_while(|c| *c == '\\').count() % 2 == 1
+}
+
+// Returns the raw key and where the value starts. Keys end at an unescaped =, : or whitespace,
+// and whitespace around the separator belongs to it
+fn split_key(line: &str) -> (&str, usize) {
+    let mut escaped = false;
+    let mut key_end = line.len();
+    for (i, c) in line.char_indices() {
+        if escaped {
+            escaped = false;
+        } else if c == '\\' {
+            escaped = true;
+        } else if c == '=' || c == ':' || c.is_whitespace() {
+            key_end = i;
+            break;
+        }
+    }
+
+    let rest = &line[key_end..];
+    let mut value_start = key_end + (rest.len() - rest.trim_start().len());
+    if line[value_start..].starts_with(['=', ':']) {
+        value_start += 1;
+        let rest = &line[value_start..];
+        value_start += rest.len() - rest.trim_start().len();
+    }
+    (&line[..key_end], value_start)
+}
+
+fn unescape(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('t') => result.push('\t'),
+            Some('n') => result.push('\n'),
+            Some('r') => result.push('\r'),
+            Some('f') => result.push('\u{c}'),
+            Some('u') => {
+                let hex: String = chars.by_ref().take(4).collect();
+                match u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32) {
+                    Some(c) => result.push(c),
+                    None => result.push_str(&hex),
+                }
+            }
+            Some(other) => result.push(other),
+            None => {}
+        }
+    }
+    result
+}
+
+// Escapes the way Java writes properties. Spaces only need escaping in keys and at the start of
+// a value
+fn escape(text: &str, is_key: bool) -> String {
+    let mut result = String::with_capacity(text.len());
+    for (i, c) in text.chars().enumerate() {
+        match c {
+            ' ' if is_key || i == 0 => result.push_str("\\ "),
+            '\\' => result.push_str("\\\\"),
+            '\t' => result.push_str("\\t"),
+            '\n' => result.push_str("\\n"),
+            '\r' => result.push_str("\\r"),
+            '\u{c}' => result.push_str("\\f"),
+            '=' | ':' | '#' | '!' => {
+                result.push('\\');
+                result.push(c);
+            }
+            c if c.is_control() => result.push_str(&format!("\\u{:04X}", c as u32)),
+            c => result.push(c),
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_entries() {
+        let cases: &[(&str, &[(&str, &str)])] = &[
+            ("motd=A Minecraft Server", &[("motd", "A Minecraft Server")]),
+            ("motd = spaced out", &[("motd", "spaced out")]),
+            ("motd:colon", &[("motd", "colon")]),
+            ("motd whitespace", &[("motd", "whitespace")]),
+            ("  motd=indented", &[("motd", "indented")]),
+            ("motd=", &[("motd", "")]),
+            ("motd", &[("motd", "")]),
+            (
+                "# comment\n! also a comment\n\npvp=true",
+                &[("pvp", "true")],
+            ),
+            (r"my\ key=value", &[("my key", "value")]),
+            (r"a\=b=c", &[("a=b", "c")]),
+            (r"motd=\u00A7aGreen", &[("motd", "\u{a7}aGreen")]),
+            (r"motd=tab\there", &[("motd", "tab\there")]),
+            ("motd=one \\\n    two", &[("motd", "one two")]),
+            (
+                "motd=ends in a backslash\\\\",
+                &[("motd", "ends in a backslash\\")],
+            ),
+            ("a=1\nb=2", &[("a", "1"), ("b", "2")]),
+        ];
+        for (text, expected) in cases {
+            let document = PropertiesDocument::parse(text);
+            let entries: Vec<_> = document.entries().collect();
+            assert_eq!(entries, *expected, "{text:?}");
+        }
+    }
+
+    #[test]
+    fn writes_back_unchanged() {
+        let text = "#Minecraft server properties\n\
+            #Mon Jan 01 00:00:00 UTC 2024\n\
+            \n\
+            motd = A Minecraft Server\n\
+            ! bang comment\n\
+            level-name:world\n\
+            long=one \\\n    two\n\
+            key\\ with\\ spaces=x\n";
+        assert_eq!(PropertiesDocument::parse(text).to_string(), text);
+    }
+
+    #[test]
+    fn sets_values() {
+        let cases = [
+            // The separator and spacing around it are kept
+            ("motd = old\n", "motd", "new", "motd = new\n"),
+            ("motd:old\n", "motd", "new", "motd:new\n"),
+            // Continued lines are rewritten onto one
+            ("motd=one \\\n  two\n", "motd", "three", "motd=three\n"),
+            (
+                "# top\npvp=true\n",
+                "motd",
+                "hi",
+                "# top\npvp=true\nmotd=hi\n",
+            ),
+            ("motd=x\n", "motd", " leading", "motd=\\ leading\n"),
+            ("motd=x\n", "motd", "a=b:c#d!e", "motd=a\\=b\\:c\\#d\\!e\n"),
+            ("motd=x\n", "motd", "back\\slash", "motd=back\\\\slash\n"),
+            ("motd=x\n", "motd", "two\nlines", "motd=two\\nlines\n"),
+            ("motd=x\n", "motd", "bell\u{7}", "motd=bell\\u0007\n"),
+            ("", "my key", "v", "my\\ key=v\n"),
+        ];
+        for (text, key, value, expected) in cases {
+            let mut document = PropertiesDocument::parse(text);
+            document.set(key, value);
+            assert_eq!(
+                document.to_string(),
+                expected,
+                "{key}={value:?} in {text:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn set_values_read_back() {
+        let values = [
+            "plain",
+            " leading",
+            "a=b:c",
+            "back\\slash",
+            "two\nlines",
+            "§aGreen",
+            "",
+        ];
+        for value in values {
+            let mut document = PropertiesDocument::parse("# comment\nmotd=old\n");
+            document.set("motd", value);
+            let reparsed = PropertiesDocument::parse(&document.to_string());
+            assert_eq!(reparsed.get("motd"), Some(value), "{value:?}");
+        }
+    }
+}
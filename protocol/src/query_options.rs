@@ -12,6 +12,8 @@ pub struct QueryOptions {
     software: bool,
     plugins: bool,
     mods: bool,
+    #[serde(default)]
+    uptime: bool,
 }
 
 impl QueryOptions {
@@ -26,6 +28,22 @@ impl QueryOptions {
             software: options.contains("software"),
             plugins: options.contains("plugins"),
             mods: options.contains("mods"),
+            uptime: options.contains("uptime"),
+        }
+    }
+
+    pub fn all() -> Self {
+        QueryOptions {
+            version: true,
+            player_count: true,
+            player_list: true,
+            description: true,
+            map: true,
+            gamemode: true,
+            software: true,
+            plugins: true,
+            mods: true,
+            uptime: true,
         }
     }
 
@@ -64,6 +82,10 @@ impl QueryOptions {
     pub fn mods(&mut self) -> bool {
         self.mods
     }
+
+    pub fn uptime(&mut self) -> bool {
+        self.uptime
+    }
 }
 
 #[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq)]
@@ -77,6 +99,11 @@ pub struct QueryStatus {
     software: Option<String>,
     plugins: Option<Vec<String>>,
     mods: Option<Vec<String>>,
+    // Unix seconds, so Discord can show them as relative times that keep ticking
+    #[serde(default)]
+    started_at: Option<i64>,
+    #[serde(default)]
+    online_since: Option<i64>,
 }
 
 impl QueryStatus {
@@ -116,6 +143,11 @@ impl QueryStatus {
         self.mods = mods;
     }
 
+    pub fn set_uptime(&mut self, started_at: Option<i64>, online_since: Option<i64>) {
+        self.started_at = started_at;
+        self.online_since = online_since;
+    }
+
     pub fn version(&self) -> Option<&String> {
         self.version.as_ref()
     }
@@ -151,6 +183,23 @@ impl QueryStatus {
     pub fn mods(&self) -> Option<&Vec<String>> {
         self.mods.as_ref()
     }
+
+    pub fn started_at(&self) -> Option<i64> {
+        self.started_at
+    }
+
+    pub fn online_since(&self) -> Option<i64> {
+        self.online_since
+    }
+}
+
+// One point of the player count and latency history
+#[derive(Debug, Default, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct QuerySample {
+    pub online: bool,
+    pub players: Option<u32>,
+    pub max_players: Option<u32>,
+    pub latency_ms: Option<u32>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
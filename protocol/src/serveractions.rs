@@ -1,14 +1,24 @@
-use crate::query_options::ServerStatus;
+use crate::config_files::ConfigResponse;
+use crate::files::FileResponse;
+use crate::inventory::Inventory;
+use crate::player_events::PlayerEvent;
+use crate::properties::Properties;
+use crate::query_options::{QuerySample, ServerStatus};
+use crate::tasks::Progress;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
 use uuid::Uuid;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub enum ServerActions {
     ConnectAgent(Uuid),
-    PropsResponse(Uuid, HashMap<String, String>),
+    PropsResponse(Uuid, Properties),
+    // Pushed when server.properties changes outside of a request
+    PropsChanged(Properties),
+    PropsError(Uuid, String),
     StartResponse(Uuid),
     StopResponse(Uuid),
+    // For requests without an error response of their own
+    RequestError(Uuid, String),
     StartChatResponse(Uuid),
     StopChatResponse(Uuid),
     QueryResponse {
@@ -18,22 +28,39 @@ pub enum ServerActions {
         status: ServerStatus,
     },
     UpdateQuery {
+        monitor: i64,
         status: ServerStatus,
     },
     UpdateQueryHeader {
+        monitor: i64,
         description: String,
         image: Option<Vec<u8>>,
     },
+    QuerySample(QuerySample),
+    // The server process ended. Expected when someone stopped it, otherwise it crashed
+    ServerExited {
+        expected: bool,
+    },
     ChatMessage(String),
+    PlayerEvent(PlayerEvent),
     SendCommandResponse(Uuid),
+    Progress(Uuid, Progress),
+    ModsResponse(Uuid, Inventory),
+    FilesResponse(Uuid, FileResponse),
+    ConfigFileResponse(Uuid, ConfigResponse),
 }
 
 pub enum RequestResponses {
-    PropsResponse(HashMap<String, String>),
+    PropsResponse(Properties),
+    PropsError(String),
     QueryResponse(String, Option<Vec<u8>>, ServerStatus),
     StartChatResponse,
     StopChatResponses,
     StartServerResponse,
     StopServerResponse,
     CommandResponse,
+    ModsResponse(Inventory),
+    FilesResponse(FileResponse),
+    ConfigFileResponse(ConfigResponse),
+    Error(String),
 }
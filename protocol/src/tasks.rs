@@ -0,0 +1,23 @@
+use serde::{Deserialize, Serialize};
+
+// Long running jobs on the agent report back with a series of progress messages instead of a
+// single response. Finished and Failed are always the last message for a job.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub enum Progress {
+    Step(String),
+    Finished(String),
+    Failed(String),
+}
+
+impl Progress {
+    pub fn is_final(&self) -> bool {
+        !matches!(self, Progress::Step(_))
+    }
+}
+
+// A file the agent should download itself, usually a Discord attachment
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RemoteFile {
+    pub file_name: String,
+    pub url: String,
+}